@@ -0,0 +1,142 @@
+//! Criterion benchmarks of srs setup, indexing, proving and verifying over a grid of table sizes, witness sizes
+//! and curves. Filter with the group and parameter names, e.g. `cargo bench --bench cq -- prove/bn254`.
+
+use ark_bls12_381::Bls12_381;
+use ark_bn254::Bn254;
+use ark_ec::PairingEngine;
+use ark_std::{
+    rand::{rngs::StdRng, Rng, SeedableRng},
+    UniformRand,
+};
+use cqext::{
+    utils::unsafe_setup_from_rng, Index, Prover, ProvingKey, SimpleHashFiatShamirRng, Statement,
+    Table, Verifier, VerifierKey, Witness,
+};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand_chacha::ChaChaRng;
+use sha3::Keccak256;
+
+type FS = SimpleHashFiatShamirRng<Keccak256, ChaChaRng>;
+
+/// log2 of the table sizes and of the witness sizes, witnesses larger than the table are skipped
+const TABLE_LOG_SIZES: [u32; 3] = [8, 10, 12];
+const WITNESS_LOG_SIZES: [u32; 3] = [4, 6, 8];
+
+struct Instance<E: PairingEngine> {
+    pk: ProvingKey<E>,
+    srs_g2: Vec<E::G2Affine>,
+    vk: VerifierKey<E>,
+    table: Table<E::Fr>,
+    index: Index<E>,
+    witness: Witness<E::Fr>,
+    statement: Statement<E>,
+}
+
+fn instance<E: PairingEngine>(table_size: usize, witness_size: usize) -> Instance<E> {
+    let mut rng = StdRng::seed_from_u64(table_size as u64 ^ ((witness_size as u64) << 32));
+    let (srs_g1, srs_g2) = unsafe_setup_from_rng::<E, StdRng>(table_size - 1, table_size, &mut rng);
+    let pk = ProvingKey::<E>::new(srs_g1);
+
+    let values: Vec<E::Fr> = (0..table_size).map(|_| E::Fr::rand(&mut rng)).collect();
+    let table = Table::new(&values).unwrap();
+    let index = Index::<E>::gen(pk.srs_g1(), &srs_g2, &table).unwrap();
+
+    let indices: Vec<usize> = (0..witness_size)
+        .map(|_| rng.gen_range(0..table_size))
+        .collect();
+    let witness = Witness::from_indices(&table, &indices).unwrap();
+    let statement = Statement::from_witness(&pk, &witness);
+    let vk = VerifierKey::<E>::for_table(&srs_g2, table_size, witness_size, false).unwrap();
+
+    Instance {
+        pk,
+        srs_g2,
+        vk,
+        table,
+        index,
+        witness,
+        statement,
+    }
+}
+
+fn bench_curve<E: PairingEngine>(c: &mut Criterion, curve: &str) {
+    let mut setup = c.benchmark_group(format!("setup/{}", curve));
+    for log_n in TABLE_LOG_SIZES {
+        let n = 1usize << log_n;
+        setup.bench_with_input(BenchmarkId::from_parameter(n), &n, |b, &n| {
+            let mut rng = StdRng::seed_from_u64(0);
+            b.iter(|| unsafe_setup_from_rng::<E, StdRng>(n - 1, n, &mut rng))
+        });
+    }
+    setup.finish();
+
+    let mut index = c.benchmark_group(format!("index/{}", curve));
+    index.sample_size(10);
+    for log_n in TABLE_LOG_SIZES {
+        let n = 1usize << log_n;
+        let instance = instance::<E>(n, 1 << WITNESS_LOG_SIZES[0]);
+        index.bench_with_input(BenchmarkId::from_parameter(n), &instance, |b, instance| {
+            b.iter(|| {
+                Index::<E>::gen(instance.pk.srs_g1(), &instance.srs_g2, &instance.table).unwrap()
+            })
+        });
+    }
+    index.finish();
+
+    let instances: Vec<(BenchmarkId, Instance<E>)> = TABLE_LOG_SIZES
+        .into_iter()
+        .flat_map(|log_n| {
+            WITNESS_LOG_SIZES
+                .into_iter()
+                .filter(move |&log_m| log_m <= log_n)
+                .map(move |log_m| {
+                    let id = BenchmarkId::from_parameter(format!("N=2^{}/n=2^{}", log_n, log_m));
+                    (id, instance::<E>(1 << log_n, 1 << log_m))
+                })
+        })
+        .collect();
+    let prove = |instance: &Instance<E>| {
+        Prover::<E, FS>::prove(
+            &instance.pk,
+            &instance.vk,
+            &instance.index,
+            &instance.table,
+            &instance.witness,
+            &instance.statement,
+        )
+        .unwrap()
+    };
+
+    let mut group = c.benchmark_group(format!("prove/{}", curve));
+    for (id, instance) in &instances {
+        group.bench_with_input(id.clone(), instance, |b, instance| {
+            b.iter(|| prove(instance))
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group(format!("verify/{}", curve));
+    for (id, instance) in &instances {
+        let proof = prove(instance);
+        group.bench_with_input(id.clone(), instance, |b, instance| {
+            b.iter(|| {
+                Verifier::<E, FS>::verify(
+                    &instance.vk,
+                    instance.index.common(),
+                    &instance.statement,
+                    &proof,
+                )
+                .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn benches(c: &mut Criterion) {
+    bench_curve::<Bn254>(c, "bn254");
+    bench_curve::<Bls12_381>(c, "bls12-381");
+}
+
+criterion_group!(cq, benches);
+criterion_main!(cq);
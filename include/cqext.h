@@ -0,0 +1,78 @@
+/* Generated with cbindgen from src/ffi.rs, regenerate with
+ *   cbindgen --config cbindgen.toml --crate cqext --output include/cqext.h
+ */
+
+#ifndef CQEXT_H
+#define CQEXT_H
+
+#include <stdint.h>
+#include <stddef.h>
+
+/* Size of a field element in the C interface */
+#define CQEXT_FIELD_BYTES 32
+
+typedef enum CqextStatus {
+  CQEXT_STATUS_OK = 0,
+  CQEXT_STATUS_NULL_POINTER = 1,
+  CQEXT_STATUS_INVALID_INPUT = 2,
+  CQEXT_STATUS_VERIFICATION_FAILED = 3,
+  CQEXT_STATUS_PANIC = 4,
+} CqextStatus;
+
+typedef struct CqextProverKey CqextProverKey;
+
+typedef struct CqextVerifierKey CqextVerifierKey;
+
+/* Bytes allocated by the library, release with `cqext_buffer_free` */
+typedef struct CqextBuffer {
+  uint8_t *data;
+  size_t len;
+} CqextBuffer;
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Samples an insecure srs from `seed` and indexes the table for witnesses of `witness_size`, only exported
+   by builds with the `test-utils` feature */
+CqextStatus cqext_setup(const uint8_t *table,
+                        size_t table_len,
+                        size_t witness_size,
+                        uint64_t seed,
+                        CqextProverKey **pk_out,
+                        CqextVerifierKey **vk_out);
+
+/* Proves that all `witness_len` witness values are in the table */
+CqextStatus cqext_prove(const CqextProverKey *pk,
+                        const uint8_t *witness,
+                        size_t witness_len,
+                        CqextBuffer *statement_out,
+                        CqextBuffer *proof_out);
+
+CqextStatus cqext_verify(const CqextVerifierKey *vk,
+                         const uint8_t *statement,
+                         size_t statement_len,
+                         const uint8_t *proof,
+                         size_t proof_len);
+
+CqextStatus cqext_prover_key_to_bytes(const CqextProverKey *pk, CqextBuffer *out);
+
+CqextStatus cqext_prover_key_from_bytes(const uint8_t *data, size_t len, CqextProverKey **pk_out);
+
+CqextStatus cqext_verifier_key_to_bytes(const CqextVerifierKey *vk, CqextBuffer *out);
+
+CqextStatus cqext_verifier_key_from_bytes(const uint8_t *data,
+                                          size_t len,
+                                          CqextVerifierKey **vk_out);
+
+void cqext_prover_key_free(CqextProverKey *pk);
+
+void cqext_verifier_key_free(CqextVerifierKey *vk);
+
+void cqext_buffer_free(CqextBuffer buffer);
+
+#ifdef __cplusplus
+} /* extern "C" */
+#endif
+
+#endif /* CQEXT_H */
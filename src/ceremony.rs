@@ -0,0 +1,221 @@
+//! A small powers of tau ceremony, for tables whose size no public ceremony covers.
+//!
+//! The ceremony starts from [`start`], the powers of tau = 1. Each participant runs [`contribute`], which multiplies
+//! the i-th powers by `s^i` for a fresh secret `s` and returns the updated srs with an [`UpdateProof`]: `[s]_1`,
+//! `[s]_2`, a Schnorr proof of knowledge of `s` and the new `[tau]_1`. The final tau is the product of all
+//! secrets, so it stays unknown as long as one participant deleted theirs. [`verify_contribution`] checks one step
+//! against the previous srs, [`verify_chain`] checks the final srs against the proofs alone.
+
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{One, PrimeField, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{
+    iter,
+    rand::{RngCore, SeedableRng},
+    vec::Vec,
+    UniformRand,
+};
+use rand_chacha::ChaChaRng;
+use sha3::{Digest, Keccak256};
+use zeroize::Zeroize;
+
+use crate::{
+    error::Error,
+    srs::{Srs, StructureCheck},
+};
+
+/// Evidence that one contribution multiplied tau by a secret its author knows
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct UpdateProof<E: PairingEngine> {
+    /// `[tau]_1` after the contribution
+    pub tau_g1: E::G1Affine,
+    pub s_g1: E::G1Affine,
+    pub s_g2: E::G2Affine,
+    /// commitment `[k]_1` and response `k + c·s` of the Schnorr proof
+    pub pok_r: E::G1Affine,
+    pub pok_z: E::Fr,
+}
+
+/// Powers of tau = 1 with `max_power_g1 + 1` points in G1 and `max_power_g2 + 1` in G2, insecure until someone
+/// contributes
+pub fn start<E: PairingEngine>(max_power_g1: usize, max_power_g2: usize) -> Srs<E> {
+    Srs {
+        g1: vec![E::G1Affine::prime_subgroup_generator(); max_power_g1 + 1],
+        g2: vec![E::G2Affine::prime_subgroup_generator(); max_power_g2 + 1],
+    }
+}
+
+/// Schnorr challenge, binding the proof to the previous and the new `[tau]_1`
+fn challenge<E: PairingEngine>(
+    prev_tau_g1: &E::G1Affine,
+    tau_g1: &E::G1Affine,
+    s_g1: &E::G1Affine,
+    pok_r: &E::G1Affine,
+) -> E::Fr {
+    let mut bytes = vec![];
+    for p in [prev_tau_g1, tau_g1, s_g1, pok_r] {
+        p.serialize(&mut bytes).unwrap();
+    }
+    let mut seed = [0u8; 32];
+    seed.copy_from_slice(&Keccak256::digest(&bytes));
+    E::Fr::rand(&mut ChaChaRng::from_seed(seed))
+}
+
+fn scale<G: AffineCurve>(points: &[G], s: G::ScalarField) -> Vec<G> {
+    let mut scaled: Vec<G::Projective> = points
+        .iter()
+        .zip(iter::successors(Some(G::ScalarField::one()), |p| {
+            Some(*p * s)
+        }))
+        .map(|(g, p)| g.mul(p.into_repr()))
+        .collect();
+    G::Projective::batch_normalization(&mut scaled);
+    scaled.iter().map(|g| g.into_affine()).collect()
+}
+
+/// Multiplies tau by a fresh secret from `rng`, the caller must not keep the rng state. The secret is wiped
+/// before returning.
+pub fn contribute<E: PairingEngine, R: RngCore>(
+    srs: &Srs<E>,
+    rng: &mut R,
+) -> (Srs<E>, UpdateProof<E>) {
+    let mut s = iter::repeat_with(|| E::Fr::rand(rng))
+        .find(|s| !s.is_zero())
+        .unwrap();
+    let next = Srs {
+        g1: scale(&srs.g1, s),
+        g2: scale(&srs.g2, s),
+    };
+
+    let g1_gen = E::G1Affine::prime_subgroup_generator();
+    let s_g1 = g1_gen.mul(s.into_repr()).into_affine();
+    let s_g2 = E::G2Affine::prime_subgroup_generator()
+        .mul(s.into_repr())
+        .into_affine();
+    let mut k = E::Fr::rand(rng);
+    let pok_r = g1_gen.mul(k.into_repr()).into_affine();
+    let tau_g1 = next.g1[1];
+    let c = challenge::<E>(&srs.g1[1], &tau_g1, &s_g1, &pok_r);
+
+    let proof = UpdateProof {
+        tau_g1,
+        s_g1,
+        s_g2,
+        pok_r,
+        pok_z: k + c * s,
+    };
+    s.zeroize();
+    k.zeroize();
+    (next, proof)
+}
+
+/// Checks that `proof` moves `[tau]_1` from `prev_tau_g1` to `proof.tau_g1` by a known nonzero secret
+fn verify_update<E: PairingEngine>(prev_tau_g1: &E::G1Affine, proof: &UpdateProof<E>) -> bool {
+    let g1_gen = E::G1Affine::prime_subgroup_generator();
+    let g2_gen = E::G2Affine::prime_subgroup_generator();
+    if proof.s_g1.is_zero() {
+        return false;
+    }
+    let c = challenge::<E>(prev_tau_g1, &proof.tau_g1, &proof.s_g1, &proof.pok_r);
+    let z_g1 = g1_gen.mul(proof.pok_z.into_repr());
+    if z_g1 != proof.pok_r.into_projective() + proof.s_g1.mul(c.into_repr()) {
+        return false;
+    }
+    // [s]_2 matches [s]_1, and the new [tau]_1 is the previous one times s
+    E::pairing(proof.s_g1, g2_gen) == E::pairing(g1_gen, proof.s_g2)
+        && E::pairing(proof.tau_g1, g2_gen) == E::pairing(*prev_tau_g1, proof.s_g2)
+}
+
+/// Checks a single contribution from `prev` to `next`, including the full structure of `next`
+pub fn verify_contribution<E: PairingEngine>(
+    prev: &Srs<E>,
+    next: &Srs<E>,
+    proof: &UpdateProof<E>,
+) -> Result<(), Error> {
+    if prev.g1.len() != next.g1.len() || prev.g2.len() != next.g2.len() || next.g1.len() < 2 {
+        return Err(Error::InvalidContribution(0));
+    }
+    if next.g1[1] != proof.tau_g1 || !verify_update(&prev.g1[1], proof) {
+        return Err(Error::InvalidContribution(0));
+    }
+    next.verify_structure(StructureCheck::Full)
+}
+
+/// Checks that `srs` is the outcome of the contributions in `proofs`, in order, starting from `start`. The error
+/// carries the position of the first invalid contribution.
+pub fn verify_chain<E: PairingEngine>(
+    srs: &Srs<E>,
+    proofs: &[UpdateProof<E>],
+) -> Result<(), Error> {
+    if proofs.is_empty() {
+        return Err(Error::NoContributions);
+    }
+    if srs.g1.len() < 2 {
+        return Err(Error::SrsG1TooSmall(2, srs.g1.len()));
+    }
+    let mut prev_tau_g1 = E::G1Affine::prime_subgroup_generator();
+    for (i, proof) in proofs.iter().enumerate() {
+        if !verify_update(&prev_tau_g1, proof) {
+            return Err(Error::InvalidContribution(i));
+        }
+        prev_tau_g1 = proof.tau_g1;
+    }
+    if srs.g1[1] != prev_tau_g1 {
+        return Err(Error::InvalidContribution(proofs.len() - 1));
+    }
+    srs.verify_structure(StructureCheck::Full)
+}
+
+#[cfg(test)]
+mod ceremony_tests {
+    use ark_bn254::{Bn254, Fr};
+    use ark_std::test_rng;
+    use rand_chacha::ChaChaRng;
+    use sha3::Keccak256;
+
+    use crate::{
+        config::CqConfig, cq::Cq, error::Error, rng::SimpleHashFiatShamirRng, table::Table,
+        utils::to_field,
+    };
+
+    use super::{contribute, start, verify_chain, verify_contribution};
+
+    type FS = SimpleHashFiatShamirRng<Keccak256, ChaChaRng>;
+
+    #[test]
+    fn test_ceremony() {
+        let mut rng = test_rng();
+        let mut srs = start::<Bn254>(7, 8);
+        let mut proofs = vec![];
+        for _ in 0..3 {
+            let (next, proof) = contribute(&srs, &mut rng);
+            assert!(verify_contribution(&srs, &next, &proof).is_ok());
+            srs = next;
+            proofs.push(proof);
+        }
+        assert!(verify_chain(&srs, &proofs).is_ok());
+
+        let table = Table::<Fr>::new(&to_field(&[1, 5, 10, 15, 20, 25, 30, 35])).unwrap();
+        let config = CqConfig::<Bn254, FS>::builder()
+            .table_size(8)
+            .witness_size(4)
+            .build()
+            .unwrap();
+        let (pk, vk) = config.setup(&srs, table).unwrap();
+        let (statement, proof) = Cq::<Bn254, FS>::prove(&pk, &to_field(&[5, 15, 20, 35])).unwrap();
+        assert!(Cq::<Bn254, FS>::verify(&vk, &statement, &proof).is_ok());
+
+        // a contribution with an invalid proof of knowledge, and a chain missing the last contribution
+        let mut forged = proofs.clone();
+        forged[1].pok_z += Fr::from(1u64);
+        assert_eq!(
+            verify_chain(&srs, &forged).err(),
+            Some(Error::InvalidContribution(1))
+        );
+        assert_eq!(
+            verify_chain(&srs, &proofs[..2]).err(),
+            Some(Error::InvalidContribution(1))
+        );
+        assert_eq!(verify_chain(&srs, &[]).err(), Some(Error::NoContributions));
+    }
+}
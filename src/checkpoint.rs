@@ -0,0 +1,224 @@
+//! Checkpointed index generation, so that a crash or preemption during the hours long preprocessing of a large
+//! table doesn't force a restart from scratch.
+//!
+//! [`Index::gen_checkpointed`] saves an [`IndexCheckpoint`] after the Toeplitz product and the cached quotients of
+//! Feist–Khovratovich, after every `interval` quotients of the naive algorithm and after the Lagrange commitments.
+//! The file is replaced by a rename, so a crash while writing leaves the previous checkpoint intact, and removed
+//! once the index is complete.
+
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_poly::{univariate::DensePolynomial, EvaluationDomain, UVPolynomial};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+
+use crate::{
+    error::Error,
+    indexer::{CommonPreprocessedInput, Index, QuotientAlgorithm},
+    table::Table,
+    tools::{
+        compute_fk_h, compute_lagrange_basis_commitments, compute_qs_from_h, compute_qs_naive_range,
+    },
+    utils::{domain, is_pow_2},
+};
+
+fn io_err(e: std::io::Error) -> Error {
+    Error::Io(e.to_string())
+}
+
+fn serialization_err(e: ark_serialize::SerializationError) -> Error {
+    Error::Serialization(format!("{:?}", e))
+}
+
+/// Partially generated index, the stages are filled in order
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct IndexCheckpoint<E: PairingEngine> {
+    /// binds the checkpoint to the table and `srs_g2`
+    pub(crate) common: CommonPreprocessedInput<E>,
+    /// binds the checkpoint to `srs_g1`
+    pub(crate) x_pow_n_minus_1: E::G1Affine,
+    /// [h_i(x)]_1 of Feist–Khovratovich, cleared once `qs` is complete
+    pub(crate) h: Vec<E::G1Affine>,
+    /// prefix of the cached quotients [Qi(x)]_1
+    pub(crate) qs: Vec<E::G1Affine>,
+    /// [Li(x)]_1, empty until all quotients are done
+    pub(crate) ls: Vec<E::G1Affine>,
+}
+
+impl<E: PairingEngine> IndexCheckpoint<E> {
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = File::open(path).map_err(io_err)?;
+        Self::deserialize(BufReader::new(file)).map_err(serialization_err)
+    }
+
+    /// Writes to a temporary file next to `path` and renames it over `path`
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+        let tmp = path.with_extension("tmp");
+        let mut writer = BufWriter::new(File::create(&tmp).map_err(io_err)?);
+        self.serialize(&mut writer).map_err(serialization_err)?;
+        let file = writer.into_inner().map_err(|e| io_err(e.into_error()))?;
+        file.sync_all().map_err(io_err)?;
+        fs::rename(&tmp, path).map_err(io_err)
+    }
+
+    fn matches(&self, common: &CommonPreprocessedInput<E>, x_pow_n_minus_1: &E::G1Affine) -> bool {
+        self.common.zv_2 == common.zv_2
+            && self.common.t_2 == common.t_2
+            && self.x_pow_n_minus_1 == *x_pow_n_minus_1
+    }
+}
+
+impl<E: PairingEngine> Index<E> {
+    /// `gen_with` that saves its progress to `checkpoint`. With `resume` an existing checkpoint for the same table
+    /// and srs is continued, otherwise it is overwritten.
+    pub fn gen_checkpointed<P: AsRef<Path>>(
+        srs_g1: &[E::G1Affine],
+        srs_g2: &[E::G2Affine],
+        table: &Table<E::Fr>,
+        quotients: QuotientAlgorithm,
+        checkpoint: P,
+        interval: usize,
+        resume: bool,
+    ) -> Result<Self, Error> {
+        let path = checkpoint.as_ref();
+        let n = table.size;
+        if !is_pow_2(n) {
+            return Err(Error::TableSizeNotPow2(n));
+        }
+        if srs_g1.len() < n {
+            return Err(Error::SrsG1TooSmall(n, srs_g1.len()));
+        }
+        if srs_g2.len() < n + 1 {
+            return Err(Error::SrsG2TooSmall(n + 1, srs_g2.len()));
+        }
+
+        let domain = domain::<E::Fr>(n)?;
+        let table_poly = DensePolynomial::from_coefficients_vec(domain.ifft(&table.values));
+        let common = Self::compute_common(srs_g2, table)?;
+        let x_pow_n_minus_1 = srs_g1[n - 1];
+
+        let mut state = if resume && path.exists() {
+            let state = IndexCheckpoint::read(path)?;
+            if !state.matches(&common, &x_pow_n_minus_1) {
+                return Err(Error::CheckpointMismatch);
+            }
+            state
+        } else {
+            IndexCheckpoint {
+                common,
+                x_pow_n_minus_1,
+                h: Vec::new(),
+                qs: Vec::new(),
+                ls: Vec::new(),
+            }
+        };
+
+        // both algorithms compute the same quotients, so a checkpoint may be continued with either
+        let srs_n = &srs_g1[..n];
+        match quotients {
+            QuotientAlgorithm::Fk if state.qs.len() < n => {
+                if state.h.is_empty() {
+                    let mut h = compute_fk_h::<E>(&table_poly, &domain, srs_n);
+                    E::G1Projective::batch_normalization(&mut h);
+                    state.h = h.iter().map(|hi| hi.into_affine()).collect();
+                    state.write(path)?;
+                }
+                let h: Vec<E::G1Projective> =
+                    state.h.iter().map(|hi| hi.into_projective()).collect();
+                state.qs = compute_qs_from_h::<E>(&h, &domain);
+                state.h = Vec::new();
+                state.write(path)?;
+            }
+            QuotientAlgorithm::Naive => {
+                while state.qs.len() < n {
+                    let start = state.qs.len();
+                    let end = (start + interval.max(1)).min(n);
+                    let batch =
+                        compute_qs_naive_range::<E>(&table_poly, &domain, srs_n, start..end);
+                    state.qs.extend(batch);
+                    state.write(path)?;
+                }
+            }
+            QuotientAlgorithm::Fk => {}
+        }
+
+        if state.ls.is_empty() {
+            state.ls = compute_lagrange_basis_commitments(srs_n);
+            state.write(path)?;
+        }
+
+        let index = Self::from_parts(srs_g1, &table_poly, state.common, state.qs, state.ls)?;
+        fs::remove_file(path).map_err(io_err)?;
+        Ok(index)
+    }
+}
+
+#[cfg(test)]
+mod checkpoint_tests {
+    use ark_bn254::{Bn254, Fr};
+    use ark_serialize::CanonicalSerialize;
+    use ark_std::{rand::rngs::StdRng, test_rng};
+
+    use crate::{
+        error::Error,
+        indexer::{Index, QuotientAlgorithm},
+        table::Table,
+        utils::{to_field, unsafe_setup_from_rng},
+    };
+
+    use super::IndexCheckpoint;
+
+    fn bytes(index: &Index<Bn254>) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        index.serialize(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_resume_index() {
+        let n = 8;
+        let mut rng = test_rng();
+        let (srs_g1, srs_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(n - 1, n, &mut rng);
+        let table = Table::<Fr>::new(&to_field(&[1, 5, 10, 15, 20, 25, 30, 35])).unwrap();
+        let expected = Index::<Bn254>::gen(&srs_g1, &srs_g2, &table).unwrap();
+        let path =
+            std::env::temp_dir().join(format!("cqext-checkpoint-{}.bin", std::process::id()));
+
+        // a run that stopped after three quotients
+        IndexCheckpoint::<Bn254> {
+            common: Index::<Bn254>::compute_common(&srs_g2, &table).unwrap(),
+            x_pow_n_minus_1: srs_g1[n - 1],
+            h: Vec::new(),
+            qs: expected.qs[..3].to_vec(),
+            ls: Vec::new(),
+        }
+        .write(&path)
+        .unwrap();
+
+        let other = Table::<Fr>::new(&to_field(&[2, 5, 10, 15, 20, 25, 30, 35])).unwrap();
+        let res = Index::<Bn254>::gen_checkpointed(
+            &srs_g1,
+            &srs_g2,
+            &other,
+            QuotientAlgorithm::Naive,
+            &path,
+            2,
+            true,
+        );
+        assert_eq!(res.err(), Some(Error::CheckpointMismatch));
+
+        for quotients in [QuotientAlgorithm::Naive, QuotientAlgorithm::Fk] {
+            let index = Index::<Bn254>::gen_checkpointed(
+                &srs_g1, &srs_g2, &table, quotients, &path, 2, true,
+            )
+            .unwrap();
+            assert_eq!(bytes(&index), bytes(&expected));
+            assert!(!path.exists());
+        }
+    }
+}
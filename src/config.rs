@@ -0,0 +1,589 @@
+use ark_ec::PairingEngine;
+use ark_std::{marker::PhantomData, vec::Vec};
+
+use crate::{
+    cq::{CqProverKey, CqVerifierKey},
+    data_structures::ProvingKey,
+    error::Error,
+    indexer::{CommonPreprocessedInput, Index},
+    rng::FiatShamirRng,
+    table::Table,
+    utils::max_table_size,
+    verifier::{a_bound_shift, exact_srs_degree, VerifierKey},
+};
+
+/// Validated protocol parameters.
+/// The curve and the Fiat-Shamir hash are fixed by the `E` and `FS` type parameters.
+pub struct CqConfig<E: PairingEngine, FS: FiatShamirRng> {
+    table_size: usize,
+    witness_size: usize,
+    num_threads: usize,
+    zk: bool,
+    _e: PhantomData<E>,
+    _fs: PhantomData<FS>,
+}
+
+pub struct CqConfigBuilder<E: PairingEngine, FS: FiatShamirRng> {
+    table_size: Option<usize>,
+    witness_size: Option<usize>,
+    num_threads: usize,
+    zk: bool,
+    _e: PhantomData<E>,
+    _fs: PhantomData<FS>,
+}
+
+impl<E: PairingEngine, FS: FiatShamirRng> Default for CqConfigBuilder<E, FS> {
+    fn default() -> Self {
+        Self {
+            table_size: None,
+            witness_size: None,
+            num_threads: 1,
+            zk: false,
+            _e: PhantomData,
+            _fs: PhantomData,
+        }
+    }
+}
+
+impl<E: PairingEngine, FS: FiatShamirRng> CqConfigBuilder<E, FS> {
+    pub fn table_size(mut self, table_size: usize) -> Self {
+        self.table_size = Some(table_size);
+        self
+    }
+
+    pub fn witness_size(mut self, witness_size: usize) -> Self {
+        self.witness_size = Some(witness_size);
+        self
+    }
+
+    /// Number of worker threads of `CqConfig::install`, more than one needs the `parallel` feature
+    pub fn parallelism(mut self, num_threads: usize) -> Self {
+        self.num_threads = num_threads;
+        self
+    }
+
+    pub fn zk(mut self, zk: bool) -> Self {
+        self.zk = zk;
+        self
+    }
+
+    /// Checks that the parameters are compatible before any expensive work starts
+    pub fn build(self) -> Result<CqConfig<E, FS>, Error> {
+        let table_size = self
+            .table_size
+            .ok_or(Error::MissingParameter("table_size"))?;
+        let witness_size = self
+            .witness_size
+            .ok_or(Error::MissingParameter("witness_size"))?;
+
+        if !table_size.is_power_of_two() {
+            return Err(Error::TableSizeNotPow2(table_size));
+        }
+        if !witness_size.is_power_of_two() {
+            return Err(Error::WitnessSizeNotPow2(witness_size));
+        }
+        // degree bound of B0 is witness_size - 2
+        if witness_size < 2 {
+            return Err(Error::WitnessSizeTooSmall(witness_size));
+        }
+        // the blinded B0 and G of zk proofs need n <= N, plain proofs take any n with a larger srs
+        if self.zk && witness_size > table_size {
+            return Err(Error::WitnessLargerThanTable(witness_size, table_size));
+        }
+        // the quotients are computed with ffts over a domain of twice the table size
+        let max_table_size = max_table_size::<E::Fr>();
+        if table_size > max_table_size {
+            return Err(Error::TableTooLargeForField(table_size, max_table_size));
+        }
+        if self.num_threads == 0 || (!cfg!(feature = "parallel") && self.num_threads != 1) {
+            return Err(Error::UnsupportedParallelism(self.num_threads));
+        }
+
+        Ok(CqConfig {
+            table_size,
+            witness_size,
+            num_threads: self.num_threads,
+            zk: self.zk,
+            _e: PhantomData,
+            _fs: PhantomData,
+        })
+    }
+}
+
+impl<E: PairingEngine, FS: FiatShamirRng> CqConfig<E, FS> {
+    pub fn builder() -> CqConfigBuilder<E, FS> {
+        CqConfigBuilder::default()
+    }
+
+    pub fn table_size(&self) -> usize {
+        self.table_size
+    }
+
+    pub fn witness_size(&self) -> usize {
+        self.witness_size
+    }
+
+    pub fn num_threads(&self) -> usize {
+        self.num_threads
+    }
+
+    pub fn zk(&self) -> bool {
+        self.zk
+    }
+
+    /// Runs `op` on a rayon pool of `num_threads` workers, proofs and indexing started inside use all of them
+    #[cfg(feature = "parallel")]
+    pub fn install<R: Send>(&self, op: impl FnOnce() -> R + Send) -> R {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(self.num_threads)
+            .build()
+            .expect("failed to start the thread pool")
+            .install(op)
+    }
+
+    /// Runs `op` on the current thread, without the `parallel` feature there are no workers
+    #[cfg(not(feature = "parallel"))]
+    pub fn install<R: Send>(&self, op: impl FnOnce() -> R + Send) -> R {
+        op()
+    }
+
+    /// Highest power of tau needed in G1, the blinders of zk proofs reach x^(N+1) and witnesses larger than the
+    /// table x^(n-1). Plain keys take any larger srs, zk keys need exactly this degree.
+    pub fn srs_g1_degree(&self) -> usize {
+        exact_srs_degree(self.table_size, self.witness_size, self.zk)
+    }
+
+    /// Highest power of tau the index reads, the srs of witnesses larger than the table goes further
+    fn index_g1_degree(&self) -> usize {
+        if self.zk {
+            self.table_size + 1
+        } else {
+            self.table_size - 1
+        }
+    }
+
+    /// Highest power of tau needed in G2 with an srs of `srs_g1_degree`, [zV(x)]_2 needs x^N and the degree
+    /// bound of A for witnesses larger than the table x^(n-N). A larger G1 srs needs more, see
+    /// `VerifierKey::for_srs_degree`.
+    pub fn srs_g2_degree(&self) -> usize {
+        let shift = a_bound_shift(self.table_size, self.srs_g1_degree(), self.zk);
+        self.table_size.max(shift.unwrap_or(0))
+    }
+
+    pub fn check_table(&self, table: &Table<E::Fr>) -> Result<(), Error> {
+        if table.size != self.table_size {
+            return Err(Error::TableSizeMismatch(self.table_size, table.size));
+        }
+        Ok(())
+    }
+
+    pub fn check_srs(&self, srs_g1: &[E::G1Affine], srs_g2: &[E::G2Affine]) -> Result<(), Error> {
+        if srs_g1.len() < self.srs_g1_degree() + 1 {
+            return Err(Error::SrsG1TooSmall(self.srs_g1_degree() + 1, srs_g1.len()));
+        }
+        if srs_g2.len() < self.srs_g2_degree() + 1 {
+            return Err(Error::SrsG2TooSmall(self.srs_g2_degree() + 1, srs_g2.len()));
+        }
+        Ok(())
+    }
+
+    /// Same as `Index::gen`, also checking the table and the srs against this config. The srs may be
+    /// larger than this config needs, powers above the ones of the table are ignored so that a universal srs gives
+    /// the same index as one of exactly the needed size.
+    pub fn index(
+        &self,
+        srs_g1: &[E::G1Affine],
+        srs_g2: &[E::G2Affine],
+        table: &Table<E::Fr>,
+    ) -> Result<Index<E>, Error> {
+        self.check_table(table)?;
+        self.check_srs(srs_g1, srs_g2)?;
+        Index::<E>::gen(&srs_g1[..=self.index_g1_degree()], srs_g2, table)
+    }
+
+    /// Same as `index`, saving its progress to `checkpoint` and continuing it with `resume`
+    #[cfg(feature = "std")]
+    pub fn index_checkpointed<P: AsRef<std::path::Path>>(
+        &self,
+        srs_g1: &[E::G1Affine],
+        srs_g2: &[E::G2Affine],
+        table: &Table<E::Fr>,
+        checkpoint: P,
+        resume: bool,
+    ) -> Result<Index<E>, Error> {
+        self.check_table(table)?;
+        self.check_srs(srs_g1, srs_g2)?;
+        Index::<E>::gen_checkpointed(
+            &srs_g1[..=self.index_g1_degree()],
+            srs_g2,
+            table,
+            crate::indexer::QuotientAlgorithm::Fk,
+            checkpoint,
+            table.size,
+            resume,
+        )
+    }
+
+    /// Derives prover and verifier keys from an existing srs, which may be larger than needed for plain keys. The
+    /// degree bounds have to hold against every power a prover knows, so the proving key keeps the whole srs and
+    /// the verifier key checks against its highest power in G1. zk keys need an srs of exactly `srs_g1_degree`.
+    pub fn keygen(
+        &self,
+        srs_g1: Vec<E::G1Affine>,
+        srs_g2: &[E::G2Affine],
+        table: Table<E::Fr>,
+    ) -> Result<(CqProverKey<E>, CqVerifierKey<E>), Error> {
+        self.check_table(&table)?;
+        self.check_srs(&srs_g1, srs_g2)?;
+        let vk = VerifierKey::<E>::for_srs_degree(
+            srs_g2,
+            srs_g1.len() - 1,
+            self.table_size,
+            self.witness_size,
+            self.zk,
+        )?;
+        let index = self.index(&srs_g1, srs_g2, &table)?;
+        let common = Index::<E>::compute_common(srs_g2, &table)?;
+        let pk = ProvingKey::<E>::new(srs_g1);
+
+        Ok((
+            CqProverKey {
+                pk,
+                vk: vk.clone(),
+                index,
+                table,
+            },
+            CqVerifierKey { vk, common },
+        ))
+    }
+
+    /// Verifier keys for a table known only by its commitment [T(x)]_2, no table values involved. A claimed table
+    /// can be checked against it with `common.check_table`. `srs_g1_degree` is the highest power of x in G1 of
+    /// the srs the prover holds, as in `keygen`.
+    pub fn committed_verifier_key(
+        &self,
+        srs_g2: &[E::G2Affine],
+        srs_g1_degree: usize,
+        t_2: E::G2Affine,
+    ) -> Result<CqVerifierKey<E>, Error> {
+        let common = CommonPreprocessedInput::from_commitment(srs_g2, self.table_size, t_2)?;
+        let vk = VerifierKey::<E>::for_srs_degree(
+            srs_g2,
+            srs_g1_degree,
+            self.table_size,
+            self.witness_size,
+            self.zk,
+        )?;
+        Ok(CqVerifierKey { vk, common })
+    }
+
+    /// Derives the keys from a ceremony or verified srs, see `keygen` for how a larger srs is used
+    #[cfg(feature = "std")]
+    pub fn setup(
+        &self,
+        srs: &crate::srs::Srs<E>,
+        table: Table<E::Fr>,
+    ) -> Result<(CqProverKey<E>, CqVerifierKey<E>), Error> {
+        self.keygen(srs.g1().to_vec(), srs.g2(), table)
+    }
+
+    /// The prefix of a larger srs that this config needs, checked in full, so one ceremony file serves indexes
+    /// of many sizes. Keys from the prefix are only sound if the powers of the ceremony above it stay secret,
+    /// otherwise derive them from the whole ceremony with `setup`.
+    #[cfg(feature = "std")]
+    pub fn extract_srs(&self, srs: &crate::srs::Srs<E>) -> Result<crate::srs::Srs<E>, Error> {
+        srs.truncate(self.srs_g1_degree(), self.srs_g2_degree())
+    }
+
+    /// Samples an srs of exactly the needed size from `rng` and derives the keys, see `unsafe_setup_from_rng`
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn unsafe_setup<R: ark_std::rand::RngCore>(
+        &self,
+        table: Table<E::Fr>,
+        rng: &mut R,
+    ) -> Result<(CqProverKey<E>, CqVerifierKey<E>), Error> {
+        self.check_table(&table)?;
+        let (srs_g1, srs_g2) = crate::utils::unsafe_setup_from_rng::<E, R>(
+            self.srs_g1_degree(),
+            self.srs_g2_degree(),
+            rng,
+        );
+        self.keygen(srs_g1, &srs_g2, table)
+    }
+}
+
+#[cfg(test)]
+mod config_tests {
+    use ark_bn254::{Bn254, Fr};
+    use ark_ec::{AffineCurve, ProjectiveCurve};
+    use ark_serialize::CanonicalSerialize;
+    use ark_std::{rand::rngs::StdRng, test_rng};
+    use rand_chacha::ChaChaRng;
+    use sha3::Keccak256;
+
+    use crate::{
+        cq::Cq,
+        data_structures::Witness,
+        error::{Error, VerificationError},
+        kzg::Kzg,
+        rng::SimpleHashFiatShamirRng,
+        table::Table,
+        utils::{to_field, unsafe_setup_from_rng},
+        verifier::Verifier,
+    };
+
+    use super::CqConfig;
+
+    type FS = SimpleHashFiatShamirRng<Keccak256, ChaChaRng>;
+    type Config = CqConfig<Bn254, FS>;
+
+    #[test]
+    fn test_invalid_parameters() {
+        let res = Config::builder().witness_size(4).build();
+        assert_eq!(res.err(), Some(Error::MissingParameter("table_size")));
+
+        let res = Config::builder().table_size(12).witness_size(4).build();
+        assert_eq!(res.err(), Some(Error::TableSizeNotPow2(12)));
+
+        let res = Config::builder().table_size(8).witness_size(3).build();
+        assert_eq!(res.err(), Some(Error::WitnessSizeNotPow2(3)));
+
+        let res = Config::builder().table_size(8).witness_size(1).build();
+        assert_eq!(res.err(), Some(Error::WitnessSizeTooSmall(1)));
+
+        let res = Config::builder()
+            .table_size(8)
+            .witness_size(16)
+            .zk(true)
+            .build();
+        assert_eq!(res.err(), Some(Error::WitnessLargerThanTable(16, 8)));
+
+        // the bn254 scalar field has 2-adicity 28
+        let res = Config::builder()
+            .table_size(1 << 28)
+            .witness_size(4)
+            .build();
+        assert_eq!(
+            res.err(),
+            Some(Error::TableTooLargeForField(1 << 28, 1 << 27))
+        );
+
+        let res = Config::builder()
+            .table_size(8)
+            .witness_size(4)
+            .parallelism(2)
+            .build();
+        #[cfg(not(feature = "parallel"))]
+        assert_eq!(res.err(), Some(Error::UnsupportedParallelism(2)));
+        #[cfg(feature = "parallel")]
+        assert_eq!(res.unwrap().install(rayon::current_num_threads), 2);
+
+        let res = Config::builder()
+            .table_size(8)
+            .witness_size(4)
+            .parallelism(0)
+            .build();
+        assert_eq!(res.err(), Some(Error::UnsupportedParallelism(0)));
+    }
+
+    #[test]
+    fn test_srs_too_small() {
+        let mut rng = test_rng();
+        let config = Config::builder()
+            .table_size(8)
+            .witness_size(4)
+            .build()
+            .unwrap();
+
+        let table = Table::new(&to_field(&[1, 5, 10, 15, 20, 25, 30, 35])).unwrap();
+
+        let (srs_g1, srs_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(7, 7, &mut rng);
+        let res = config.index(&srs_g1, &srs_g2, &table);
+        assert_eq!(res.err(), Some(Error::SrsG2TooSmall(9, 8)));
+
+        let (srs_g1, srs_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(3, 8, &mut rng);
+        let res = config.index(&srs_g1, &srs_g2, &table);
+        assert_eq!(res.err(), Some(Error::SrsG1TooSmall(8, 4)));
+    }
+
+    #[test]
+    fn test_zk_srs_degree() {
+        let config = Config::builder()
+            .table_size(8)
+            .witness_size(4)
+            .zk(true)
+            .build()
+            .unwrap();
+        assert_eq!(config.srs_g1_degree(), 9);
+
+        let mut rng = test_rng();
+        let table = Table::new(&to_field(&[1, 5, 10, 15, 20, 25, 30, 35])).unwrap();
+        let (prover_key, verifier_key) = config.unsafe_setup(table, &mut rng).unwrap();
+        assert_eq!(prover_key.pk.srs_g1.len(), 10);
+        assert!(verifier_key.vk.is_zk());
+    }
+
+    #[test]
+    fn test_universal_srs() {
+        let mut rng = test_rng();
+        let (srs_g1, srs_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(63, 64, &mut rng);
+        let values = to_field(&[1, 5, 10, 15, 20, 25, 30, 35]);
+
+        let config = Config::builder()
+            .table_size(8)
+            .witness_size(4)
+            .build()
+            .unwrap();
+        let exact = &srs_g1[..=config.srs_g1_degree()];
+        let mut expected = vec![];
+        config
+            .index(
+                exact,
+                &srs_g2[..=config.srs_g2_degree()],
+                &Table::new(&values).unwrap(),
+            )
+            .unwrap()
+            .serialize(&mut expected)
+            .unwrap();
+
+        // the bounds are checked against x^63, the highest power a prover of this srs knows
+        let (pk, vk) = config
+            .keygen(srs_g1.clone(), &srs_g2, Table::new(&values).unwrap())
+            .unwrap();
+        assert_eq!(pk.pk.srs_g1(), &srs_g1[..]);
+        assert_eq!(vk.vk.srs_degree(), 63);
+        assert_eq!(vk.vk.x_pow_a_bound_2, Some(srs_g2[56]));
+        assert_eq!(vk.vk.x_pow_b0_bound_2, srs_g2[61]);
+        let mut bytes = vec![];
+        pk.index.serialize(&mut bytes).unwrap();
+        assert_eq!(bytes, expected);
+
+        let (statement, proof) = Cq::<Bn254, FS>::prove(&pk, &to_field(&[5, 15, 20, 35])).unwrap();
+        assert!(proof.second_msg.a_shift_cm.is_some());
+        assert!(Cq::<Bn254, FS>::verify(&vk, &statement, &proof).is_ok());
+
+        // G2 has to reach x^(63+2-4)
+        assert_eq!(
+            config
+                .keygen(srs_g1.clone(), &srs_g2[..61], Table::new(&values).unwrap())
+                .err(),
+            Some(Error::SrsG2TooSmall(62, 61))
+        );
+
+        // the bound of G in zk proofs only holds for an srs that stops at x^(N+1)
+        let zk_config = Config::builder()
+            .table_size(8)
+            .witness_size(4)
+            .zk(true)
+            .build()
+            .unwrap();
+        assert_eq!(
+            zk_config
+                .keygen(srs_g1.clone(), &srs_g2, Table::new(&values).unwrap())
+                .err(),
+            Some(Error::SrsG1TooLarge(10, 64))
+        );
+        let (pk, vk) = zk_config
+            .keygen(srs_g1[..10].to_vec(), &srs_g2, Table::new(&values).unwrap())
+            .unwrap();
+        let (statement, proof) =
+            Cq::<Bn254, FS>::prove_zk(&pk, &to_field(&[5, 15, 20, 35]), &mut rng).unwrap();
+        assert!(Cq::<Bn254, FS>::verify(&vk, &statement, &proof).is_ok());
+    }
+
+    #[test]
+    fn test_witness_larger_than_table() {
+        let mut rng = test_rng();
+        let config = Config::builder()
+            .table_size(4)
+            .witness_size(16)
+            .build()
+            .unwrap();
+        assert_eq!((config.srs_g1_degree(), config.srs_g2_degree()), (15, 12));
+
+        let table = Table::new(&to_field(&[1, 5, 10, 15])).unwrap();
+        let (pk, vk) = config.unsafe_setup(table, &mut rng).unwrap();
+        // 11 lookups, padded to the witness size
+        let witness = Witness::padded(&to_field(&[5, 1, 1, 15, 10, 5, 5, 15, 1, 10, 10])).unwrap();
+        assert_eq!(witness.size(), 16);
+        let (statement, proof) = Cq::<Bn254, FS>::prove(&pk, &witness.values().to_vec()).unwrap();
+        assert!(Cq::<Bn254, FS>::verify(&vk, &statement, &proof).is_ok());
+
+        // the srs reaches past x^3, so the keys bound A and A(X) + zV(X) is caught by its degree
+        assert!(vk.vk.x_pow_a_bound_2.is_some());
+        let beta =
+            Verifier::<Bn254, FS>::derive_challenges(&vk.vk, &vk.common, &statement, &proof).beta;
+        let srs = &pk.pk.srs_g1;
+        let t_1 = Kzg::<Bn254>::msm_g1(&pk.index.ls, pk.table.values()).into_affine();
+        let prove = || {
+            Cq::<Bn254, FS>::prove(&pk, &witness.values().to_vec())
+                .unwrap()
+                .1
+        };
+        let mut forged = prove();
+        forged.second_msg.a_cm = forged.second_msg.a_cm + srs[4] + -srs[0];
+        forged.second_msg.qa_cm = forged.second_msg.qa_cm + t_1 + srs[0].mul(beta).into_affine();
+        assert_eq!(
+            Cq::<Bn254, FS>::verify(&vk, &statement, &forged),
+            Err(Error::Verification(VerificationError::DegreeBoundFailed(
+                "a_cm"
+            )))
+        );
+        let mut forged = prove();
+        forged.second_msg.a_shift_cm = None;
+        assert_eq!(
+            Cq::<Bn254, FS>::verify(&vk, &statement, &forged),
+            Err(Error::Verification(VerificationError::ProofShapeMismatch))
+        );
+
+        let mut values = witness.values().to_vec();
+        values[3] = Fr::from(2u64);
+        assert!(Cq::<Bn254, FS>::prove(&pk, &values).is_err());
+    }
+
+    #[test]
+    fn test_committed_verifier_key() {
+        let mut rng = test_rng();
+        let config = Config::builder()
+            .table_size(8)
+            .witness_size(4)
+            .build()
+            .unwrap();
+        let (srs_g1, srs_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(7, 8, &mut rng);
+        let table = Table::new(&to_field(&[1, 5, 10, 15, 20, 25, 30, 35])).unwrap();
+        let (pk, vk) = config.keygen(srs_g1, &srs_g2, table).unwrap();
+
+        // the verifier only gets the published commitment
+        let t_2 = *vk.common.table_commitment();
+        let committed = config.committed_verifier_key(&srs_g2, 7, t_2).unwrap();
+        let (statement, proof) = Cq::<Bn254, FS>::prove(&pk, &to_field(&[5, 15, 20, 35])).unwrap();
+        assert!(Cq::<Bn254, FS>::verify(&committed, &statement, &proof).is_ok());
+
+        let claimed = Table::new(&to_field(&[1, 5, 10, 15, 20, 25, 30, 35])).unwrap();
+        assert!(committed.common.check_table(&srs_g2, &claimed).is_ok());
+        let other = Table::new(&to_field(&[1, 5, 10, 15, 20, 25, 30, 40])).unwrap();
+        assert_eq!(
+            committed.common.check_table(&srs_g2, &other).err(),
+            Some(Error::TableCommitmentMismatch)
+        );
+        let smaller = Table::new(&to_field(&[1, 5, 10, 15])).unwrap();
+        assert_eq!(
+            committed.common.check_table(&srs_g2, &smaller).err(),
+            Some(Error::TableCommitmentMismatch)
+        );
+    }
+
+    #[test]
+    fn test_table_size_mismatch() {
+        let mut rng = test_rng();
+        let config = Config::builder()
+            .table_size(16)
+            .witness_size(4)
+            .build()
+            .unwrap();
+
+        let table = Table::new(&to_field(&[1, 5, 10, 15, 20, 25, 30, 35])).unwrap();
+        let res = config.unsafe_setup(table, &mut rng);
+        assert_eq!(res.err(), Some(Error::TableSizeMismatch(16, 8)));
+    }
+}
@@ -0,0 +1,333 @@
+use ark_ec::PairingEngine;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{marker::PhantomData, rand::RngCore, vec::Vec};
+
+use crate::{
+    config::CqConfig,
+    data_structures::{Proof, ProvingKey, Statement, StatementBlinder, Witness},
+    error::Error,
+    indexer::{CommonPreprocessedInput, Index},
+    prover::Prover,
+    rng::FiatShamirRng,
+    table::Table,
+    verifier::{PairingEquation, Verifier, VerifierKey},
+};
+
+/// Everything the prover needs to prove lookups into one table
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct CqProverKey<E: PairingEngine> {
+    pub pk: ProvingKey<E>,
+    /// absorbed into the transcript, the verifier has to use the same key
+    pub vk: VerifierKey<E>,
+    pub index: Index<E>,
+    pub table: Table<E::Fr>,
+}
+
+/// Everything the verifier needs to check lookups into one table
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct CqVerifierKey<E: PairingEngine> {
+    pub vk: VerifierKey<E>,
+    pub common: CommonPreprocessedInput<E>,
+}
+
+/// One-call facade over setup, indexing, proving and verification
+pub struct Cq<E: PairingEngine, FS: FiatShamirRng> {
+    _e: PhantomData<E>,
+    _fs: PhantomData<FS>,
+}
+
+impl<E: PairingEngine, FS: FiatShamirRng> Cq<E, FS> {
+    /// Indexes the table against a ceremony or verified srs and derives both keys for witnesses of
+    /// `witness_size`
+    #[cfg(feature = "std")]
+    pub fn keygen(
+        srs: &crate::srs::Srs<E>,
+        table_values: &Vec<E::Fr>,
+        witness_size: usize,
+    ) -> Result<(CqProverKey<E>, CqVerifierKey<E>), Error> {
+        let table = Table::new(table_values)?;
+        let config = CqConfig::<E, FS>::builder()
+            .table_size(table.size)
+            .witness_size(witness_size)
+            .build()?;
+
+        config.setup(srs, table)
+    }
+
+    /// Samples an srs from `rng`, indexes the table and derives both keys for witnesses of `witness_size`.
+    /// The srs is produced with `unsafe_setup_from_rng`, so the caller must not be trusted by the verifier.
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn setup<R: RngCore>(
+        table_values: &Vec<E::Fr>,
+        witness_size: usize,
+        rng: &mut R,
+    ) -> Result<(CqProverKey<E>, CqVerifierKey<E>), Error> {
+        let table = Table::new(table_values)?;
+        let config = CqConfig::<E, FS>::builder()
+            .table_size(table.size)
+            .witness_size(witness_size)
+            .build()?;
+
+        config.unsafe_setup(table, rng)
+    }
+
+    /// Commits to the witness and proves that all of its values are in the table
+    pub fn prove(
+        key: &CqProverKey<E>,
+        witness_values: &Vec<E::Fr>,
+    ) -> Result<(Statement<E>, Proof<E>), Error> {
+        let witness = Witness::<E::Fr>::new(witness_values)?;
+        let statement = Statement::from_witness(&key.pk, &witness);
+        let proof = Prover::<E, FS>::prove(
+            &key.pk, &key.vk, &key.index, &key.table, &witness, &statement,
+        )?;
+
+        Ok((statement, proof))
+    }
+
+    /// Same as `prove` with blinded prover messages, `key` must come from a config with `zk(true)`
+    pub fn prove_zk<R: RngCore>(
+        key: &CqProverKey<E>,
+        witness_values: &Vec<E::Fr>,
+        rng: &mut R,
+    ) -> Result<(Statement<E>, Proof<E>), Error> {
+        let witness = Witness::<E::Fr>::new(witness_values)?;
+        let statement = Statement::from_witness(&key.pk, &witness);
+        let proof = Prover::<E, FS>::prove_zk(
+            &key.pk, &key.vk, &key.index, &key.table, &witness, &statement, rng,
+        )?;
+
+        Ok((statement, proof))
+    }
+
+    /// Hiding commitment to the witness, keep the blinder for `prove_hiding`
+    pub fn commit_hiding<R: RngCore>(
+        key: &CqProverKey<E>,
+        witness_values: &Vec<E::Fr>,
+        rng: &mut R,
+    ) -> Result<(Statement<E>, StatementBlinder<E::Fr>), Error> {
+        let blinder = StatementBlinder::rand(rng);
+        let witness = Witness::<E::Fr>::new(witness_values)?.with_blinder(blinder);
+        if key.pk.srs_g1.len() < witness.size + 2 {
+            return Err(Error::SrsG1TooSmall(witness.size + 2, key.pk.srs_g1.len()));
+        }
+
+        Ok((Statement::from_witness(&key.pk, &witness), blinder))
+    }
+
+    /// Proves a statement from `commit_hiding`, with blinded prover messages if the key is zk
+    pub fn prove_hiding<R: RngCore>(
+        key: &CqProverKey<E>,
+        statement: &Statement<E>,
+        witness_values: &Vec<E::Fr>,
+        blinder: StatementBlinder<E::Fr>,
+        rng: &mut R,
+    ) -> Result<Proof<E>, Error> {
+        let witness = Witness::<E::Fr>::new(witness_values)?.with_blinder(blinder);
+        statement.check_opening(&key.pk, &witness)?;
+
+        let (pk, vk, index, table) = (&key.pk, &key.vk, &key.index, &key.table);
+        if vk.is_zk() {
+            Prover::<E, FS>::prove_zk(pk, vk, index, table, &witness, statement, rng)
+        } else {
+            Prover::<E, FS>::prove(pk, vk, index, table, &witness, statement)
+        }
+    }
+
+    pub fn verify(
+        key: &CqVerifierKey<E>,
+        statement: &Statement<E>,
+        proof: &Proof<E>,
+    ) -> Result<(), Error> {
+        Verifier::<E, FS>::verify(&key.vk, &key.common, statement, proof)
+    }
+
+    /// Pairing check of `verify`, to be folded with other checks of a composite proof
+    pub(crate) fn pairing_equation(
+        key: &CqVerifierKey<E>,
+        statement: &Statement<E>,
+        proof: &Proof<E>,
+    ) -> Result<PairingEquation<E>, Error> {
+        let challenges =
+            Verifier::<E, FS>::derive_challenges(&key.vk, &key.common, statement, proof);
+        Verifier::<E, FS>::pairing_equation(&key.vk, &key.common, statement, proof, &challenges)
+    }
+}
+
+#[cfg(test)]
+mod cq_tests {
+    use ark_bls12_381::Bls12_381;
+    use ark_bn254::{Bn254, Fr};
+    use ark_std::test_rng;
+    use rand_chacha::ChaChaRng;
+    use sha3::Keccak256;
+
+    use crate::{
+        config::CqConfig,
+        data_structures::StatementBlinder,
+        error::{Error, VerificationError},
+        rng::SimpleHashFiatShamirRng,
+        table::Table,
+        utils::to_field,
+    };
+
+    use super::Cq;
+
+    type FS = SimpleHashFiatShamirRng<Keccak256, ChaChaRng>;
+
+    #[test]
+    fn test_facade_roundtrip() {
+        let mut rng = test_rng();
+
+        let table_values = to_field::<Fr>(&[1, 5, 10, 15, 20, 25, 30, 35]);
+        let (prover_key, verifier_key) =
+            Cq::<Bn254, FS>::setup(&table_values, 4, &mut rng).unwrap();
+
+        let witness_values = to_field(&[5, 15, 20, 35]);
+        let (statement, proof) = Cq::<Bn254, FS>::prove(&prover_key, &witness_values).unwrap();
+
+        let res = Cq::<Bn254, FS>::verify(&verifier_key, &statement, &proof);
+        assert!(res.is_ok());
+    }
+
+    #[test]
+    fn test_facade_roundtrip_bls12_381() {
+        let mut rng = test_rng();
+
+        let table_values = to_field(&[1, 5, 10, 15, 20, 25, 30, 35]);
+        let (prover_key, verifier_key) =
+            Cq::<Bls12_381, FS>::setup(&table_values, 4, &mut rng).unwrap();
+
+        let witness_values = to_field(&[5, 15, 20, 35]);
+        let (statement, proof) = Cq::<Bls12_381, FS>::prove(&prover_key, &witness_values).unwrap();
+
+        let res = Cq::<Bls12_381, FS>::verify(&verifier_key, &statement, &proof);
+        assert!(res.is_ok());
+
+        let witness_values = to_field(&[5, 15, 20, 36]);
+        assert!(Cq::<Bls12_381, FS>::prove(&prover_key, &witness_values).is_err());
+    }
+
+    #[test]
+    fn test_zk_roundtrip() {
+        let mut rng = test_rng();
+
+        let table = Table::new(&to_field::<Fr>(&[1, 5, 10, 15, 20, 25, 30, 35])).unwrap();
+        let config = CqConfig::<Bn254, FS>::builder()
+            .table_size(8)
+            .witness_size(4)
+            .zk(true)
+            .build()
+            .unwrap();
+        let (prover_key, verifier_key) = config.unsafe_setup(table, &mut rng).unwrap();
+
+        let witness_values = to_field(&[5, 15, 20, 35]);
+        let (statement, proof) =
+            Cq::<Bn254, FS>::prove_zk(&prover_key, &witness_values, &mut rng).unwrap();
+        assert!(Cq::<Bn254, FS>::verify(&verifier_key, &statement, &proof).is_ok());
+
+        // fresh blinders for every proof of the same witness
+        let (_, other) = Cq::<Bn254, FS>::prove_zk(&prover_key, &witness_values, &mut rng).unwrap();
+        assert!(Cq::<Bn254, FS>::verify(&verifier_key, &statement, &other).is_ok());
+        assert_ne!(proof.first_msg.m_cm, other.first_msg.m_cm);
+        assert_ne!(proof.third_msg.a_at_zero, other.third_msg.a_at_zero);
+
+        // the key decides the variant
+        let res = Cq::<Bn254, FS>::prove(&prover_key, &witness_values);
+        assert_eq!(
+            res.err(),
+            Some(Error::Verification(VerificationError::ZkModeMismatch(true)))
+        );
+        let mut tampered = other;
+        tampered.second_msg.g_cm = None;
+        let res = Cq::<Bn254, FS>::verify(&verifier_key, &statement, &tampered);
+        assert_eq!(
+            res,
+            Err(Error::Verification(VerificationError::ZkModeMismatch(true)))
+        );
+    }
+
+    #[test]
+    fn test_hiding_statement() {
+        let mut rng = test_rng();
+
+        let table_values = to_field::<Fr>(&[1, 5, 10, 15, 20, 25, 30, 35]);
+        let (prover_key, verifier_key) =
+            Cq::<Bn254, FS>::setup(&table_values, 4, &mut rng).unwrap();
+
+        let witness_values = to_field(&[5, 15, 20, 35]);
+        let (statement, blinder) =
+            Cq::<Bn254, FS>::commit_hiding(&prover_key, &witness_values, &mut rng).unwrap();
+        let (plain, _) = Cq::<Bn254, FS>::prove(&prover_key, &witness_values).unwrap();
+        assert_ne!(statement.commitment(), plain.commitment());
+
+        let proof = Cq::<Bn254, FS>::prove_hiding(
+            &prover_key,
+            &statement,
+            &witness_values,
+            blinder,
+            &mut rng,
+        )
+        .unwrap();
+        assert!(Cq::<Bn254, FS>::verify(&verifier_key, &statement, &proof).is_ok());
+
+        // the blinder doesn't open the statement to other values
+        let res = Cq::<Bn254, FS>::prove_hiding(
+            &prover_key,
+            &statement,
+            &to_field(&[5, 15, 20, 30]),
+            blinder,
+            &mut rng,
+        );
+        assert_eq!(res.err(), Some(Error::StatementOpeningMismatch));
+    }
+
+    #[test]
+    fn test_zk_range_under_hiding_statement() {
+        let mut rng = test_rng();
+
+        let range = to_field::<Fr>(&[0, 1, 2, 3, 4, 5, 6, 7]);
+        let config = CqConfig::<Bn254, FS>::builder()
+            .table_size(8)
+            .witness_size(4)
+            .zk(true)
+            .build()
+            .unwrap();
+        let (prover_key, verifier_key) = config
+            .unsafe_setup(Table::new(&range).unwrap(), &mut rng)
+            .unwrap();
+
+        let values = to_field(&[3, 3, 0, 7]);
+        let (statement, blinder) =
+            Cq::<Bn254, FS>::commit_hiding(&prover_key, &values, &mut rng).unwrap();
+        let proof =
+            Cq::<Bn254, FS>::prove_hiding(&prover_key, &statement, &values, blinder, &mut rng)
+                .unwrap();
+        assert!(Cq::<Bn254, FS>::verify(&verifier_key, &statement, &proof).is_ok());
+        assert!(proof.second_msg.g_cm.is_some());
+
+        let res = Cq::<Bn254, FS>::prove_hiding(
+            &prover_key,
+            &statement,
+            &to_field(&[3, 3, 0, 7]),
+            StatementBlinder::rand(&mut rng),
+            &mut rng,
+        );
+        assert_eq!(res.err(), Some(Error::StatementOpeningMismatch));
+    }
+
+    #[test]
+    fn test_facade_value_not_in_table() {
+        let mut rng = test_rng();
+
+        let table_values = to_field::<Fr>(&[1, 5, 10, 15, 20, 25, 30, 35]);
+        let (prover_key, _) = Cq::<Bn254, FS>::setup(&table_values, 4, &mut rng).unwrap();
+
+        let witness_values = to_field(&[5, 15, 21, 35]);
+        let res = Cq::<Bn254, FS>::prove(&prover_key, &witness_values);
+        assert_eq!(
+            res.err(),
+            Some(Error::ValueNotInTable(format!("{}", Fr::from(21u64))))
+        );
+    }
+}
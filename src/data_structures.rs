@@ -1,32 +1,144 @@
-use ark_ec::PairingEngine;
-use ark_ff::{FftField, ToBytes};
-use ark_poly::{
-    univariate::DensePolynomial, EvaluationDomain, GeneralEvaluationDomain, UVPolynomial,
-};
+use ark_ec::{AffineCurve, PairingEngine};
+use ark_ff::{FftField, ToBytes, Zero};
+use ark_poly::{univariate::DensePolynomial, EvaluationDomain, Polynomial, UVPolynomial};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{fmt, format, io, iter, rand::RngCore, vec::Vec, UniformRand};
+use zeroize::Zeroize;
 
 use crate::{
     error::Error,
+    kzg::{FixedBaseTables, Kzg, LagrangeSrs},
     prover::{ProverFirstMessage, ProverSecondMessage, ProverThirdMessage},
+    table::Table,
+    utils::domain,
+    PROTOCOL_VERSION,
 };
 
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
 pub struct ProvingKey<E: PairingEngine> {
     pub(crate) srs_g1: Vec<E::G1Affine>,
 }
 
+impl<E: PairingEngine> ProvingKey<E> {
+    pub fn new(srs_g1: Vec<E::G1Affine>) -> Self {
+        Self { srs_g1 }
+    }
+
+    /// Key for a table of `table_size` from a possibly larger srs, keeping x^0..x^(N-1), up to x^(N+1) for zk
+    /// proofs or up to x^(n-1) for witnesses larger than the table. It goes with `VerifierKey::for_table`, which is
+    /// only sound if the powers of the srs above these stay secret; for a public universal srs keep all of it with
+    /// `new` and use `VerifierKey::for_srs_degree`.
+    pub fn for_table(
+        srs_g1: &[E::G1Affine],
+        table_size: usize,
+        witness_size: usize,
+        zk: bool,
+    ) -> Result<Self, Error> {
+        let len = (table_size + if zk { 2 } else { 0 }).max(witness_size);
+        if srs_g1.len() < len {
+            return Err(Error::SrsG1TooSmall(len, srs_g1.len()));
+        }
+        Ok(Self::new(srs_g1[..len].to_vec()))
+    }
+
+    pub fn srs_g1(&self) -> &[E::G1Affine] {
+        &self.srs_g1
+    }
+}
+
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
 pub struct Statement<E: PairingEngine> {
     pub(crate) f: E::G1Affine,
 }
 
+impl<E: PairingEngine> Statement<E> {
+    /// Statement from an existing commitment to the witness polynomial
+    pub fn new(f: E::G1Affine) -> Self {
+        Self { f }
+    }
+
+    /// Statement obtained by committing to the witness with the proving key, hiding if the witness has a
+    /// blinder. A hiding commitment needs the srs to reach x^(n+1) in G1.
+    pub fn from_witness(pk: &ProvingKey<E>, witness: &Witness<E::Fr>) -> Self {
+        Self {
+            f: Kzg::<E>::commit_g1(&pk.srs_g1, witness.committed_f()).into(),
+        }
+    }
+
+    /// Same as `from_witness` with precomputed tables of the proving key's srs
+    pub fn from_witness_with(
+        tables: &FixedBaseTables<E>,
+        witness: &Witness<E::Fr>,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            f: tables.commit_g1(witness.committed_f())?.into(),
+        })
+    }
+
+    /// Commitment to the unblinded witness `values` with the srs in Lagrange form, which skips the ifft
+    pub fn from_evaluations(lagrange: &LagrangeSrs<E>, values: &[E::Fr]) -> Result<Self, Error> {
+        Ok(Self {
+            f: lagrange.commit_evals(values)?.into(),
+        })
+    }
+
+    /// Opens the statement, checks that it commits to the values of `witness` with the witness' blinder
+    pub fn check_opening(&self, pk: &ProvingKey<E>, witness: &Witness<E::Fr>) -> Result<(), Error> {
+        let f = witness.committed_f();
+        if pk.srs_g1.len() <= f.degree() {
+            return Err(Error::SrsG1TooSmall(f.degree() + 1, pk.srs_g1.len()));
+        }
+        if Kzg::<E>::commit_g1(&pk.srs_g1, f) != self.f.into_projective() {
+            return Err(Error::StatementOpeningMismatch);
+        }
+        Ok(())
+    }
+
+    pub fn commitment(&self) -> E::G1Affine {
+        self.f
+    }
+}
+
 impl<E: PairingEngine> ToBytes for Statement<E> {
-    fn write<W: std::io::Write>(&self, mut w: W) -> std::io::Result<()> {
+    fn write<W: io::Write>(&self, mut w: W) -> io::Result<()> {
         self.f.write(&mut w)
     }
 }
 
+/// Randomness of a hiding statement. The statement commits to `f(X) + (r0 + r1·X)·zH(X)`, which takes the
+/// witness values on the witness domain, so proofs need no extra argument and `f(gamma)` stays masked
+#[derive(Clone, Copy, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct StatementBlinder<F: FftField> {
+    pub(crate) r0: F,
+    pub(crate) r1: F,
+}
+
+impl<F: FftField> StatementBlinder<F> {
+    pub fn rand<R: RngCore>(rng: &mut R) -> Self {
+        Self {
+            r0: F::rand(rng),
+            r1: F::rand(rng),
+        }
+    }
+}
+
+/// The blinder is `Copy`, so callers holding one for `prove_hiding` wipe it themselves once the proof is made
+impl<F: FftField> Zeroize for StatementBlinder<F> {
+    fn zeroize(&mut self) {
+        self.r0.zeroize();
+        self.r1.zeroize();
+    }
+}
+
 pub struct Witness<F: FftField> {
     pub(crate) size: usize,
     pub(crate) f: DensePolynomial<F>,
     pub(crate) f_evals: Vec<F>,
+    /// positions of the witness values in the table, when known
+    pub(crate) indices: Option<Vec<usize>>,
+    /// blinder of a hiding statement and the committed polynomial
+    pub(crate) blinder: Option<StatementBlinder<F>>,
+    pub(crate) f_blinded: Option<DensePolynomial<F>>,
 }
 
 impl<F: FftField> Witness<F> {
@@ -35,19 +147,223 @@ impl<F: FftField> Witness<F> {
             return Err(Error::WitnessSizeNotPow2(values.len()));
         }
 
-        let domain = GeneralEvaluationDomain::<F>::new(values.len()).unwrap();
-        let f = DensePolynomial::from_coefficients_slice(&domain.ifft(values));
+        let domain = domain::<F>(values.len())?;
+        let mut coeffs = values.clone();
+        domain.ifft_in_place(&mut coeffs);
 
         Ok(Self {
             size: values.len(),
-            f,
+            f: DensePolynomial::from_coefficients_vec(coeffs),
             f_evals: values.clone(),
+            indices: None,
+            blinder: None,
+            f_blinded: None,
         })
     }
+
+    /// Witness of any nonempty length, padded to the next power of two (at least 2) with copies of its last value
+    pub fn padded(values: &[F]) -> Result<Self, Error> {
+        let last = match values.last() {
+            Some(&last) => last,
+            None => return Err(Error::WitnessSizeTooSmall(0)),
+        };
+        let mut padded = values.to_vec();
+        padded.resize(values.len().next_power_of_two().max(2), last);
+        Self::new(&padded)
+    }
+
+    /// Witness of a statement made hiding with `blinder`, use the same blinder for committing and proving
+    pub fn with_blinder(mut self, blinder: StatementBlinder<F>) -> Self {
+        let n = self.size;
+        let mut coeffs = self.f.coeffs.clone();
+        coeffs.resize(n + 2, F::zero());
+        coeffs[0] -= blinder.r0;
+        coeffs[1] -= blinder.r1;
+        coeffs[n] += blinder.r0;
+        coeffs[n + 1] += blinder.r1;
+
+        self.f_blinded = Some(DensePolynomial::from_coefficients_vec(coeffs));
+        self.blinder = Some(blinder);
+        self
+    }
+
+    /// Polynomial the statement commits to
+    pub(crate) fn committed_f(&self) -> &DensePolynomial<F> {
+        self.f_blinded.as_ref().unwrap_or(&self.f)
+    }
+
+    /// Builds the witness `table[indices[0]], table[indices[1]], ...` and keeps the indices around
+    pub fn from_indices(table: &Table<F>, indices: &[usize]) -> Result<Self, Error> {
+        let values = indices
+            .iter()
+            .map(|&i| {
+                table
+                    .values
+                    .get(i)
+                    .copied()
+                    .ok_or(Error::IndexOutOfTable(i, table.size))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut witness = Self::new(&values)?;
+        witness.indices = Some(indices.to_vec());
+        Ok(witness)
+    }
+
+    /// Witness holding `count` copies of `table[index]` for every `(index, count)`, in that order
+    pub fn from_multiplicities(
+        table: &Table<F>,
+        multiplicities: &[(usize, usize)],
+    ) -> Result<Self, Error> {
+        let indices: Vec<usize> = multiplicities
+            .iter()
+            .flat_map(|&(index, count)| iter::repeat(index).take(count))
+            .collect();
+        Self::from_indices(table, &indices)
+    }
+
+    /// Checks that every value is in `table`, and at its recorded position for a witness from `from_indices`.
+    /// Fails with the position and value of the first value that isn't.
+    pub fn check_table(&self, table: &Table<F>) -> Result<(), Error> {
+        for (i, value) in self.f_evals.iter().enumerate() {
+            let found = match &self.indices {
+                Some(indices) => table.values.get(indices[i]) == Some(value),
+                None => table.value_index_mapping.contains_key(value),
+            };
+            if !found {
+                return Err(Error::WitnessValueNotInTable(i, format!("{}", value)));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn values(&self) -> &[F] {
+        &self.f_evals
+    }
+
+    pub fn indices(&self) -> Option<&[usize]> {
+        self.indices.as_deref()
+    }
+
+    pub fn blinder(&self) -> Option<&StatementBlinder<F>> {
+        self.blinder.as_ref()
+    }
+}
+
+/// Wipes the blinder and the blinded polynomial, from which the blinder follows given `f`
+impl<F: FftField> Drop for Witness<F> {
+    fn drop(&mut self) {
+        self.blinder.zeroize();
+        if let Some(f_blinded) = &mut self.f_blinded {
+            f_blinded.coeffs.zeroize();
+        }
+    }
 }
 
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
 pub struct Proof<E: PairingEngine> {
+    /// `PROTOCOL_VERSION` of the prover
+    pub(crate) version: u8,
     pub(crate) first_msg: ProverFirstMessage<E>,
     pub(crate) second_msg: ProverSecondMessage<E>,
     pub(crate) third_msg: ProverThirdMessage<E>,
 }
+
+impl<E: PairingEngine> Proof<E> {
+    /// Assembles a proof from messages of an interactive run
+    pub fn new(
+        first_msg: ProverFirstMessage<E>,
+        second_msg: ProverSecondMessage<E>,
+        third_msg: ProverThirdMessage<E>,
+    ) -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            first_msg,
+            second_msg,
+            third_msg,
+        }
+    }
+
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    pub fn first_msg(&self) -> &ProverFirstMessage<E> {
+        &self.first_msg
+    }
+
+    pub fn second_msg(&self) -> &ProverSecondMessage<E> {
+        &self.second_msg
+    }
+
+    pub fn third_msg(&self) -> &ProverThirdMessage<E> {
+        &self.third_msg
+    }
+}
+
+/// One `label: 0x<compressed hex>` line per proof element
+impl<E: PairingEngine> fmt::Display for Proof<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn line<T: CanonicalSerialize>(
+            f: &mut fmt::Formatter<'_>,
+            label: &str,
+            value: &T,
+        ) -> fmt::Result {
+            let mut bytes = Vec::with_capacity(value.serialized_size());
+            value.serialize(&mut bytes).map_err(|_| fmt::Error)?;
+            writeln!(f, "{}: 0x{}", label, hex::encode(bytes))
+        }
+
+        let (first, second, third) = (&self.first_msg, &self.second_msg, &self.third_msg);
+        line(f, "m_cm", &first.m_cm)?;
+        line(f, "a_cm", &second.a_cm)?;
+        line(f, "qa_cm", &second.qa_cm)?;
+        line(f, "b0_cm", &second.b0_cm)?;
+        line(f, "qb_cm", &second.qb_cm)?;
+        line(f, "p_cm", &second.p_cm)?;
+        line(f, "b0_at_gamma", &third.b0_at_gamma)?;
+        line(f, "f_at_gamma", &third.f_at_gamma)?;
+        line(f, "a_at_zero", &third.a_at_zero)?;
+        line(f, "pi_gamma", &third.pi_gamma)?;
+        line(f, "a0_cm", &third.a0_cm)
+    }
+}
+
+#[cfg(test)]
+mod witness_tests {
+    use ark_bn254::Fr;
+
+    use crate::{error::Error, table::Table, utils::to_field};
+
+    use super::Witness;
+
+    #[test]
+    fn test_from_indices() {
+        let table = Table::<Fr>::new(&to_field(&[1, 5, 10, 15, 20, 25, 30, 35])).unwrap();
+
+        let witness = Witness::from_indices(&table, &[1, 3, 3, 7]).unwrap();
+        assert_eq!(witness.values(), &to_field::<Fr>(&[5, 15, 15, 35])[..]);
+        assert_eq!(witness.indices(), Some(&[1, 3, 3, 7][..]));
+    }
+
+    #[test]
+    fn test_from_multiplicities() {
+        let table = Table::<Fr>::new(&to_field(&[1, 5, 10, 15, 20, 25, 30, 35])).unwrap();
+
+        let witness = Witness::from_multiplicities(&table, &[(1, 1), (3, 2), (7, 1)]).unwrap();
+        assert_eq!(witness.values(), &to_field::<Fr>(&[5, 15, 15, 35])[..]);
+        assert_eq!(witness.indices(), Some(&[1, 3, 3, 7][..]));
+    }
+
+    #[test]
+    fn test_from_indices_out_of_bounds() {
+        let table = Table::<Fr>::new(&to_field(&[1, 5, 10, 15, 20, 25, 30, 35])).unwrap();
+
+        let res = Witness::from_indices(&table, &[1, 3, 8, 7]);
+        assert_eq!(res.err(), Some(Error::IndexOutOfTable(8, 8)));
+    }
+}
@@ -0,0 +1,386 @@
+//! Lookups into decomposable tables, in the style of Lasso.
+//!
+//! A virtual table `{ sum_i w_i·s_i : s_i in S }` is never materialized, only the subtable `S` is indexed.
+//! Every witness value is split into one limb per weight, each limb column is committed and proven to be in `S`
+//! with cq, and the recombination is a single check on the commitments: the witness polynomial is the weighted
+//! sum of the limb polynomials, so `[f]_1 = sum_i w_i·[f_i]_1`.
+
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{BigInteger, Field, PrimeField};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{format, iter, marker::PhantomData, rand::RngCore, vec::Vec};
+
+use crate::{
+    config::CqConfig,
+    cq::{Cq, CqProverKey, CqVerifierKey},
+    data_structures::{Proof, Statement, StatementBlinder, Witness},
+    error::{Error, VerificationError},
+    kzg::Kzg,
+    rng::FiatShamirRng,
+    table::Table,
+    verifier::{verify_folded, PairingEquation},
+};
+
+/// Virtual table given by a subtable and the weights of its limbs
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct DecomposableTable<F: PrimeField> {
+    pub(crate) subtable: Table<F>,
+    pub(crate) weights: Vec<F>,
+    /// set for `range` tables, whose values are split into limbs automatically
+    pub(crate) limb_bits: Option<u32>,
+}
+
+impl<F: PrimeField> DecomposableTable<F> {
+    pub fn new(subtable: Table<F>, weights: Vec<F>) -> Self {
+        Self {
+            subtable,
+            weights,
+            limb_bits: None,
+        }
+    }
+
+    /// `[0, 2^(limb_bits·limbs))` as `limbs` chunks of `[0, 2^limb_bits)`
+    pub fn range(limb_bits: u32, limbs: usize) -> Result<Self, Error> {
+        let subtable = Table::range(0..1u64 << limb_bits)?;
+        let base = F::from(1u64 << limb_bits);
+        let weights = iter::successors(Some(F::one()), |w| Some(*w * base))
+            .take(limbs)
+            .collect();
+        Ok(Self {
+            subtable,
+            weights,
+            limb_bits: Some(limb_bits),
+        })
+    }
+
+    pub fn limbs(&self) -> usize {
+        self.weights.len()
+    }
+
+    /// Little endian limbs of `value`, only for `range` tables
+    pub fn decompose(&self, value: &F) -> Result<Vec<F>, Error> {
+        let limb_bits = self.limb_bits.ok_or(Error::MissingParameter("limb_bits"))? as usize;
+        let bits = value.into_repr().to_bits_le();
+        if bits.iter().skip(limb_bits * self.limbs()).any(|&bit| bit) {
+            return Err(Error::ValueNotInTable(format!("{}", value)));
+        }
+
+        Ok(bits
+            .chunks(limb_bits)
+            .take(self.limbs())
+            .map(|chunk| {
+                let limb = chunk
+                    .iter()
+                    .rev()
+                    .fold(0u64, |acc, &bit| (acc << 1) | bit as u64);
+                F::from(limb)
+            })
+            .collect())
+    }
+}
+
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct DecomposableProverKey<E: PairingEngine> {
+    /// cq key of the subtable, shared by all limbs
+    pub key: CqProverKey<E>,
+    pub weights: Vec<E::Fr>,
+}
+
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct DecomposableVerifierKey<E: PairingEngine> {
+    pub key: CqVerifierKey<E>,
+    pub weights: Vec<E::Fr>,
+}
+
+/// One statement and one cq proof per limb column
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct DecomposedProof<E: PairingEngine> {
+    pub limb_statements: Vec<Statement<E>>,
+    pub limb_proofs: Vec<Proof<E>>,
+}
+
+pub struct DecomposableCq<E: PairingEngine, FS: FiatShamirRng> {
+    _e: PhantomData<E>,
+    _fs: PhantomData<FS>,
+}
+
+impl<E: PairingEngine, FS: FiatShamirRng> DecomposableCq<E, FS> {
+    /// Indexes the subtable with an srs sampled from `rng`, see `Cq::setup`
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn setup<R: RngCore>(
+        table: DecomposableTable<E::Fr>,
+        witness_size: usize,
+        rng: &mut R,
+    ) -> Result<(DecomposableProverKey<E>, DecomposableVerifierKey<E>), Error> {
+        let config = CqConfig::<E, FS>::builder()
+            .table_size(table.subtable.size)
+            .witness_size(witness_size)
+            .build()?;
+        let (srs_g1, srs_g2) = crate::utils::unsafe_setup_from_rng::<E, R>(
+            config.srs_g1_degree(),
+            config.srs_g2_degree(),
+            rng,
+        );
+        Self::keygen(table, witness_size, srs_g1, &srs_g2)
+    }
+
+    /// Indexes the subtable with an existing srs, commitments of keys sharing an srs can be combined
+    pub fn keygen(
+        table: DecomposableTable<E::Fr>,
+        witness_size: usize,
+        srs_g1: Vec<E::G1Affine>,
+        srs_g2: &[E::G2Affine],
+    ) -> Result<(DecomposableProverKey<E>, DecomposableVerifierKey<E>), Error> {
+        let config = CqConfig::<E, FS>::builder()
+            .table_size(table.subtable.size)
+            .witness_size(witness_size)
+            .build()?;
+        let (pk, vk) = config.keygen(srs_g1, srs_g2, table.subtable)?;
+
+        Ok((
+            DecomposableProverKey {
+                key: pk,
+                weights: table.weights.clone(),
+            },
+            DecomposableVerifierKey {
+                key: vk,
+                weights: table.weights,
+            },
+        ))
+    }
+
+    /// Splits every value with `table.decompose` and proves the limbs
+    pub fn prove(
+        pk: &DecomposableProverKey<E>,
+        table: &DecomposableTable<E::Fr>,
+        witness_values: &[E::Fr],
+    ) -> Result<(Statement<E>, DecomposedProof<E>), Error> {
+        let limb_columns = Self::limb_columns(pk, table, witness_values)?;
+        Self::prove_limbs(pk, &limb_columns)
+    }
+
+    fn limb_columns(
+        pk: &DecomposableProverKey<E>,
+        table: &DecomposableTable<E::Fr>,
+        witness_values: &[E::Fr],
+    ) -> Result<Vec<Vec<E::Fr>>, Error> {
+        let decomposed = witness_values
+            .iter()
+            .map(|value| table.decompose(value))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok((0..pk.weights.len())
+            .map(|i| decomposed.iter().map(|limbs| limbs[i]).collect())
+            .collect())
+    }
+
+    /// Links a hiding statement from `Cq::commit_hiding` under `pk.key` to hiding limb statements: every limb but
+    /// the first gets a fresh blinder and the first absorbs the rest, so `sum_i w_i·[f_i]_1` is still the statement
+    pub fn prove_hiding<R: RngCore>(
+        pk: &DecomposableProverKey<E>,
+        table: &DecomposableTable<E::Fr>,
+        statement: &Statement<E>,
+        witness_values: &[E::Fr],
+        blinder: StatementBlinder<E::Fr>,
+        rng: &mut R,
+    ) -> Result<DecomposedProof<E>, Error> {
+        let limb_columns = Self::limb_columns(pk, table, witness_values)?;
+        let w0_inv = pk.weights[0]
+            .inverse()
+            .ok_or(Error::MissingParameter("weights"))?;
+
+        let mut limb_blinders: Vec<StatementBlinder<E::Fr>> = (1..pk.weights.len())
+            .map(|_| StatementBlinder::rand(rng))
+            .collect();
+        let (r0, r1) = pk.weights[1..]
+            .iter()
+            .zip(&limb_blinders)
+            .fold((blinder.r0, blinder.r1), |(r0, r1), (w, b)| {
+                (r0 - *w * b.r0, r1 - *w * b.r1)
+            });
+        limb_blinders.insert(
+            0,
+            StatementBlinder {
+                r0: r0 * w0_inv,
+                r1: r1 * w0_inv,
+            },
+        );
+
+        let mut limb_statements = Vec::with_capacity(limb_columns.len());
+        let mut limb_proofs = Vec::with_capacity(limb_columns.len());
+        for (column, limb_blinder) in limb_columns.iter().zip(limb_blinders) {
+            let witness = Witness::new(column)?.with_blinder(limb_blinder);
+            let limb_statement = Statement::from_witness(&pk.key.pk, &witness);
+            let proof =
+                Cq::<E, FS>::prove_hiding(&pk.key, &limb_statement, column, limb_blinder, rng)?;
+            limb_statements.push(limb_statement);
+            limb_proofs.push(proof);
+        }
+
+        if recombine(&pk.weights, &limb_statements) != statement.f {
+            return Err(Error::StatementOpeningMismatch);
+        }
+        Ok(DecomposedProof {
+            limb_statements,
+            limb_proofs,
+        })
+    }
+
+    /// Proves limb columns given by the caller, the statement is the commitment to their weighted sum
+    pub fn prove_limbs(
+        pk: &DecomposableProverKey<E>,
+        limb_columns: &[Vec<E::Fr>],
+    ) -> Result<(Statement<E>, DecomposedProof<E>), Error> {
+        if limb_columns.len() != pk.weights.len() {
+            return Err(Error::LimbCountMismatch(
+                pk.weights.len(),
+                limb_columns.len(),
+            ));
+        }
+
+        let mut limb_statements = Vec::with_capacity(limb_columns.len());
+        let mut limb_proofs = Vec::with_capacity(limb_columns.len());
+        for column in limb_columns {
+            let (statement, proof) = Cq::<E, FS>::prove(&pk.key, column)?;
+            limb_statements.push(statement);
+            limb_proofs.push(proof);
+        }
+
+        let statement = Statement::new(recombine(&pk.weights, &limb_statements));
+        Ok((
+            statement,
+            DecomposedProof {
+                limb_statements,
+                limb_proofs,
+            },
+        ))
+    }
+
+    pub fn verify(
+        vk: &DecomposableVerifierKey<E>,
+        statement: &Statement<E>,
+        proof: &DecomposedProof<E>,
+    ) -> Result<(), Error> {
+        verify_folded::<E, FS>(Self::pairing_checks(vk, statement, proof)?)
+    }
+
+    /// The pairing checks of the limb proofs, after checking the recombination
+    pub(crate) fn pairing_checks(
+        vk: &DecomposableVerifierKey<E>,
+        statement: &Statement<E>,
+        proof: &DecomposedProof<E>,
+    ) -> Result<Vec<(PairingEquation<E>, Error)>, Error> {
+        let limbs = proof.limb_statements.len();
+        if limbs != vk.weights.len() || proof.limb_proofs.len() != limbs {
+            return Err(Error::LimbCountMismatch(vk.weights.len(), limbs));
+        }
+        if recombine(&vk.weights, &proof.limb_statements) != statement.f {
+            return Err(Error::RecombinationMismatch);
+        }
+
+        proof
+            .limb_statements
+            .iter()
+            .zip(&proof.limb_proofs)
+            .map(|(limb_statement, limb_proof)| {
+                let equation = Cq::<E, FS>::pairing_equation(&vk.key, limb_statement, limb_proof)?;
+                Ok((
+                    equation,
+                    Error::Verification(VerificationError::BatchedPairingFailed),
+                ))
+            })
+            .collect()
+    }
+}
+
+/// `sum_i w_i·[f_i]_1`
+fn recombine<E: PairingEngine>(weights: &[E::Fr], statements: &[Statement<E>]) -> E::G1Affine {
+    let bases: Vec<E::G1Affine> = statements.iter().map(|statement| statement.f).collect();
+    Kzg::<E>::msm_g1(&bases, weights).into_affine()
+}
+
+#[cfg(test)]
+mod decomposable_tests {
+    use ark_bn254::{Bn254, Fr};
+    use ark_std::test_rng;
+    use rand_chacha::ChaChaRng;
+    use sha3::Keccak256;
+
+    use crate::{
+        cq::Cq,
+        data_structures::{Statement, Witness},
+        error::Error,
+        kzg::Kzg,
+        rng::SimpleHashFiatShamirRng,
+        utils::to_field,
+    };
+
+    use super::{DecomposableCq, DecomposableTable};
+
+    type FS = SimpleHashFiatShamirRng<Keccak256, ChaChaRng>;
+
+    #[test]
+    fn test_range_beyond_subtable() {
+        let mut rng = test_rng();
+
+        // [0, 2^12) from a table of 8 entries
+        let table = DecomposableTable::<Fr>::range(3, 4).unwrap();
+        assert_eq!(
+            table.decompose(&Fr::from(0o7301u64)).unwrap(),
+            to_field::<Fr>(&[1, 0, 3, 7])
+        );
+        let (pk, vk) = DecomposableCq::<Bn254, FS>::setup(
+            DecomposableTable::range(3, 4).unwrap(),
+            4,
+            &mut rng,
+        )
+        .unwrap();
+
+        let values = to_field(&[0, 9, 4000, 4095]);
+        let (statement, proof) = DecomposableCq::<Bn254, FS>::prove(&pk, &table, &values).unwrap();
+        assert!(DecomposableCq::<Bn254, FS>::verify(&vk, &statement, &proof).is_ok());
+
+        // the statement is the plain commitment to the values
+        let witness = Witness::new(&values).unwrap();
+        let direct: Statement<Bn254> =
+            Statement::new(Kzg::<Bn254>::commit_g1(&pk.key.pk.srs_g1, &witness.f).into());
+        assert_eq!(direct.f, statement.f);
+
+        let res = DecomposableCq::<Bn254, FS>::prove(&pk, &table, &to_field(&[0, 9, 4096, 1]));
+        assert_eq!(
+            res.err(),
+            Some(Error::ValueNotInTable(format!("{}", Fr::from(4096u64))))
+        );
+
+        let other = Statement::new(proof.limb_statements[0].f);
+        let res = DecomposableCq::<Bn254, FS>::verify(&vk, &other, &proof);
+        assert_eq!(res, Err(Error::RecombinationMismatch));
+    }
+    #[test]
+    fn test_hiding_recomposition() {
+        let mut rng = test_rng();
+        let table = DecomposableTable::<Fr>::range(3, 4).unwrap();
+        let (pk, vk) = DecomposableCq::<Bn254, FS>::setup(
+            DecomposableTable::range(3, 4).unwrap(),
+            4,
+            &mut rng,
+        )
+        .unwrap();
+
+        // the original commitment is hiding and made without looking at the limbs
+        let values = to_field(&[7, 64, 511, 4095]);
+        let (statement, blinder) =
+            Cq::<Bn254, FS>::commit_hiding(&pk.key, &values, &mut rng).unwrap();
+        let proof = DecomposableCq::<Bn254, FS>::prove_hiding(
+            &pk, &table, &statement, &values, blinder, &mut rng,
+        )
+        .unwrap();
+        assert!(DecomposableCq::<Bn254, FS>::verify(&vk, &statement, &proof).is_ok());
+
+        let (other, _) = Cq::<Bn254, FS>::commit_hiding(&pk.key, &values, &mut rng).unwrap();
+        let res = DecomposableCq::<Bn254, FS>::prove_hiding(
+            &pk, &table, &other, &values, blinder, &mut rng,
+        );
+        assert_eq!(res.err(), Some(Error::StatementOpeningMismatch));
+    }
+}
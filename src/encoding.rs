@@ -0,0 +1,334 @@
+//! Versioned binary encoding of keys and proofs.
+//!
+//! Every encoding starts with an 8 byte header followed by the ark-serialize encoding of the artifact:
+//!
+//! | bytes | content                                                  |
+//! |-------|----------------------------------------------------------|
+//! | 0..4  | magic `b"cqex"`                                          |
+//! | 4     | encoding version, bumped on every message format change  |
+//! | 5     | curve id, see [`CurveId`]                                |
+//! | 6     | artifact kind, see [`ArtifactKind`]                      |
+//! | 7     | 0 for compressed points, 1 for uncompressed points       |
+//!
+//! Compressed encodings are about half the size, uncompressed ones skip the square roots when parsing.
+
+use ark_bls12_377::Bls12_377;
+use ark_bls12_381::Bls12_381;
+use ark_bn254::Bn254;
+use ark_bw6_761::BW6_761;
+use ark_ec::PairingEngine;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{format, string::String, vec::Vec};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+use crate::{
+    data_structures::{Proof, ProvingKey, Statement},
+    error::Error,
+    indexer::{CommonPreprocessedInput, Index},
+    kzg::LagrangeSrs,
+    verifier::VerifierKey,
+};
+
+pub const MAGIC: [u8; 4] = *b"cqex";
+pub const ENCODING_VERSION: u8 = 4;
+pub const HEADER_SIZE: usize = 8;
+
+/// Identifies the pairing engine in encoded artifacts
+pub trait CurveId: PairingEngine {
+    const CURVE_ID: u8;
+}
+
+impl CurveId for Bn254 {
+    const CURVE_ID: u8 = 1;
+}
+
+impl CurveId for Bls12_381 {
+    const CURVE_ID: u8 = 2;
+}
+
+impl CurveId for Bls12_377 {
+    const CURVE_ID: u8 = 3;
+}
+
+impl CurveId for BW6_761 {
+    const CURVE_ID: u8 = 4;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Compression {
+    Compressed = 0,
+    Uncompressed = 1,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArtifactKind {
+    Proof = 1,
+    Statement = 2,
+    VerifierKey = 3,
+    ProvingKey = 4,
+    Index = 5,
+    CommonPreprocessedInput = 6,
+    MappedIndex = 7,
+    LagrangeSrs = 8,
+    Srs = 9,
+}
+
+/// Artifacts with a versioned encoding
+pub trait Artifact: CanonicalSerialize + CanonicalDeserialize {
+    type Engine: CurveId;
+    const KIND: ArtifactKind;
+
+    fn encode(&self, compression: Compression) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + self.serialized_size());
+        bytes.extend_from_slice(&MAGIC);
+        bytes.push(ENCODING_VERSION);
+        bytes.push(Self::Engine::CURVE_ID);
+        bytes.push(Self::KIND as u8);
+        bytes.push(compression as u8);
+
+        let res = match compression {
+            Compression::Compressed => self.serialize(&mut bytes),
+            Compression::Uncompressed => self.serialize_uncompressed(&mut bytes),
+        };
+        res.expect("serializing into a vec can't fail");
+        bytes
+    }
+
+    /// Decodes an artifact, whichever compression it was written with
+    fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() < HEADER_SIZE || bytes[..4] != MAGIC {
+            return Err(Error::InvalidEncoding("missing cqex header"));
+        }
+        if bytes[4] != ENCODING_VERSION {
+            return Err(Error::UnsupportedEncodingVersion(bytes[4]));
+        }
+        if bytes[5] != Self::Engine::CURVE_ID {
+            return Err(Error::CurveMismatch(Self::Engine::CURVE_ID, bytes[5]));
+        }
+        if bytes[6] != Self::KIND as u8 {
+            return Err(Error::ArtifactKindMismatch(Self::KIND as u8, bytes[6]));
+        }
+
+        let mut reader = &bytes[HEADER_SIZE..];
+        let value = match bytes[7] {
+            0 => Self::deserialize(&mut reader),
+            1 => Self::deserialize_uncompressed(&mut reader),
+            _ => return Err(Error::InvalidEncoding("unknown compression flag")),
+        }
+        .map_err(|e| Error::Serialization(format!("{:?}", e)))?;
+
+        if !reader.is_empty() {
+            return Err(Error::InvalidEncoding("trailing bytes"));
+        }
+        // compressed points are decoded on the curve and checked for the subgroup, uncompressed ones only for the
+        // subgroup
+        if bytes[7] == Compression::Uncompressed as u8 {
+            value.check_points()?;
+        }
+        Ok(value)
+    }
+
+    /// Checks that every point is on the curve and in the prime order subgroup, for values that didn't come
+    /// from `decode` or compressed deserialization: decoding the compressed encoding, which checks both, has to
+    /// give the same value back
+    fn check_points(&self) -> Result<(), Error> {
+        let mut compressed = Vec::new();
+        self.serialize(&mut compressed)
+            .expect("serializing into a vec can't fail");
+        let checked = Self::deserialize(&compressed[..]).map_err(|_| Error::InvalidPoint)?;
+        let (mut expected, mut actual) = (Vec::new(), Vec::new());
+        self.serialize_uncompressed(&mut expected)
+            .expect("serializing into a vec can't fail");
+        checked
+            .serialize_uncompressed(&mut actual)
+            .expect("serializing into a vec can't fail");
+        if expected != actual {
+            return Err(Error::InvalidPoint);
+        }
+        Ok(())
+    }
+
+    /// `0x` prefixed hex of the compressed encoding
+    fn to_hex(&self) -> String {
+        format!("0x{}", hex::encode(self.encode(Compression::Compressed)))
+    }
+
+    /// Decodes hex with or without the `0x` prefix
+    fn from_hex(s: &str) -> Result<Self, Error> {
+        let bytes = hex::decode(s.strip_prefix("0x").unwrap_or(s))
+            .map_err(|_| Error::InvalidEncoding("invalid hex"))?;
+        Self::decode(&bytes)
+    }
+
+    /// Padded standard base64 of the compressed encoding
+    fn to_base64(&self) -> String {
+        BASE64.encode(self.encode(Compression::Compressed))
+    }
+
+    fn from_base64(s: &str) -> Result<Self, Error> {
+        let bytes = BASE64
+            .decode(s)
+            .map_err(|_| Error::InvalidEncoding("invalid base64"))?;
+        Self::decode(&bytes)
+    }
+}
+
+impl<E: CurveId> Artifact for Proof<E> {
+    type Engine = E;
+    const KIND: ArtifactKind = ArtifactKind::Proof;
+}
+
+impl<E: CurveId> Artifact for Statement<E> {
+    type Engine = E;
+    const KIND: ArtifactKind = ArtifactKind::Statement;
+}
+
+impl<E: CurveId> Artifact for VerifierKey<E> {
+    type Engine = E;
+    const KIND: ArtifactKind = ArtifactKind::VerifierKey;
+}
+
+impl<E: CurveId> Artifact for ProvingKey<E> {
+    type Engine = E;
+    const KIND: ArtifactKind = ArtifactKind::ProvingKey;
+}
+
+impl<E: CurveId> Artifact for Index<E> {
+    type Engine = E;
+    const KIND: ArtifactKind = ArtifactKind::Index;
+}
+
+impl<E: CurveId> Artifact for CommonPreprocessedInput<E> {
+    type Engine = E;
+    const KIND: ArtifactKind = ArtifactKind::CommonPreprocessedInput;
+}
+
+impl<E: CurveId> Artifact for LagrangeSrs<E> {
+    type Engine = E;
+    const KIND: ArtifactKind = ArtifactKind::LagrangeSrs;
+}
+
+#[cfg(test)]
+mod encoding_tests {
+    use ark_bls12_381::Bls12_381;
+    use ark_bn254::{Bn254, Fq, Fr, G1Affine};
+    use ark_ff::One;
+    use ark_std::test_rng;
+    use rand_chacha::ChaChaRng;
+    use sha3::Keccak256;
+
+    use crate::{
+        cq::Cq,
+        data_structures::{Proof, Statement},
+        error::Error,
+        rng::SimpleHashFiatShamirRng,
+        utils::to_field,
+    };
+
+    use super::{Artifact, Compression, ENCODING_VERSION, HEADER_SIZE};
+
+    type FS = SimpleHashFiatShamirRng<Keccak256, ChaChaRng>;
+
+    #[test]
+    fn test_encoding_roundtrip() {
+        let mut rng = test_rng();
+
+        let table_values = to_field::<Fr>(&[1, 5, 10, 15, 20, 25, 30, 35]);
+        let (prover_key, verifier_key) =
+            Cq::<Bn254, FS>::setup(&table_values, 4, &mut rng).unwrap();
+        let (statement, proof) =
+            Cq::<Bn254, FS>::prove(&prover_key, &to_field(&[5, 15, 20, 35])).unwrap();
+
+        let compressed = proof.encode(Compression::Compressed);
+        let uncompressed = proof.encode(Compression::Uncompressed);
+        assert!(compressed.len() < uncompressed.len());
+
+        for bytes in [compressed, uncompressed.clone()] {
+            let proof = Proof::<Bn254>::decode(&bytes).unwrap();
+            let statement =
+                Statement::<Bn254>::decode(&statement.encode(Compression::Compressed)).unwrap();
+            assert!(Cq::<Bn254, FS>::verify(&verifier_key, &statement, &proof).is_ok());
+        }
+
+        // the version byte, then x and y of m_cm: a flipped bit of y leaves the curve
+        let mut off_curve = uncompressed;
+        off_curve[HEADER_SIZE + 1 + 32] ^= 1;
+        assert!(Proof::<Bn254>::decode(&off_curve).is_err());
+
+        let f = statement.f;
+        let off_curve = Statement::<Bn254>::new(G1Affine::new(f.x, f.y + Fq::one(), false));
+        assert_eq!(off_curve.check_points(), Err(Error::InvalidPoint));
+        assert!(statement.check_points().is_ok());
+    }
+
+    #[test]
+    fn test_header_checks() {
+        let mut rng = test_rng();
+
+        let table_values = to_field::<Fr>(&[1, 5, 10, 15, 20, 25, 30, 35]);
+        let (prover_key, _) = Cq::<Bn254, FS>::setup(&table_values, 4, &mut rng).unwrap();
+        let (statement, _) =
+            Cq::<Bn254, FS>::prove(&prover_key, &to_field(&[5, 15, 20, 35])).unwrap();
+
+        let bytes = statement.encode(Compression::Compressed);
+
+        let res = Proof::<Bn254>::decode(&bytes);
+        assert_eq!(res.err(), Some(Error::ArtifactKindMismatch(1, 2)));
+
+        let mut wrong_version = bytes.clone();
+        wrong_version[4] = ENCODING_VERSION + 1;
+        let res = Statement::<Bn254>::decode(&wrong_version);
+        assert_eq!(
+            res.err(),
+            Some(Error::UnsupportedEncodingVersion(ENCODING_VERSION + 1))
+        );
+
+        let mut wrong_curve = bytes.clone();
+        wrong_curve[5] = 7;
+        let res = Statement::<Bn254>::decode(&wrong_curve);
+        assert_eq!(res.err(), Some(Error::CurveMismatch(1, 7)));
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(Statement::<Bn254>::decode(&trailing).is_err());
+
+        assert!(Statement::<Bn254>::decode(&bytes[..HEADER_SIZE]).is_err());
+
+        let res = Statement::<Bls12_381>::decode(&bytes);
+        assert_eq!(res.err(), Some(Error::CurveMismatch(2, 1)));
+    }
+
+    #[test]
+    fn test_text_encodings() {
+        let mut rng = test_rng();
+
+        let table_values = to_field::<Fr>(&[1, 5, 10, 15, 20, 25, 30, 35]);
+        let (prover_key, verifier_key) =
+            Cq::<Bn254, FS>::setup(&table_values, 4, &mut rng).unwrap();
+        let (statement, proof) =
+            Cq::<Bn254, FS>::prove(&prover_key, &to_field(&[5, 15, 20, 35])).unwrap();
+
+        let hex = proof.to_hex();
+        assert!(hex.starts_with("0x"));
+        let from_hex = Proof::<Bn254>::from_hex(&hex).unwrap();
+        let from_bare_hex = Proof::<Bn254>::from_hex(&hex[2..]).unwrap();
+        let from_base64 = Proof::<Bn254>::from_base64(&proof.to_base64()).unwrap();
+        for proof in [from_hex, from_bare_hex, from_base64] {
+            assert!(Cq::<Bn254, FS>::verify(&verifier_key, &statement, &proof).is_ok());
+        }
+
+        assert_eq!(
+            Proof::<Bn254>::from_hex("0xzz").err(),
+            Some(Error::InvalidEncoding("invalid hex"))
+        );
+        assert_eq!(
+            Proof::<Bn254>::from_base64("not base64!").err(),
+            Some(Error::InvalidEncoding("invalid base64"))
+        );
+
+        let display = proof.to_string();
+        assert_eq!(display.lines().count(), 11);
+        assert!(display.starts_with("m_cm: 0x"));
+        assert!(display.contains("pi_gamma: 0x"));
+    }
+}
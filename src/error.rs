@@ -1,14 +1,325 @@
+use ark_std::string::String;
+
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
 pub enum Error {
+    #[cfg_attr(feature = "std", error("table size {0} is not a power of two"))]
     TableSizeNotPow2(usize),
+    #[cfg_attr(feature = "std", error("witness size {0} is not a power of two"))]
     WitnessSizeNotPow2(usize),
+    #[cfg_attr(feature = "std", error("value {0} appears twice in the table"))]
     DuplicateValueInTable(String),
+    #[cfg_attr(feature = "std", error("value {0} is not in the table"))]
     ValueNotInTable(String),
+    #[cfg_attr(
+        feature = "std",
+        error("witness value {1} at position {0} is not in the table")
+    )]
+    WitnessValueNotInTable(usize, String),
+    #[cfg_attr(
+        feature = "std",
+        error("index {0} is outside of a table of {1} entries")
+    )]
+    IndexOutOfTable(usize, usize),
+    #[cfg_attr(feature = "std", error("entry {0} is padding"))]
+    PaddedEntry(usize),
+    #[cfg_attr(feature = "std", error("all {0} entries of the table hold values"))]
+    TableFull(usize),
+    #[cfg_attr(feature = "std", error("table is not an extension of the base table"))]
+    NotAnExtension,
+
+    #[cfg_attr(feature = "std", error("missing parameter `{0}`"))]
+    MissingParameter(&'static str),
+    #[cfg_attr(feature = "std", error("witness size {0} is below 2"))]
+    WitnessSizeTooSmall(usize),
+    #[cfg_attr(
+        feature = "std",
+        error("witness size {0} is larger than the table size {1}")
+    )]
+    WitnessLargerThanTable(usize, usize),
+    #[cfg_attr(
+        feature = "std",
+        error("table size {0} is above the largest size {1} the scalar field supports")
+    )]
+    TableTooLargeForField(usize, usize),
+    #[cfg_attr(
+        feature = "std",
+        error("fft domain of size {0} is above the largest size {1} the scalar field supports")
+    )]
+    DomainTooLarge(usize, usize),
+    #[cfg_attr(feature = "std", error("expected a table size of {0}, got {1}"))]
+    TableSizeMismatch(usize, usize),
+    #[cfg_attr(feature = "std", error("expected a witness size of {0}, got {1}"))]
+    WitnessSizeMismatch(usize, usize),
+    #[cfg_attr(feature = "std", error("srs needs {0} G1 powers, got {1}"))]
+    SrsG1TooSmall(usize, usize),
+    #[cfg_attr(feature = "std", error("srs needs {0} G2 powers, got {1}"))]
+    SrsG2TooSmall(usize, usize),
+    #[cfg_attr(
+        feature = "std",
+        error("zk keys need an srs of exactly {0} G1 powers, got {1}")
+    )]
+    SrsG1TooLarge(usize, usize),
+    #[cfg_attr(feature = "std", error("keys come from different srs"))]
+    SrsMismatch,
+    #[cfg_attr(feature = "std", error("checkpoint is for another table or srs"))]
+    CheckpointMismatch,
+    #[cfg_attr(
+        feature = "std",
+        error("table values don't match the table commitment")
+    )]
+    TableCommitmentMismatch,
+    #[cfg_attr(feature = "std", error("invalid srs file: {0}"))]
+    InvalidSrsFile(String),
+    #[cfg_attr(
+        feature = "std",
+        error("srs {0} point {1} is not in the prime order subgroup")
+    )]
+    InvalidSrsPoint(&'static str, usize),
+    #[cfg_attr(feature = "std", error("ceremony contribution {0} is invalid"))]
+    InvalidContribution(usize),
+    #[cfg_attr(feature = "std", error("ceremony has no contributions"))]
+    NoContributions,
+    #[cfg_attr(feature = "std", error("{0} threads are not supported by this build"))]
+    UnsupportedParallelism(usize),
+    #[cfg_attr(feature = "std", error("queue of {0} requests is full"))]
+    QueueFull(usize),
+    #[cfg_attr(feature = "std", error("service is stopped"))]
+    ServiceStopped,
+    #[cfg_attr(
+        feature = "std",
+        error("index has no zk commitments, the srs doesn't reach x^(N+1)")
+    )]
+    MissingZkCommitments,
+    #[cfg_attr(
+        feature = "std",
+        error("statement opening doesn't match the commitment")
+    )]
+    StatementOpeningMismatch,
+    #[cfg_attr(feature = "std", error("expected {0} limbs, got {1}"))]
+    LimbCountMismatch(usize, usize),
+    #[cfg_attr(feature = "std", error("expected {0} columns, got {1}"))]
+    ColumnCountMismatch(usize, usize),
+    #[cfg_attr(feature = "std", error("limbs don't recombine to the statement"))]
+    RecombinationMismatch,
+    #[cfg_attr(feature = "std", error("range of at most {0} bits supported, got {1}"))]
+    RangeTooLarge(u32, u32),
+    #[cfg_attr(feature = "std", error("value {0} is out of range"))]
+    ValueOutOfRange(String),
+    #[cfg_attr(feature = "std", error("value {0} is in the table"))]
+    ValueInTable(String),
+    #[cfg_attr(
+        feature = "std",
+        error("value at position {0} is larger than its bound")
+    )]
+    NotLessOrEqual(usize),
+
+    #[cfg_attr(feature = "std", error("io error: {0}"))]
+    Io(String),
+    #[cfg_attr(feature = "std", error("serialization error: {0}"))]
+    Serialization(String),
+    #[cfg_attr(feature = "std", error("invalid table value {1} at position {0}"))]
+    InvalidTableValue(usize, String),
+    #[cfg_attr(feature = "std", error("invalid encoding: {0}"))]
+    InvalidEncoding(&'static str),
+    #[cfg_attr(
+        feature = "std",
+        error("encoded point is not on the curve or not in the prime order subgroup")
+    )]
+    InvalidPoint,
+    #[cfg_attr(feature = "std", error("unsupported encoding version {0}"))]
+    UnsupportedEncodingVersion(u8),
+    #[cfg_attr(feature = "std", error("expected curve id {0}, got {1}"))]
+    CurveMismatch(u8, u8),
+    #[cfg_attr(feature = "std", error("expected artifact kind {0}, got {1}"))]
+    ArtifactKindMismatch(u8, u8),
+    #[cfg_attr(feature = "std", error("test vector mismatch in {0}"))]
+    TestVectorMismatch(&'static str),
+
+    #[cfg_attr(feature = "std", error("proof {0} of the batch is invalid"))]
+    BatchProofFailed(usize),
+    #[cfg_attr(feature = "std", error("batch link failed"))]
+    BatchLinkFailed,
+    #[cfg_attr(feature = "std", error("pedersen link failed"))]
+    PedersenLinkFailed,
+
+    #[cfg_attr(feature = "std", error(transparent))]
+    Verification(VerificationError),
+}
 
+/// Reason `Verifier::verify` rejects a proof. The first two, `MalformedPoint` and `ProofShapeMismatch` mean the
+/// proof is not a proof for this key at all, `GammaInDomain` that its challenge can't be used; the others name
+/// the equation a well formed proof fails.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+pub enum VerificationError {
+    #[cfg_attr(feature = "std", error("unsupported protocol version {0}"))]
+    UnsupportedProtocolVersion(u8),
+    #[cfg_attr(feature = "std", error("key expects zk proofs: {0}"))]
+    ZkModeMismatch(bool),
+    /// named point of the proof or statement off the curve or outside the prime order subgroup
+    #[cfg_attr(
+        feature = "std",
+        error("{0} is not a point of the prime order subgroup")
+    )]
+    MalformedPoint(&'static str),
+    /// optional messages of the proof don't match the key, e.g. a shifted A without a degree bound
+    #[cfg_attr(feature = "std", error("proof doesn't have the shape of the key"))]
+    ProofShapeMismatch,
+    /// the challenge is a point of the witness domain, where Q_B can't be evaluated
+    #[cfg_attr(feature = "std", error("gamma is in the witness domain"))]
+    GammaInDomain,
+    /// `e(A, [T(x)]_2 + beta·[1]_2) = e(Q_A, [zV(x)]_2)·e(M, [1]_2)`
+    #[cfg_attr(feature = "std", error("A is not well formed"))]
+    WellFormednessFailed,
+    /// degree bound checked on the named commitment
+    #[cfg_attr(feature = "std", error("degree bound of {0} failed"))]
+    DegreeBoundFailed(&'static str),
+    /// opening checked with the named opening proof
+    #[cfg_attr(feature = "std", error("opening {0} failed"))]
+    OpeningFailed(&'static str),
+    /// every single equation holds but their batch doesn't
+    #[cfg_attr(feature = "std", error("batched pairing check failed"))]
     BatchedPairingFailed,
+}
+
+impl From<VerificationError> for Error {
+    fn from(err: VerificationError) -> Self {
+        Error::Verification(err)
+    }
+}
+
+// without std thiserror needs `core::error::Error` (rustc 1.81), the messages are spelled out once more here
+#[cfg(not(feature = "std"))]
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::TableSizeNotPow2(a) => write!(f, "table size {0} is not a power of two", a),
+            Error::WitnessSizeNotPow2(a) => write!(f, "witness size {0} is not a power of two", a),
+            Error::DuplicateValueInTable(a) => write!(f, "value {0} appears twice in the table", a),
+            Error::ValueNotInTable(a) => write!(f, "value {0} is not in the table", a),
+            Error::WitnessValueNotInTable(a, b) => write!(
+                f,
+                "witness value {1} at position {0} is not in the table",
+                a, b
+            ),
+            Error::IndexOutOfTable(a, b) => {
+                write!(f, "index {0} is outside of a table of {1} entries", a, b)
+            }
+            Error::PaddedEntry(a) => write!(f, "entry {0} is padding", a),
+            Error::TableFull(a) => write!(f, "all {0} entries of the table hold values", a),
+            Error::NotAnExtension => write!(f, "table is not an extension of the base table"),
+            Error::MissingParameter(a) => write!(f, "missing parameter `{0}`", a),
+            Error::WitnessSizeTooSmall(a) => write!(f, "witness size {0} is below 2", a),
+            Error::WitnessLargerThanTable(a, b) => write!(
+                f,
+                "witness size {0} is larger than the table size {1}",
+                a, b
+            ),
+            Error::TableTooLargeForField(a, b) => write!(
+                f,
+                "table size {0} is above the largest size {1} the scalar field supports",
+                a, b
+            ),
+            Error::DomainTooLarge(a, b) => write!(
+                f,
+                "fft domain of size {0} is above the largest size {1} the scalar field supports",
+                a, b
+            ),
+            Error::TableSizeMismatch(a, b) => {
+                write!(f, "expected a table size of {0}, got {1}", a, b)
+            }
+            Error::WitnessSizeMismatch(a, b) => {
+                write!(f, "expected a witness size of {0}, got {1}", a, b)
+            }
+            Error::SrsG1TooSmall(a, b) => write!(f, "srs needs {0} G1 powers, got {1}", a, b),
+            Error::SrsG2TooSmall(a, b) => write!(f, "srs needs {0} G2 powers, got {1}", a, b),
+            Error::SrsG1TooLarge(a, b) => write!(
+                f,
+                "zk keys need an srs of exactly {0} G1 powers, got {1}",
+                a, b
+            ),
+            Error::SrsMismatch => write!(f, "keys come from different srs"),
+            Error::CheckpointMismatch => write!(f, "checkpoint is for another table or srs"),
+            Error::TableCommitmentMismatch => {
+                write!(f, "table values don't match the table commitment")
+            }
+            Error::InvalidSrsFile(a) => write!(f, "invalid srs file: {0}", a),
+            Error::InvalidSrsPoint(a, b) => write!(
+                f,
+                "srs {0} point {1} is not in the prime order subgroup",
+                a, b
+            ),
+            Error::InvalidContribution(a) => write!(f, "ceremony contribution {0} is invalid", a),
+            Error::NoContributions => write!(f, "ceremony has no contributions"),
+            Error::UnsupportedParallelism(a) => {
+                write!(f, "{0} threads are not supported by this build", a)
+            }
+            Error::QueueFull(a) => write!(f, "queue of {0} requests is full", a),
+            Error::ServiceStopped => write!(f, "service is stopped"),
+            Error::MissingZkCommitments => write!(
+                f,
+                "index has no zk commitments, the srs doesn't reach x^(N+1)"
+            ),
+            Error::StatementOpeningMismatch => {
+                write!(f, "statement opening doesn't match the commitment")
+            }
+            Error::LimbCountMismatch(a, b) => write!(f, "expected {0} limbs, got {1}", a, b),
+            Error::ColumnCountMismatch(a, b) => write!(f, "expected {0} columns, got {1}", a, b),
+            Error::RecombinationMismatch => write!(f, "limbs don't recombine to the statement"),
+            Error::RangeTooLarge(a, b) => {
+                write!(f, "range of at most {0} bits supported, got {1}", a, b)
+            }
+            Error::ValueOutOfRange(a) => write!(f, "value {0} is out of range", a),
+            Error::ValueInTable(a) => write!(f, "value {0} is in the table", a),
+            Error::NotLessOrEqual(a) => {
+                write!(f, "value at position {0} is larger than its bound", a)
+            }
+            Error::Io(a) => write!(f, "io error: {0}", a),
+            Error::Serialization(a) => write!(f, "serialization error: {0}", a),
+            Error::InvalidTableValue(a, b) => {
+                write!(f, "invalid table value {1} at position {0}", a, b)
+            }
+            Error::InvalidEncoding(a) => write!(f, "invalid encoding: {0}", a),
+            Error::InvalidPoint => write!(
+                f,
+                "encoded point is not on the curve or not in the prime order subgroup"
+            ),
+            Error::UnsupportedEncodingVersion(a) => {
+                write!(f, "unsupported encoding version {0}", a)
+            }
+            Error::CurveMismatch(a, b) => write!(f, "expected curve id {0}, got {1}", a, b),
+            Error::ArtifactKindMismatch(a, b) => {
+                write!(f, "expected artifact kind {0}, got {1}", a, b)
+            }
+            Error::TestVectorMismatch(a) => write!(f, "test vector mismatch in {0}", a),
+            Error::BatchProofFailed(a) => write!(f, "proof {0} of the batch is invalid", a),
+            Error::BatchLinkFailed => write!(f, "batch link failed"),
+            Error::PedersenLinkFailed => write!(f, "pedersen link failed"),
+            Error::Verification(err) => core::fmt::Display::fmt(err, f),
+        }
+    }
+}
 
-    Pairing1Failed,
-    Pairing2Failed,
-    Pairing3Failed,
-    Pairing4Failed,
+#[cfg(not(feature = "std"))]
+impl core::fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            VerificationError::UnsupportedProtocolVersion(a) => {
+                write!(f, "unsupported protocol version {0}", a)
+            }
+            VerificationError::ZkModeMismatch(a) => write!(f, "key expects zk proofs: {0}", a),
+            VerificationError::MalformedPoint(a) => {
+                write!(f, "{0} is not a point of the prime order subgroup", a)
+            }
+            VerificationError::ProofShapeMismatch => {
+                write!(f, "proof doesn't have the shape of the key")
+            }
+            VerificationError::GammaInDomain => write!(f, "gamma is in the witness domain"),
+            VerificationError::WellFormednessFailed => write!(f, "A is not well formed"),
+            VerificationError::DegreeBoundFailed(a) => write!(f, "degree bound of {0} failed", a),
+            VerificationError::OpeningFailed(a) => write!(f, "opening {0} failed", a),
+            VerificationError::BatchedPairingFailed => write!(f, "batched pairing check failed"),
+        }
+    }
 }
@@ -0,0 +1,257 @@
+//! Predicted costs of a cq deployment, for capacity planning without running `bench` for every parameter
+//! combination.
+//!
+//! Sizes are exact. Times are the operation counts of indexing, proving and verifying weighted by the unit costs of
+//! a [`CostModel`]; the default costs are single threaded BN254 figures, `CostModel::calibrate` measures them for
+//! the curve and machine at hand.
+
+use ark_ec::{AffineCurve, PairingEngine};
+use ark_ff::Zero;
+use ark_serialize::CanonicalSerialize;
+use ark_std::{mem, time::Duration};
+#[cfg(feature = "std")]
+use ark_std::{rand::RngCore, vec::Vec, UniformRand};
+
+use crate::{
+    data_structures::Proof,
+    prover::{ProverFirstMessage, ProverSecondMessage, ProverThirdMessage},
+};
+
+/// Unit costs in nanoseconds, the defaults are BN254 on one core of a recent x86 machine
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CostModel {
+    /// one G1 scalar multiplication, also the butterfly of a group fft
+    pub g1_mul: f64,
+    /// one base of a large G1 msm
+    pub g1_msm_base: f64,
+    /// one base of a large G2 msm
+    pub g2_msm_base: f64,
+    /// one field multiplication, also the butterfly of a field fft
+    pub field_mul: f64,
+    pub field_inverse: f64,
+    pub miller_loop: f64,
+    pub final_exponentiation: f64,
+}
+
+impl Default for CostModel {
+    fn default() -> Self {
+        Self {
+            g1_mul: 70_000.0,
+            g1_msm_base: 4_500.0,
+            g2_msm_base: 13_000.0,
+            field_mul: 25.0,
+            field_inverse: 3_000.0,
+            miller_loop: 500_000.0,
+            final_exponentiation: 700_000.0,
+        }
+    }
+}
+
+/// Predicted costs of indexing a table of `N` entries and of proving and verifying lookups of `n` values into it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CostEstimate {
+    pub setup: Duration,
+    pub prove: Duration,
+    pub verify: Duration,
+    /// prover heap in bytes, including the srs and the index held in memory
+    pub peak_prover_memory: usize,
+    /// compressed `Index` on disk
+    pub index_bytes: usize,
+    /// compressed `Proof`
+    pub proof_bytes: usize,
+}
+
+/// `CostModel::estimate` with the default unit costs
+pub fn estimate<E: PairingEngine>(table_size: usize, witness_size: usize) -> CostEstimate {
+    CostModel::default().estimate::<E>(table_size, witness_size)
+}
+
+impl CostModel {
+    pub fn estimate<E: PairingEngine>(
+        &self,
+        table_size: usize,
+        witness_size: usize,
+    ) -> CostEstimate {
+        let (big_n, n) = (table_size as f64, witness_size as f64);
+        let log = |size: usize| size.next_power_of_two().trailing_zeros() as f64;
+
+        // the Toeplitz product of Feist–Khovratovich over 2N and the group ffts of h and the Lagrange basis, then one
+        // scaling per quotient, [Li(x)]_1 and [(Li(x) - Li(0)) / x]_1, and [T(x)]_2
+        let group_butterflies = 2.0 * big_n * log(2 * table_size) + big_n * log(table_size);
+        let setup = self.g1_mul * (group_butterflies + 3.0 * big_n) + self.g2_msm_base * big_n;
+
+        // m, A, QA and A0 over at most n distinct entries, B0, QB, P and the opening over n coefficients, four ffts
+        // of size n for B and QB, and the batch inversions of (t_i + beta) and (f_i + beta) at three
+        // multiplications per element and one inverse each
+        let prove = self.g1_msm_base * 8.0 * n
+            + self.field_mul * (2.0 * n * log(witness_size) + 6.0 * n)
+            + self.field_inverse * 2.0;
+
+        // the folded pairing equation has five pairs, plus the scalings of the verifier's linear combination
+        let verify = self.miller_loop * 5.0 + self.final_exponentiation + self.g1_mul * 8.0;
+
+        // the srs and the three vectors of the index, and in round 2 f with its evaluations, B, which becomes B0,
+        // QB and the coset evaluations of B and f
+        let peak_prover_memory = mem::size_of::<E::G1Affine>() * 4 * table_size
+            + mem::size_of::<E::Fr>() * 6 * witness_size;
+
+        // the common input, three vectors of N points and the empty zk commitments, each vector with a u64 length
+        let g1_bytes = E::G1Affine::zero().serialized_size();
+        let g2_bytes = E::G2Affine::zero().serialized_size();
+        let index_bytes = 2 * g2_bytes + 3 * table_size * g1_bytes + 4 * 8;
+
+        CostEstimate {
+            setup: nanos(setup),
+            prove: nanos(prove),
+            verify: nanos(verify),
+            peak_prover_memory,
+            index_bytes,
+            proof_bytes: proof_bytes::<E>(witness_size > table_size),
+        }
+    }
+
+    /// Measures the unit costs of `E` on this machine, with msms of 2^12 bases. Takes a few seconds.
+    #[cfg(feature = "std")]
+    pub fn calibrate<E: PairingEngine, R: RngCore>(rng: &mut R) -> Self {
+        use ark_ec::{msm::VariableBaseMSM, ProjectiveCurve};
+        use ark_ff::{Field, One, PrimeField};
+        use std::{hint::black_box, time::Instant};
+
+        const SIZE: usize = 1 << 12;
+        const REPS: usize = 64;
+        let per = |start: Instant, count: usize| start.elapsed().as_nanos() as f64 / count as f64;
+
+        let scalars: Vec<E::Fr> = (0..SIZE).map(|_| E::Fr::rand(rng)).collect();
+        let mut g1: Vec<E::G1Projective> = (0..SIZE).map(|_| E::G1Projective::rand(rng)).collect();
+        E::G1Projective::batch_normalization(&mut g1);
+        let g1: Vec<E::G1Affine> = g1.iter().map(|p| p.into_affine()).collect();
+        let mut g2: Vec<E::G2Projective> = (0..SIZE).map(|_| E::G2Projective::rand(rng)).collect();
+        E::G2Projective::batch_normalization(&mut g2);
+        let g2: Vec<E::G2Affine> = g2.iter().map(|p| p.into_affine()).collect();
+
+        let start = Instant::now();
+        for (p, s) in g1.iter().zip(&scalars).take(REPS) {
+            black_box(p.mul(*s));
+        }
+        let g1_mul = per(start, REPS);
+
+        let start = Instant::now();
+        let repr: Vec<_> = scalars.iter().map(|s| s.into_repr()).collect();
+        black_box(VariableBaseMSM::multi_scalar_mul(&g1, &repr));
+        let g1_msm_base = per(start, SIZE);
+
+        let start = Instant::now();
+        let repr: Vec<_> = scalars.iter().map(|s| s.into_repr()).collect();
+        black_box(VariableBaseMSM::multi_scalar_mul(&g2, &repr));
+        let g2_msm_base = per(start, SIZE);
+
+        let start = Instant::now();
+        let product = scalars.iter().fold(E::Fr::one(), |acc, s| acc * s);
+        black_box(product);
+        let field_mul = per(start, SIZE);
+
+        let start = Instant::now();
+        for s in scalars.iter().take(REPS) {
+            black_box(s.inverse());
+        }
+        let field_inverse = per(start, REPS);
+
+        let pairs: Vec<(E::G1Prepared, E::G2Prepared)> = g1
+            .iter()
+            .zip(&g2)
+            .take(REPS)
+            .map(|(p, q)| ((*p).into(), (*q).into()))
+            .collect();
+        let start = Instant::now();
+        for pair in &pairs {
+            black_box(E::miller_loop(ark_std::iter::once(pair)));
+        }
+        let miller_loop = per(start, REPS);
+
+        let f = E::miller_loop(&pairs[..1]);
+        let start = Instant::now();
+        for _ in 0..REPS {
+            black_box(E::final_exponentiation(&f));
+        }
+        let final_exponentiation = per(start, REPS);
+
+        Self {
+            g1_mul,
+            g1_msm_base,
+            g2_msm_base,
+            field_mul,
+            field_inverse,
+            miller_loop,
+            final_exponentiation,
+        }
+    }
+}
+
+fn nanos(ns: f64) -> Duration {
+    Duration::from_nanos(ns as u64)
+}
+
+/// Compressed size of a non zk proof, one G1 element more if the witness is larger than the table
+fn proof_bytes<E: PairingEngine>(a_shift: bool) -> usize {
+    let g1 = E::G1Affine::zero();
+    let fr = E::Fr::zero();
+    Proof::new(
+        ProverFirstMessage { m_cm: g1 },
+        ProverSecondMessage {
+            a_cm: g1,
+            qa_cm: g1,
+            b0_cm: g1,
+            qb_cm: g1,
+            p_cm: g1,
+            g_cm: None,
+            a_shift_cm: a_shift.then_some(g1),
+        },
+        ProverThirdMessage {
+            b0_at_gamma: fr,
+            f_at_gamma: fr,
+            a_at_zero: fr,
+            pi_gamma: g1,
+            a0_cm: g1,
+        },
+    )
+    .serialized_size()
+}
+
+#[cfg(test)]
+mod estimate_tests {
+    use ark_bn254::{Bn254, Fr};
+    use ark_serialize::CanonicalSerialize;
+    use ark_std::test_rng;
+    use rand_chacha::ChaChaRng;
+    use sha3::Keccak256;
+
+    use crate::{
+        config::CqConfig, cq::Cq, rng::SimpleHashFiatShamirRng, table::Table, utils::to_field,
+    };
+
+    use super::estimate;
+
+    type FS = SimpleHashFiatShamirRng<Keccak256, ChaChaRng>;
+
+    #[test]
+    fn test_estimate() {
+        let mut rng = test_rng();
+        let table = Table::<Fr>::new(&to_field(&[1, 5, 10, 15, 20, 25, 30, 35])).unwrap();
+        let config = CqConfig::<Bn254, FS>::builder()
+            .table_size(8)
+            .witness_size(4)
+            .build()
+            .unwrap();
+        let (pk, _) = config.unsafe_setup(table, &mut rng).unwrap();
+        let (_, proof) = Cq::<Bn254, FS>::prove(&pk, &to_field(&[5, 15, 20, 35])).unwrap();
+
+        let cost = estimate::<Bn254>(8, 4);
+        assert_eq!(cost.proof_bytes, proof.serialized_size());
+        assert_eq!(cost.index_bytes, pk.index.serialized_size());
+
+        let larger = estimate::<Bn254>(1 << 20, 1 << 10);
+        assert!(larger.setup > cost.setup && larger.prove > cost.prove);
+        assert_eq!(larger.verify, cost.verify);
+        assert!(larger.peak_prover_memory > cost.peak_prover_memory);
+    }
+}
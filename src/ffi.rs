@@ -0,0 +1,418 @@
+//! C interface over the BN254 `Cq` facade, see `include/cqext.h`.
+//!
+//! Keys are opaque handles owned by the caller and released with the matching `_free` function.
+//! Field elements are passed as 32 byte little endian integers, statements and proofs as the
+//! versioned encodings of [`crate::encoding`]. Every function returns a [`CqextStatus`], panics are
+//! caught at the boundary and reported as `CQEXT_PANIC`.
+
+use std::{panic, ptr, slice};
+
+use ark_bn254::{Bn254, Fr};
+use ark_ff::PrimeField;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+#[cfg(any(test, feature = "test-utils"))]
+use ark_std::rand::{rngs::StdRng, SeedableRng};
+use rand_chacha::ChaChaRng;
+use sha3::Keccak256;
+
+use crate::{
+    cq::{Cq, CqProverKey, CqVerifierKey},
+    data_structures::{Proof, Statement},
+    encoding::{Artifact, Compression},
+    rng::SimpleHashFiatShamirRng,
+};
+
+type FS = SimpleHashFiatShamirRng<Keccak256, ChaChaRng>;
+
+/// Size of a field element in the C interface
+pub const CQEXT_FIELD_BYTES: usize = 32;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CqextStatus {
+    Ok = 0,
+    NullPointer = 1,
+    InvalidInput = 2,
+    VerificationFailed = 3,
+    Panic = 4,
+}
+
+pub struct CqextProverKey(CqProverKey<Bn254>);
+
+pub struct CqextVerifierKey(CqVerifierKey<Bn254>);
+
+/// Bytes allocated by the library, release with `cqext_buffer_free`
+#[repr(C)]
+pub struct CqextBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+impl CqextBuffer {
+    fn from_vec(bytes: Vec<u8>) -> Self {
+        let bytes = bytes.into_boxed_slice();
+        let len = bytes.len();
+        Self {
+            data: Box::into_raw(bytes) as *mut u8,
+            len,
+        }
+    }
+}
+
+fn guard(f: impl FnOnce() -> CqextStatus) -> CqextStatus {
+    panic::catch_unwind(panic::AssertUnwindSafe(f)).unwrap_or(CqextStatus::Panic)
+}
+
+unsafe fn bytes<'a>(data: *const u8, len: usize) -> Option<&'a [u8]> {
+    if data.is_null() {
+        return if len == 0 { Some(&[]) } else { None };
+    }
+    Some(slice::from_raw_parts(data, len))
+}
+
+/// Reads `len` field elements, rejecting non canonical encodings
+unsafe fn field_elements(data: *const u8, len: usize) -> Result<Vec<Fr>, CqextStatus> {
+    let bytes = bytes(data, len * CQEXT_FIELD_BYTES).ok_or(CqextStatus::NullPointer)?;
+    bytes
+        .chunks(CQEXT_FIELD_BYTES)
+        .map(|chunk| {
+            let value = Fr::from_le_bytes_mod_order(chunk);
+            let mut canonical = vec![];
+            value.serialize(&mut canonical).unwrap();
+            if canonical == chunk {
+                Ok(value)
+            } else {
+                Err(CqextStatus::InvalidInput)
+            }
+        })
+        .collect()
+}
+
+/// Samples an insecure srs from `seed` and indexes the table for witnesses of `witness_size`, only built with the
+/// `test-utils` feature
+///
+/// # Safety
+/// `table` must point to `table_len` field elements, `pk_out` and `vk_out` must be valid for writes.
+#[cfg(any(test, feature = "test-utils"))]
+#[no_mangle]
+pub unsafe extern "C" fn cqext_setup(
+    table: *const u8,
+    table_len: usize,
+    witness_size: usize,
+    seed: u64,
+    pk_out: *mut *mut CqextProverKey,
+    vk_out: *mut *mut CqextVerifierKey,
+) -> CqextStatus {
+    guard(|| {
+        if pk_out.is_null() || vk_out.is_null() {
+            return CqextStatus::NullPointer;
+        }
+        let table_values = match field_elements(table, table_len) {
+            Ok(values) => values,
+            Err(status) => return status,
+        };
+        let mut rng = StdRng::seed_from_u64(seed);
+        match Cq::<Bn254, FS>::setup(&table_values, witness_size, &mut rng) {
+            Ok((pk, vk)) => {
+                *pk_out = Box::into_raw(Box::new(CqextProverKey(pk)));
+                *vk_out = Box::into_raw(Box::new(CqextVerifierKey(vk)));
+                CqextStatus::Ok
+            }
+            Err(_) => CqextStatus::InvalidInput,
+        }
+    })
+}
+
+/// Proves that all `witness_len` witness values are in the table
+///
+/// # Safety
+/// `pk` must come from `cqext_setup` or `cqext_prover_key_from_bytes`, `witness` must point to
+/// `witness_len` field elements and the outputs must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn cqext_prove(
+    pk: *const CqextProverKey,
+    witness: *const u8,
+    witness_len: usize,
+    statement_out: *mut CqextBuffer,
+    proof_out: *mut CqextBuffer,
+) -> CqextStatus {
+    guard(|| {
+        if pk.is_null() || statement_out.is_null() || proof_out.is_null() {
+            return CqextStatus::NullPointer;
+        }
+        let witness_values = match field_elements(witness, witness_len) {
+            Ok(values) => values,
+            Err(status) => return status,
+        };
+        match Cq::<Bn254, FS>::prove(&(*pk).0, &witness_values) {
+            Ok((statement, proof)) => {
+                *statement_out = CqextBuffer::from_vec(statement.encode(Compression::Compressed));
+                *proof_out = CqextBuffer::from_vec(proof.encode(Compression::Compressed));
+                CqextStatus::Ok
+            }
+            Err(_) => CqextStatus::InvalidInput,
+        }
+    })
+}
+
+/// # Safety
+/// `vk` must come from `cqext_setup` or `cqext_verifier_key_from_bytes`, the buffers must be valid
+/// for reads of the given lengths.
+#[no_mangle]
+pub unsafe extern "C" fn cqext_verify(
+    vk: *const CqextVerifierKey,
+    statement: *const u8,
+    statement_len: usize,
+    proof: *const u8,
+    proof_len: usize,
+) -> CqextStatus {
+    guard(|| {
+        if vk.is_null() {
+            return CqextStatus::NullPointer;
+        }
+        let (statement, proof) = match (bytes(statement, statement_len), bytes(proof, proof_len)) {
+            (Some(statement), Some(proof)) => (statement, proof),
+            _ => return CqextStatus::NullPointer,
+        };
+        let (statement, proof) = match (
+            Statement::<Bn254>::decode(statement),
+            Proof::<Bn254>::decode(proof),
+        ) {
+            (Ok(statement), Ok(proof)) => (statement, proof),
+            _ => return CqextStatus::InvalidInput,
+        };
+        match Cq::<Bn254, FS>::verify(&(*vk).0, &statement, &proof) {
+            Ok(()) => CqextStatus::Ok,
+            Err(_) => CqextStatus::VerificationFailed,
+        }
+    })
+}
+
+unsafe fn key_to_bytes<T: CanonicalSerialize>(key: &T, out: *mut CqextBuffer) -> CqextStatus {
+    let mut bytes = Vec::with_capacity(key.serialized_size());
+    key.serialize(&mut bytes)
+        .expect("serializing into a vec can't fail");
+    *out = CqextBuffer::from_vec(bytes);
+    CqextStatus::Ok
+}
+
+unsafe fn key_from_bytes<T: CanonicalDeserialize>(
+    data: *const u8,
+    len: usize,
+) -> Result<T, CqextStatus> {
+    let mut reader = bytes(data, len).ok_or(CqextStatus::NullPointer)?;
+    let key = T::deserialize(&mut reader).map_err(|_| CqextStatus::InvalidInput)?;
+    if !reader.is_empty() {
+        return Err(CqextStatus::InvalidInput);
+    }
+    Ok(key)
+}
+
+/// # Safety
+/// `pk` must be a live prover key and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn cqext_prover_key_to_bytes(
+    pk: *const CqextProverKey,
+    out: *mut CqextBuffer,
+) -> CqextStatus {
+    guard(|| {
+        if pk.is_null() || out.is_null() {
+            return CqextStatus::NullPointer;
+        }
+        key_to_bytes(&(*pk).0, out)
+    })
+}
+
+/// # Safety
+/// `data` must be valid for reads of `len` bytes and `pk_out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn cqext_prover_key_from_bytes(
+    data: *const u8,
+    len: usize,
+    pk_out: *mut *mut CqextProverKey,
+) -> CqextStatus {
+    guard(|| {
+        if pk_out.is_null() {
+            return CqextStatus::NullPointer;
+        }
+        match key_from_bytes(data, len) {
+            Ok(pk) => {
+                *pk_out = Box::into_raw(Box::new(CqextProverKey(pk)));
+                CqextStatus::Ok
+            }
+            Err(status) => status,
+        }
+    })
+}
+
+/// # Safety
+/// `vk` must be a live verifier key and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn cqext_verifier_key_to_bytes(
+    vk: *const CqextVerifierKey,
+    out: *mut CqextBuffer,
+) -> CqextStatus {
+    guard(|| {
+        if vk.is_null() || out.is_null() {
+            return CqextStatus::NullPointer;
+        }
+        key_to_bytes(&(*vk).0, out)
+    })
+}
+
+/// # Safety
+/// `data` must be valid for reads of `len` bytes and `vk_out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn cqext_verifier_key_from_bytes(
+    data: *const u8,
+    len: usize,
+    vk_out: *mut *mut CqextVerifierKey,
+) -> CqextStatus {
+    guard(|| {
+        if vk_out.is_null() {
+            return CqextStatus::NullPointer;
+        }
+        match key_from_bytes(data, len) {
+            Ok(vk) => {
+                *vk_out = Box::into_raw(Box::new(CqextVerifierKey(vk)));
+                CqextStatus::Ok
+            }
+            Err(status) => status,
+        }
+    })
+}
+
+/// # Safety
+/// `pk` must be null or a key that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn cqext_prover_key_free(pk: *mut CqextProverKey) {
+    if !pk.is_null() {
+        drop(Box::from_raw(pk));
+    }
+}
+
+/// # Safety
+/// `vk` must be null or a key that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn cqext_verifier_key_free(vk: *mut CqextVerifierKey) {
+    if !vk.is_null() {
+        drop(Box::from_raw(vk));
+    }
+}
+
+/// # Safety
+/// `buffer` must have been returned by this library and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn cqext_buffer_free(buffer: CqextBuffer) {
+    if !buffer.data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+            buffer.data,
+            buffer.len,
+        )));
+    }
+}
+
+#[cfg(test)]
+mod ffi_tests {
+    use std::ptr;
+
+    use ark_bn254::Fr;
+    use ark_serialize::CanonicalSerialize;
+
+    use crate::utils::to_field;
+
+    use super::{
+        cqext_buffer_free, cqext_prove, cqext_prover_key_free, cqext_setup,
+        cqext_verifier_key_free, cqext_verifier_key_from_bytes, cqext_verifier_key_to_bytes,
+        cqext_verify, CqextBuffer, CqextStatus, CQEXT_FIELD_BYTES,
+    };
+
+    fn encode(values: &[u64]) -> Vec<u8> {
+        let mut bytes = vec![];
+        for value in to_field::<Fr>(values) {
+            value.serialize(&mut bytes).unwrap();
+        }
+        bytes
+    }
+
+    fn empty() -> CqextBuffer {
+        CqextBuffer {
+            data: ptr::null_mut(),
+            len: 0,
+        }
+    }
+
+    #[test]
+    fn test_ffi_roundtrip() {
+        let table = encode(&[1, 5, 10, 15, 20, 25, 30, 35]);
+        let witness = encode(&[5, 15, 20, 35]);
+
+        unsafe {
+            let mut pk = ptr::null_mut();
+            let mut vk = ptr::null_mut();
+            let status = cqext_setup(table.as_ptr(), 8, 4, 42, &mut pk, &mut vk);
+            assert_eq!(status, CqextStatus::Ok);
+
+            let (mut statement, mut proof) = (empty(), empty());
+            let status = cqext_prove(pk, witness.as_ptr(), 4, &mut statement, &mut proof);
+            assert_eq!(status, CqextStatus::Ok);
+
+            // the verifier key survives a round trip through bytes
+            let mut vk_bytes = empty();
+            assert_eq!(
+                cqext_verifier_key_to_bytes(vk, &mut vk_bytes),
+                CqextStatus::Ok
+            );
+            let mut vk_copy = ptr::null_mut();
+            let status = cqext_verifier_key_from_bytes(vk_bytes.data, vk_bytes.len, &mut vk_copy);
+            assert_eq!(status, CqextStatus::Ok);
+
+            let status = cqext_verify(
+                vk_copy,
+                statement.data,
+                statement.len,
+                proof.data,
+                proof.len,
+            );
+            assert_eq!(status, CqextStatus::Ok);
+
+            *proof.data.add(proof.len - 1) ^= 1;
+            let status = cqext_verify(vk, statement.data, statement.len, proof.data, proof.len);
+            assert_ne!(status, CqextStatus::Ok);
+
+            cqext_buffer_free(statement);
+            cqext_buffer_free(proof);
+            cqext_buffer_free(vk_bytes);
+            cqext_prover_key_free(pk);
+            cqext_verifier_key_free(vk);
+            cqext_verifier_key_free(vk_copy);
+        }
+    }
+
+    #[test]
+    fn test_ffi_rejects_bad_input() {
+        let table = encode(&[1, 5, 10, 15, 20, 25, 30, 35]);
+        let witness = encode(&[5, 15, 20, 36]);
+
+        unsafe {
+            let mut pk = ptr::null_mut();
+            let mut vk = ptr::null_mut();
+            let status = cqext_setup(table.as_ptr(), 8, 4, 42, ptr::null_mut(), &mut vk);
+            assert_eq!(status, CqextStatus::NullPointer);
+
+            let status = cqext_setup(table.as_ptr(), 8, 4, 42, &mut pk, &mut vk);
+            assert_eq!(status, CqextStatus::Ok);
+
+            let (mut statement, mut proof) = (empty(), empty());
+            let status = cqext_prove(pk, witness.as_ptr(), 4, &mut statement, &mut proof);
+            assert_eq!(status, CqextStatus::InvalidInput);
+
+            let not_canonical = [0xffu8; CQEXT_FIELD_BYTES * 4];
+            let status = cqext_prove(pk, not_canonical.as_ptr(), 4, &mut statement, &mut proof);
+            assert_eq!(status, CqextStatus::InvalidInput);
+
+            cqext_prover_key_free(pk);
+            cqext_verifier_key_free(vk);
+        }
+    }
+}
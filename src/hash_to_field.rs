@@ -0,0 +1,165 @@
+//! RFC 9380 `hash_to_field`, used to turn transcript states into challenges.
+//!
+//! Each element is `L = ceil((ceil(log2(p)) + 128) / 8)` uniform bytes reduced modulo `p`, so its
+//! distance from uniform is below 2^-128, and the construction is specified precisely enough to be
+//! replayed by verifiers in other languages.
+
+use ark_ff::{FpParameters, PrimeField};
+use ark_std::{vec, vec::Vec};
+use sha3::digest::{core_api::BlockSizeUser, Digest, ExtendableOutput, Update, XofReader};
+
+/// Security parameter `k` of the RFC
+const SECURITY_BITS: usize = 128;
+
+fn dst_prime(dst: &[u8]) -> Vec<u8> {
+    assert!(
+        dst.len() <= 255,
+        "domain separation tag longer than 255 bytes"
+    );
+    [dst, &[dst.len() as u8]].concat()
+}
+
+/// `expand_message_xmd` of section 5.3.1
+pub fn expand_message_xmd<D: Digest + BlockSizeUser>(
+    msg: &[u8],
+    dst: &[u8],
+    len: usize,
+) -> Vec<u8> {
+    let b_in_bytes = <D as Digest>::output_size();
+    let ell = (len + b_in_bytes - 1) / b_in_bytes;
+    assert!(ell <= 255 && len <= 65535, "requested too many bytes");
+    let dst_prime = dst_prime(dst);
+
+    let b_0 = D::new()
+        .chain_update(vec![0u8; D::block_size()])
+        .chain_update(msg)
+        .chain_update((len as u16).to_be_bytes())
+        .chain_update([0u8])
+        .chain_update(&dst_prime)
+        .finalize();
+
+    let mut uniform_bytes = Vec::with_capacity(ell * b_in_bytes);
+    let mut b_i = D::new()
+        .chain_update(&b_0)
+        .chain_update([1u8])
+        .chain_update(&dst_prime)
+        .finalize();
+    uniform_bytes.extend_from_slice(&b_i);
+    for i in 2..=ell {
+        let xored: Vec<u8> = b_0.iter().zip(b_i.iter()).map(|(a, b)| a ^ b).collect();
+        b_i = D::new()
+            .chain_update(xored)
+            .chain_update([i as u8])
+            .chain_update(&dst_prime)
+            .finalize();
+        uniform_bytes.extend_from_slice(&b_i);
+    }
+    uniform_bytes.truncate(len);
+    uniform_bytes
+}
+
+/// `expand_message_xof` of section 5.3.2
+pub fn expand_message_xof<X: ExtendableOutput + Update + Default>(
+    msg: &[u8],
+    dst: &[u8],
+    len: usize,
+) -> Vec<u8> {
+    assert!(len <= 65535, "requested too many bytes");
+    let mut xof = X::default();
+    xof.update(msg);
+    xof.update(&(len as u16).to_be_bytes());
+    xof.update(&dst_prime(dst));
+    let mut uniform_bytes = vec![0u8; len];
+    xof.finalize_xof().read(&mut uniform_bytes);
+    uniform_bytes
+}
+
+/// Number of uniform bytes `L` reduced into one element of `F`
+pub fn element_length<F: PrimeField>() -> usize {
+    (F::Params::MODULUS_BITS as usize + SECURITY_BITS + 7) / 8
+}
+
+fn reduce<F: PrimeField>(uniform_bytes: &[u8], count: usize) -> Vec<F> {
+    uniform_bytes
+        .chunks(element_length::<F>())
+        .take(count)
+        .map(F::from_be_bytes_mod_order)
+        .collect()
+}
+
+/// `hash_to_field` with `expand_message_xmd` over `D`
+pub fn hash_to_field<F: PrimeField, D: Digest + BlockSizeUser>(
+    msg: &[u8],
+    dst: &[u8],
+    count: usize,
+) -> Vec<F> {
+    reduce(
+        &expand_message_xmd::<D>(msg, dst, count * element_length::<F>()),
+        count,
+    )
+}
+
+/// `hash_to_field` with `expand_message_xof` over `X`
+pub fn hash_to_field_xof<F: PrimeField, X: ExtendableOutput + Update + Default>(
+    msg: &[u8],
+    dst: &[u8],
+    count: usize,
+) -> Vec<F> {
+    reduce(
+        &expand_message_xof::<X>(msg, dst, count * element_length::<F>()),
+        count,
+    )
+}
+
+#[cfg(test)]
+mod hash_to_field_tests {
+    use ark_bn254::Fr;
+    use ark_ff::PrimeField;
+    use sha2::Sha256;
+    use sha3::Shake128;
+
+    use super::{element_length, expand_message_xmd, expand_message_xof, hash_to_field};
+
+    // appendix K.1 and K.6 of RFC 9380
+    #[test]
+    fn test_rfc_vectors() {
+        let dst = b"QUUX-V01-CS02-with-expander-SHA256-128";
+        assert_eq!(
+            hex::encode(expand_message_xmd::<Sha256>(b"", dst, 0x20)),
+            "68a985b87eb6b46952128911f2a4412bbc302a9d759667f87f7a21d803f07235"
+        );
+        assert_eq!(
+            hex::encode(expand_message_xmd::<Sha256>(b"abc", dst, 0x20)),
+            "d8ccab23b5985ccea865c6c97b6e5b8350e794e603b4b97902f53a8a0d605615"
+        );
+        assert_eq!(
+            hex::encode(expand_message_xmd::<Sha256>(b"", dst, 0x80)),
+            "af84c27ccfd45d41914fdff5df25293e221afc53d8ad2ac06d5e3e29485dadbe\
+             e0d121587713a3e0dd4d5e69e93eb7cd4f5df4cd103e188cf60cb02edc3edf18\
+             eda8576c412b18ffb658e3dd6ec849469b979d444cf7b26911a08e63cf31f9dc\
+             c541708d3491184472c2c29bb749d4286b004ceb5ee6b9a7fa5b646c993f0ced"
+        );
+
+        let dst = b"QUUX-V01-CS02-with-expander-SHAKE128";
+        assert_eq!(
+            hex::encode(expand_message_xof::<Shake128>(b"", dst, 0x20)),
+            "86518c9cd86581486e9485aa74ab35ba150d1c75c88e26b7043e44e2acd735a2"
+        );
+        assert_eq!(
+            hex::encode(expand_message_xof::<Shake128>(b"abc", dst, 0x20)),
+            "8696af52a4d862417c0763556073f47bc9b9ba43c99b505305cb1ec04a9ab468"
+        );
+    }
+
+    #[test]
+    fn test_hash_to_field() {
+        assert_eq!(element_length::<Fr>(), 48);
+
+        let elements = hash_to_field::<Fr, Sha256>(b"msg", b"dst", 2);
+        let bytes = expand_message_xmd::<Sha256>(b"msg", b"dst", 96);
+        assert_eq!(elements.len(), 2);
+        assert_eq!(elements[0], Fr::from_be_bytes_mod_order(&bytes[..48]));
+        assert_eq!(elements[1], Fr::from_be_bytes_mod_order(&bytes[48..]));
+        assert_ne!(elements[0], elements[1]);
+    }
+}
@@ -1,40 +1,183 @@
-use ark_ec::{AffineCurve, PairingEngine};
-use ark_ff::{Field, ToBytes};
-use ark_poly::{
-    univariate::DensePolynomial, EvaluationDomain, GeneralEvaluationDomain, UVPolynomial,
-};
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{batch_inversion, Field, One, PrimeField, ToBytes, Zero};
+use ark_poly::{univariate::DensePolynomial, EvaluationDomain, UVPolynomial};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use ark_std::{cfg_iter, io, vec, vec::Vec};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 use crate::{
-    kzg::Kzg,
+    error::Error,
+    kzg::{Kzg, LagrangeSrs},
     table::Table,
-    tools::{compute_lagrange_basis_commitments, compute_qs},
-    utils::is_pow_2,
+    tools::{compute_lagrange_basis_commitments, compute_qs, compute_qs_naive},
+    utils::{domain, is_pow_2},
 };
 
 pub struct CommonPreprocessedInput<E: PairingEngine> {
     pub(crate) zv_2: E::G2Affine,
     pub(crate) t_2: E::G2Affine,
+    /// [zV(x)]_2 and [T(x)]_2 prepared once for all proofs
+    pub(crate) zv: E::G2Prepared,
+    pub(crate) t: E::G2Prepared,
+}
+
+impl<E: PairingEngine> CommonPreprocessedInput<E> {
+    pub(crate) fn new(zv_2: E::G2Affine, t_2: E::G2Affine) -> Self {
+        Self {
+            zv_2,
+            t_2,
+            zv: zv_2.into(),
+            t: t_2.into(),
+        }
+    }
+
+    /// Common input of a table the verifier knows only by its commitment [T(x)]_2, e.g. published by the
+    /// table owner: [zV(x)]_2 only depends on the size N
+    pub fn from_commitment(
+        srs_g2: &[E::G2Affine],
+        table_size: usize,
+        t_2: E::G2Affine,
+    ) -> Result<Self, Error> {
+        if !is_pow_2(table_size) {
+            return Err(Error::TableSizeNotPow2(table_size));
+        }
+        if srs_g2.len() < table_size + 1 {
+            return Err(Error::SrsG2TooSmall(table_size + 1, srs_g2.len()));
+        }
+        let zv_2 = srs_g2[table_size] + -E::G2Affine::prime_subgroup_generator();
+        Ok(Self::new(zv_2, t_2))
+    }
+
+    /// [T(x)]_2
+    pub fn table_commitment(&self) -> &E::G2Affine {
+        &self.t_2
+    }
+
+    /// Checks that `table` is the committed table, recomputing its commitment with about N G2 multiplications
+    pub fn check_table(&self, srs_g2: &[E::G2Affine], table: &Table<E::Fr>) -> Result<(), Error> {
+        let common = Index::<E>::compute_common(srs_g2, table)?;
+        if common.zv_2 != self.zv_2 || common.t_2 != self.t_2 {
+            return Err(Error::TableCommitmentMismatch);
+        }
+        Ok(())
+    }
 }
 
 impl<E: PairingEngine> ToBytes for CommonPreprocessedInput<E> {
-    fn write<W: std::io::Write>(&self, mut w: W) -> std::io::Result<()> {
+    fn write<W: io::Write>(&self, mut w: W) -> io::Result<()> {
         self.zv_2.write(&mut w)?;
         self.t_2.write(&mut w)
     }
 }
 
+// only the affine points are encoded, the prepared point is recomputed on deserialization
+impl<E: PairingEngine> CanonicalSerialize for CommonPreprocessedInput<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.zv_2.serialize(&mut writer)?;
+        self.t_2.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.zv_2.serialized_size() + self.t_2.serialized_size()
+    }
+
+    fn serialize_uncompressed<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.zv_2.serialize_uncompressed(&mut writer)?;
+        self.t_2.serialize_uncompressed(&mut writer)
+    }
+
+    fn uncompressed_size(&self) -> usize {
+        self.zv_2.uncompressed_size() + self.t_2.uncompressed_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for CommonPreprocessedInput<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let zv_2 = E::G2Affine::deserialize(&mut reader)?;
+        let t_2 = E::G2Affine::deserialize(&mut reader)?;
+        Ok(Self::new(zv_2, t_2))
+    }
+
+    fn deserialize_uncompressed<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let zv_2 = E::G2Affine::deserialize_uncompressed(&mut reader)?;
+        let t_2 = E::G2Affine::deserialize_uncompressed(&mut reader)?;
+        Ok(Self::new(zv_2, t_2))
+    }
+}
+
+/// How the indexer computes the cached quotients [Qi(x)]_1
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuotientAlgorithm {
+    /// Feist–Khovratovich, O(N log N) group operations
+    Fk,
+    /// one division and one commitment per table element, O(N^2)
+    Naive,
+}
+
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
 pub struct Index<E: PairingEngine> {
     pub(crate) common: CommonPreprocessedInput<E>,
     pub(crate) qs: Vec<E::G1Affine>,
     pub(crate) ls: Vec<E::G1Affine>,
     pub(crate) ls_at_0: Vec<E::G1Affine>,
+    /// [T(x)]_1 and [x·T(x)]_1 for blinding zk proofs, empty unless the srs reaches x^(N+1)
+    pub(crate) zk_t: Vec<E::G1Affine>,
 }
 
 impl<E: PairingEngine> Index<E> {
-    pub fn gen(srs_g1: &[E::G1Affine], srs_g2: &[E::G2Affine], table: &Table<E::Fr>) -> Self {
-        assert!(is_pow_2(table.size));
-        let domain = GeneralEvaluationDomain::<E::Fr>::new(table.size).unwrap();
-        let n = domain.size(); // same as table.size
+    /// Powers of tau in `srs_g1` beyond x^(N-1) are only used for the commitments of zk proofs. Fails if the srs
+    /// doesn't reach x^(N-1) in G1 and x^N in G2.
+    pub fn gen(
+        srs_g1: &[E::G1Affine],
+        srs_g2: &[E::G2Affine],
+        table: &Table<E::Fr>,
+    ) -> Result<Self, Error> {
+        Self::gen_with(srs_g1, srs_g2, table, QuotientAlgorithm::Fk)
+    }
+
+    pub fn gen_with(
+        srs_g1: &[E::G1Affine],
+        srs_g2: &[E::G2Affine],
+        table: &Table<E::Fr>,
+        quotients: QuotientAlgorithm,
+    ) -> Result<Self, Error> {
+        Self::gen_from(srs_g1, srs_g2, table, quotients, None)
+    }
+
+    /// Same as `gen` with [Li(x)]_1 taken from a precomputed `lagrange` over the table domain
+    pub fn gen_with_lagrange(
+        srs_g1: &[E::G1Affine],
+        srs_g2: &[E::G2Affine],
+        table: &Table<E::Fr>,
+        lagrange: &LagrangeSrs<E>,
+    ) -> Result<Self, Error> {
+        if lagrange.domain_size() != table.size {
+            return Err(Error::TableSizeMismatch(table.size, lagrange.domain_size()));
+        }
+        Self::gen_from(srs_g1, srs_g2, table, QuotientAlgorithm::Fk, Some(lagrange))
+    }
+
+    fn gen_from(
+        srs_g1: &[E::G1Affine],
+        srs_g2: &[E::G2Affine],
+        table: &Table<E::Fr>,
+        quotients: QuotientAlgorithm,
+        lagrange: Option<&LagrangeSrs<E>>,
+    ) -> Result<Self, Error> {
+        let n = table.size;
+        if !is_pow_2(n) {
+            return Err(Error::TableSizeNotPow2(n));
+        }
+        if srs_g1.len() < n {
+            return Err(Error::SrsG1TooSmall(n, srs_g1.len()));
+        }
+        if srs_g2.len() < n + 1 {
+            return Err(Error::SrsG2TooSmall(n + 1, srs_g2.len()));
+        }
+        let domain = domain::<E::Fr>(n)?;
+        let zk_srs_g1 = srs_g1;
+        let srs_g1 = &srs_g1[..n];
 
         // step 2: compute [zV(x)]_2
         let tau_pow_n = srs_g2[n];
@@ -42,49 +185,314 @@ impl<E: PairingEngine> Index<E> {
         let zv_2 = tau_pow_n + minus_one;
 
         // step 3: compute [T(x)]_2
-        let table_poly = DensePolynomial::from_coefficients_slice(&domain.ifft(&table.values));
+        let table_poly = DensePolynomial::from_coefficients_vec(domain.ifft(&table.values));
         let t_2: E::G2Affine = Kzg::<E>::commit_g2(srs_g2, &table_poly).into();
 
         // step 4: compute [Qi(x)]_1
-        let qs = compute_qs::<E>(&table_poly, &domain, srs_g1);
+        let qs = match quotients {
+            QuotientAlgorithm::Fk => compute_qs::<E>(&table_poly, &domain, srs_g1),
+            QuotientAlgorithm::Naive => compute_qs_naive::<E>(&table_poly, &domain, srs_g1),
+        };
         // step 5: compute [Li(x)]_1
-        let lagrange_basis_1: Vec<E::G1Affine> = compute_lagrange_basis_commitments(srs_g1);
+        let lagrange_basis_1: Vec<E::G1Affine> = match lagrange {
+            Some(lagrange) => lagrange.ls.clone(),
+            None => compute_lagrange_basis_commitments(srs_g1),
+        };
+
+        let common = CommonPreprocessedInput::new(zv_2, t_2);
+        Self::from_parts(zk_srs_g1, &table_poly, common, qs, lagrange_basis_1)
+    }
+
+    /// Steps 6 and 7 of `gen_with`, given the cached quotients and Lagrange commitments
+    pub(crate) fn from_parts(
+        srs_g1: &[E::G1Affine],
+        table_poly: &DensePolynomial<E::Fr>,
+        common: CommonPreprocessedInput<E>,
+        qs: Vec<E::G1Affine>,
+        lagrange_basis_1: Vec<E::G1Affine>,
+    ) -> Result<Self, Error> {
+        let n = lagrange_basis_1.len();
+        let domain = domain::<E::Fr>(n)?;
+        let zk_srs_g1 = srs_g1;
+        let srs_g1 = &srs_g1[..n];
 
         // step 6: compute [(Li(x) - Li(0)) / x]_1
         // commit to all zero openings of lagrange basis
-        let rhs = srs_g1[n - 1].mul(-domain.size_as_field_element().inverse().unwrap());
-        let mut li_proofs: Vec<E::G1Affine> = Vec::with_capacity(n);
-        for (i, li_1) in lagrange_basis_1.iter().enumerate() {
-            let lhs = li_1.mul(domain.element(n - i));
-            li_proofs.push((lhs + rhs).into());
-        }
+        let n_inv = domain
+            .size_as_field_element()
+            .inverse()
+            .ok_or(Error::TableSizeNotPow2(n))?;
+        let rhs = srs_g1[n - 1].mul(-n_inv);
+        let li_proofs: Vec<E::G1Affine> = cfg_iter!(lagrange_basis_1)
+            .enumerate()
+            .map(|(i, li_1)| (li_1.mul(domain.element(n - i)) + rhs).into())
+            .collect();
 
-        let common = CommonPreprocessedInput { zv_2, t_2 };
+        // step 7: compute [T(x)]_1 and [x·T(x)]_1 if the srs is large enough for zk proofs
+        let mut zk_t = Vec::new();
+        if zk_srs_g1.len() >= n + 2 {
+            let mut x_table_coeffs = vec![E::Fr::zero()];
+            x_table_coeffs.extend_from_slice(table_poly);
+            let x_table_poly = DensePolynomial::from_coefficients_vec(x_table_coeffs);
+            zk_t.push(Kzg::<E>::commit_g1(srs_g1, table_poly).into());
+            zk_t.push(Kzg::<E>::commit_g1(zk_srs_g1, &x_table_poly).into());
+        }
 
-        Self {
+        Ok(Self {
             common,
             qs,
             ls: lagrange_basis_1,
             ls_at_0: li_proofs,
-        }
+            zk_t,
+        })
     }
 
     pub fn compute_common(
         srs_g2: &[E::G2Affine],
         table: &Table<E::Fr>,
-    ) -> CommonPreprocessedInput<E> {
-        assert!(is_pow_2(table.size));
-        let domain = GeneralEvaluationDomain::<E::Fr>::new(table.size).unwrap();
+    ) -> Result<CommonPreprocessedInput<E>, Error> {
+        if !is_pow_2(table.size) {
+            return Err(Error::TableSizeNotPow2(table.size));
+        }
+        if srs_g2.len() < table.size + 1 {
+            return Err(Error::SrsG2TooSmall(table.size + 1, srs_g2.len()));
+        }
+        let domain = domain::<E::Fr>(table.size)?;
         // step 2: compute [zV(x)]_2
         let tau_pow_n = srs_g2[table.size];
         let minus_one = -E::G2Affine::prime_subgroup_generator();
         let zv_2 = tau_pow_n + minus_one;
 
         // step 3: compute [T(x)]_2
-        let table_poly = DensePolynomial::from_coefficients_slice(&domain.ifft(&table.values));
+        let table_poly = DensePolynomial::from_coefficients_vec(domain.ifft(&table.values));
         let t_2: E::G2Affine = Kzg::<E>::commit_g2(srs_g2, &table_poly).into();
 
-        CommonPreprocessedInput { zv_2, t_2 }
+        Ok(CommonPreprocessedInput::new(zv_2, t_2))
+    }
+
+    pub fn common(&self) -> &CommonPreprocessedInput<E> {
+        &self.common
+    }
+
+    /// Replaces `table[i]` by `value` and patches the index to match, giving the same index as `gen` over the
+    /// new table. Only [T(x)]_2, the cached quotients and the zk commitments depend on the values: with
+    /// `T' = T + d·Li`, every `[Qj(x)]_1` moves by a combination of `[Li(x)]_1` and `[Lj(x)]_1`, about 2N scalar
+    /// multiplications instead of the ffts of reindexing. Verifiers need the new `common()`.
+    pub fn update_entry(
+        &mut self,
+        srs_g1: &[E::G1Affine],
+        srs_g2: &[E::G2Affine],
+        table: &mut Table<E::Fr>,
+        i: usize,
+        value: E::Fr,
+    ) -> Result<(), Error> {
+        self.check_patch(srs_g1, srs_g2, table.size)?;
+        let delta = value - table.replace(i, value)?;
+        self.patch_entry(srs_g1, srs_g2, i, delta)
+    }
+
+    /// Appends `value` to a table with padding left, e.g. one from `Table::padded_to(&values, capacity)`, and
+    /// patches the index like `update_entry`: the first padding entry becomes `value`. Returns its position.
+    pub fn append_entry(
+        &mut self,
+        srs_g1: &[E::G1Affine],
+        srs_g2: &[E::G2Affine],
+        table: &mut Table<E::Fr>,
+        value: E::Fr,
+    ) -> Result<usize, Error> {
+        self.check_patch(srs_g1, srs_g2, table.size)?;
+        let (i, prev) = table.push(value)?;
+        self.patch_entry(srs_g1, srs_g2, i, value - prev)?;
+        Ok(i)
+    }
+
+    /// Index of the union of `base_table` and `extension`, a table from `Table::extension_of(base_table, values)`,
+    /// from the index of each. The index is linear in the table values and the two tables only overlap in copies
+    /// of the base padding value `p`, so the union index is the sum of the two minus the index of the constant
+    /// table `p`: N additions, no ffts. Single values are cheaper to add with `append_entry`.
+    pub fn union(
+        &self,
+        srs_g1: &[E::G1Affine],
+        base_table: &Table<E::Fr>,
+        extension_index: &Self,
+        extension: &Table<E::Fr>,
+    ) -> Result<(Table<E::Fr>, Self), Error> {
+        let n = base_table.size;
+        if self.qs.len() != n || extension_index.qs.len() != n {
+            return Err(Error::TableSizeMismatch(n, extension_index.qs.len()));
+        }
+        // the Lagrange commitments only depend on the srs and the domain
+        if self.ls != extension_index.ls || self.common.zv_2 != extension_index.common.zv_2 {
+            return Err(Error::SrsMismatch);
+        }
+        if self.zk_t.len() != extension_index.zk_t.len() {
+            return Err(Error::MissingZkCommitments);
+        }
+        if !self.zk_t.is_empty() && srs_g1.len() < 2 {
+            return Err(Error::SrsG1TooSmall(2, srs_g1.len()));
+        }
+        let table = Table::union(base_table, extension)?;
+
+        // the constant table p has zero quotients, [T(x)]_2 = p·[1]_2 and [T(x)]_1, [x·T(x)]_1 = p·[1]_1, p·[x]_1
+        let p = base_table.values[n - 1].into_repr();
+        let sum = |a: &E::G1Affine, b: &E::G1Affine| a.into_projective() + b.into_projective();
+        let mut qs: Vec<E::G1Projective> = cfg_iter!(self.qs)
+            .zip(cfg_iter!(extension_index.qs))
+            .map(|(a, b)| sum(a, b))
+            .collect();
+        E::G1Projective::batch_normalization(&mut qs);
+        let t_2 = self.common.t_2.into_projective() + extension_index.common.t_2.into_projective()
+            - E::G2Affine::prime_subgroup_generator().mul(p);
+        let zk_t = self
+            .zk_t
+            .iter()
+            .zip(&extension_index.zk_t)
+            .zip(srs_g1)
+            .map(|((a, b), g)| (sum(a, b) - g.mul(p)).into_affine())
+            .collect();
+
+        let index = Self {
+            common: CommonPreprocessedInput::new(self.common.zv_2, t_2.into_affine()),
+            qs: cfg_iter!(qs).map(|q| q.into_affine()).collect(),
+            ls: self.ls.clone(),
+            ls_at_0: self.ls_at_0.clone(),
+            zk_t,
+        };
+        Ok((table, index))
+    }
+
+    fn check_patch(
+        &self,
+        srs_g1: &[E::G1Affine],
+        srs_g2: &[E::G2Affine],
+        n: usize,
+    ) -> Result<(), Error> {
+        if self.qs.len() != n {
+            return Err(Error::TableSizeMismatch(self.qs.len(), n));
+        }
+        if srs_g2.len() < n {
+            return Err(Error::SrsG2TooSmall(n, srs_g2.len()));
+        }
+        if !self.zk_t.is_empty() && srs_g1.len() < n + 1 {
+            return Err(Error::SrsG1TooSmall(n + 1, srs_g1.len()));
+        }
+        Ok(())
+    }
+
+    /// Moves the index from T to T + delta·Li
+    fn patch_entry(
+        &mut self,
+        srs_g1: &[E::G1Affine],
+        srs_g2: &[E::G2Affine],
+        i: usize,
+        delta: E::Fr,
+    ) -> Result<(), Error> {
+        if delta.is_zero() {
+            return Ok(());
+        }
+        let n = self.qs.len();
+        let domain = domain::<E::Fr>(n)?;
+        let roots: Vec<E::Fr> = domain.elements().collect();
+        let w_i = roots[i];
+        let n_inv = domain
+            .size_as_field_element()
+            .inverse()
+            .ok_or(Error::TableSizeNotPow2(n))?;
+        let delta_n = delta * n_inv;
+
+        // Qj = wj / N·(T(X) - T(wj)) / (X - wj), and for j != i, Li(X) / (X - wj) = (Li - wi / wj·Lj) / (wi - wj),
+        // so Qj moves by d / N·(wj·[Li] - wi·[Lj]) / (wi - wj). The quotients always sum to 0, so Qi moves by
+        // minus the sum of the others.
+        let mut inv: Vec<E::Fr> = roots.iter().map(|&w_j| w_i - w_j).collect();
+        inv[i] = E::Fr::one();
+        batch_inversion(&mut inv);
+        let l_i = self.ls[i];
+        let mut deltas: Vec<E::G1Projective> = cfg_iter!(roots)
+            .zip(cfg_iter!(inv))
+            .zip(cfg_iter!(self.ls))
+            .enumerate()
+            .map(|(j, ((&w_j, &inv_j), l_j))| {
+                if j == i {
+                    return E::G1Projective::zero();
+                }
+                let scale = delta_n * inv_j;
+                l_i.mul(scale * w_j) - l_j.mul(scale * w_i)
+            })
+            .collect();
+        deltas[i] = -deltas.iter().sum::<E::G1Projective>();
+        let mut qs: Vec<E::G1Projective> = cfg_iter!(self.qs)
+            .zip(cfg_iter!(deltas))
+            .map(|(q, d)| q.into_projective() + d)
+            .collect();
+        E::G1Projective::batch_normalization(&mut qs);
+        self.qs = cfg_iter!(qs).map(|q| q.into_affine()).collect();
+
+        // [Li(x)]_2 from the coefficients of Li, the inverse fft of the i-th unit vector
+        let mut unit = vec![E::Fr::zero(); n];
+        unit[i] = E::Fr::one();
+        let li_poly = DensePolynomial::from_coefficients_vec(domain.ifft(&unit));
+        let li_2 = Kzg::<E>::commit_g2(srs_g2, &li_poly).mul(delta.into_repr());
+        let t_2 = (self.common.t_2.into_projective() + li_2).into_affine();
+        self.common = CommonPreprocessedInput::new(self.common.zv_2, t_2);
+
+        // x·Li(x) = wi·Li(x) + wi / N·zV(x)
+        if let [t_1, x_t_1] = self.zk_t[..] {
+            let zv_1 = srs_g1[n] + -E::G1Affine::prime_subgroup_generator();
+            let x_li = l_i.mul(w_i) + zv_1.mul(w_i * n_inv);
+            self.zk_t = vec![
+                (t_1.into_projective() + l_i.mul(delta)).into_affine(),
+                (x_t_1.into_projective() + x_li.mul(delta.into_repr())).into_affine(),
+            ];
+        }
+        Ok(())
+    }
+}
+
+/// Read access to the cached commitments of an index, the prover only asks for the entries of the table
+/// values present in the witness
+pub trait IndexView<E: PairingEngine> {
+    fn common(&self) -> &CommonPreprocessedInput<E>;
+    /// [Qi(x)]_1
+    fn q(&self, i: usize) -> Result<E::G1Affine, Error>;
+    /// [Li(x)]_1
+    fn l(&self, i: usize) -> Result<E::G1Affine, Error>;
+    /// [(Li(x) - Li(0)) / x]_1
+    fn l_at_0(&self, i: usize) -> Result<E::G1Affine, Error>;
+    /// [T(x)]_1 and [x·T(x)]_1, only needed for zk proofs
+    fn t_1(&self) -> Result<(E::G1Affine, E::G1Affine), Error>;
+}
+
+impl<E: PairingEngine> IndexView<E> for Index<E> {
+    fn common(&self) -> &CommonPreprocessedInput<E> {
+        &self.common
+    }
+
+    fn q(&self, i: usize) -> Result<E::G1Affine, Error> {
+        self.qs
+            .get(i)
+            .copied()
+            .ok_or(Error::IndexOutOfTable(i, self.qs.len()))
+    }
+
+    fn l(&self, i: usize) -> Result<E::G1Affine, Error> {
+        self.ls
+            .get(i)
+            .copied()
+            .ok_or(Error::IndexOutOfTable(i, self.ls.len()))
+    }
+
+    fn l_at_0(&self, i: usize) -> Result<E::G1Affine, Error> {
+        self.ls_at_0
+            .get(i)
+            .copied()
+            .ok_or(Error::IndexOutOfTable(i, self.ls_at_0.len()))
+    }
+
+    fn t_1(&self) -> Result<(E::G1Affine, E::G1Affine), Error> {
+        match self.zk_t[..] {
+            [t_1, x_t_1] => Ok((t_1, x_t_1)),
+            _ => Err(Error::MissingZkCommitments),
+        }
     }
 }
 
@@ -97,12 +505,13 @@ mod indexer_tests {
     use ark_std::{rand::rngs::StdRng, test_rng};
 
     use crate::{
+        error::Error,
         kzg::Kzg,
         table::Table,
         utils::{construct_lagrange_basis, unsafe_setup_from_rng},
     };
 
-    use super::Index;
+    use super::{Index, QuotientAlgorithm};
 
     #[test]
     fn test_index_gen() {
@@ -114,7 +523,133 @@ mod indexer_tests {
         let table_values: Vec<_> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
         let table = Table::new(&table_values).unwrap();
 
-        let _ = Index::<Bn254>::gen(&srs_g1, &srs_g2, &table);
+        let _ = Index::<Bn254>::gen(&srs_g1, &srs_g2, &table).unwrap();
+
+        // a short srs is an error with the sizes involved, not a panic
+        let res = Index::<Bn254>::gen(&srs_g1[..16], &srs_g2, &table);
+        assert_eq!(res.err(), Some(Error::SrsG1TooSmall(32, 16)));
+        let res = Index::<Bn254>::compute_common(&srs_g2[..n], &table);
+        let err = res.err().unwrap();
+        assert_eq!(err, Error::SrsG2TooSmall(33, 32));
+        assert_eq!(format!("{}", err), "srs needs 33 G2 powers, got 32");
+    }
+
+    #[test]
+    fn test_update_entry() {
+        let n = 16;
+        let mut rng = test_rng();
+        // x^(N+1) in G1 for the zk commitments
+        let (srs_g1, srs_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(n + 1, n, &mut rng);
+        let table_values: Vec<_> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let mut table = Table::new(&table_values).unwrap();
+        let mut index = Index::<Bn254>::gen(&srs_g1, &srs_g2, &table).unwrap();
+
+        let value = Fr::rand(&mut rng);
+        index
+            .update_entry(&srs_g1, &srs_g2, &mut table, 5, value)
+            .unwrap();
+        assert_eq!(table.values()[5], value);
+        let expected = Index::<Bn254>::gen(&srs_g1, &srs_g2, &table).unwrap();
+        assert_eq!(index.qs, expected.qs);
+        assert_eq!(index.common.t_2, expected.common.t_2);
+        assert_eq!(index.zk_t, expected.zk_t);
+
+        let res = index.update_entry(&srs_g1, &srs_g2, &mut table, 6, value);
+        assert!(matches!(res, Err(Error::DuplicateValueInTable(_))));
+        let mut padded = Table::padded(&table_values[..5]).unwrap();
+        let mut index = Index::<Bn254>::gen(&srs_g1[..8], &srs_g2, &padded).unwrap();
+        let res = index.update_entry(&srs_g1, &srs_g2, &mut padded, 4, value);
+        assert_eq!(res, Err(Error::PaddedEntry(4)));
+    }
+
+    #[test]
+    fn test_append_entry() {
+        let mut rng = test_rng();
+        let (srs_g1, srs_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(9, 8, &mut rng);
+        let values: Vec<_> = (0..5).map(|_| Fr::rand(&mut rng)).collect();
+        let mut table = Table::padded_to(&values, 8).unwrap();
+        let mut index = Index::<Bn254>::gen(&srs_g1, &srs_g2, &table).unwrap();
+
+        for expected_position in 5..8 {
+            let value = Fr::rand(&mut rng);
+            let i = index
+                .append_entry(&srs_g1, &srs_g2, &mut table, value)
+                .unwrap();
+            assert_eq!(i, expected_position);
+            let expected = Index::<Bn254>::gen(&srs_g1, &srs_g2, &table).unwrap();
+            assert_eq!(index.qs, expected.qs);
+            assert_eq!(index.common.t_2, expected.common.t_2);
+            assert_eq!(index.zk_t, expected.zk_t);
+        }
+        assert_eq!(table.free_capacity(), 0);
+        let res = index.append_entry(&srs_g1, &srs_g2, &mut table, Fr::rand(&mut rng));
+        assert_eq!(res, Err(Error::TableFull(8)));
+    }
+
+    #[test]
+    fn test_union() {
+        let mut rng = test_rng();
+        let (srs_g1, srs_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(9, 8, &mut rng);
+        let values: Vec<_> = (0..8).map(|_| Fr::rand(&mut rng)).collect();
+        let base = Table::padded_to(&values[..3], 8).unwrap();
+        let base_index = Index::<Bn254>::gen(&srs_g1, &srs_g2, &base).unwrap();
+        let extension = Table::extension_of(&base, &values[3..7]).unwrap();
+        let extension_index = Index::<Bn254>::gen(&srs_g1, &srs_g2, &extension).unwrap();
+
+        let (table, index) = base_index
+            .union(&srs_g1, &base, &extension_index, &extension)
+            .unwrap();
+        assert_eq!(&table.values()[..7], &values[..7]);
+        assert_eq!((table.unpadded_size(), table.free_capacity()), (7, 1));
+        let expected = Index::<Bn254>::gen(&srs_g1, &srs_g2, &table).unwrap();
+        assert_eq!(index.qs, expected.qs);
+        assert_eq!(index.common.t_2, expected.common.t_2);
+        assert_eq!(index.zk_t, expected.zk_t);
+
+        let res = Table::extension_of(&base, &values[2..]);
+        assert_eq!(res.err(), Some(Error::TableFull(8)));
+        // an extension repeating a base value
+        let extension = Table::extension_of(&base, &values[..2]).unwrap();
+        let extension_index = Index::<Bn254>::gen(&srs_g1, &srs_g2, &extension).unwrap();
+        let res = base_index.union(&srs_g1, &base, &extension_index, &extension);
+        assert!(matches!(res.err(), Some(Error::DuplicateValueInTable(_))));
+    }
+
+    #[test]
+    fn test_quotient_algorithms() {
+        let n = 16;
+        let mut rng = test_rng();
+        let (srs_g1, srs_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(n - 1, n, &mut rng);
+        let table_values: Vec<_> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let table = Table::new(&table_values).unwrap();
+
+        let fk = Index::<Bn254>::gen_with(&srs_g1, &srs_g2, &table, QuotientAlgorithm::Fk).unwrap();
+        let naive =
+            Index::<Bn254>::gen_with(&srs_g1, &srs_g2, &table, QuotientAlgorithm::Naive).unwrap();
+        assert_eq!(fk.qs, naive.qs);
+    }
+
+    /// the parallel index is the same as the one computed on a single worker
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parallel_index_gen() {
+        let n = 64;
+        let mut rng = test_rng();
+        let (srs_g1, srs_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(n - 1, n, &mut rng);
+        let table_values: Vec<_> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let table = Table::new(&table_values).unwrap();
+
+        let pool = |threads| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap()
+        };
+        let single = pool(1).install(|| Index::<Bn254>::gen(&srs_g1, &srs_g2, &table).unwrap());
+        let parallel = pool(4).install(|| Index::<Bn254>::gen(&srs_g1, &srs_g2, &table).unwrap());
+        assert_eq!(single.qs, parallel.qs);
+        assert_eq!(single.ls, parallel.ls);
+        assert_eq!(single.ls_at_0, parallel.ls_at_0);
     }
 
     #[test]
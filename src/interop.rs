@@ -0,0 +1,224 @@
+//! Test vectors for checking byte level agreement with other cq implementations, in particular
+//! geometryresearch/cq which shares the arkworks encodings. Unlike the reference, the transcript here also
+//! absorbs the verifier key, so challenges only agree with implementations that bind it the same way.
+//!
+//! A vector fixes the srs through the seed of a `StdRng` passed to `unsafe_setup_from_rng`, and
+//! records the table, the witness, the transcript challenges and the compressed statement and proof.
+//! All field elements and artifacts are `0x` prefixed hex of their compressed ark-serialize encoding.
+
+use ark_bn254::{Bn254, Fr};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::rand::{rngs::StdRng, SeedableRng};
+use rand_chacha::ChaChaRng;
+use serde::{Deserialize, Serialize};
+use sha3::Keccak256;
+
+use crate::{
+    config::CqConfig,
+    cq::{Cq, CqProverKey, CqVerifierKey},
+    data_structures::{Proof, Statement},
+    error::Error,
+    rng::SimpleHashFiatShamirRng,
+    table::Table,
+    verifier::{Challenges, Verifier},
+};
+
+/// Transcript rng of the reference implementation
+pub type ReferenceFiatShamirRng = SimpleHashFiatShamirRng<Keccak256, ChaChaRng>;
+
+type FS = ReferenceFiatShamirRng;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TestVector {
+    pub srs_seed: u64,
+    pub table: Vec<String>,
+    pub witness: Vec<String>,
+    pub beta: String,
+    pub gamma: String,
+    pub eta: String,
+    pub u: String,
+    pub statement: String,
+    pub proof: String,
+}
+
+fn to_hex<T: CanonicalSerialize>(value: &T) -> String {
+    let mut bytes = Vec::with_capacity(value.serialized_size());
+    value
+        .serialize(&mut bytes)
+        .expect("serializing into a vec can't fail");
+    format!("0x{}", hex::encode(bytes))
+}
+
+fn from_hex<T: CanonicalDeserialize>(s: &str) -> Result<T, Error> {
+    let bytes = s
+        .strip_prefix("0x")
+        .and_then(|s| hex::decode(s).ok())
+        .ok_or(Error::InvalidEncoding("expected 0x prefixed hex"))?;
+    let mut reader = &bytes[..];
+    let value =
+        T::deserialize(&mut reader).map_err(|e| Error::Serialization(format!("{:?}", e)))?;
+    if !reader.is_empty() {
+        return Err(Error::InvalidEncoding("trailing bytes"));
+    }
+    Ok(value)
+}
+
+fn from_hex_vec(values: &[String]) -> Result<Vec<Fr>, Error> {
+    values.iter().map(|v| from_hex(v)).collect()
+}
+
+/// Reproduces the keys of a vector from its srs seed and table
+fn setup(
+    srs_seed: u64,
+    table_values: &[Fr],
+    witness_size: usize,
+) -> Result<(CqProverKey<Bn254>, CqVerifierKey<Bn254>), Error> {
+    let table = Table::new(&table_values.to_vec())?;
+    let config = CqConfig::<Bn254, FS>::builder()
+        .table_size(table.size)
+        .witness_size(witness_size)
+        .build()?;
+    config.unsafe_setup(table, &mut StdRng::seed_from_u64(srs_seed))
+}
+
+impl TestVector {
+    /// Runs the prover on fixed inputs and records everything another implementation has to reproduce
+    pub fn generate(
+        srs_seed: u64,
+        table_values: &[Fr],
+        witness_values: &[Fr],
+    ) -> Result<Self, Error> {
+        let (prover_key, verifier_key) = setup(srs_seed, table_values, witness_values.len())?;
+        let (statement, proof) = Cq::<Bn254, FS>::prove(&prover_key, &witness_values.to_vec())?;
+        let challenges = Verifier::<Bn254, FS>::derive_challenges(
+            &verifier_key.vk,
+            &verifier_key.common,
+            &statement,
+            &proof,
+        );
+
+        Ok(Self {
+            srs_seed,
+            table: table_values.iter().map(to_hex).collect(),
+            witness: witness_values.iter().map(to_hex).collect(),
+            beta: to_hex(&challenges.beta),
+            gamma: to_hex(&challenges.gamma),
+            eta: to_hex(&challenges.eta),
+            u: to_hex(&challenges.u),
+            statement: to_hex(&statement),
+            proof: to_hex(&proof),
+        })
+    }
+
+    /// Regenerates the vector with this implementation and reports the first field that differs
+    pub fn check(&self) -> Result<(), Error> {
+        let expected = Self::generate(
+            self.srs_seed,
+            &from_hex_vec(&self.table)?,
+            &from_hex_vec(&self.witness)?,
+        )?;
+
+        let fields = [
+            ("beta", &expected.beta, &self.beta),
+            ("gamma", &expected.gamma, &self.gamma),
+            ("eta", &expected.eta, &self.eta),
+            ("u", &expected.u, &self.u),
+            ("statement", &expected.statement, &self.statement),
+            ("proof", &expected.proof, &self.proof),
+        ];
+        for (name, expected, actual) in fields {
+            if expected.to_lowercase() != actual.to_lowercase() {
+                return Err(Error::TestVectorMismatch(name));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn challenges(&self) -> Result<Challenges<Fr>, Error> {
+        Ok(Challenges {
+            beta: from_hex(&self.beta)?,
+            gamma: from_hex(&self.gamma)?,
+            eta: from_hex(&self.eta)?,
+            u: from_hex(&self.u)?,
+        })
+    }
+}
+
+/// Verifies the statement and proof of a vector produced by another implementation, also checking that
+/// both sides derived the same challenges
+pub fn verify_external(vector: &TestVector) -> Result<(), Error> {
+    let table_values = from_hex_vec(&vector.table)?;
+    let (_, verifier_key) = setup(vector.srs_seed, &table_values, vector.witness.len())?;
+    let statement: Statement<Bn254> = from_hex(&vector.statement)?;
+    let proof: Proof<Bn254> = from_hex(&vector.proof)?;
+
+    let challenges = Verifier::<Bn254, FS>::derive_challenges(
+        &verifier_key.vk,
+        &verifier_key.common,
+        &statement,
+        &proof,
+    );
+    let external = vector.challenges()?;
+    for (name, ours, theirs) in [
+        ("beta", challenges.beta, external.beta),
+        ("gamma", challenges.gamma, external.gamma),
+        ("eta", challenges.eta, external.eta),
+        ("u", challenges.u, external.u),
+    ] {
+        if ours != theirs {
+            return Err(Error::TestVectorMismatch(name));
+        }
+    }
+
+    Verifier::<Bn254, FS>::verify_with_challenges(
+        &verifier_key.vk,
+        &verifier_key.common,
+        &statement,
+        &proof,
+        &challenges,
+    )
+}
+
+#[cfg(test)]
+mod interop_tests {
+    use ark_bn254::Fr;
+
+    use crate::{error::Error, utils::to_field};
+
+    use super::{verify_external, TestVector};
+
+    fn vector() -> TestVector {
+        let table_values = to_field::<Fr>(&[1, 5, 10, 15, 20, 25, 30, 35]);
+        TestVector::generate(42, &table_values, &to_field(&[5, 15, 20, 35])).unwrap()
+    }
+
+    #[test]
+    fn test_vector_roundtrip() {
+        let vector = vector();
+        let json = serde_json::to_string_pretty(&vector).unwrap();
+        let parsed: TestVector = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(parsed, vector);
+        assert!(parsed.check().is_ok());
+        assert!(verify_external(&parsed).is_ok());
+    }
+
+    #[test]
+    fn test_vector_mismatch() {
+        let mut vector = vector();
+        vector.beta = vector.gamma.clone();
+        assert_eq!(vector.check(), Err(Error::TestVectorMismatch("beta")));
+        assert_eq!(
+            verify_external(&vector),
+            Err(Error::TestVectorMismatch("beta"))
+        );
+
+        // a different srs changes the table commitments and with them every challenge
+        let other_srs = TestVector {
+            srs_seed: 43,
+            ..self::vector()
+        };
+        assert!(verify_external(&other_srs).is_err());
+    }
+}
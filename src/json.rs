@@ -0,0 +1,312 @@
+//! serde support for the artifacts exchanged between prover and verifier.
+//!
+//! Every group and field element is encoded as its compressed ark-serialize encoding in lowercase hex
+//! with a `0x` prefix. The JSON schema is:
+//!
+//! ```text
+//! Statement:   { "f": G1 }
+//! Proof:       { "version": number, "m_cm": G1,
+//!                "a_cm": G1, "qa_cm": G1, "b0_cm": G1, "qb_cm": G1, "p_cm": G1, "g_cm": G1 (zk only),
+//!                "a_shift_cm": G1 (plain proofs with an srs past x^(N-1) only),
+//!                "b0_at_gamma": Fr, "f_at_gamma": Fr, "a_at_zero": Fr, "pi_gamma": G1, "a0_cm": G1 }
+//! VerifierKey: { "x": G2, "x_pow_b0_bound": G2, "table_size": number, "witness_size": number,
+//!                "x_sq": G2 (zk only), "x_pow_a_bound": G2 (plain keys with an srs past x^(N-1) only),
+//!                "srs_degree": number }
+//! Transcript:  [ { "kind": "round" | "g1" | "g2" | "field" | "challenge", "label": string,
+//!                  "value": u8 hex | G1 | G2 | Fr }, ... ]
+//! ```
+
+use ark_ec::PairingEngine;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    data_structures::{Proof, Statement},
+    prover::{ProverFirstMessage, ProverSecondMessage, ProverThirdMessage},
+    transcript::RecordingTranscript,
+    verifier::VerifierKey,
+};
+
+fn to_hex<T: CanonicalSerialize>(value: &T) -> String {
+    let mut bytes = Vec::with_capacity(value.serialized_size());
+    value
+        .serialize(&mut bytes)
+        .expect("serializing into a vec can't fail");
+    format!("0x{}", hex::encode(bytes))
+}
+
+fn from_hex<T: CanonicalDeserialize, E: serde::de::Error>(s: &str) -> Result<T, E> {
+    let s = s
+        .strip_prefix("0x")
+        .ok_or_else(|| E::custom("hex string must start with 0x"))?;
+    let bytes = hex::decode(s).map_err(E::custom)?;
+    let mut reader = &bytes[..];
+    let value = T::deserialize(&mut reader).map_err(|e| E::custom(format!("{:?}", e)))?;
+    if !reader.is_empty() {
+        return Err(E::custom("trailing bytes after encoded element"));
+    }
+    Ok(value)
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StatementJson {
+    f: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProofJson {
+    version: u8,
+    m_cm: String,
+
+    a_cm: String,
+    qa_cm: String,
+    b0_cm: String,
+    qb_cm: String,
+    p_cm: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    g_cm: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    a_shift_cm: Option<String>,
+
+    b0_at_gamma: String,
+    f_at_gamma: String,
+    a_at_zero: String,
+    pi_gamma: String,
+    a0_cm: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct VerifierKeyJson {
+    x: String,
+    x_pow_b0_bound: String,
+    table_size: usize,
+    witness_size: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    x_sq: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    x_pow_a_bound: Option<String>,
+    srs_degree: usize,
+}
+
+impl<E: PairingEngine> Serialize for Statement<E> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        StatementJson { f: to_hex(&self.f) }.serialize(serializer)
+    }
+}
+
+impl<'de, E: PairingEngine> Deserialize<'de> for Statement<E> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let json = StatementJson::deserialize(deserializer)?;
+        Ok(Self {
+            f: from_hex::<_, D::Error>(&json.f)?,
+        })
+    }
+}
+
+impl<E: PairingEngine> Serialize for Proof<E> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ProofJson {
+            version: self.version,
+            m_cm: to_hex(&self.first_msg.m_cm),
+
+            a_cm: to_hex(&self.second_msg.a_cm),
+            qa_cm: to_hex(&self.second_msg.qa_cm),
+            b0_cm: to_hex(&self.second_msg.b0_cm),
+            qb_cm: to_hex(&self.second_msg.qb_cm),
+            p_cm: to_hex(&self.second_msg.p_cm),
+            g_cm: self.second_msg.g_cm.as_ref().map(to_hex),
+            a_shift_cm: self.second_msg.a_shift_cm.as_ref().map(to_hex),
+
+            b0_at_gamma: to_hex(&self.third_msg.b0_at_gamma),
+            f_at_gamma: to_hex(&self.third_msg.f_at_gamma),
+            a_at_zero: to_hex(&self.third_msg.a_at_zero),
+            pi_gamma: to_hex(&self.third_msg.pi_gamma),
+            a0_cm: to_hex(&self.third_msg.a0_cm),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, E: PairingEngine> Deserialize<'de> for Proof<E> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let json = ProofJson::deserialize(deserializer)?;
+        Ok(Self {
+            version: json.version,
+            first_msg: ProverFirstMessage {
+                m_cm: from_hex::<_, D::Error>(&json.m_cm)?,
+            },
+            second_msg: ProverSecondMessage {
+                a_cm: from_hex::<_, D::Error>(&json.a_cm)?,
+                qa_cm: from_hex::<_, D::Error>(&json.qa_cm)?,
+                b0_cm: from_hex::<_, D::Error>(&json.b0_cm)?,
+                qb_cm: from_hex::<_, D::Error>(&json.qb_cm)?,
+                p_cm: from_hex::<_, D::Error>(&json.p_cm)?,
+                g_cm: json
+                    .g_cm
+                    .as_deref()
+                    .map(from_hex::<_, D::Error>)
+                    .transpose()?,
+                a_shift_cm: json
+                    .a_shift_cm
+                    .as_deref()
+                    .map(from_hex::<_, D::Error>)
+                    .transpose()?,
+            },
+            third_msg: ProverThirdMessage {
+                b0_at_gamma: from_hex::<_, D::Error>(&json.b0_at_gamma)?,
+                f_at_gamma: from_hex::<_, D::Error>(&json.f_at_gamma)?,
+                a_at_zero: from_hex::<_, D::Error>(&json.a_at_zero)?,
+                pi_gamma: from_hex::<_, D::Error>(&json.pi_gamma)?,
+                a0_cm: from_hex::<_, D::Error>(&json.a0_cm)?,
+            },
+        })
+    }
+}
+
+impl<E: PairingEngine> Serialize for VerifierKey<E> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        VerifierKeyJson {
+            x: to_hex(&self.x_2),
+            x_pow_b0_bound: to_hex(&self.x_pow_b0_bound_2),
+            table_size: self.table_size,
+            witness_size: self.witness_size,
+            x_sq: self.x_sq_2.as_ref().map(to_hex),
+            x_pow_a_bound: self.x_pow_a_bound_2.as_ref().map(to_hex),
+            srs_degree: self.srs_degree,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, E: PairingEngine> Deserialize<'de> for VerifierKey<E> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let json = VerifierKeyJson::deserialize(deserializer)?;
+        let vk = Self::from_parts(
+            from_hex::<_, D::Error>(&json.x)?,
+            from_hex::<_, D::Error>(&json.x_pow_b0_bound)?,
+            json.table_size,
+            json.witness_size,
+        )
+        .with_x_sq(
+            json.x_sq
+                .as_deref()
+                .map(from_hex::<_, D::Error>)
+                .transpose()?,
+        )
+        .with_a_bound(
+            json.x_pow_a_bound
+                .as_deref()
+                .map(from_hex::<_, D::Error>)
+                .transpose()?,
+        )
+        .with_srs_degree(json.srs_degree);
+        vk.check_shape()
+            .map_err(|e| <D::Error as serde::de::Error>::custom(format!("{}", e)))?;
+        Ok(vk)
+    }
+}
+
+impl<T> RecordingTranscript<T> {
+    /// The recorded entries as a JSON array, for diffing against the log of another verifier
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self.entries()).expect("entries are plain strings")
+    }
+}
+
+#[cfg(test)]
+mod json_tests {
+    use ark_bn254::{Bn254, Fr};
+    use ark_std::test_rng;
+    use rand_chacha::ChaChaRng;
+    use sha3::Keccak256;
+
+    use crate::{
+        cq::Cq,
+        data_structures::{Proof, Statement},
+        rng::SimpleHashFiatShamirRng,
+        transcript::TranscriptEntry,
+        utils::to_field,
+        verifier::{Verifier, VerifierKey},
+    };
+
+    use super::to_hex;
+
+    type FS = SimpleHashFiatShamirRng<Keccak256, ChaChaRng>;
+
+    #[test]
+    fn test_json_roundtrip() {
+        let mut rng = test_rng();
+
+        let table_values = to_field::<Fr>(&[1, 5, 10, 15, 20, 25, 30, 35]);
+        let (prover_key, mut verifier_key) =
+            Cq::<Bn254, FS>::setup(&table_values, 4, &mut rng).unwrap();
+        let (statement, proof) =
+            Cq::<Bn254, FS>::prove(&prover_key, &to_field(&[5, 15, 20, 35])).unwrap();
+
+        let statement_json = serde_json::to_string(&statement).unwrap();
+        let proof_json = serde_json::to_string(&proof).unwrap();
+        let vk_json = serde_json::to_string(&verifier_key.vk).unwrap();
+
+        let statement: Statement<Bn254> = serde_json::from_str(&statement_json).unwrap();
+        let proof: Proof<Bn254> = serde_json::from_str(&proof_json).unwrap();
+        verifier_key.vk = serde_json::from_str::<VerifierKey<Bn254>>(&vk_json).unwrap();
+
+        assert!(Cq::<Bn254, FS>::verify(&verifier_key, &statement, &proof).is_ok());
+        assert_eq!(serde_json::to_string(&proof).unwrap(), proof_json);
+    }
+
+    #[test]
+    fn test_transcript_log() {
+        let mut rng = test_rng();
+        let table_values = to_field::<Fr>(&[1, 5, 10, 15, 20, 25, 30, 35]);
+        let (prover_key, verifier_key) =
+            Cq::<Bn254, FS>::setup(&table_values, 4, &mut rng).unwrap();
+        let (statement, mut proof) =
+            Cq::<Bn254, FS>::prove(&prover_key, &to_field(&[5, 15, 20, 35])).unwrap();
+
+        let record = || {
+            Verifier::<Bn254, FS>::record_transcript(
+                &verifier_key.vk,
+                &verifier_key.common,
+                &statement,
+                &proof,
+            )
+        };
+        let log = record();
+        let entries: Vec<TranscriptEntry> = serde_json::from_str(&log.to_json()).unwrap();
+        assert_eq!(entries, log.entries());
+        assert_eq!(
+            (entries[0].kind.as_str(), entries[0].label.as_str()),
+            ("round", "public-input")
+        );
+        assert_eq!(
+            (entries[1].kind.as_str(), entries[1].label.as_str()),
+            ("g2", "x")
+        );
+
+        let challenges = Verifier::<Bn254, FS>::derive_challenges(
+            &verifier_key.vk,
+            &verifier_key.common,
+            &statement,
+            &proof,
+        );
+        assert_eq!(entries[10].label, "beta");
+        assert_eq!(entries[10].value, to_hex(&challenges.beta));
+
+        // a different a_cm shows up as the first divergence, right after the round-2 separator
+        proof.second_msg.a_cm = proof.first_msg.m_cm;
+        assert_eq!(record().first_divergence(log.entries()), Some(12));
+    }
+
+    #[test]
+    fn test_json_rejects_bad_hex() {
+        let res = serde_json::from_str::<Statement<Bn254>>(r#"{"f": "0xzz"}"#);
+        assert!(res.is_err());
+
+        let res = serde_json::from_str::<Statement<Bn254>>(r#"{"f": "00"}"#);
+        assert!(res.is_err());
+    }
+}
@@ -0,0 +1,219 @@
+//! Lookups of (key, value) pairs, e.g. reads of a read only memory: every witness row `(k_i, v_i)` is a row of a
+//! two column table whose keys are distinct, so `v_i` is the value stored at `k_i`.
+//!
+//! The table is a [`MultiColumnTable`] of width 2 and proofs are [`MultiColumnCq`] proofs, with the key column
+//! first. Distinct keys are checked when the table is built; the verifier relies on the table owner for it, as it
+//! only sees the column commitments.
+
+use ark_ec::PairingEngine;
+use ark_ff::FftField;
+#[cfg(any(test, feature = "test-utils"))]
+use ark_std::rand::RngCore;
+use ark_std::{collections::BTreeMap, format, marker::PhantomData, vec, vec::Vec};
+
+use crate::{
+    data_structures::Proof,
+    error::Error,
+    multi_column::{
+        MultiColumnCq, MultiColumnProverKey, MultiColumnStatement, MultiColumnTable,
+        MultiColumnVerifierKey,
+    },
+    rng::FiatShamirRng,
+};
+
+/// Table mapping distinct keys to values
+pub struct KeyValueTable<F: FftField> {
+    pub(crate) table: MultiColumnTable<F>,
+    pub(crate) values: BTreeMap<F, F>,
+}
+
+impl<F: FftField> KeyValueTable<F> {
+    /// Table of `(key, value)` entries with distinct keys, a power of two of them
+    pub fn new(entries: &[(F, F)]) -> Result<Self, Error> {
+        let keys = entries.iter().map(|&(k, _)| k).collect();
+        let values = entries.iter().map(|&(_, v)| v).collect();
+        Self::from_table(MultiColumnTable::from_columns(vec![keys, values])?)
+    }
+
+    /// Memory of `values`, the key of every value being its address `0, 1, ...`
+    pub fn memory(values: &[F]) -> Result<Self, Error> {
+        let entries: Vec<(F, F)> = values
+            .iter()
+            .enumerate()
+            .map(|(address, &v)| (F::from(address as u64), v))
+            .collect();
+        Self::new(&entries)
+    }
+
+    fn from_table(table: MultiColumnTable<F>) -> Result<Self, Error> {
+        if table.width() != 2 {
+            return Err(Error::ColumnCountMismatch(2, table.width()));
+        }
+        let mut values = BTreeMap::new();
+        for (&k, &v) in table.columns[0].iter().zip(&table.columns[1]) {
+            if values.insert(k, v).is_some() {
+                return Err(Error::DuplicateValueInTable(format!("key {}", k)));
+            }
+        }
+        Ok(Self { table, values })
+    }
+
+    pub fn size(&self) -> usize {
+        self.table.size()
+    }
+
+    /// Value stored at `key`
+    pub fn get(&self, key: &F) -> Result<F, Error> {
+        self.values
+            .get(key)
+            .copied()
+            .ok_or_else(|| Error::ValueNotInTable(format!("key {}", key)))
+    }
+}
+
+/// Prover key of a [`KeyValueTable`], with the key to value map for `prove_reads`
+pub struct KeyValueProverKey<E: PairingEngine> {
+    pub pk: MultiColumnProverKey<E>,
+    pub(crate) values: BTreeMap<E::Fr, E::Fr>,
+}
+
+impl<E: PairingEngine> KeyValueProverKey<E> {
+    /// Key of a deserialized multi column key, checking that it is a key value table
+    pub fn from_multi_column(pk: MultiColumnProverKey<E>) -> Result<Self, Error> {
+        let table = KeyValueTable::from_table(pk.table)?;
+        Ok(Self {
+            pk: MultiColumnProverKey {
+                table: table.table,
+                ..pk
+            },
+            values: table.values,
+        })
+    }
+}
+
+pub struct KeyValueCq<E: PairingEngine, FS: FiatShamirRng> {
+    _e: PhantomData<E>,
+    _fs: PhantomData<FS>,
+}
+
+impl<E: PairingEngine, FS: FiatShamirRng> KeyValueCq<E, FS> {
+    /// Indexes both columns with an srs sampled from `rng`, see `MultiColumnCq::setup`
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn setup<R: RngCore>(
+        table: KeyValueTable<E::Fr>,
+        witness_size: usize,
+        rng: &mut R,
+    ) -> Result<(KeyValueProverKey<E>, MultiColumnVerifierKey<E>), Error> {
+        let (pk, vk) = MultiColumnCq::<E, FS>::setup(table.table, witness_size, rng)?;
+        Ok((
+            KeyValueProverKey {
+                pk,
+                values: table.values,
+            },
+            vk,
+        ))
+    }
+
+    /// Indexes both columns against an existing srs
+    pub fn keygen(
+        table: KeyValueTable<E::Fr>,
+        witness_size: usize,
+        srs_g1: Vec<E::G1Affine>,
+        srs_g2: &[E::G2Affine],
+    ) -> Result<(KeyValueProverKey<E>, MultiColumnVerifierKey<E>), Error> {
+        let (pk, vk) = MultiColumnCq::<E, FS>::keygen(table.table, witness_size, srs_g1, srs_g2)?;
+        Ok((
+            KeyValueProverKey {
+                pk,
+                values: table.values,
+            },
+            vk,
+        ))
+    }
+
+    /// Proves that every `(key, value)` of `reads` is an entry of the table, a wrong value fails with
+    /// `ValueNotInTable`
+    pub fn prove(
+        pk: &KeyValueProverKey<E>,
+        reads: &[(E::Fr, E::Fr)],
+    ) -> Result<(MultiColumnStatement<E>, Proof<E>), Error> {
+        let rows: Vec<Vec<E::Fr>> = reads.iter().map(|&(k, v)| vec![k, v]).collect();
+        MultiColumnCq::<E, FS>::prove(&pk.pk, &rows)
+    }
+
+    /// Looks up the value of every key and proves the reads, returning the values with the proof
+    pub fn prove_reads(
+        pk: &KeyValueProverKey<E>,
+        keys: &[E::Fr],
+    ) -> Result<(Vec<E::Fr>, MultiColumnStatement<E>, Proof<E>), Error> {
+        let values = keys
+            .iter()
+            .map(|k| {
+                pk.values
+                    .get(k)
+                    .copied()
+                    .ok_or_else(|| Error::ValueNotInTable(format!("key {}", k)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let reads: Vec<_> = keys.iter().copied().zip(values.iter().copied()).collect();
+        let (statement, proof) = Self::prove(pk, &reads)?;
+        Ok((values, statement, proof))
+    }
+
+    /// Verifies reads whose key and value columns are committed in `statement`, in that order
+    pub fn verify(
+        vk: &MultiColumnVerifierKey<E>,
+        statement: &MultiColumnStatement<E>,
+        proof: &Proof<E>,
+    ) -> Result<(), Error> {
+        if vk.commons.len() != 2 {
+            return Err(Error::ColumnCountMismatch(2, vk.commons.len()));
+        }
+        MultiColumnCq::<E, FS>::verify(vk, statement, proof)
+    }
+}
+
+#[cfg(test)]
+mod key_value_tests {
+    use ark_bn254::{Bn254, Fr};
+    use ark_std::test_rng;
+    use rand_chacha::ChaChaRng;
+    use sha3::Keccak256;
+
+    use crate::{error::Error, rng::SimpleHashFiatShamirRng, utils::to_field};
+
+    use super::{KeyValueCq, KeyValueTable};
+
+    type FS = SimpleHashFiatShamirRng<Keccak256, ChaChaRng>;
+
+    #[test]
+    fn test_memory_reads() {
+        let mut rng = test_rng();
+        let memory = to_field::<Fr>(&[7, 7, 42, 0, 9, 100, 3, 7]);
+        let table = KeyValueTable::memory(&memory).unwrap();
+        assert_eq!(table.get(&Fr::from(5u64)), Ok(Fr::from(100u64)));
+        let (pk, vk) = KeyValueCq::<Bn254, FS>::setup(table, 4, &mut rng).unwrap();
+
+        let keys = to_field::<Fr>(&[2, 5, 2, 0]);
+        let (values, statement, proof) = KeyValueCq::<Bn254, FS>::prove_reads(&pk, &keys).unwrap();
+        assert_eq!(values, to_field::<Fr>(&[42, 100, 42, 7]));
+        assert!(KeyValueCq::<Bn254, FS>::verify(&vk, &statement, &proof).is_ok());
+
+        // 7 is stored in the table, but not at address 2
+        let reads = vec![
+            (Fr::from(2u64), Fr::from(7u64)),
+            (Fr::from(5u64), Fr::from(100u64)),
+            (Fr::from(2u64), Fr::from(42u64)),
+            (Fr::from(0u64), Fr::from(7u64)),
+        ];
+        let res = KeyValueCq::<Bn254, FS>::prove(&pk, &reads);
+        assert!(matches!(res.err(), Some(Error::ValueNotInTable(_))));
+
+        let entries = vec![
+            (Fr::from(1u64), Fr::from(10u64)),
+            (Fr::from(1u64), Fr::from(20u64)),
+        ];
+        let res = KeyValueTable::new(&entries);
+        assert!(matches!(res.err(), Some(Error::DuplicateValueInTable(_))));
+    }
+}
@@ -1,8 +1,19 @@
-use std::{iter, marker::PhantomData};
-
-use ark_ec::{msm::VariableBaseMSM, PairingEngine};
-use ark_ff::{One, PrimeField};
+use ark_ec::{
+    msm::{FixedBaseMSM, VariableBaseMSM},
+    AffineCurve, PairingEngine, ProjectiveCurve,
+};
+use ark_ff::PrimeField;
 use ark_poly::{univariate::DensePolynomial, Polynomial, UVPolynomial};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{cfg_iter, iter, marker::PhantomData, vec::Vec};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::{
+    error::Error,
+    tools::{compute_lagrange_basis_commitments, divide_by_linear},
+    utils::is_pow_2,
+};
 
 /// Minimal KZG functionalities needed for cq
 pub struct Kzg<E: PairingEngine> {
@@ -18,8 +29,31 @@ impl<E: PairingEngine> Kzg<E> {
                 srs.len()
             );
         }
-        let coeff_scalars: Vec<_> = poly.coeffs.iter().map(|c| c.into_repr()).collect();
-        VariableBaseMSM::multi_scalar_mul(srs, &coeff_scalars)
+        Self::msm_g1(srs, &poly.coeffs)
+    }
+
+    /// `commit_g1` as one msm per `chunk` coefficients. The polynomial is still held in full, this only bounds the
+    /// size of each msm and its scalar conversion.
+    pub fn commit_g1_chunked(
+        srs: &[E::G1Affine],
+        poly: &DensePolynomial<E::Fr>,
+        chunk: usize,
+    ) -> Result<E::G1Projective, Error> {
+        if srs.len() < poly.coeffs.len() {
+            return Err(Error::SrsG1TooSmall(poly.coeffs.len(), srs.len()));
+        }
+        Ok(poly
+            .coeffs
+            .chunks(chunk)
+            .zip(srs.chunks(chunk))
+            .map(|(coeffs, bases)| Self::msm_g1(bases, coeffs))
+            .sum())
+    }
+
+    /// `sum_i scalars[i]·bases[i]` with Pippenger, the longer of the two slices is truncated
+    pub fn msm_g1(bases: &[E::G1Affine], scalars: &[E::Fr]) -> E::G1Projective {
+        let scalars: Vec<_> = scalars.iter().map(|s| s.into_repr()).collect();
+        VariableBaseMSM::multi_scalar_mul(bases, &scalars)
     }
 
     pub fn commit_g2(srs: &[E::G2Affine], poly: &DensePolynomial<E::Fr>) -> E::G2Projective {
@@ -39,7 +73,7 @@ impl<E: PairingEngine> Kzg<E> {
         poly: &DensePolynomial<E::Fr>,
         challenge: E::Fr,
     ) -> (E::Fr, E::G1Affine) {
-        let q = poly / &DensePolynomial::from_coefficients_slice(&[-challenge, E::Fr::one()]);
+        let q = divide_by_linear(poly.coeffs.clone(), challenge);
         if srs.len() - 1 < q.degree() {
             panic!(
                 "Open g1: SRS size to small! Can't commit to polynomial of degree {} with srs of size {}",
@@ -51,23 +85,32 @@ impl<E: PairingEngine> Kzg<E> {
         (poly.evaluate(&challenge), proof.into())
     }
 
-    pub fn batch_open_g1(
-        srs: &[E::G1Affine],
-        polys: &[DensePolynomial<E::Fr>],
+    /// Quotient of `sum_i separation^i·polys[i]` by `X - opening_challenge`, whose commitment is the opening proof
+    pub fn batch_open_quotient(
+        polys: &[&DensePolynomial<E::Fr>],
         opening_challenge: E::Fr,
         separation_challenge: E::Fr,
-    ) -> E::G1Affine {
+    ) -> DensePolynomial<E::Fr> {
         let powers_of_gamma = iter::successors(Some(separation_challenge), |p| {
             Some(*p * separation_challenge)
         });
 
         let mut batched = polys[0].clone();
         for (p_i, gamma_pow_i) in polys.iter().skip(1).zip(powers_of_gamma) {
-            batched += (gamma_pow_i, p_i);
+            batched += (gamma_pow_i, *p_i);
         }
 
-        let q = &batched
-            / &DensePolynomial::from_coefficients_slice(&[-opening_challenge, E::Fr::one()]);
+        divide_by_linear(batched.coeffs, opening_challenge)
+    }
+
+    pub fn batch_open_g1(
+        srs: &[E::G1Affine],
+        polys: &[DensePolynomial<E::Fr>],
+        opening_challenge: E::Fr,
+        separation_challenge: E::Fr,
+    ) -> E::G1Affine {
+        let polys: Vec<_> = polys.iter().collect();
+        let q = Self::batch_open_quotient(&polys, opening_challenge, separation_challenge);
 
         if srs.len() - 1 < q.degree() {
             panic!(
@@ -80,3 +123,145 @@ impl<E: PairingEngine> Kzg<E> {
         Self::commit_g1(srs, &q).into()
     }
 }
+
+/// Window tables of srs points, for services that commit to many witnesses with the same key. Each point
+/// takes `ceil(|Fr| / window)·2^window` affine points, a commitment then costs `|Fr| / window` additions
+/// per coefficient and no doublings.
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct FixedBaseTables<E: PairingEngine> {
+    pub(crate) window: usize,
+    pub(crate) tables: Vec<Vec<Vec<E::G1Affine>>>,
+}
+
+impl<E: PairingEngine> FixedBaseTables<E> {
+    /// Tables for every point of `srs_g1`, pass `&pk.srs_g1()[..n]` to commit to witnesses of size `n`
+    pub fn new(srs_g1: &[E::G1Affine], window: usize) -> Self {
+        let scalar_size = E::Fr::size_in_bits();
+        let tables = cfg_iter!(srs_g1)
+            .map(|g| FixedBaseMSM::get_window_table(scalar_size, window, g.into_projective()))
+            .collect();
+        Self { window, tables }
+    }
+
+    pub fn commit_g1(&self, poly: &DensePolynomial<E::Fr>) -> Result<E::G1Projective, Error> {
+        if self.tables.len() < poly.coeffs.len() {
+            return Err(Error::SrsG1TooSmall(poly.coeffs.len(), self.tables.len()));
+        }
+        let outerc = (E::Fr::size_in_bits() + self.window - 1) / self.window;
+        Ok(cfg_iter!(poly.coeffs)
+            .zip(cfg_iter!(self.tables))
+            .map(|(c, table)| {
+                FixedBaseMSM::windowed_mul::<E::G1Projective>(outerc, self.window, table, c)
+            })
+            .sum())
+    }
+}
+
+/// The srs in Lagrange form [L_i(x)]_1 over a domain of size `n`, computed once and shared by the indexer, statement
+/// commitments and the Pedersen bridge instead of being derived from the powers of tau on every call
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct LagrangeSrs<E: PairingEngine> {
+    pub(crate) ls: Vec<E::G1Affine>,
+}
+
+impl<E: PairingEngine> LagrangeSrs<E> {
+    pub fn new(srs_g1: &[E::G1Affine], domain_size: usize) -> Result<Self, Error> {
+        if !is_pow_2(domain_size) {
+            return Err(Error::TableSizeNotPow2(domain_size));
+        }
+        if srs_g1.len() < domain_size {
+            return Err(Error::SrsG1TooSmall(domain_size, srs_g1.len()));
+        }
+        Ok(Self {
+            ls: compute_lagrange_basis_commitments(&srs_g1[..domain_size]),
+        })
+    }
+
+    pub fn domain_size(&self) -> usize {
+        self.ls.len()
+    }
+
+    pub fn bases(&self) -> &[E::G1Affine] {
+        &self.ls
+    }
+
+    /// Commitment to the polynomial taking the values `evals` over the domain, without an ifft
+    pub fn commit_evals(&self, evals: &[E::Fr]) -> Result<E::G1Projective, Error> {
+        if evals.len() != self.ls.len() {
+            return Err(Error::WitnessSizeMismatch(self.ls.len(), evals.len()));
+        }
+        Ok(Kzg::<E>::msm_g1(&self.ls, evals))
+    }
+}
+
+#[cfg(test)]
+mod kzg_tests {
+    use ark_bn254::{Bn254, Fr};
+    use ark_poly::{
+        univariate::DensePolynomial, EvaluationDomain, GeneralEvaluationDomain, UVPolynomial,
+    };
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+    use ark_std::{rand::rngs::StdRng, test_rng, UniformRand};
+
+    use crate::{error::Error, utils::unsafe_setup_from_rng};
+
+    use super::{FixedBaseTables, Kzg, LagrangeSrs};
+
+    #[test]
+    fn test_fixed_base_commit() {
+        let mut rng = test_rng();
+        let (srs_g1, _) = unsafe_setup_from_rng::<Bn254, StdRng>(15, 0, &mut rng);
+        let tables = FixedBaseTables::<Bn254>::new(&srs_g1[..8], 6);
+
+        let poly = DensePolynomial::<Fr>::rand(7, &mut rng);
+        assert_eq!(
+            tables.commit_g1(&poly).unwrap(),
+            Kzg::<Bn254>::commit_g1(&srs_g1, &poly)
+        );
+
+        let poly = DensePolynomial::<Fr>::rand(8, &mut rng);
+        assert_eq!(tables.commit_g1(&poly), Err(Error::SrsG1TooSmall(9, 8)));
+    }
+
+    #[test]
+    fn test_chunked_commit() {
+        let mut rng = test_rng();
+        let (srs_g1, _) = unsafe_setup_from_rng::<Bn254, StdRng>(7, 0, &mut rng);
+
+        let poly = DensePolynomial::<Fr>::rand(7, &mut rng);
+        assert_eq!(
+            Kzg::<Bn254>::commit_g1_chunked(&srs_g1, &poly, 3),
+            Ok(Kzg::<Bn254>::commit_g1(&srs_g1, &poly))
+        );
+
+        let poly = DensePolynomial::<Fr>::rand(8, &mut rng);
+        assert_eq!(
+            Kzg::<Bn254>::commit_g1_chunked(&srs_g1, &poly, 3),
+            Err(Error::SrsG1TooSmall(9, 8))
+        );
+    }
+
+    #[test]
+    fn test_lagrange_srs() {
+        let mut rng = test_rng();
+        let (srs_g1, _) = unsafe_setup_from_rng::<Bn254, StdRng>(7, 0, &mut rng);
+        let lagrange = LagrangeSrs::<Bn254>::new(&srs_g1, 8).unwrap();
+
+        let mut bytes = Vec::new();
+        lagrange.serialize(&mut bytes).unwrap();
+        let lagrange = LagrangeSrs::<Bn254>::deserialize(&bytes[..]).unwrap();
+
+        let domain = GeneralEvaluationDomain::<Fr>::new(8).unwrap();
+        let evals: Vec<Fr> = (0..8).map(|_| Fr::rand(&mut rng)).collect();
+        let poly = DensePolynomial::from_coefficients_vec(domain.ifft(&evals));
+        assert_eq!(
+            lagrange.commit_evals(&evals).unwrap(),
+            Kzg::<Bn254>::commit_g1(&srs_g1, &poly)
+        );
+
+        assert_eq!(
+            LagrangeSrs::<Bn254>::new(&srs_g1, 16).err(),
+            Some(Error::SrsG1TooSmall(16, 8))
+        );
+    }
+}
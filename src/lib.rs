@@ -1,21 +1,99 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "std")]
+pub mod ceremony;
+#[cfg(feature = "std")]
+pub mod checkpoint;
+pub mod config;
+pub mod cq;
 pub mod data_structures;
+pub mod decomposable;
+pub mod encoding;
 pub mod error;
+pub mod estimate;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod hash_to_field;
 pub mod indexer;
+#[cfg(all(feature = "serde", any(test, feature = "test-utils")))]
+pub mod interop;
+#[cfg(feature = "serde")]
+pub mod json;
+pub mod key_value;
 pub mod kzg;
+pub mod logup;
+pub mod lookup;
+#[cfg(feature = "mmap")]
+pub mod mapped_index;
+#[cfg(feature = "mmap")]
+pub mod mapped_srs;
+#[cfg(feature = "merlin")]
+pub mod merlin_transcript;
+pub mod metrics;
+pub mod multi_column;
+pub mod non_membership;
+pub mod pedersen;
+pub mod plus;
+#[cfg(feature = "poly-commit")]
+pub mod poly_commit;
+pub mod poseidon;
 pub mod prover;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod range;
+#[cfg(feature = "std")]
+pub mod registry;
 pub mod rng;
+pub mod segment;
+#[cfg(feature = "std")]
+pub mod service;
+#[cfg(feature = "std")]
+pub mod solidity;
+#[cfg(feature = "std")]
+pub mod srs;
 pub mod table;
+#[cfg(feature = "std")]
+pub mod table_loader;
+pub mod tables;
 pub mod tools;
 pub mod transcript;
 pub mod utils;
 pub mod verifier;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use config::{CqConfig, CqConfigBuilder};
+pub use cq::{Cq, CqProverKey, CqVerifierKey};
+pub use data_structures::{Proof, ProvingKey, Statement, StatementBlinder, Witness};
+pub use error::{Error, VerificationError};
+pub use estimate::{estimate, CostEstimate, CostModel};
+pub use indexer::{CommonPreprocessedInput, Index, IndexView, QuotientAlgorithm};
+pub use kzg::{FixedBaseTables, LagrangeSrs};
+pub use lookup::LookupArgument;
+pub use metrics::{ProverMetrics, TrackingAllocator};
+pub use poseidon::PoseidonFiatShamirRng;
+pub use prover::{Prover, ProverContext};
+pub use rng::{
+    CounterRng, DomainSeparatedFiatShamirRng, FiatShamirRng, KeccakCounterRng,
+    SimpleHashFiatShamirRng, TranscriptDigest, XofFiatShamirRng,
+};
+pub use table::Table;
+pub use verifier::{Challenges, DegreeBound, Verifier, VerifierKey};
 
 pub const PROTOCOL_NAME: &[u8] = b"CQ-1.0";
 
+/// `major << 4 | minor` of the version in `PROTOCOL_NAME`, carried by proofs and absorbed in every round
+pub const PROTOCOL_VERSION: u8 = ((PROTOCOL_NAME[3] - b'0') << 4) | (PROTOCOL_NAME[5] - b'0');
+
 #[cfg(test)]
 mod roundtrip_test {
+    use ark_bls12_377::Bls12_377;
+    use ark_bls12_381::Bls12_381;
     use ark_bn254::Bn254;
+    use ark_bw6_761::BW6_761;
     use ark_ec::PairingEngine;
+    use ark_ff::{BigInteger, FpParameters, PrimeField};
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
     use ark_std::{
         rand::{rngs::StdRng, Rng, RngCore},
         test_rng, UniformRand,
@@ -56,23 +134,21 @@ mod roundtrip_test {
         let table_values: Vec<_> = (0..n).map(|_| E::Fr::rand(rng)).collect();
         let table = Table::new(&table_values).unwrap();
 
-        let index = Index::<E>::gen(&pk.srs_g1, &srs_g2, &table);
+        let index = Index::<E>::gen(&pk.srs_g1, &srs_g2, &table).unwrap();
 
-        let witness_values: Vec<_> = subvector_indices.iter().map(|&i| table_values[i]).collect();
-        let witness = Witness::<E::Fr>::new(&witness_values).unwrap();
+        let witness = Witness::<E::Fr>::from_indices(&table, subvector_indices).unwrap();
 
         let statement = Statement::<E> {
             f: Kzg::<E>::commit_g1(&pk.srs_g1, &witness.f).into(),
         };
 
-        let vk = VerifierKey::<E>::new(&srs_g2, table.size, witness.size);
-        let common = Index::<E>::compute_common(&srs_g2, &table);
+        let vk = VerifierKey::<E>::for_table(&srs_g2, table.size, witness.size, false).unwrap();
+        let common = Index::<E>::compute_common(&srs_g2, &table).unwrap();
 
         (table, index, statement, common, pk, vk, witness)
     }
 
-    #[test]
-    fn test_roundtrip() {
+    fn roundtrip<E: PairingEngine>() {
         let two: usize = 2;
         let n = two.pow(6);
 
@@ -82,10 +158,99 @@ mod roundtrip_test {
         let subvector_indices: Vec<usize> =
             (0..witness_size).map(|_| rng.gen_range(0..n - 1)).collect();
 
+        let (table, index, statement, common, pk, vk, witness) =
+            prepare::<E, StdRng>(n, &subvector_indices, &mut rng);
+
+        let proof = Prover::<E, FS>::prove(&pk, &vk, &index, &table, &witness, &statement).unwrap();
+        let res = Verifier::<E, FS>::verify(&vk, &common, &statement, &proof);
+        assert!(res.is_ok());
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        roundtrip::<Bn254>();
+    }
+
+    #[test]
+    fn test_roundtrip_bls12_381() {
+        roundtrip::<Bls12_381>();
+    }
+
+    #[test]
+    fn test_roundtrip_bls12_377() {
+        roundtrip::<Bls12_377>();
+    }
+
+    #[test]
+    fn test_roundtrip_bw6_761() {
+        roundtrip::<BW6_761>();
+    }
+
+    #[test]
+    fn test_bw6_761_embeds_bls12_377() {
+        // a bls12-377 verifier is native arithmetic over the bw6-761 scalar field
+        assert_eq!(
+            <<BW6_761 as PairingEngine>::Fr as PrimeField>::Params::MODULUS.to_bytes_le(),
+            <<Bls12_377 as PairingEngine>::Fq as PrimeField>::Params::MODULUS.to_bytes_le()
+        );
+    }
+
+    #[test]
+    fn test_transcript_binds_public_input() {
+        let n = 16;
+        let mut rng = test_rng();
+        let (table, index, statement, common, pk, vk, witness) =
+            prepare::<Bn254, StdRng>(n, &[1, 3, 5, 7], &mut rng);
+        let proof =
+            Prover::<Bn254, FS>::prove(&pk, &vk, &index, &table, &witness, &statement).unwrap();
+        let challenges = Verifier::<Bn254, FS>::derive_challenges(&vk, &common, &statement, &proof);
+
+        let other_vk = VerifierKey::<Bn254>::from_parts(vk.x_2, vk.x_pow_b0_bound_2, n, 8);
+        let other_statement = Statement::<Bn254>::new(proof.first_msg.m_cm);
+        assert_ne!(
+            challenges,
+            Verifier::<Bn254, FS>::derive_challenges(&other_vk, &common, &statement, &proof)
+        );
+        assert_ne!(
+            challenges,
+            Verifier::<Bn254, FS>::derive_challenges(&vk, &common, &other_statement, &proof)
+        );
+    }
+
+    fn reencode<T: CanonicalSerialize + CanonicalDeserialize>(value: &T) -> T {
+        let mut bytes = vec![];
+        value.serialize(&mut bytes).unwrap();
+        let decoded = T::deserialize(&bytes[..]).unwrap();
+
+        let mut decoded_bytes = vec![];
+        decoded.serialize(&mut decoded_bytes).unwrap();
+        assert_eq!(bytes, decoded_bytes);
+
+        decoded
+    }
+
+    #[test]
+    fn test_serialization_roundtrip() {
+        let n = 32;
+        let mut rng = test_rng();
+
+        let subvector_indices: Vec<usize> = (0..8).map(|_| rng.gen_range(0..n - 1)).collect();
         let (table, index, statement, common, pk, vk, witness) =
             prepare::<Bn254, StdRng>(n, &subvector_indices, &mut rng);
 
-        let proof = Prover::<Bn254, FS>::prove(&pk, &index, &table, &witness, &statement).unwrap();
+        let table = reencode(&table);
+        let index = reencode(&index);
+        let pk = reencode(&pk);
+        assert_eq!(index.qs.len(), n);
+
+        let proof =
+            Prover::<Bn254, FS>::prove(&pk, &vk, &index, &table, &witness, &statement).unwrap();
+
+        let vk = reencode(&vk);
+        let common = reencode(&common);
+        let statement = reencode(&statement);
+        let proof = reencode(&proof);
+
         let res = Verifier::<Bn254, FS>::verify(&vk, &common, &statement, &proof);
         assert!(res.is_ok());
     }
@@ -0,0 +1,459 @@
+//! logUp over KZG, an alternative backend without table dependent preprocessing.
+//!
+//! The witness is padded with `t_0` to the table size and everything lives on the table domain V. With
+//! `A_j = m_j / (t_j + beta)` and `B_j = 1 / (f_j + beta)` the prover shows `A·(T + beta) = m` and
+//! `B·(f + beta) = 1` on V with one quotient `Q`, and `sum_V (A - B) = 0` through `A - B = X·R`. All seven
+//! polynomials are opened at `gamma` with a single batched proof. Proving is O(N log N) per proof instead of
+//! O(n log n), in exchange the key is a table commitment instead of N cached quotients.
+
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{batch_inversion, Field, One, Zero};
+use ark_poly::{
+    univariate::DensePolynomial, EvaluationDomain, GeneralEvaluationDomain, Polynomial,
+    UVPolynomial,
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+#[cfg(any(test, feature = "test-utils"))]
+use ark_std::rand::RngCore;
+use ark_std::{format, iter, marker::PhantomData, vec, vec::Vec};
+
+use crate::{
+    data_structures::Statement,
+    error::{Error, VerificationError},
+    kzg::Kzg,
+    lookup::LookupArgument,
+    rng::FiatShamirRng,
+    table::Table,
+    transcript::{Transcript, TranscriptOracle},
+    PROTOCOL_NAME, PROTOCOL_VERSION,
+};
+
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct LogUpVerifierKey<E: PairingEngine> {
+    pub(crate) x_2: E::G2Affine,
+    pub(crate) table_size: usize,
+    /// [T(x)]_1
+    pub(crate) t_cm: E::G1Affine,
+}
+
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct LogUpProverKey<E: PairingEngine> {
+    pub(crate) srs_g1: Vec<E::G1Affine>,
+    pub(crate) witness_size: usize,
+    pub(crate) table: Table<E::Fr>,
+    pub(crate) vk: LogUpVerifierKey<E>,
+}
+
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct LogUpProof<E: PairingEngine> {
+    pub(crate) version: u8,
+    pub m_cm: E::G1Affine,
+    pub a_cm: E::G1Affine,
+    pub b_cm: E::G1Affine,
+    pub q_cm: E::G1Affine,
+    pub r_cm: E::G1Affine,
+    pub a_at_gamma: E::Fr,
+    pub b_at_gamma: E::Fr,
+    pub t_at_gamma: E::Fr,
+    pub f_at_gamma: E::Fr,
+    pub m_at_gamma: E::Fr,
+    pub pi_gamma: E::G1Affine,
+}
+
+/// logUp backend of [`LookupArgument`], `FS` is the Fiat-Shamir rng
+pub struct LogUp<E: PairingEngine, FS: FiatShamirRng> {
+    _e: PhantomData<E>,
+    _fs: PhantomData<FS>,
+}
+
+struct LogUpChallenges<F> {
+    beta: F,
+    lambda: F,
+    gamma: F,
+    eta: F,
+}
+
+fn absorb_public_input<E: PairingEngine, T: Transcript<E>>(
+    transcript: &mut T,
+    vk: &LogUpVerifierKey<E>,
+    statement: &Statement<E>,
+) {
+    transcript.begin_round(PROTOCOL_VERSION, b"logup-public-input");
+    transcript.absorb_g2(b"x", &vk.x_2);
+    transcript.absorb_field(b"table_size", &E::Fr::from(vk.table_size as u64));
+    transcript.absorb_g1(b"t", &vk.t_cm);
+    transcript.absorb_g1(b"f", &statement.f);
+}
+
+fn absorb_commitments<E: PairingEngine, T: Transcript<E>>(
+    transcript: &mut T,
+    round: &'static [u8],
+    commitments: &[(&'static [u8], &E::G1Affine)],
+) {
+    transcript.begin_round(PROTOCOL_VERSION, round);
+    for &(label, cm) in commitments {
+        transcript.absorb_g1(label, cm);
+    }
+}
+
+fn absorb_evaluations<E: PairingEngine, T: Transcript<E>>(
+    transcript: &mut T,
+    proof: &LogUpProof<E>,
+) {
+    transcript.begin_round(PROTOCOL_VERSION, b"logup-round-4");
+    transcript.absorb_field(b"a_at_gamma", &proof.a_at_gamma);
+    transcript.absorb_field(b"b_at_gamma", &proof.b_at_gamma);
+    transcript.absorb_field(b"t_at_gamma", &proof.t_at_gamma);
+    transcript.absorb_field(b"f_at_gamma", &proof.f_at_gamma);
+    transcript.absorb_field(b"m_at_gamma", &proof.m_at_gamma);
+}
+
+fn derive_challenges<E: PairingEngine, T: Transcript<E>>(
+    transcript: &mut T,
+    vk: &LogUpVerifierKey<E>,
+    statement: &Statement<E>,
+    proof: &LogUpProof<E>,
+) -> LogUpChallenges<E::Fr> {
+    absorb_public_input(transcript, vk, statement);
+    absorb_commitments(transcript, b"logup-round-1", &[(b"m", &proof.m_cm)]);
+    let beta = transcript.squeeze_challenge(b"beta");
+    absorb_commitments(
+        transcript,
+        b"logup-round-2",
+        &[(b"a", &proof.a_cm), (b"b", &proof.b_cm)],
+    );
+    let lambda = transcript.squeeze_challenge(b"lambda");
+    absorb_commitments(
+        transcript,
+        b"logup-round-3",
+        &[(b"q", &proof.q_cm), (b"r", &proof.r_cm)],
+    );
+    let gamma = transcript.squeeze_challenge(b"gamma");
+    absorb_evaluations(transcript, proof);
+    let eta = transcript.squeeze_challenge(b"eta");
+
+    LogUpChallenges {
+        beta,
+        lambda,
+        gamma,
+        eta,
+    }
+}
+
+impl<E: PairingEngine, FS: FiatShamirRng> LogUp<E, FS> {
+    /// Samples an srs from `rng` and commits to the table, the caller must not be trusted by the verifier
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn setup<R: RngCore>(
+        table_values: &Vec<E::Fr>,
+        witness_size: usize,
+        rng: &mut R,
+    ) -> Result<(LogUpProverKey<E>, LogUpVerifierKey<E>), Error> {
+        let table_size = table_values.len();
+        if !table_size.is_power_of_two() {
+            return Err(Error::TableSizeNotPow2(table_size));
+        }
+        let (srs_g1, srs_g2) = crate::utils::unsafe_setup_from_rng::<E, R>(table_size - 1, 1, rng);
+        Self::keygen(table_values, witness_size, srs_g1, &srs_g2)
+    }
+
+    /// Commits to the table with an existing srs reaching `[x^(N-1)]_1` and `[x]_2`
+    pub fn keygen(
+        table_values: &Vec<E::Fr>,
+        witness_size: usize,
+        mut srs_g1: Vec<E::G1Affine>,
+        srs_g2: &[E::G2Affine],
+    ) -> Result<(LogUpProverKey<E>, LogUpVerifierKey<E>), Error> {
+        let table = Table::new(table_values)?;
+        if witness_size > table.size {
+            return Err(Error::WitnessLargerThanTable(witness_size, table.size));
+        }
+        if srs_g1.len() < table.size {
+            return Err(Error::SrsG1TooSmall(table.size, srs_g1.len()));
+        }
+        if srs_g2.len() < 2 {
+            return Err(Error::SrsG2TooSmall(2, srs_g2.len()));
+        }
+        srs_g1.truncate(table.size);
+
+        let domain = GeneralEvaluationDomain::<E::Fr>::new(table.size).unwrap();
+        let t_poly = DensePolynomial::from_coefficients_vec(domain.ifft(&table.values));
+        let vk = LogUpVerifierKey {
+            x_2: srs_g2[1],
+            table_size: table.size,
+            t_cm: Kzg::<E>::commit_g1(&srs_g1, &t_poly).into(),
+        };
+        let pk = LogUpProverKey {
+            srs_g1,
+            witness_size,
+            table,
+            vk: vk.clone(),
+        };
+        Ok((pk, vk))
+    }
+
+    /// Commits to the witness padded with the first table value and proves that it is in the table
+    pub fn prove(
+        pk: &LogUpProverKey<E>,
+        witness_values: &Vec<E::Fr>,
+    ) -> Result<(Statement<E>, LogUpProof<E>), Error> {
+        if witness_values.len() != pk.witness_size {
+            return Err(Error::WitnessSizeMismatch(
+                pk.witness_size,
+                witness_values.len(),
+            ));
+        }
+        let table = &pk.table;
+        let n_table = table.size;
+        let domain = GeneralEvaluationDomain::<E::Fr>::new(n_table).unwrap();
+        let commit = |poly: &DensePolynomial<E::Fr>| -> E::G1Affine {
+            Kzg::<E>::commit_g1(&pk.srs_g1, poly).into()
+        };
+
+        let mut f_evals = witness_values.clone();
+        f_evals.resize(n_table, table.values[0]);
+        let mut m_evals = vec![E::Fr::zero(); n_table];
+        for fi in &f_evals {
+            let index = table
+                .value_index_mapping
+                .get(fi)
+                .ok_or_else(|| Error::ValueNotInTable(format!("{}", fi)))?;
+            m_evals[*index] += E::Fr::one();
+        }
+
+        let f_poly = DensePolynomial::from_coefficients_vec(domain.ifft(&f_evals));
+        let t_poly = DensePolynomial::from_coefficients_vec(domain.ifft(&table.values));
+        let statement = Statement::new(commit(&f_poly));
+
+        let mut transcript = TranscriptOracle::<FS>::initialize(&PROTOCOL_NAME);
+        absorb_public_input(&mut transcript, &pk.vk, &statement);
+
+        // round 1: multiplicities
+        let m_poly = DensePolynomial::from_coefficients_vec(domain.ifft(&m_evals));
+        let m_cm = commit(&m_poly);
+        absorb_commitments(&mut transcript, b"logup-round-1", &[(b"m", &m_cm)]);
+        let beta = Transcript::<E>::squeeze_challenge(&mut transcript, b"beta");
+
+        // round 2: A and B, with one inversion for all 2N denominators
+        let mut inverses: Vec<_> = table
+            .values
+            .iter()
+            .chain(f_evals.iter())
+            .map(|&v| v + beta)
+            .collect();
+        batch_inversion(&mut inverses);
+        let mut b_evals = inverses.split_off(table.size);
+        let mut a_evals = inverses;
+        a_evals
+            .iter_mut()
+            .zip(m_evals.iter())
+            .for_each(|(aj, &mj)| *aj *= mj);
+        domain.ifft_in_place(&mut a_evals);
+        domain.ifft_in_place(&mut b_evals);
+        let a_poly = DensePolynomial::from_coefficients_vec(a_evals);
+        let b_poly = DensePolynomial::from_coefficients_vec(b_evals);
+        let (a_cm, b_cm) = (commit(&a_poly), commit(&b_poly));
+        absorb_commitments(
+            &mut transcript,
+            b"logup-round-2",
+            &[(b"a", &a_cm), (b"b", &b_cm)],
+        );
+        let lambda = Transcript::<E>::squeeze_challenge(&mut transcript, b"lambda");
+
+        // round 3: Q = (A·(T + beta) - m + lambda·(B·(f + beta) - 1)) / zV and R = (A - B) / X, the
+        // quotient has degree below N so N coset evaluations determine it
+        let [a_coset, b_coset, t_coset, f_coset, m_coset] =
+            [&a_poly, &b_poly, &t_poly, &f_poly, &m_poly].map(|p| domain.coset_fft(p));
+        let mut q_evals: Vec<_> = (0..n_table)
+            .map(|i| {
+                a_coset[i] * (t_coset[i] + beta) - m_coset[i]
+                    + lambda * (b_coset[i] * (f_coset[i] + beta) - E::Fr::one())
+            })
+            .collect();
+        domain.divide_by_vanishing_poly_on_coset_in_place(&mut q_evals);
+        let q_poly = DensePolynomial::from_coefficients_vec(domain.coset_ifft(&q_evals));
+        let a_minus_b = &a_poly - &b_poly;
+        let r_poly =
+            DensePolynomial::from_coefficients_slice(a_minus_b.coeffs.get(1..).unwrap_or(&[]));
+        let (q_cm, r_cm) = (commit(&q_poly), commit(&r_poly));
+        absorb_commitments(
+            &mut transcript,
+            b"logup-round-3",
+            &[(b"q", &q_cm), (b"r", &r_cm)],
+        );
+        let gamma = Transcript::<E>::squeeze_challenge(&mut transcript, b"gamma");
+
+        // round 4: evaluations and one batched opening
+        let mut proof = LogUpProof {
+            version: PROTOCOL_VERSION,
+            m_cm,
+            a_cm,
+            b_cm,
+            q_cm,
+            r_cm,
+            a_at_gamma: a_poly.evaluate(&gamma),
+            b_at_gamma: b_poly.evaluate(&gamma),
+            t_at_gamma: t_poly.evaluate(&gamma),
+            f_at_gamma: f_poly.evaluate(&gamma),
+            m_at_gamma: m_poly.evaluate(&gamma),
+            pi_gamma: E::G1Affine::zero(),
+        };
+        absorb_evaluations(&mut transcript, &proof);
+        let eta = Transcript::<E>::squeeze_challenge(&mut transcript, b"eta");
+        proof.pi_gamma = Kzg::<E>::batch_open_g1(
+            &pk.srs_g1,
+            &[a_poly, b_poly, t_poly, f_poly, m_poly, q_poly, r_poly],
+            gamma,
+            eta,
+        );
+
+        Ok((statement, proof))
+    }
+
+    pub fn verify(
+        vk: &LogUpVerifierKey<E>,
+        statement: &Statement<E>,
+        proof: &LogUpProof<E>,
+    ) -> Result<(), Error> {
+        if proof.version != PROTOCOL_VERSION {
+            return Err(Error::Verification(
+                VerificationError::UnsupportedProtocolVersion(proof.version),
+            ));
+        }
+        let LogUpChallenges {
+            beta,
+            lambda,
+            gamma,
+            eta,
+        } = derive_challenges(
+            &mut TranscriptOracle::<FS>::initialize(&PROTOCOL_NAME),
+            vk,
+            statement,
+            proof,
+        );
+
+        let domain = GeneralEvaluationDomain::<E::Fr>::new(vk.table_size).unwrap();
+        let zv_at_gamma = domain.evaluate_vanishing_polynomial(gamma);
+        let (a, b) = (proof.a_at_gamma, proof.b_at_gamma);
+        let q_at_gamma = (a * (proof.t_at_gamma + beta) - proof.m_at_gamma
+            + lambda * (b * (proof.f_at_gamma + beta) - E::Fr::one()))
+            * zv_at_gamma.inverse().unwrap();
+        let r_at_gamma = (a - b) * gamma.inverse().unwrap();
+
+        let commitments = [
+            proof.a_cm,
+            proof.b_cm,
+            vk.t_cm,
+            statement.f,
+            proof.m_cm,
+            proof.q_cm,
+            proof.r_cm,
+        ];
+        let evaluations = [
+            a,
+            b,
+            proof.t_at_gamma,
+            proof.f_at_gamma,
+            proof.m_at_gamma,
+            q_at_gamma,
+            r_at_gamma,
+        ];
+        let eta_powers: Vec<E::Fr> = iter::successors(Some(E::Fr::one()), |p| Some(*p * eta))
+            .take(commitments.len())
+            .collect();
+        let c = Kzg::<E>::msm_g1(&commitments, &eta_powers);
+        let v: E::Fr = evaluations
+            .iter()
+            .zip(&eta_powers)
+            .map(|(eval, eta_pow)| *eval * eta_pow)
+            .sum();
+
+        // e(C - v + gamma·pi, [1]) = e(pi, [x])
+        let g_1 = E::G1Affine::prime_subgroup_generator();
+        let lhs = c + g_1.mul(-v) + proof.pi_gamma.mul(gamma);
+        let res = E::product_of_pairings(&[
+            (
+                lhs.into_affine().into(),
+                E::G2Affine::prime_subgroup_generator().into(),
+            ),
+            ((-proof.pi_gamma).into(), vk.x_2.into()),
+        ]);
+
+        if res != E::Fqk::one() {
+            return Err(Error::Verification(VerificationError::BatchedPairingFailed));
+        }
+        Ok(())
+    }
+}
+
+impl<E: PairingEngine, FS: FiatShamirRng> LookupArgument<E> for LogUp<E, FS> {
+    type ProverKey = LogUpProverKey<E>;
+    type VerifierKey = LogUpVerifierKey<E>;
+    type Statement = Statement<E>;
+    type Proof = LogUpProof<E>;
+
+    #[cfg(any(test, feature = "test-utils"))]
+    fn keygen<R: RngCore>(
+        table_values: &Vec<E::Fr>,
+        witness_size: usize,
+        rng: &mut R,
+    ) -> Result<(Self::ProverKey, Self::VerifierKey), Error> {
+        Self::setup(table_values, witness_size, rng)
+    }
+
+    fn prove(
+        pk: &Self::ProverKey,
+        witness_values: &Vec<E::Fr>,
+    ) -> Result<(Self::Statement, Self::Proof), Error> {
+        LogUp::<E, FS>::prove(pk, witness_values)
+    }
+
+    fn verify(
+        vk: &Self::VerifierKey,
+        statement: &Self::Statement,
+        proof: &Self::Proof,
+    ) -> Result<(), Error> {
+        LogUp::<E, FS>::verify(vk, statement, proof)
+    }
+}
+
+#[cfg(test)]
+mod logup_tests {
+    use ark_bn254::{Bn254, Fr};
+    use ark_std::test_rng;
+    use rand_chacha::ChaChaRng;
+    use sha3::Keccak256;
+
+    use crate::{
+        error::{Error, VerificationError},
+        rng::SimpleHashFiatShamirRng,
+        utils::to_field,
+    };
+
+    use super::LogUp;
+
+    type FS = SimpleHashFiatShamirRng<Keccak256, ChaChaRng>;
+
+    #[test]
+    fn test_logup_rejects() {
+        let mut rng = test_rng();
+        let table_values = to_field::<Fr>(&[1, 5, 10, 15, 20, 25, 30, 35]);
+        let (pk, vk) = LogUp::<Bn254, FS>::setup(&table_values, 4, &mut rng).unwrap();
+
+        let (statement, mut proof) =
+            LogUp::<Bn254, FS>::prove(&pk, &to_field(&[5, 5, 20, 35])).unwrap();
+        assert!(LogUp::<Bn254, FS>::verify(&vk, &statement, &proof).is_ok());
+
+        proof.m_at_gamma += Fr::from(1u64);
+        let res = LogUp::<Bn254, FS>::verify(&vk, &statement, &proof);
+        assert_eq!(
+            res,
+            Err(Error::Verification(VerificationError::BatchedPairingFailed))
+        );
+
+        let res = LogUp::<Bn254, FS>::prove(&pk, &to_field(&[5, 6, 20, 35]));
+        assert_eq!(
+            res.err(),
+            Some(Error::ValueNotInTable(format!("{}", Fr::from(6u64))))
+        );
+    }
+}
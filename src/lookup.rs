@@ -0,0 +1,104 @@
+use ark_ec::PairingEngine;
+#[cfg(any(test, feature = "test-utils"))]
+use ark_std::rand::RngCore;
+use ark_std::vec::Vec;
+
+use crate::{
+    cq::{Cq, CqProverKey, CqVerifierKey},
+    data_structures::{Proof, Statement},
+    error::Error,
+    rng::FiatShamirRng,
+};
+
+/// Common interface of lookup arguments, so application code does not depend on a specific backend
+pub trait LookupArgument<E: PairingEngine> {
+    type ProverKey;
+    type VerifierKey;
+    type Statement;
+    type Proof;
+
+    /// Preprocesses the table for witnesses of `witness_size` with an srs sampled from `rng`
+    #[cfg(any(test, feature = "test-utils"))]
+    fn keygen<R: RngCore>(
+        table_values: &Vec<E::Fr>,
+        witness_size: usize,
+        rng: &mut R,
+    ) -> Result<(Self::ProverKey, Self::VerifierKey), Error>;
+
+    /// Proves that every witness value is in the table
+    fn prove(
+        pk: &Self::ProverKey,
+        witness_values: &Vec<E::Fr>,
+    ) -> Result<(Self::Statement, Self::Proof), Error>;
+
+    fn verify(
+        vk: &Self::VerifierKey,
+        statement: &Self::Statement,
+        proof: &Self::Proof,
+    ) -> Result<(), Error>;
+}
+
+impl<E: PairingEngine, FS: FiatShamirRng> LookupArgument<E> for Cq<E, FS> {
+    type ProverKey = CqProverKey<E>;
+    type VerifierKey = CqVerifierKey<E>;
+    type Statement = Statement<E>;
+    type Proof = Proof<E>;
+
+    #[cfg(any(test, feature = "test-utils"))]
+    fn keygen<R: RngCore>(
+        table_values: &Vec<E::Fr>,
+        witness_size: usize,
+        rng: &mut R,
+    ) -> Result<(Self::ProverKey, Self::VerifierKey), Error> {
+        Self::setup(table_values, witness_size, rng)
+    }
+
+    fn prove(
+        pk: &Self::ProverKey,
+        witness_values: &Vec<E::Fr>,
+    ) -> Result<(Self::Statement, Self::Proof), Error> {
+        Cq::<E, FS>::prove(pk, witness_values)
+    }
+
+    fn verify(
+        vk: &Self::VerifierKey,
+        statement: &Self::Statement,
+        proof: &Self::Proof,
+    ) -> Result<(), Error> {
+        Cq::<E, FS>::verify(vk, statement, proof)
+    }
+}
+
+#[cfg(test)]
+mod lookup_tests {
+    use ark_bn254::{Bn254, Fr};
+    use ark_std::test_rng;
+    use rand_chacha::ChaChaRng;
+    use sha3::Keccak256;
+
+    use crate::{cq::Cq, logup::LogUp, rng::SimpleHashFiatShamirRng, utils::to_field};
+
+    use super::LookupArgument;
+
+    type FS = SimpleHashFiatShamirRng<Keccak256, ChaChaRng>;
+
+    fn roundtrip<L: LookupArgument<Bn254>>() {
+        let mut rng = test_rng();
+
+        let table_values = to_field::<Fr>(&[1, 5, 10, 15, 20, 25, 30, 35]);
+        let (pk, vk) = L::keygen(&table_values, 4, &mut rng).unwrap();
+
+        let (statement, proof) = L::prove(&pk, &to_field(&[5, 15, 20, 35])).unwrap();
+        assert!(L::verify(&vk, &statement, &proof).is_ok());
+    }
+
+    #[test]
+    fn test_cq_lookup_argument() {
+        roundtrip::<Cq<Bn254, FS>>();
+    }
+
+    #[test]
+    fn test_logup_lookup_argument() {
+        roundtrip::<LogUp<Bn254, FS>>();
+    }
+}
@@ -1,118 +1,871 @@
-pub mod data_structures;
-pub mod error;
-pub mod indexer;
-pub mod kzg;
-pub mod prover;
-pub mod rng;
-pub mod table;
-pub mod tools;
-pub mod transcript;
-pub mod utils;
-pub mod verifier;
-
-pub const PROTOCOL_NAME: &[u8] = b"CQ-1.0";
-
-use std::time::{Instant};
-use std::cmp;
+use std::{
+    collections::HashMap,
+    env,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter},
+    process,
+    str::FromStr,
+    time::Duration,
+};
 
+use ark_bls12_377::Bls12_377;
+use ark_bls12_381::Bls12_381;
 use ark_bn254::Bn254;
 use ark_ec::PairingEngine;
-use ark_std::{
-    rand::{rngs::StdRng, Rng, RngCore},
-    test_rng, UniformRand,
+use ark_ff::PrimeField;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::rand::{rngs::StdRng, SeedableRng};
+use cqext::{
+    range::RangePlan,
+    srs::{self, Srs},
+    CommonPreprocessedInput, CostModel, CqConfig, Index, Proof, Prover, ProvingKey,
+    SimpleHashFiatShamirRng, Statement, Table, Verifier, VerifierKey, Witness,
 };
 use rand_chacha::ChaChaRng;
 use sha3::Keccak256;
 
-use crate::{
-    data_structures::{ProvingKey, Statement, Witness},
-    indexer::{CommonPreprocessedInput, Index},
-    kzg::Kzg,
-    prover::Prover,
-    rng::SimpleHashFiatShamirRng,
-    table::Table,
-    utils::unsafe_setup_from_rng,
-    verifier::{Verifier, VerifierKey},
-};
-
 type FS = SimpleHashFiatShamirRng<Keccak256, ChaChaRng>;
-type PrepareResult<E> = (
-    Table<<E as PairingEngine>::Fr>,
-    Index<E>,
-    Statement<E>,
-    CommonPreprocessedInput<E>,
-    ProvingKey<E>,
-    VerifierKey<E>,
-    Witness<<E as PairingEngine>::Fr>,
-);
 
-fn prepare<E: PairingEngine, R: RngCore>(
-    n: usize,
-    subvector_indices: &[usize],
-    rng: &mut R,
-) -> PrepareResult<E> {
-    let (srs_g1, srs_g2) = unsafe_setup_from_rng::<E, R>(n - 1, n, rng);
-    let pk = ProvingKey::<E> { srs_g1 };
+const USAGE: &str =
+    "usage: cqext <command> [--curve bn254|bls12-381|bls12-377] [--option value ...]
+
+commands:
+  setup   --table-size N --srs SRS [--seed S] [--witness-size M]
+          samples an UNSAFE srs for tables of size N and witnesses of up to
+          max(N, M) values, for testing only (test-utils builds)
+  index   --srs SRS [--srs-format FORMAT] --table TABLE --witness-size M --index INDEX --vk VK
+          [--checkpoint CHECKPOINT | --resume CHECKPOINT]
+          preprocesses the table, writes the prover index and the verifier key,
+          --checkpoint saves the progress to CHECKPOINT and --resume continues
+          a run that was interrupted
+  prove   --srs SRS [--srs-format FORMAT] --index INDEX --table TABLE --witness WITNESS
+          --statement STATEMENT --proof PROOF
+          commits to the witness and proves that all of its values are in the table
+  verify  --vk VK --statement STATEMENT --proof PROOF
+          exits with a non-zero status if the proof is rejected
+  bench   [--table-sizes LIST] [--lookup-sizes LIST] [--reps R] [--format csv|json]
+          [--variants cq,plus,zk] [--quotients fk|naive]
+          runs the CPRange benchmark over every (table size, lookup size) pair,
+          sizes are comma separated and may be written as 2^k, plus is the
+          variant with the smaller proof and zk proves the range of values
+          under a hiding commitment with blinded proofs.
+          Prints one row per run with setup, prove and verify times, proof bytes
+          and the peak resident memory of the process so far, --quotients picks
+          how the cq and plus indexes compute their cached quotients
+          (test-utils builds)
+
+  plan    --range-bits W --lookups M [--proofs P]
+          picks the limb size for proving values in [0, 2^W), M at a time,
+          that minimizes the estimated cost of indexing plus P proofs
+  estimate --table-size N --witness-size M [--calibrate yes]
+          predicts setup, prove and verify times, peak prover memory, index
+          and proof bytes, --calibrate measures the unit costs on this machine
+          instead of using the BN254 defaults
+
+SRS is a file written by setup (FORMAT cqext, the default) or a ceremony output:
+ptau for a snarkjs Powers of Tau file on bn254, eip4844 for the c-kzg
+trusted_setup.txt on bls12-381. Ceremony files are read in full, the degree
+bounds of the keys hold against their highest power. Aztec Ignition is not
+accepted, it only published [x]_2 and can't index tables.
 
-    let table_values: Vec<_> = (0..n).map(|_| E::Fr::rand(rng)).collect();
-    let table = Table::new(&table_values).unwrap();
+TABLE and WITNESS are text files with one decimal field element per line,
+all other files are produced by cqext and must be used with the curve they were
+created with, bn254 by default.";
 
-    let index = Index::<E>::gen(&pk.srs_g1, &srs_g2, &table);
+/// Pairing engines the binary can dispatch to
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Curve {
+    Bn254,
+    Bls12_381,
+    Bls12_377,
+}
+
+impl Curve {
+    fn name(self) -> &'static str {
+        match self {
+            Curve::Bn254 => "bn254",
+            Curve::Bls12_381 => "bls12-381",
+            Curve::Bls12_377 => "bls12-377",
+        }
+    }
+}
+
+impl FromStr for Curve {
+    type Err = String;
 
-    let witness_values: Vec<_> = subvector_indices.iter().map(|&i| table_values[i]).collect();
-    let witness = Witness::<E::Fr>::new(&witness_values).unwrap();
+    fn from_str(name: &str) -> Result<Self, String> {
+        match name {
+            "bn254" => Ok(Curve::Bn254),
+            "bls12-381" => Ok(Curve::Bls12_381),
+            "bls12-377" => Ok(Curve::Bls12_377),
+            _ => Err(format!(
+                "unknown curve `{}`, expected bn254, bls12-381 or bls12-377",
+                name
+            )),
+        }
+    }
+}
+
+/// Subcommands that sample their own srs, so the machine running them knows tau. Only built with `test-utils`.
+#[cfg(feature = "test-utils")]
+mod testing {
+    use std::{
+        fs,
+        str::FromStr,
+        time::{Duration, Instant},
+    };
 
-    let statement = Statement::<E> {
-        f: Kzg::<E>::commit_g1(&pk.srs_g1, &witness.f).into(),
+    use ark_ec::PairingEngine;
+    use ark_serialize::CanonicalSerialize;
+    use ark_std::{
+        rand::{rngs::StdRng, Rng, RngCore, SeedableRng},
+        test_rng, UniformRand,
     };
+    use cqext::{
+        plus::{PlusProver, PlusVerifier, PlusVerifierKey},
+        utils::unsafe_setup_from_rng,
+        CommonPreprocessedInput, Cq, CqConfig, Index, Prover, ProvingKey, QuotientAlgorithm,
+        Statement, Table, Verifier, Witness,
+    };
+
+    use super::{create, millis, Args, Curve, FS};
+
+    /// Prover/verifier pairs the benchmark can run
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Variant {
+        Cq,
+        Plus,
+        Zk,
+    }
+
+    impl Variant {
+        fn name(self) -> &'static str {
+            match self {
+                Variant::Cq => "cq",
+                Variant::Plus => "plus",
+                Variant::Zk => "zk",
+            }
+        }
+    }
+
+    impl FromStr for Variant {
+        type Err = String;
+
+        fn from_str(name: &str) -> Result<Self, String> {
+            match name.trim() {
+                "cq" => Ok(Variant::Cq),
+                "plus" => Ok(Variant::Plus),
+                "zk" => Ok(Variant::Zk),
+                _ => Err(format!(
+                    "unknown variant `{}`, expected cq, plus or zk",
+                    name
+                )),
+            }
+        }
+    }
+
+    fn parse_quotients(name: &str) -> Result<QuotientAlgorithm, String> {
+        match name.trim() {
+            "fk" => Ok(QuotientAlgorithm::Fk),
+            "naive" => Ok(QuotientAlgorithm::Naive),
+            _ => Err(format!(
+                "unknown quotient algorithm `{}`, expected fk or naive",
+                name
+            )),
+        }
+    }
+
+    type PrepareResult<E> = (
+        Table<<E as PairingEngine>::Fr>,
+        Index<E>,
+        Statement<E>,
+        CommonPreprocessedInput<E>,
+        ProvingKey<E>,
+        PlusVerifierKey<E>,
+        Witness<<E as PairingEngine>::Fr>,
+    );
+
+    fn prepare<E: PairingEngine, R: RngCore>(
+        n: usize,
+        subvector_indices: &[usize],
+        quotients: QuotientAlgorithm,
+        rng: &mut R,
+    ) -> PrepareResult<E> {
+        // lookups above twice the table push the degree bound of A past x^N in G2
+        let config = CqConfig::<E, FS>::builder()
+            .table_size(n)
+            .witness_size(subvector_indices.len())
+            .build()
+            .unwrap();
+        let (srs_g1, srs_g2) =
+            unsafe_setup_from_rng::<E, R>(config.srs_g1_degree(), config.srs_g2_degree(), rng);
+        let pk = ProvingKey::<E>::new(srs_g1);
+
+        let table_values: Vec<_> = (0..n).map(|_| E::Fr::rand(rng)).collect();
+        let table = Table::new(&table_values).unwrap();
+
+        let index = Index::<E>::gen_with(&pk.srs_g1()[..n], &srs_g2, &table, quotients).unwrap();
+
+        let witness = Witness::<E::Fr>::from_indices(&table, subvector_indices).unwrap();
+
+        let statement = Statement::<E>::from_witness(&pk, &witness);
+
+        let vk = PlusVerifierKey::<E>::for_table(&srs_g2, table.size(), witness.size()).unwrap();
+        let common = Index::<E>::compute_common(&srs_g2, &table).unwrap();
+
+        (table, index, statement, common, pk, vk, witness)
+    }
+
+    struct BenchRow {
+        curve: Curve,
+        variant: Variant,
+        table_size: usize,
+        lookup_size: usize,
+        rep: usize,
+        setup: Duration,
+        prove: Duration,
+        verify: Duration,
+        proof_bytes: usize,
+        peak_rss_kb: Option<u64>,
+    }
+
+    impl BenchRow {
+        const CSV_HEADER: &'static str =
+            "curve,variant,table_size,lookup_size,rep,setup_ms,prove_ms,verify_ms,proof_bytes,peak_rss_kb";
+
+        fn csv(&self) -> String {
+            format!(
+                "{},{},{},{},{},{:.3},{:.3},{:.3},{},{}",
+                self.curve.name(),
+                self.variant.name(),
+                self.table_size,
+                self.lookup_size,
+                self.rep,
+                millis(self.setup),
+                millis(self.prove),
+                millis(self.verify),
+                self.proof_bytes,
+                self.peak_rss_kb
+                    .map(|kb| kb.to_string())
+                    .unwrap_or_default()
+            )
+        }
+
+        fn json(&self) -> String {
+            format!(
+                "{{\"curve\":\"{}\",\"variant\":\"{}\",\"table_size\":{},\"lookup_size\":{},\"rep\":{},\"setup_ms\":{:.3},\"prove_ms\":{:.3},\"verify_ms\":{:.3},\"proof_bytes\":{},\"peak_rss_kb\":{}}}",
+                self.curve.name(),
+                self.variant.name(),
+                self.table_size,
+                self.lookup_size,
+                self.rep,
+                millis(self.setup),
+                millis(self.prove),
+                millis(self.verify),
+                self.proof_bytes,
+                self.peak_rss_kb
+                    .map(|kb| kb.to_string())
+                    .unwrap_or_else(|| "null".to_string())
+            )
+        }
+    }
+
+    /// High water mark of the resident set of this process, only available on linux
+    fn peak_rss_kb() -> Option<u64> {
+        let status = fs::read_to_string("/proc/self/status").ok()?;
+        let line = status.lines().find(|l| l.starts_with("VmHWM:"))?;
+        line.split_whitespace().nth(1)?.parse().ok()
+    }
+
+    fn measure_cq<E: PairingEngine>(
+        curve: Curve,
+        variant: Variant,
+        table_size: usize,
+        lookup_size: usize,
+        rep: usize,
+        quotients: QuotientAlgorithm,
+    ) -> BenchRow {
+        if variant == Variant::Zk {
+            return measure_zk_range::<E>(curve, table_size, lookup_size, rep);
+        }
+        let n = table_size;
+
+        let mut rng = test_rng();
+
+        let witness_size = lookup_size;
+        let subvector_indices: Vec<usize> =
+            (0..witness_size).map(|_| rng.gen_range(0..n)).collect();
+
+        let start = Instant::now();
+        let (table, index, statement, common, pk, vk, witness) =
+            prepare::<E, StdRng>(n, &subvector_indices, quotients, &mut rng);
+        let setup = start.elapsed();
+
+        let (prove, verify, proof_bytes) = match variant {
+            Variant::Cq => {
+                let start = Instant::now();
+                let proof =
+                    Prover::<E, FS>::prove(&pk, vk.vk(), &index, &table, &witness, &statement)
+                        .unwrap();
+                let prove = start.elapsed();
+
+                let start = Instant::now();
+                let res = Verifier::<E, FS>::verify(vk.vk(), &common, &statement, &proof);
+                let verify = start.elapsed();
+                assert!(res.is_ok());
+                (prove, verify, proof.serialized_size())
+            }
+            Variant::Plus => {
+                let start = Instant::now();
+                let proof =
+                    PlusProver::<E, FS>::prove(&pk, &vk, &index, &table, &witness, &statement)
+                        .unwrap();
+                let prove = start.elapsed();
+
+                let start = Instant::now();
+                let res = PlusVerifier::<E, FS>::verify(&vk, &common, &statement, &proof);
+                let verify = start.elapsed();
+                assert!(res.is_ok());
+                (prove, verify, proof.serialized_size())
+            }
+        };
+
+        BenchRow {
+            curve,
+            variant,
+            table_size,
+            lookup_size,
+            rep,
+            setup,
+            prove,
+            verify,
+            proof_bytes,
+            peak_rss_kb: peak_rss_kb(),
+        }
+    }
+
+    /// CPRange proper: values in `[0, N)` behind a hiding commitment, proven with blinded messages so neither the
+    /// statement nor the proof reveals anything beyond the range
+    fn measure_zk_range<E: PairingEngine>(
+        curve: Curve,
+        table_size: usize,
+        lookup_size: usize,
+        rep: usize,
+    ) -> BenchRow {
+        let mut rng = test_rng();
+
+        let start = Instant::now();
+        let table = Table::range(0..table_size as u64).unwrap();
+        let config = CqConfig::<E, FS>::builder()
+            .table_size(table_size)
+            .witness_size(lookup_size)
+            .zk(true)
+            .build()
+            .unwrap();
+        let (pk, vk) = config.unsafe_setup(table, &mut rng).unwrap();
+        let setup = start.elapsed();
+
+        let values: Vec<E::Fr> = (0..lookup_size)
+            .map(|_| E::Fr::from(rng.gen_range(0..table_size as u64)))
+            .collect();
+        let start = Instant::now();
+        let (statement, blinder) = Cq::<E, FS>::commit_hiding(&pk, &values, &mut rng).unwrap();
+        let proof = Cq::<E, FS>::prove_hiding(&pk, &statement, &values, blinder, &mut rng).unwrap();
+        let prove = start.elapsed();
+
+        let start = Instant::now();
+        let res = Cq::<E, FS>::verify(&vk, &statement, &proof);
+        let verify = start.elapsed();
+        assert!(res.is_ok());
+
+        BenchRow {
+            curve,
+            variant: Variant::Zk,
+            table_size,
+            lookup_size,
+            rep,
+            setup,
+            prove,
+            verify,
+            proof_bytes: proof.serialized_size(),
+            peak_rss_kb: peak_rss_kb(),
+        }
+    }
+
+    /// Parses a comma separated list of sizes, each either decimal or `2^k`
+    fn parse_sizes(list: &str) -> Result<Vec<usize>, String> {
+        list.split(',')
+            .map(|size| {
+                let size = size.trim();
+                let parsed = match size.strip_prefix("2^") {
+                    Some(k) => k.parse::<u32>().ok().and_then(|k| 2usize.checked_pow(k)),
+                    None => size.parse().ok(),
+                };
+                parsed.ok_or_else(|| format!("`{}` is not a size", size))
+            })
+            .collect()
+    }
+
+    pub(super) fn bench<E: PairingEngine>(curve: Curve, args: &Args) -> Result<(), String> {
+        // defaults reproduce the CPRange(2^16, 2 * d * m) workload with d = m = 2^6
+        let table_sizes = parse_sizes(args.get_or("table-sizes", "2^16"))?;
+        let lookup_sizes = parse_sizes(args.get_or("lookup-sizes", "2^13"))?;
+        let reps = if args.has("reps") {
+            args.get_usize("reps")?
+        } else {
+            1
+        };
+        let format = args.get_or("format", "csv");
+        let variants = args
+            .get_or("variants", "cq")
+            .split(',')
+            .map(Variant::from_str)
+            .collect::<Result<Vec<_>, _>>()?;
+        let quotients = parse_quotients(args.get_or("quotients", "fk"))?;
+
+        for &n in &table_sizes {
+            if !n.is_power_of_two() || n < 2 {
+                return Err(format!("table size {} is not a power of two >= 2", n));
+            }
+        }
+        for &m in &lookup_sizes {
+            if !m.is_power_of_two() || m < 2 {
+                return Err(format!("lookup size {} is not a power of two >= 2", m));
+            }
+        }
+
+        let emit: fn(&BenchRow) -> String = match format {
+            "csv" => {
+                println!("{}", BenchRow::CSV_HEADER);
+                BenchRow::csv
+            }
+            "json" => BenchRow::json,
+            _ => return Err(format!("unknown format `{}`, expected csv or json", format)),
+        };
+
+        for &table_size in &table_sizes {
+            for &lookup_size in &lookup_sizes {
+                for rep in 0..reps {
+                    for &variant in &variants {
+                        if variant == Variant::Zk && lookup_size >= table_size {
+                            // the hiding commitment has degree n + 1, the zk srs stops at x^(N+1)
+                            eprintln!(
+                                "# skipping zk with lookup size {} >= table size {}",
+                                lookup_size, table_size
+                            );
+                            continue;
+                        }
+                        eprintln!(
+                            "# CPRange({},{}) {} rep {}",
+                            table_size,
+                            lookup_size,
+                            variant.name(),
+                            rep
+                        );
+                        let row = measure_cq::<E>(
+                            curve,
+                            variant,
+                            table_size,
+                            lookup_size,
+                            rep,
+                            quotients,
+                        );
+                        println!("{}", emit(&row));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn setup<E: PairingEngine>(args: &Args) -> Result<(), String> {
+        let table_size = args.get_usize("table-size")?;
+        if !table_size.is_power_of_two() {
+            return Err(format!("table size {} is not a power of two", table_size));
+        }
 
-    let vk = VerifierKey::<E>::new(&srs_g2, table.size, witness.size);
-    let common = Index::<E>::compute_common(&srs_g2, &table);
+        let mut rng = if args.has("seed") {
+            StdRng::seed_from_u64(args.get_usize("seed")? as u64)
+        } else {
+            StdRng::from_entropy()
+        };
+        // witnesses larger than the table need G1 powers up to x^(n-1)
+        let witness_size = if args.has("witness-size") {
+            args.get_usize("witness-size")?
+        } else {
+            table_size
+        };
+        let config = CqConfig::<E, FS>::builder()
+            .table_size(table_size)
+            .witness_size(witness_size)
+            .build()
+            .map_err(|e| format!("{:?}", e))?;
+        let (srs_g1, srs_g2) = unsafe_setup_from_rng::<E, StdRng>(
+            config.srs_g1_degree(),
+            config.srs_g2_degree(),
+            &mut rng,
+        );
 
-    (table, index, statement, common, pk, vk, witness)
+        let path = args.get("srs")?;
+        let mut writer = create(path)?;
+        srs_g1
+            .serialize(&mut writer)
+            .and_then(|_| srs_g2.serialize(&mut writer))
+            .map_err(|e| format!("{}: {:?}", path, e))
+    }
+
+    #[cfg(test)]
+    mod bench_tests {
+        use ark_bn254::Bn254;
+
+        use super::{bench, Args, Curve};
+
+        fn args(raw: &[&str]) -> Args {
+            let raw: Vec<String> = raw.iter().map(|arg| arg.to_string()).collect();
+            Args::parse(&raw).unwrap()
+        }
+
+        #[test]
+        fn test_table_size_below_2() {
+            let res = bench::<Bn254>(
+                Curve::Bn254,
+                &args(&["--table-sizes", "1", "--lookup-sizes", "2"]),
+            );
+            assert_eq!(
+                res.err(),
+                Some("table size 1 is not a power of two >= 2".to_string())
+            );
+        }
+
+        #[test]
+        fn test_lookups_above_twice_the_table() {
+            let res = bench::<Bn254>(
+                Curve::Bn254,
+                &args(&[
+                    "--table-sizes",
+                    "4",
+                    "--lookup-sizes",
+                    "16",
+                    "--variants",
+                    "cq,plus",
+                ]),
+            );
+            assert_eq!(res, Ok(()));
+        }
+    }
+}
+
+fn millis(d: Duration) -> f64 {
+    d.as_secs_f64() * 1000.0
 }
 
-fn measure_cq(msg:String, table_size:usize, lookup_size:usize) {
-    let two: usize = 2;
+fn plan<E: PairingEngine>(args: &Args) -> Result<(), String> {
+    let range_bits = args.get_usize("range-bits")? as u32;
+    let lookups = args.get_usize("lookups")?;
+    let proofs = if args.has("proofs") {
+        args.get_usize("proofs")?
+    } else {
+        1
+    };
+    let plan =
+        RangePlan::new::<E::Fr>(range_bits, lookups, proofs).map_err(|e| format!("{:?}", e))?;
+    println!(
+        "limb_bits {} limbs {} setup_cost {} prove_cost {} total_cost {}",
+        plan.limb_bits,
+        plan.limbs,
+        plan.setup_cost,
+        plan.prove_cost,
+        plan.total_cost(proofs)
+    );
+    Ok(())
+}
 
-    let n = table_size;
+fn estimate<E: PairingEngine>(args: &Args) -> Result<(), String> {
+    let table_size = args.get_usize("table-size")?;
+    let witness_size = args.get_usize("witness-size")?;
+    let model = if args.has("calibrate") {
+        CostModel::calibrate::<E, _>(&mut StdRng::from_entropy())
+    } else {
+        CostModel::default()
+    };
+    let cost = model.estimate::<E>(table_size, witness_size);
+    println!(
+        "setup_ms {:.3} prove_ms {:.3} verify_ms {:.3} peak_prover_bytes {} index_bytes {} proof_bytes {}",
+        millis(cost.setup),
+        millis(cost.prove),
+        millis(cost.verify),
+        cost.peak_prover_memory,
+        cost.index_bytes,
+        cost.proof_bytes
+    );
+    Ok(())
+}
 
-    let mut rng = test_rng();
+struct Args {
+    options: HashMap<String, String>,
+}
+
+impl Args {
+    fn parse(raw: &[String]) -> Result<Self, String> {
+        let mut options = HashMap::new();
+        let mut raw = raw.iter();
+        while let Some(key) = raw.next() {
+            let key = key
+                .strip_prefix("--")
+                .ok_or_else(|| format!("unexpected argument `{}`", key))?;
+            let value = raw
+                .next()
+                .ok_or_else(|| format!("missing value for `--{}`", key))?;
+            options.insert(key.to_string(), value.clone());
+        }
+        Ok(Self { options })
+    }
 
-    let witness_size = lookup_size;
-    let subvector_indices: Vec<usize> =
-        (0..witness_size).map(|_| rng.gen_range(0..n - 1)).collect();
+    fn has(&self, key: &str) -> bool {
+        self.options.contains_key(key)
+    }
 
-    let start = Instant::now();
-    let (table, index, statement, common, pk, vk, witness) =
-        prepare::<Bn254, StdRng>(n, &subvector_indices, &mut rng);
-    let duration = start.elapsed();
-    println!("# Setup took: {:?}", duration);
+    fn get(&self, key: &str) -> Result<&str, String> {
+        self.options
+            .get(key)
+            .map(|v| v.as_str())
+            .ok_or_else(|| format!("missing `--{}`", key))
+    }
 
-    // measure proving time 
-    let start = Instant::now();
-    let proof = Prover::<Bn254, FS>::prove(&pk, &index, &table, &witness, &statement).unwrap();
-    let duration = start.elapsed();
-    println!("# {} proving took: {:?}", msg, duration);
+    fn get_or<'a>(&'a self, key: &str, default: &'a str) -> &'a str {
+        self.options.get(key).map(|v| v.as_str()).unwrap_or(default)
+    }
 
-    let res = Verifier::<Bn254, FS>::verify(&vk, &common, &statement, &proof);
-    assert!(res.is_ok());
+    fn get_usize(&self, key: &str) -> Result<usize, String> {
+        let value = self.get(key)?;
+        value
+            .parse()
+            .map_err(|_| format!("`--{}` expects a number, got `{}`", key, value))
+    }
+}
 
+fn read_values<F: PrimeField>(path: &str) -> Result<Vec<F>, String> {
+    let file = File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+    let mut values = Vec::new();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| format!("{}: {}", path, e))?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let value = F::from_str(line)
+            .map_err(|_| format!("{}:{}: `{}` is not a field element", path, i + 1, line))?;
+        values.push(value);
+    }
+    Ok(values)
 }
 
-fn measure_cprange(B:usize, n:usize)
-{
-    let two: usize = 2;
-    measure_cq(format!("CPRange({B},{n})"), B, n);
+fn create(path: &str) -> Result<BufWriter<File>, String> {
+    File::create(path)
+        .map(BufWriter::new)
+        .map_err(|e| format!("{}: {}", path, e))
+}
+
+fn open(path: &str) -> Result<BufReader<File>, String> {
+    File::open(path)
+        .map(BufReader::new)
+        .map_err(|e| format!("{}: {}", path, e))
+}
+
+fn write_artifact<T: CanonicalSerialize>(path: &str, artifact: &T) -> Result<(), String> {
+    artifact
+        .serialize(create(path)?)
+        .map_err(|e| format!("{}: {:?}", path, e))
+}
+
+fn read_artifact<T: CanonicalDeserialize>(path: &str) -> Result<T, String> {
+    T::deserialize(open(path)?).map_err(|e| format!("{}: {:?}", path, e))
+}
+
+type SrsParts<E> = (
+    Vec<<E as PairingEngine>::G1Affine>,
+    Vec<<E as PairingEngine>::G2Affine>,
+);
+
+/// Ceremony formats the srs of a curve can be read from
+trait Ceremony: PairingEngine {
+    /// formats accepted by `--srs-format` for this curve
+    const FORMATS: &'static str;
+
+    /// The whole srs of the ceremony file at `path`, or `None` if `format` isn't one of this curve
+    fn load_ceremony(format: &str, path: &str) -> Option<Result<Srs<Self>, cqext::Error>>;
+}
+
+impl Ceremony for Bn254 {
+    const FORMATS: &'static str = "cqext or ptau";
+
+    fn load_ceremony(format: &str, path: &str) -> Option<Result<Srs<Self>, cqext::Error>> {
+        (format == "ptau").then(|| {
+            let (max_power_g1, max_power_g2) = srs::ptau_max_powers(path)?;
+            Srs::load_ptau(path, max_power_g1, max_power_g2)
+        })
+    }
+}
+
+impl Ceremony for Bls12_381 {
+    const FORMATS: &'static str = "cqext or eip4844";
+
+    fn load_ceremony(format: &str, path: &str) -> Option<Result<Srs<Self>, cqext::Error>> {
+        (format == "eip4844").then(|| {
+            Srs::load_eip4844(path, srs::EIP4844_G1_POWERS - 1, srs::EIP4844_G2_POWERS - 1)
+        })
+    }
+}
+
+impl Ceremony for Bls12_377 {
+    const FORMATS: &'static str = "cqext";
+
+    fn load_ceremony(_format: &str, _path: &str) -> Option<Result<Srs<Self>, cqext::Error>> {
+        None
+    }
+}
+
+/// Reads `--srs` in the `--srs-format` of the curve, cqext files are the ones `setup` writes
+fn read_srs<E: Ceremony>(args: &Args, curve: Curve) -> Result<SrsParts<E>, String> {
+    let path = args.get("srs")?;
+    let format = args.get_or("srs-format", "cqext");
+    if format == "cqext" {
+        let mut reader = open(path)?;
+        let srs_g1 = Vec::<E::G1Affine>::deserialize(&mut reader)
+            .map_err(|e| format!("{}: {:?}", path, e))?;
+        let srs_g2 = Vec::<E::G2Affine>::deserialize(&mut reader)
+            .map_err(|e| format!("{}: {:?}", path, e))?;
+        return Ok((srs_g1, srs_g2));
+    }
+    match E::load_ceremony(format, path) {
+        Some(srs) => srs
+            .map(Srs::into_parts)
+            .map_err(|e| format!("{}: {:?}", path, e)),
+        None => Err(format!(
+            "unknown srs format `{}` for {}, expected {}",
+            format,
+            curve.name(),
+            E::FORMATS
+        )),
+    }
+}
+
+fn index<E: Ceremony>(curve: Curve, args: &Args) -> Result<(), String> {
+    let (srs_g1, srs_g2) = read_srs::<E>(args, curve)?;
+    let table = Table::padded(&read_values(args.get("table")?)?).map_err(|e| format!("{:?}", e))?;
+
+    let config = CqConfig::<E, FS>::builder()
+        .table_size(table.size())
+        .witness_size(args.get_usize("witness-size")?)
+        .build()
+        .map_err(|e| format!("{:?}", e))?;
+    let index = if args.has("resume") {
+        config.index_checkpointed(&srs_g1, &srs_g2, &table, args.get("resume")?, true)
+    } else if args.has("checkpoint") {
+        config.index_checkpointed(&srs_g1, &srs_g2, &table, args.get("checkpoint")?, false)
+    } else {
+        config.index(&srs_g1, &srs_g2, &table)
+    }
+    .map_err(|e| format!("{:?}", e))?;
+    // the bounds are checked against the highest power of the srs file, however large it is
+    let vk = VerifierKey::<E>::for_srs_degree(
+        &srs_g2,
+        srs_g1.len() - 1,
+        config.table_size(),
+        config.witness_size(),
+        false,
+    )
+    .map_err(|e| format!("{:?}", e))?;
+
+    write_artifact(args.get("index")?, &index)?;
+
+    let path = args.get("vk")?;
+    let mut writer = create(path)?;
+    vk.serialize(&mut writer)
+        .and_then(|_| index.common().serialize(&mut writer))
+        .map_err(|e| format!("{}: {:?}", path, e))
+}
+
+fn prove<E: Ceremony>(curve: Curve, args: &Args) -> Result<(), String> {
+    let (srs_g1, srs_g2) = read_srs::<E>(args, curve)?;
+    let srs_degree = srs_g1.len().saturating_sub(1);
+    let pk = ProvingKey::<E>::new(srs_g1);
+    let index: Index<E> = read_artifact(args.get("index")?)?;
+    let table = Table::padded(&read_values(args.get("table")?)?).map_err(|e| format!("{:?}", e))?;
+    let witness_values = read_values(args.get("witness")?)?;
+    let witness = Witness::new(&witness_values).map_err(|e| format!("{:?}", e))?;
+    let vk = VerifierKey::<E>::for_srs_degree(
+        &srs_g2,
+        srs_degree,
+        table.size(),
+        witness_values.len(),
+        false,
+    )
+    .map_err(|e| format!("{:?}", e))?;
+
+    let statement = Statement::from_witness(&pk, &witness);
+    let proof = Prover::<E, FS>::prove(&pk, &vk, &index, &table, &witness, &statement)
+        .map_err(|e| format!("{:?}", e))?;
+
+    write_artifact(args.get("statement")?, &statement)?;
+    write_artifact(args.get("proof")?, &proof)
+}
+
+fn verify<E: PairingEngine>(args: &Args) -> Result<(), String> {
+    let path = args.get("vk")?;
+    let mut reader = open(path)?;
+    let vk =
+        VerifierKey::<E>::deserialize(&mut reader).map_err(|e| format!("{}: {:?}", path, e))?;
+    let common = CommonPreprocessedInput::<E>::deserialize(&mut reader)
+        .map_err(|e| format!("{}: {:?}", path, e))?;
+    let statement: Statement<E> = read_artifact(args.get("statement")?)?;
+    let proof: Proof<E> = read_artifact(args.get("proof")?)?;
+
+    Verifier::<E, FS>::verify(&vk, &common, &statement, &proof)
+        .map_err(|e| format!("proof rejected: {:?}", e))?;
+    println!("proof accepted");
+    Ok(())
+}
+
+fn run<E: Ceremony>(cmd: &str, curve: Curve, args: &Args) -> Result<(), String> {
+    match cmd {
+        #[cfg(feature = "test-utils")]
+        "setup" => testing::setup::<E>(args),
+        #[cfg(feature = "test-utils")]
+        "bench" => testing::bench::<E>(curve, args),
+        #[cfg(not(feature = "test-utils"))]
+        "setup" | "bench" => Err(format!(
+            "`{}` samples its own srs and is only built with --features test-utils",
+            cmd
+        )),
+        "index" => index::<E>(curve, args),
+        "prove" => prove::<E>(curve, args),
+        "verify" => verify::<E>(args),
+        "plan" => plan::<E>(args),
+        "estimate" => estimate::<E>(args),
+        _ => Err(USAGE.to_string()),
+    }
 }
 
 fn main() {
-    let two: usize = 2;
-    let B = two.pow(16);
-    let d:usize = two.pow(6); // should be roughly 1K
-    let m = two.pow(6); // should be roughly 2K
-    let num_cpranges: usize = 2*d*m;  // should be roughly 4M
-
-    measure_cprange( B, num_cpranges);
-    
-}
\ No newline at end of file
+    let raw: Vec<String> = env::args().skip(1).collect();
+    let res = match raw.first().map(|s| s.as_str()) {
+        Some(cmd @ ("setup" | "index" | "prove" | "verify" | "bench" | "plan" | "estimate")) => {
+            Args::parse(&raw[1..]).and_then(|args| {
+                let curve: Curve = args.get_or("curve", "bn254").parse()?;
+                match curve {
+                    Curve::Bn254 => run::<Bn254>(cmd, curve, &args),
+                    Curve::Bls12_381 => run::<Bls12_381>(cmd, curve, &args),
+                    Curve::Bls12_377 => run::<Bls12_377>(cmd, curve, &args),
+                }
+            })
+        }
+        _ => Err(USAGE.to_string()),
+    };
+
+    if let Err(e) = res {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,271 @@
+//! Memory mappable on disk layout of an [`Index`], so proving against a large table only reads the
+//! commitments selected by the witness instead of deserializing the whole index on every run.
+//!
+//! | bytes          | content                                                                   |
+//! |----------------|---------------------------------------------------------------------------|
+//! | 0..8           | cqex header with kind [`ArtifactKind::MappedIndex`], always uncompressed  |
+//! | 8..16          | table size n, little endian                                               |
+//! | 16..16+c       | `CommonPreprocessedInput`                                                 |
+//! | then 3·n·g     | [Qi(x)]_1, [Li(x)]_1 and [(Li(x) - Li(0)) / x]_1 for i in 0..n            |
+//!
+//! All points are uncompressed so every entry has the same size `g` and can be located without parsing the
+//! ones before it.
+
+use std::{fs::File, io::Write, path::Path};
+
+use ark_ec::AffineCurve;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::Zero;
+use memmap2::Mmap;
+
+use crate::{
+    encoding::{ArtifactKind, Compression, CurveId, ENCODING_VERSION, HEADER_SIZE, MAGIC},
+    error::Error,
+    indexer::{CommonPreprocessedInput, Index, IndexView},
+};
+
+const SIZE_BYTES: usize = 8;
+
+fn io_err(e: std::io::Error) -> Error {
+    Error::Io(e.to_string())
+}
+
+fn serialization_err(e: ark_serialize::SerializationError) -> Error {
+    Error::Serialization(format!("{:?}", e))
+}
+
+impl<E: CurveId> Index<E> {
+    /// Writes the index in the layout read by [`MappedIndex`]
+    pub fn write_mapped<W: Write>(&self, mut w: W) -> Result<(), Error> {
+        w.write_all(&MAGIC).map_err(io_err)?;
+        w.write_all(&[
+            ENCODING_VERSION,
+            E::CURVE_ID,
+            ArtifactKind::MappedIndex as u8,
+            Compression::Uncompressed as u8,
+        ])
+        .map_err(io_err)?;
+        w.write_all(&(self.qs.len() as u64).to_le_bytes())
+            .map_err(io_err)?;
+        self.common
+            .serialize_uncompressed(&mut w)
+            .map_err(serialization_err)?;
+        for p in self.qs.iter().chain(&self.ls).chain(&self.ls_at_0) {
+            p.serialize_uncompressed(&mut w)
+                .map_err(serialization_err)?;
+        }
+        Ok(())
+    }
+}
+
+/// Index backed by bytes in the [`Index::write_mapped`] layout, points are decoded when the prover asks for them
+pub struct MappedIndex<E: CurveId, B: AsRef<[u8]> = Mmap> {
+    bytes: B,
+    common: CommonPreprocessedInput<E>,
+    table_size: usize,
+    point_size: usize,
+    points_offset: usize,
+}
+
+impl<E: CurveId> MappedIndex<E> {
+    /// Maps an index file, the file must not be modified while the index is alive
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = File::open(path).map_err(io_err)?;
+        // the mapping is read only and we only hand out decoded copies of its contents
+        let mmap = unsafe { Mmap::map(&file) }.map_err(io_err)?;
+        Self::from_bytes(mmap)
+    }
+}
+
+impl<E: CurveId, B: AsRef<[u8]>> MappedIndex<E, B> {
+    /// Checks the header and the length of `bytes` and decodes the common input
+    pub fn from_bytes(bytes: B) -> Result<Self, Error> {
+        let data = bytes.as_ref();
+        if data.len() < HEADER_SIZE + SIZE_BYTES || data[..4] != MAGIC {
+            return Err(Error::InvalidEncoding("missing cqex header"));
+        }
+        if data[4] != ENCODING_VERSION {
+            return Err(Error::UnsupportedEncodingVersion(data[4]));
+        }
+        if data[5] != E::CURVE_ID {
+            return Err(Error::CurveMismatch(E::CURVE_ID, data[5]));
+        }
+        if data[6] != ArtifactKind::MappedIndex as u8 {
+            return Err(Error::ArtifactKindMismatch(
+                ArtifactKind::MappedIndex as u8,
+                data[6],
+            ));
+        }
+        if data[7] != Compression::Uncompressed as u8 {
+            return Err(Error::InvalidEncoding("mapped indices are uncompressed"));
+        }
+
+        let mut size = [0u8; SIZE_BYTES];
+        size.copy_from_slice(&data[HEADER_SIZE..HEADER_SIZE + SIZE_BYTES]);
+        let table_size = u64::from_le_bytes(size) as usize;
+
+        let mut reader = &data[HEADER_SIZE + SIZE_BYTES..];
+        let common = CommonPreprocessedInput::<E>::deserialize_uncompressed(&mut reader)
+            .map_err(serialization_err)?;
+        let points_offset = data.len() - reader.len();
+        let point_size = E::G1Affine::zero().uncompressed_size();
+
+        let expected_len = table_size
+            .checked_mul(3 * point_size)
+            .and_then(|len| len.checked_add(points_offset));
+        if expected_len != Some(data.len()) {
+            return Err(Error::InvalidEncoding("mapped index length mismatch"));
+        }
+
+        Ok(Self {
+            bytes,
+            common,
+            table_size,
+            point_size,
+            points_offset,
+        })
+    }
+
+    pub fn table_size(&self) -> usize {
+        self.table_size
+    }
+
+    fn point(&self, section: usize, i: usize) -> Result<E::G1Affine, Error> {
+        if i >= self.table_size {
+            return Err(Error::IndexOutOfTable(i, self.table_size));
+        }
+        let start = self.points_offset + (section * self.table_size + i) * self.point_size;
+        let mut reader = &self.bytes.as_ref()[start..start + self.point_size];
+        E::G1Affine::deserialize_uncompressed(&mut reader).map_err(serialization_err)
+    }
+
+    /// Decodes every entry into an in memory index
+    pub fn to_index(&self) -> Result<Index<E>, Error> {
+        let section = |s| {
+            (0..self.table_size)
+                .map(|i| self.point(s, i))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(Index {
+            common: CommonPreprocessedInput::new(self.common.zv_2, self.common.t_2),
+            qs: section(0)?,
+            ls: section(1)?,
+            ls_at_0: section(2)?,
+            zk_t: Vec::new(),
+        })
+    }
+}
+
+impl<E: CurveId, B: AsRef<[u8]>> IndexView<E> for MappedIndex<E, B> {
+    fn common(&self) -> &CommonPreprocessedInput<E> {
+        &self.common
+    }
+
+    fn q(&self, i: usize) -> Result<E::G1Affine, Error> {
+        self.point(0, i)
+    }
+
+    fn l(&self, i: usize) -> Result<E::G1Affine, Error> {
+        self.point(1, i)
+    }
+
+    fn l_at_0(&self, i: usize) -> Result<E::G1Affine, Error> {
+        self.point(2, i)
+    }
+
+    /// the mapped layout has no zk commitments
+    fn t_1(&self) -> Result<(E::G1Affine, E::G1Affine), Error> {
+        Err(Error::MissingZkCommitments)
+    }
+}
+
+#[cfg(test)]
+mod mapped_index_tests {
+    use std::fs;
+
+    use ark_bn254::{Bn254, Fr};
+    use ark_std::{rand::rngs::StdRng, test_rng};
+    use rand_chacha::ChaChaRng;
+    use sha3::Keccak256;
+
+    use crate::{
+        data_structures::{ProvingKey, Statement, Witness},
+        encoding::{Artifact, Compression},
+        error::Error,
+        indexer::{Index, IndexView},
+        prover::Prover,
+        rng::SimpleHashFiatShamirRng,
+        table::Table,
+        utils::{to_field, unsafe_setup_from_rng},
+        verifier::{Verifier, VerifierKey},
+    };
+
+    use super::MappedIndex;
+
+    type FS = SimpleHashFiatShamirRng<Keccak256, ChaChaRng>;
+
+    #[test]
+    fn test_prove_with_mapped_index() {
+        let mut rng = test_rng();
+        let (srs_g1, srs_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(7, 8, &mut rng);
+        let table = Table::new(&to_field::<Fr>(&[1, 5, 10, 15, 20, 25, 30, 35])).unwrap();
+        let index = Index::<Bn254>::gen(&srs_g1, &srs_g2, &table).unwrap();
+
+        let mut bytes = vec![];
+        index.write_mapped(&mut bytes).unwrap();
+        let path = std::env::temp_dir().join(format!("cqext-index-{}.bin", std::process::id()));
+        fs::write(&path, &bytes).unwrap();
+        let mapped = MappedIndex::<Bn254>::open(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(mapped.table_size(), 8);
+
+        let witness = Witness::<Fr>::new(&to_field(&[5, 15, 20, 35])).unwrap();
+        let pk = ProvingKey::<Bn254>::new(srs_g1);
+        let statement = Statement::from_witness(&pk, &witness);
+        let vk = VerifierKey::<Bn254>::for_table(&srs_g2, 8, 4, false).unwrap();
+        let proof =
+            Prover::<Bn254, FS>::prove(&pk, &vk, &mapped, &table, &witness, &statement).unwrap();
+        let expected =
+            Prover::<Bn254, FS>::prove(&pk, &vk, &index, &table, &witness, &statement).unwrap();
+        assert_eq!(
+            proof.encode(Compression::Compressed),
+            expected.encode(Compression::Compressed)
+        );
+
+        assert!(Verifier::<Bn254, FS>::verify(&vk, index.common(), &statement, &proof).is_ok());
+
+        let decoded = mapped.to_index().unwrap();
+        assert!(
+            decoded.qs == index.qs && decoded.ls == index.ls && decoded.ls_at_0 == index.ls_at_0
+        );
+    }
+
+    #[test]
+    fn test_mapped_index_checks() {
+        let mut rng = test_rng();
+        let (srs_g1, srs_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(3, 4, &mut rng);
+        let table = Table::new(&to_field::<Fr>(&[1, 2, 3, 4])).unwrap();
+        let mut bytes = vec![];
+        Index::<Bn254>::gen(&srs_g1, &srs_g2, &table)
+            .unwrap()
+            .write_mapped(&mut bytes)
+            .unwrap();
+
+        let mapped = MappedIndex::<Bn254, _>::from_bytes(&bytes[..]).unwrap();
+        assert_eq!(
+            IndexView::l(&mapped, 4).err(),
+            Some(Error::IndexOutOfTable(4, 4))
+        );
+
+        let res = MappedIndex::<Bn254, _>::from_bytes(&bytes[..bytes.len() - 1]);
+        assert_eq!(
+            res.err(),
+            Some(Error::InvalidEncoding("mapped index length mismatch"))
+        );
+
+        let mut wrong_kind = bytes.clone();
+        wrong_kind[6] = 5;
+        let res = MappedIndex::<Bn254, _>::from_bytes(wrong_kind);
+        assert_eq!(res.err(), Some(Error::ArtifactKindMismatch(7, 5)));
+    }
+}
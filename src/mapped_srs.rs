@@ -0,0 +1,290 @@
+//! cqext srs file, memory mapped so that indexing a small table against a multi-GB ceremony only reads and checks
+//! the G1 powers it uses.
+//!
+//! | bytes              | content                                                                       |
+//! |--------------------|-------------------------------------------------------------------------------|
+//! | 0..8               | cqex header with kind [`ArtifactKind::Srs`], always uncompressed              |
+//! | 8..16              | number of G1 points, little endian                                            |
+//! | 16..24             | number of G2 points                                                           |
+//! | 24..32             | G1 points per checksum chunk                                                  |
+//! | then n2·g2         | [x^0..n2]_2                                                                   |
+//! | then 32 per chunk  | Keccak256 of every chunk of G1 points                                         |
+//! | then 32            | Keccak256 of everything before it                                             |
+//! | then n1·g1         | [x^0..n1]_1                                                                   |
+//!
+//! Opening checks the header checksum, so the sizes, the G2 points and the chunk checksums are trusted from then
+//! on; reading G1 points checks the chunks they fall into. The checksums only catch corrupt or truncated files,
+//! `MappedSrs::load` still checks the structure of the points it returns.
+
+use std::{fs::File, io::Write, ops::Range, path::Path};
+
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::Zero;
+use memmap2::Mmap;
+use sha3::{Digest, Keccak256};
+
+use crate::{
+    encoding::{ArtifactKind, Compression, CurveId, ENCODING_VERSION, HEADER_SIZE, MAGIC},
+    error::Error,
+    srs::{Srs, StructureCheck},
+};
+
+/// Bytes of the three sizes after the header
+const SIZES_BYTES: usize = 24;
+const CHECKSUM_BYTES: usize = 32;
+/// 2^16 points, 4 MiB of uncompressed BN254 G1 points
+pub const DEFAULT_CHUNK_POINTS: usize = 1 << 16;
+
+fn io_err(e: std::io::Error) -> Error {
+    Error::Io(e.to_string())
+}
+
+fn serialization_err(e: ark_serialize::SerializationError) -> Error {
+    Error::Serialization(format!("{:?}", e))
+}
+
+fn read_u64(bytes: &[u8], at: usize) -> usize {
+    let mut buf = [0u8; 8];
+    buf.copy_from_slice(&bytes[at..at + 8]);
+    u64::from_le_bytes(buf) as usize
+}
+
+impl<E: CurveId> Srs<E> {
+    /// Writes the srs in the layout read by [`MappedSrs`], with a checksum per `chunk_points` G1 points
+    pub fn write_file<W: Write>(&self, mut w: W, chunk_points: usize) -> Result<(), Error> {
+        let chunk_points = chunk_points.max(1);
+        let mut head = MAGIC.to_vec();
+        head.extend_from_slice(&[
+            ENCODING_VERSION,
+            E::CURVE_ID,
+            ArtifactKind::Srs as u8,
+            Compression::Uncompressed as u8,
+        ]);
+        for size in [self.g1.len(), self.g2.len(), chunk_points] {
+            head.extend_from_slice(&(size as u64).to_le_bytes());
+        }
+        for p in &self.g2 {
+            p.serialize_uncompressed(&mut head)
+                .map_err(serialization_err)?;
+        }
+
+        let mut g1 = Vec::with_capacity(self.g1.len() * E::G1Affine::zero().uncompressed_size());
+        for chunk in self.g1.chunks(chunk_points) {
+            let start = g1.len();
+            for p in chunk {
+                p.serialize_uncompressed(&mut g1)
+                    .map_err(serialization_err)?;
+            }
+            head.extend_from_slice(&Keccak256::digest(&g1[start..]));
+        }
+        let checksum = Keccak256::digest(&head);
+        head.extend_from_slice(&checksum);
+
+        w.write_all(&head).map_err(io_err)?;
+        w.write_all(&g1).map_err(io_err)
+    }
+}
+
+/// Srs backed by bytes in the [`Srs::write_file`] layout, G1 points are decoded and checked on demand
+pub struct MappedSrs<E: CurveId, B: AsRef<[u8]> = Mmap> {
+    bytes: B,
+    g2: Vec<E::G2Affine>,
+    g1_len: usize,
+    chunk_points: usize,
+    checksums_offset: usize,
+    g1_offset: usize,
+    point_size: usize,
+}
+
+impl<E: CurveId> MappedSrs<E> {
+    /// Maps an srs file, the file must not be modified while the srs is alive
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = File::open(path).map_err(io_err)?;
+        // the mapping is read only and we only hand out decoded copies of its contents
+        let mmap = unsafe { Mmap::map(&file) }.map_err(io_err)?;
+        Self::from_bytes(mmap)
+    }
+}
+
+impl<E: CurveId, B: AsRef<[u8]>> MappedSrs<E, B> {
+    /// Checks the header, its checksum and the length of `bytes` and decodes the G2 points
+    pub fn from_bytes(bytes: B) -> Result<Self, Error> {
+        let data = bytes.as_ref();
+        if data.len() < HEADER_SIZE + SIZES_BYTES || data[..4] != MAGIC {
+            return Err(Error::InvalidEncoding("missing cqex header"));
+        }
+        if data[4] != ENCODING_VERSION {
+            return Err(Error::UnsupportedEncodingVersion(data[4]));
+        }
+        if data[5] != E::CURVE_ID {
+            return Err(Error::CurveMismatch(E::CURVE_ID, data[5]));
+        }
+        if data[6] != ArtifactKind::Srs as u8 {
+            return Err(Error::ArtifactKindMismatch(
+                ArtifactKind::Srs as u8,
+                data[6],
+            ));
+        }
+        if data[7] != Compression::Uncompressed as u8 {
+            return Err(Error::InvalidEncoding("srs files are uncompressed"));
+        }
+
+        let g1_len = read_u64(data, HEADER_SIZE);
+        let g2_len = read_u64(data, HEADER_SIZE + 8);
+        let chunk_points = read_u64(data, HEADER_SIZE + 16);
+        if g1_len == 0 || g2_len == 0 || chunk_points == 0 {
+            return Err(Error::InvalidEncoding("empty srs"));
+        }
+        let point_size = E::G1Affine::zero().uncompressed_size();
+        let g2_size = E::G2Affine::zero().uncompressed_size();
+
+        let g2_offset = HEADER_SIZE + SIZES_BYTES;
+        let checksums_offset = g2_len
+            .checked_mul(g2_size)
+            .and_then(|len| len.checked_add(g2_offset));
+        let chunks = g1_len / chunk_points + (g1_len % chunk_points != 0) as usize;
+        let g1_offset = checksums_offset.and_then(|offset| {
+            (chunks + 1)
+                .checked_mul(CHECKSUM_BYTES)
+                .and_then(|len| len.checked_add(offset))
+        });
+        let expected_len = g1_offset.and_then(|offset| {
+            g1_len
+                .checked_mul(point_size)
+                .and_then(|len| len.checked_add(offset))
+        });
+        let (checksums_offset, g1_offset) = match (checksums_offset, g1_offset, expected_len) {
+            (Some(c), Some(g), Some(len)) if len == data.len() => (c, g),
+            _ => return Err(Error::InvalidEncoding("srs file length mismatch")),
+        };
+
+        let head = g1_offset - CHECKSUM_BYTES;
+        if Keccak256::digest(&data[..head]).as_slice() != &data[head..g1_offset] {
+            return Err(Error::InvalidEncoding("srs header checksum mismatch"));
+        }
+        let mut reader = &data[g2_offset..checksums_offset];
+        let g2 = (0..g2_len)
+            .map(|_| E::G2Affine::deserialize_uncompressed(&mut reader))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(serialization_err)?;
+
+        Ok(Self {
+            bytes,
+            g2,
+            g1_len,
+            chunk_points,
+            checksums_offset,
+            g1_offset,
+            point_size,
+        })
+    }
+
+    pub fn max_power_g1(&self) -> usize {
+        self.g1_len - 1
+    }
+
+    pub fn max_power_g2(&self) -> usize {
+        self.g2.len() - 1
+    }
+
+    pub fn g2(&self) -> &[E::G2Affine] {
+        &self.g2
+    }
+
+    /// Decodes `[x^i]_1` for `i` in `range`, after checking the checksums of the chunks it falls into
+    pub fn g1_range(&self, range: Range<usize>) -> Result<Vec<E::G1Affine>, Error> {
+        if range.end > self.g1_len {
+            return Err(Error::SrsG1TooSmall(range.end, self.g1_len));
+        }
+        if range.is_empty() {
+            return Ok(Vec::new());
+        }
+        let data = self.bytes.as_ref();
+        let chunks = range.start / self.chunk_points..(range.end - 1) / self.chunk_points + 1;
+        for chunk in chunks {
+            let start = chunk * self.chunk_points;
+            let end = (start + self.chunk_points).min(self.g1_len);
+            let points = &data
+                [self.g1_offset + start * self.point_size..self.g1_offset + end * self.point_size];
+            let checksum = self.checksums_offset + chunk * CHECKSUM_BYTES;
+            if Keccak256::digest(points).as_slice() != &data[checksum..checksum + CHECKSUM_BYTES] {
+                return Err(Error::InvalidEncoding("srs chunk checksum mismatch"));
+            }
+        }
+
+        let mut reader = &data[self.g1_offset + range.start * self.point_size
+            ..self.g1_offset + range.end * self.point_size];
+        range
+            .map(|_| E::G1Affine::deserialize_uncompressed(&mut reader))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(serialization_err)
+    }
+
+    /// `[x^0..=x^max_power_g1]_1` and `[x^0..=x^max_power_g2]_2` as an in memory srs, checked with `check`
+    pub fn load(
+        &self,
+        max_power_g1: usize,
+        max_power_g2: usize,
+        check: StructureCheck,
+    ) -> Result<Srs<E>, Error> {
+        if max_power_g2 >= self.g2.len() {
+            return Err(Error::SrsG2TooSmall(max_power_g2 + 1, self.g2.len()));
+        }
+        let g1 = self.g1_range(0..max_power_g1 + 1)?;
+        Srs::from_external_with(g1, self.g2[..=max_power_g2].to_vec(), check)
+    }
+}
+
+#[cfg(test)]
+mod mapped_srs_tests {
+    use std::fs;
+
+    use ark_bn254::Bn254;
+    use ark_std::{rand::rngs::StdRng, test_rng};
+
+    use crate::{
+        error::Error,
+        srs::{Srs, StructureCheck},
+        utils::unsafe_setup_from_rng,
+    };
+
+    use super::MappedSrs;
+
+    #[test]
+    fn test_mapped_srs() {
+        let mut rng = test_rng();
+        let (srs_g1, srs_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(40, 16, &mut rng);
+        let srs = Srs::<Bn254>::from_external(srs_g1.clone(), srs_g2).unwrap();
+
+        let mut bytes = vec![];
+        srs.write_file(&mut bytes, 16).unwrap();
+        let path = std::env::temp_dir().join(format!("cqext-srs-{}.bin", std::process::id()));
+        fs::write(&path, &bytes).unwrap();
+        let mapped = MappedSrs::<Bn254>::open(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!((mapped.max_power_g1(), mapped.max_power_g2()), (40, 16));
+
+        let small = mapped.load(7, 8, StructureCheck::Full).unwrap();
+        assert_eq!(small, srs.truncate(7, 8).unwrap());
+        assert_eq!(mapped.g1_range(20..41).unwrap(), &srs_g1[20..]);
+        assert_eq!(
+            mapped.g1_range(0..42).err(),
+            Some(Error::SrsG1TooSmall(42, 41))
+        );
+
+        // a flipped bit in the last chunk only fails reads of that chunk
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        let corrupt = MappedSrs::<Bn254, _>::from_bytes(&bytes[..]).unwrap();
+        assert!(corrupt.load(7, 8, StructureCheck::Full).is_ok());
+        assert_eq!(
+            corrupt.g1_range(32..41).err(),
+            Some(Error::InvalidEncoding("srs chunk checksum mismatch"))
+        );
+        let res = MappedSrs::<Bn254, _>::from_bytes(&bytes[..bytes.len() - 1]);
+        assert_eq!(
+            res.err(),
+            Some(Error::InvalidEncoding("srs file length mismatch"))
+        );
+    }
+}
@@ -0,0 +1,200 @@
+//! Fiat-Shamir over a Merlin transcript, so a cq proof can be one step of a larger Merlin based protocol.
+//!
+//! Standalone proofs use [`MerlinFiatShamirRng`] like any other rng, which appends every absorbed input as a
+//! `cq-message` and draws every challenge as a `cq-challenge`. [`prove_in_transcript`] and
+//! [`verify_in_transcript`] continue a transcript the caller already appended to, with a label per element.
+
+use ark_ec::PairingEngine;
+use ark_ff::{to_bytes, PrimeField, ToBytes};
+use ark_std::{rand::RngCore, vec::Vec};
+use merlin::Transcript;
+
+use crate::{
+    data_structures::{Proof, ProvingKey, Statement, Witness},
+    error::Error,
+    indexer::{CommonPreprocessedInput, IndexView},
+    prover::Prover,
+    rng::FiatShamirRng,
+    table::Table,
+    transcript::round_separator,
+    verifier::{Verifier, VerifierKey},
+    PROTOCOL_NAME,
+};
+
+const TRANSCRIPT_LABEL: &[u8] = b"cqext";
+const INIT_LABEL: &[u8] = b"cq-init";
+const MESSAGE_LABEL: &[u8] = b"cq-message";
+const CHALLENGE_LABEL: &[u8] = b"cq-challenge";
+
+/// `FiatShamirRng` appending to a Merlin transcript
+pub struct MerlinFiatShamirRng {
+    transcript: Transcript,
+}
+
+impl MerlinFiatShamirRng {
+    pub fn into_transcript(self) -> Transcript {
+        self.transcript
+    }
+}
+
+impl RngCore for MerlinFiatShamirRng {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.transcript.challenge_bytes(CHALLENGE_LABEL, dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ark_std::rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl FiatShamirRng for MerlinFiatShamirRng {
+    fn initialize<'a, T: 'a + ToBytes>(initial_input: &'a T) -> Self {
+        let mut transcript = Transcript::new(TRANSCRIPT_LABEL);
+        transcript.append_message(INIT_LABEL, &to_bytes![initial_input].unwrap());
+        Self { transcript }
+    }
+
+    fn absorb<'a, T: 'a + ToBytes>(&mut self, new_input: &'a T) {
+        let mut bytes = Vec::new();
+        new_input
+            .write(&mut bytes)
+            .expect("failed to convert to bytes");
+        self.transcript.append_message(MESSAGE_LABEL, &bytes);
+    }
+}
+
+impl<E: PairingEngine> crate::transcript::Transcript<E> for Transcript {
+    fn begin_round(&mut self, version: u8, label: &'static [u8]) {
+        self.append_message(b"cq-round", &round_separator(version, label));
+    }
+
+    fn absorb_g1(&mut self, label: &'static [u8], point: &E::G1Affine) {
+        self.append_message(label, &to_bytes![point].unwrap());
+    }
+
+    fn absorb_g2(&mut self, label: &'static [u8], point: &E::G2Affine) {
+        self.append_message(label, &to_bytes![point].unwrap());
+    }
+
+    fn absorb_field(&mut self, label: &'static [u8], x: &E::Fr) {
+        self.append_message(label, &to_bytes![x].unwrap());
+    }
+
+    /// 64 bytes reduced modulo r, so the challenge is statistically close to uniform
+    fn squeeze_challenge(&mut self, label: &'static [u8]) -> E::Fr {
+        let mut bytes = [0u8; 64];
+        self.challenge_bytes(label, &mut bytes);
+        E::Fr::from_le_bytes_mod_order(&bytes)
+    }
+}
+
+/// Proves the lookup as the next step of `transcript`, every element is appended under its own label
+pub fn prove_in_transcript<E: PairingEngine>(
+    transcript: &mut Transcript,
+    pk: &ProvingKey<E>,
+    vk: &VerifierKey<E>,
+    index: &dyn IndexView<E>,
+    table: &Table<E::Fr>,
+    witness: &Witness<E::Fr>,
+    statement: &Statement<E>,
+) -> Result<Proof<E>, Error> {
+    transcript.append_message(INIT_LABEL, PROTOCOL_NAME);
+    Prover::<E, Transcript>::prove_with_transcript(
+        transcript, pk, vk, index, table, witness, statement,
+    )
+    .map(|(proof, _)| proof)
+}
+
+/// Verifies a proof made with [`prove_in_transcript`] on a transcript in the same state
+pub fn verify_in_transcript<E: PairingEngine>(
+    transcript: &mut Transcript,
+    vk: &VerifierKey<E>,
+    common: &CommonPreprocessedInput<E>,
+    statement: &Statement<E>,
+    proof: &Proof<E>,
+) -> Result<(), Error> {
+    transcript.append_message(INIT_LABEL, PROTOCOL_NAME);
+    Verifier::<E, Transcript>::verify_with_transcript(transcript, vk, common, statement, proof)
+}
+
+#[cfg(test)]
+mod merlin_transcript_tests {
+    use ark_bn254::{Bn254, Fr};
+    use ark_std::test_rng;
+    use merlin::Transcript;
+
+    use crate::{cq::Cq, data_structures::Witness, utils::to_field};
+
+    use super::{prove_in_transcript, verify_in_transcript, MerlinFiatShamirRng};
+
+    type MerlinCq = Cq<Bn254, MerlinFiatShamirRng>;
+
+    #[test]
+    fn test_standalone() {
+        let mut rng = test_rng();
+        let (pk, vk) =
+            MerlinCq::setup(&to_field(&[1, 5, 10, 15, 20, 25, 30, 35]), 4, &mut rng).unwrap();
+        let (statement, proof) = MerlinCq::prove(&pk, &to_field::<Fr>(&[5, 15, 20, 35])).unwrap();
+        assert!(MerlinCq::verify(&vk, &statement, &proof).is_ok());
+    }
+
+    #[test]
+    fn test_shared_session() {
+        let mut rng = test_rng();
+        let (pk, vk) = MerlinCq::setup(&to_field(&[1, 2, 3, 4]), 2, &mut rng).unwrap();
+        let (statement, _) = MerlinCq::prove(&pk, &to_field::<Fr>(&[2, 4])).unwrap();
+        let witness = Witness::new(&to_field(&[2, 4])).unwrap();
+
+        let session = || {
+            let mut transcript = Transcript::new(b"outer protocol");
+            transcript.append_message(b"step", b"1");
+            transcript
+        };
+
+        let mut prover_transcript = session();
+        let proof = prove_in_transcript(
+            &mut prover_transcript,
+            &pk.pk,
+            &pk.vk,
+            &pk.index,
+            &pk.table,
+            &witness,
+            &statement,
+        )
+        .unwrap();
+
+        let mut verifier_transcript = session();
+        assert!(verify_in_transcript(
+            &mut verifier_transcript,
+            &vk.vk,
+            &vk.common,
+            &statement,
+            &proof
+        )
+        .is_ok());
+
+        // both sides continue from the same state
+        let mut prover_next = [0u8; 32];
+        let mut verifier_next = [0u8; 32];
+        prover_transcript.challenge_bytes(b"next", &mut prover_next);
+        verifier_transcript.challenge_bytes(b"next", &mut verifier_next);
+        assert_eq!(prover_next, verifier_next);
+
+        // a proof made in one session doesn't verify in another
+        let mut other = Transcript::new(b"outer protocol");
+        assert!(verify_in_transcript(&mut other, &vk.vk, &vk.common, &statement, &proof).is_err());
+    }
+}
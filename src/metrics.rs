@@ -0,0 +1,186 @@
+#[cfg(feature = "std")]
+use std::alloc::{GlobalAlloc, Layout, System};
+
+use ark_std::{
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    time::Duration,
+    vec::Vec,
+};
+
+/// Measurements of a single proof, returned by `Prover::prove_with_metrics`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProverMetrics {
+    pub round_1: Duration,
+    pub round_2: Duration,
+    pub round_3: Duration,
+    pub total: Duration,
+    /// number of multi scalar multiplications, sparse sums over the index count as one each
+    pub msm_count: usize,
+    /// number of bases summed over all msms
+    pub msm_bases: usize,
+    /// domain size of every fft in the order they were performed
+    pub fft_sizes: Vec<usize>,
+    /// peak heap usage of the process in bytes while the proof ran, only known when `TrackingAllocator` is the
+    /// global allocator and no other proof ran at the same time
+    pub peak_allocation: Option<usize>,
+}
+
+impl ProverMetrics {
+    pub(crate) fn record_msm(&mut self, num_bases: usize) {
+        self.msm_count += 1;
+        self.msm_bases += num_bases;
+    }
+
+    pub(crate) fn record_fft(&mut self, size: usize) {
+        self.fft_sizes.push(size);
+    }
+}
+
+/// Wall clock timer, `std::time::Instant` panics on wasm32-unknown-unknown and doesn't exist without std,
+/// so timings read as zero there
+pub(crate) struct Timer {
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    start: std::time::Instant,
+}
+
+impl Timer {
+    pub(crate) fn start() -> Self {
+        Self {
+            #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+            start: std::time::Instant::now(),
+        }
+    }
+
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    pub(crate) fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    #[cfg(not(all(feature = "std", not(target_arch = "wasm32"))))]
+    pub(crate) fn elapsed(&self) -> Duration {
+        Duration::ZERO
+    }
+}
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Global allocator that keeps track of peak heap usage, install it in the binary with
+/// `#[global_allocator] static ALLOC: TrackingAllocator = TrackingAllocator;`, it wraps the system allocator
+/// and needs the `std` feature
+pub struct TrackingAllocator;
+
+#[cfg(feature = "std")]
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let current = CURRENT.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(current, Ordering::Relaxed);
+            ACTIVE.store(true, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+impl TrackingAllocator {
+    /// Restarts peak tracking from the current heap usage
+    pub fn reset_peak() {
+        PEAK.store(CURRENT.load(Ordering::Relaxed), Ordering::Relaxed);
+    }
+
+    pub fn peak() -> Option<usize> {
+        if ACTIVE.load(Ordering::Relaxed) {
+            Some(PEAK.load(Ordering::Relaxed))
+        } else {
+            None
+        }
+    }
+}
+
+/// Proofs in flight. The counters of `TrackingAllocator` are process wide, so only a proof that starts while no
+/// other proof runs resets the peak, and its peak is only reported if no other proof started before it ended.
+#[cfg(feature = "std")]
+struct Proofs {
+    in_flight: usize,
+    next_id: u64,
+    /// proof that reset the peak
+    measured: Option<u64>,
+    /// another proof started while `measured` ran
+    overlapped: bool,
+}
+
+#[cfg(feature = "std")]
+static PROOFS: std::sync::Mutex<Proofs> = std::sync::Mutex::new(Proofs {
+    in_flight: 0,
+    next_id: 0,
+    measured: None,
+    overlapped: false,
+});
+
+#[cfg(feature = "std")]
+fn proofs() -> std::sync::MutexGuard<'static, Proofs> {
+    PROOFS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Heap measurement of one proof, from `open` at its start to `close` at its end
+pub(crate) struct PeakWindow {
+    #[cfg(feature = "std")]
+    id: u64,
+}
+
+impl PeakWindow {
+    #[cfg(feature = "std")]
+    pub(crate) fn open() -> Self {
+        let mut proofs = proofs();
+        let id = proofs.next_id;
+        proofs.next_id += 1;
+        if proofs.in_flight == 0 {
+            proofs.measured = Some(id);
+            proofs.overlapped = false;
+            TrackingAllocator::reset_peak();
+        } else {
+            proofs.overlapped = true;
+        }
+        proofs.in_flight += 1;
+        Self { id }
+    }
+
+    #[cfg(not(feature = "std"))]
+    pub(crate) fn open() -> Self {
+        Self {}
+    }
+
+    /// Peak of the proof, `None` if it overlapped with another one or the allocator isn't installed
+    #[cfg(feature = "std")]
+    pub(crate) fn close(self) -> Option<usize> {
+        let proofs = proofs();
+        if proofs.measured == Some(self.id) && !proofs.overlapped {
+            TrackingAllocator::peak()
+        } else {
+            None
+        }
+    }
+
+    #[cfg(not(feature = "std"))]
+    pub(crate) fn close(self) -> Option<usize> {
+        None
+    }
+}
+
+#[cfg(feature = "std")]
+impl Drop for PeakWindow {
+    fn drop(&mut self) {
+        let mut proofs = proofs();
+        proofs.in_flight -= 1;
+        if proofs.measured == Some(self.id) {
+            proofs.measured = None;
+        }
+    }
+}
@@ -0,0 +1,444 @@
+//! Lookups of tuples into tables whose rows are tuples, e.g. `(a, b, a XOR b)`.
+//!
+//! Every column is indexed on its own. The cached quotients, [T(x)]_2 and [T(x)]_1 are linear in the table values,
+//! so for a challenge zeta the index of the folded table `sum_j zeta^j·T_j` is the same combination of the column
+//! indices, and the statement of the folded witness is `sum_j zeta^j·[f_j]_1`. Zeta is hashed from the table and
+//! witness column commitments, after which one cq proof covers the folded lookup. The prover only combines the
+//! cached quotients of the rows it uses.
+
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{FftField, One, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+#[cfg(any(test, feature = "test-utils"))]
+use ark_std::rand::RngCore;
+use ark_std::{collections::BTreeMap, format, iter, marker::PhantomData, vec::Vec};
+
+use crate::{
+    config::CqConfig,
+    data_structures::{Proof, ProvingKey, Statement, Witness},
+    error::Error,
+    indexer::{CommonPreprocessedInput, Index, IndexView},
+    kzg::Kzg,
+    prover::Prover,
+    rng::FiatShamirRng,
+    table::Table,
+    transcript::{Transcript, TranscriptOracle},
+    verifier::{PairingEquation, Verifier, VerifierKey},
+    PROTOCOL_NAME, PROTOCOL_VERSION,
+};
+
+/// Table of distinct rows of the same width, stored by column
+pub struct MultiColumnTable<F: FftField> {
+    pub(crate) size: usize,
+    pub(crate) columns: Vec<Vec<F>>,
+    pub(crate) row_index_mapping: BTreeMap<Vec<F>, usize>,
+}
+
+impl<F: FftField> MultiColumnTable<F> {
+    pub fn new(rows: &[Vec<F>]) -> Result<Self, Error> {
+        let width = rows.first().map_or(0, |row| row.len());
+        if width == 0 {
+            return Err(Error::ColumnCountMismatch(1, 0));
+        }
+        if let Some(row) = rows.iter().find(|row| row.len() != width) {
+            return Err(Error::ColumnCountMismatch(width, row.len()));
+        }
+        let columns = (0..width)
+            .map(|j| rows.iter().map(|row| row[j]).collect())
+            .collect();
+        Self::from_columns(columns)
+    }
+
+    pub fn from_columns(columns: Vec<Vec<F>>) -> Result<Self, Error> {
+        let size = columns.first().map_or(0, |column| column.len());
+        if columns.is_empty() {
+            return Err(Error::ColumnCountMismatch(1, 0));
+        }
+        if let Some(column) = columns.iter().find(|column| column.len() != size) {
+            return Err(Error::TableSizeMismatch(size, column.len()));
+        }
+        if !size.is_power_of_two() {
+            return Err(Error::TableSizeNotPow2(size));
+        }
+
+        let mut row_index_mapping = BTreeMap::new();
+        for i in 0..size {
+            let row: Vec<F> = columns.iter().map(|column| column[i]).collect();
+            if let Some(prev) = row_index_mapping.insert(row, i) {
+                return Err(Error::DuplicateValueInTable(format!(
+                    "row {} and row {}",
+                    prev, i
+                )));
+            }
+        }
+        Ok(Self {
+            size,
+            columns,
+            row_index_mapping,
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn width(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// Position of `row` in the table
+    pub fn row_index(&self, row: &[F]) -> Result<usize, Error> {
+        if row.len() != self.width() {
+            return Err(Error::ColumnCountMismatch(self.width(), row.len()));
+        }
+        self.row_index_mapping
+            .get(row)
+            .copied()
+            .ok_or_else(|| Error::ValueNotInTable(format!("{:?}", row)))
+    }
+
+    fn fold(&self, powers: &[F]) -> Table<F> {
+        let values = (0..self.size)
+            .map(|i| fold_values(powers, self.columns.iter().map(|column| column[i])))
+            .collect();
+        Table::from_values_unchecked(values)
+    }
+}
+
+// only the columns are encoded, deserialization rebuilds and revalidates the table
+impl<F: FftField> CanonicalSerialize for MultiColumnTable<F> {
+    fn serialize<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        self.columns.serialize(writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.columns.serialized_size()
+    }
+
+    fn serialize_uncompressed<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        self.columns.serialize_uncompressed(writer)
+    }
+
+    fn uncompressed_size(&self) -> usize {
+        self.columns.uncompressed_size()
+    }
+}
+
+impl<F: FftField> CanonicalDeserialize for MultiColumnTable<F> {
+    fn deserialize<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        let columns = Vec::<Vec<F>>::deserialize(reader)?;
+        Self::from_columns(columns).map_err(|_| SerializationError::InvalidData)
+    }
+
+    fn deserialize_uncompressed<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        let columns = Vec::<Vec<F>>::deserialize_uncompressed(reader)?;
+        Self::from_columns(columns).map_err(|_| SerializationError::InvalidData)
+    }
+}
+
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct MultiColumnProverKey<E: PairingEngine> {
+    pub pk: ProvingKey<E>,
+    pub vk: VerifierKey<E>,
+    /// one index per column
+    pub indexes: Vec<Index<E>>,
+    pub table: MultiColumnTable<E::Fr>,
+}
+
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct MultiColumnVerifierKey<E: PairingEngine> {
+    pub vk: VerifierKey<E>,
+    /// [zV(x)]_2 and [T_j(x)]_2 of every column
+    pub commons: Vec<CommonPreprocessedInput<E>>,
+}
+
+/// Commitments to the witness columns
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct MultiColumnStatement<E: PairingEngine> {
+    pub columns: Vec<Statement<E>>,
+}
+
+/// `sum_j zeta^j·v_j`
+fn fold_values<F: FftField>(powers: &[F], values: impl Iterator<Item = F>) -> F {
+    powers
+        .iter()
+        .zip(values)
+        .fold(F::zero(), |acc, (p, v)| acc + *p * v)
+}
+
+fn fold_points<G: AffineCurve>(powers: &[G::ScalarField], points: impl Iterator<Item = G>) -> G {
+    powers
+        .iter()
+        .zip(points)
+        .fold(G::Projective::zero(), |acc, (p, g)| acc + g.mul(*p))
+        .into_affine()
+}
+
+/// Index of the folded table, combining the cached quotients of the columns on demand
+struct FoldedIndex<'a, E: PairingEngine> {
+    columns: &'a [Index<E>],
+    powers: &'a [E::Fr],
+    common: CommonPreprocessedInput<E>,
+}
+
+impl<'a, E: PairingEngine> IndexView<E> for FoldedIndex<'a, E> {
+    fn common(&self) -> &CommonPreprocessedInput<E> {
+        &self.common
+    }
+
+    fn q(&self, i: usize) -> Result<E::G1Affine, Error> {
+        let qs = self
+            .columns
+            .iter()
+            .map(|column| column.q(i))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(fold_points(self.powers, qs.into_iter()))
+    }
+
+    /// the Lagrange commitments only depend on the domain, all columns share them
+    fn l(&self, i: usize) -> Result<E::G1Affine, Error> {
+        self.columns[0].l(i)
+    }
+
+    fn l_at_0(&self, i: usize) -> Result<E::G1Affine, Error> {
+        self.columns[0].l_at_0(i)
+    }
+
+    fn t_1(&self) -> Result<(E::G1Affine, E::G1Affine), Error> {
+        let ts = self
+            .columns
+            .iter()
+            .map(|column| column.t_1())
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((
+            fold_points(self.powers, ts.iter().map(|t| t.0)),
+            fold_points(self.powers, ts.iter().map(|t| t.1)),
+        ))
+    }
+}
+
+pub struct MultiColumnCq<E: PairingEngine, FS: FiatShamirRng> {
+    _e: PhantomData<E>,
+    _fs: PhantomData<FS>,
+}
+
+impl<E: PairingEngine, FS: FiatShamirRng> MultiColumnCq<E, FS> {
+    /// Indexes every column with an srs sampled from `rng`, see `Cq::setup`
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn setup<R: RngCore>(
+        table: MultiColumnTable<E::Fr>,
+        witness_size: usize,
+        rng: &mut R,
+    ) -> Result<(MultiColumnProverKey<E>, MultiColumnVerifierKey<E>), Error> {
+        let config = CqConfig::<E, FS>::builder()
+            .table_size(table.size)
+            .witness_size(witness_size)
+            .build()?;
+        let (srs_g1, srs_g2) = crate::utils::unsafe_setup_from_rng::<E, R>(
+            config.srs_g1_degree(),
+            config.srs_g2_degree(),
+            rng,
+        );
+        Self::keygen(table, witness_size, srs_g1, &srs_g2)
+    }
+
+    /// Indexes every column against an existing srs
+    pub fn keygen(
+        table: MultiColumnTable<E::Fr>,
+        witness_size: usize,
+        srs_g1: Vec<E::G1Affine>,
+        srs_g2: &[E::G2Affine],
+    ) -> Result<(MultiColumnProverKey<E>, MultiColumnVerifierKey<E>), Error> {
+        let config = CqConfig::<E, FS>::builder()
+            .table_size(table.size)
+            .witness_size(witness_size)
+            .build()?;
+        let indexes = table
+            .columns
+            .iter()
+            .map(|column| {
+                let column = Table::from_values_unchecked(column.clone());
+                config.index(&srs_g1, srs_g2, &column)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        // the whole srs is kept, the degree bounds are checked against its highest power
+        let vk = VerifierKey::<E>::for_srs_degree(
+            srs_g2,
+            srs_g1.len() - 1,
+            table.size,
+            witness_size,
+            false,
+        )?;
+        let commons = indexes
+            .iter()
+            .map(|index| CommonPreprocessedInput::new(index.common.zv_2, index.common.t_2))
+            .collect();
+
+        Ok((
+            MultiColumnProverKey {
+                pk: ProvingKey::new(srs_g1),
+                vk: vk.clone(),
+                indexes,
+                table,
+            },
+            MultiColumnVerifierKey { vk, commons },
+        ))
+    }
+
+    /// Commits to every witness column and proves that each row of `rows` is a row of the table
+    pub fn prove(
+        pk: &MultiColumnProverKey<E>,
+        rows: &[Vec<E::Fr>],
+    ) -> Result<(MultiColumnStatement<E>, Proof<E>), Error> {
+        let indices = rows
+            .iter()
+            .map(|row| pk.table.row_index(row))
+            .collect::<Result<Vec<_>, _>>()?;
+        let columns = (0..pk.table.width())
+            .map(|j| {
+                let values: Vec<E::Fr> = rows.iter().map(|row| row[j]).collect();
+                let witness = Witness::new(&values)?;
+                Ok(Statement::from_witness(&pk.pk, &witness))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let statement = MultiColumnStatement { columns };
+
+        let commons: Vec<_> = pk.indexes.iter().map(|index| &index.common).collect();
+        let powers = Self::fold_powers(&commons, &statement);
+        let folded_table = pk.table.fold(&powers);
+        let witness = Witness::from_indices(&folded_table, &indices)?;
+        let index = FoldedIndex {
+            columns: &pk.indexes,
+            powers: &powers,
+            common: fold_common(&powers, &commons),
+        };
+        let folded = fold_statement(&powers, &statement);
+
+        let proof =
+            Prover::<E, FS>::prove(&pk.pk, &pk.vk, &index, &folded_table, &witness, &folded)?;
+        Ok((statement, proof))
+    }
+
+    pub fn verify(
+        vk: &MultiColumnVerifierKey<E>,
+        statement: &MultiColumnStatement<E>,
+        proof: &Proof<E>,
+    ) -> Result<(), Error> {
+        if statement.columns.len() != vk.commons.len() {
+            return Err(Error::ColumnCountMismatch(
+                vk.commons.len(),
+                statement.columns.len(),
+            ));
+        }
+        let commons: Vec<_> = vk.commons.iter().collect();
+        let powers = Self::fold_powers(&commons, statement);
+        let common = fold_common(&powers, &commons);
+        let folded = fold_statement(&powers, statement);
+        Verifier::<E, FS>::verify(&vk.vk, &common, &folded, proof)
+    }
+
+    /// The batched pairing check of `verify`, for folding with other checks
+    pub(crate) fn pairing_equation(
+        vk: &MultiColumnVerifierKey<E>,
+        statement: &MultiColumnStatement<E>,
+        proof: &Proof<E>,
+    ) -> Result<PairingEquation<E>, Error> {
+        if statement.columns.len() != vk.commons.len() {
+            return Err(Error::ColumnCountMismatch(
+                vk.commons.len(),
+                statement.columns.len(),
+            ));
+        }
+        let commons: Vec<_> = vk.commons.iter().collect();
+        let powers = Self::fold_powers(&commons, statement);
+        let common = fold_common(&powers, &commons);
+        let folded = fold_statement(&powers, statement);
+        let challenges = Verifier::<E, FS>::derive_challenges(&vk.vk, &common, &folded, proof);
+        Verifier::<E, FS>::pairing_equation(&vk.vk, &common, &folded, proof, &challenges)
+    }
+
+    /// `1, zeta, zeta^2, ...` with zeta hashed from the table and witness columns
+    fn fold_powers(
+        commons: &[&CommonPreprocessedInput<E>],
+        statement: &MultiColumnStatement<E>,
+    ) -> Vec<E::Fr> {
+        let mut transcript = TranscriptOracle::<FS>::initialize(&PROTOCOL_NAME);
+        Transcript::<E>::begin_round(&mut transcript, PROTOCOL_VERSION, b"multi-column");
+        for common in commons {
+            Transcript::<E>::absorb_g2(&mut transcript, b"t", &common.t_2);
+        }
+        for column in &statement.columns {
+            Transcript::<E>::absorb_g1(&mut transcript, b"f", &column.f);
+        }
+        let zeta = Transcript::<E>::squeeze_challenge(&mut transcript, b"zeta");
+        iter::successors(Some(E::Fr::one()), |p| Some(*p * zeta))
+            .take(commons.len())
+            .collect()
+    }
+}
+
+fn fold_common<E: PairingEngine>(
+    powers: &[E::Fr],
+    commons: &[&CommonPreprocessedInput<E>],
+) -> CommonPreprocessedInput<E> {
+    let t_2 = fold_points(powers, commons.iter().map(|common| common.t_2));
+    CommonPreprocessedInput::new(commons[0].zv_2, t_2)
+}
+
+fn fold_statement<E: PairingEngine>(
+    powers: &[E::Fr],
+    statement: &MultiColumnStatement<E>,
+) -> Statement<E> {
+    let bases: Vec<E::G1Affine> = statement.columns.iter().map(|column| column.f).collect();
+    Statement::new(Kzg::<E>::msm_g1(&bases, powers).into_affine())
+}
+
+#[cfg(test)]
+mod multi_column_tests {
+    use ark_bn254::{Bn254, Fr};
+    use ark_std::test_rng;
+    use rand_chacha::ChaChaRng;
+    use sha3::Keccak256;
+
+    use crate::{error::Error, rng::SimpleHashFiatShamirRng, utils::to_field};
+
+    use super::{MultiColumnCq, MultiColumnStatement, MultiColumnTable};
+
+    type FS = SimpleHashFiatShamirRng<Keccak256, ChaChaRng>;
+
+    #[test]
+    fn test_xor_table() {
+        let mut rng = test_rng();
+        // (a, b, a XOR b) for 2 bit a and b
+        let rows: Vec<Vec<Fr>> = (0..16u64)
+            .map(|i| to_field(&[i >> 2, i & 3, (i >> 2) ^ (i & 3)]))
+            .collect();
+        let table = MultiColumnTable::new(&rows).unwrap();
+        assert_eq!((table.size(), table.width()), (16, 3));
+        let (pk, vk) = MultiColumnCq::<Bn254, FS>::setup(table, 4, &mut rng).unwrap();
+
+        let witness: Vec<Vec<Fr>> = vec![
+            to_field(&[1, 2, 3]),
+            to_field(&[3, 3, 0]),
+            to_field(&[1, 2, 3]),
+            to_field(&[0, 2, 2]),
+        ];
+        let (statement, proof) = MultiColumnCq::<Bn254, FS>::prove(&pk, &witness).unwrap();
+        assert!(MultiColumnCq::<Bn254, FS>::verify(&vk, &statement, &proof).is_ok());
+
+        // every column is in its own column, but the row isn't in the table
+        let mut wrong = witness.clone();
+        wrong[1] = to_field(&[3, 3, 3]);
+        let res = MultiColumnCq::<Bn254, FS>::prove(&pk, &wrong);
+        assert!(matches!(res.err(), Some(Error::ValueNotInTable(_))));
+
+        // swapping two witness columns changes the folded statement
+        let mut columns = statement.columns;
+        columns.swap(0, 1);
+        let swapped = MultiColumnStatement { columns };
+        assert!(MultiColumnCq::<Bn254, FS>::verify(&vk, &swapped, &proof).is_err());
+
+        let res = MultiColumnTable::new(&[to_field::<Fr>(&[1, 2]), to_field(&[1, 2])]);
+        assert!(matches!(res.err(), Some(Error::DuplicateValueInTable(_))));
+    }
+}
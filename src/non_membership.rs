@@ -0,0 +1,325 @@
+//! Proofs that committed values are not in a table, with a sorted-table adjacency argument.
+//!
+//! The blocklist `s_0 < s_1 < ... < s_{k-1}` in `[0, 2^b)` splits `[0, 2^b)` into gaps `[s_i + 1, s_{i+1} - 1]`,
+//! with `s_{-1} = -1` and `s_k = 2^b`. A value `v` is not blocklisted iff it lies in a gap, so the prover commits
+//! to the bounds `lo`, `hi` of the gap of every value and shows
+//!
+//! - `lo + 2^64·hi` is in the table of gaps, with cq,
+//! - `v - lo` and `hi - v` are in `[0, 2^b)`, with `DecomposableCq` range lookups.
+//!
+//! All three statements are linear in `[f]_1`, `[lo]_1` and `[hi]_1`, so the verifier derives them itself. With
+//! `b <= 62` the encoding of a gap can't be opened to other bounds that pass both range checks.
+
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+#[cfg(any(test, feature = "test-utils"))]
+use ark_std::rand::RngCore;
+use ark_std::{format, marker::PhantomData, vec, vec::Vec};
+
+use crate::{
+    config::CqConfig,
+    cq::{Cq, CqProverKey, CqVerifierKey},
+    data_structures::{Proof, Statement, Witness},
+    decomposable::{
+        DecomposableCq, DecomposableProverKey, DecomposableTable, DecomposableVerifierKey,
+        DecomposedProof,
+    },
+    error::{Error, VerificationError},
+    rng::FiatShamirRng,
+    table::Table,
+    verifier::verify_folded,
+};
+
+/// Largest `b`, values and gaps need to stay far below the `2^64` shift of the gap encoding
+pub const MAX_RANGE_BITS: u32 = 62;
+
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct NonMembershipProverKey<E: PairingEngine> {
+    /// sorted and deduplicated
+    pub blocklist: Vec<u64>,
+    pub gaps: CqProverKey<E>,
+    pub range: DecomposableProverKey<E>,
+    pub range_table: DecomposableTable<E::Fr>,
+}
+
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct NonMembershipVerifierKey<E: PairingEngine> {
+    pub gaps: CqVerifierKey<E>,
+    pub range: DecomposableVerifierKey<E>,
+}
+
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct NonMembershipProof<E: PairingEngine> {
+    pub lo_cm: E::G1Affine,
+    pub hi_cm: E::G1Affine,
+    /// `lo + 2^64·hi` is a gap
+    pub gap_proof: Proof<E>,
+    /// `v - lo` is in range
+    pub lower_proof: DecomposedProof<E>,
+    /// `hi - v` is in range
+    pub upper_proof: DecomposedProof<E>,
+}
+
+pub struct NonMembership<E: PairingEngine, FS: FiatShamirRng> {
+    _e: PhantomData<E>,
+    _fs: PhantomData<FS>,
+}
+
+impl<E: PairingEngine, FS: FiatShamirRng> NonMembership<E, FS> {
+    /// Indexes the gaps of `blocklist` in `[0, 2^(limb_bits·limbs))` and the range subtable with one srs
+    /// sampled from `rng`, the caller must not be trusted by the verifier
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn setup<R: RngCore>(
+        blocklist: &[u64],
+        limb_bits: u32,
+        limbs: usize,
+        witness_size: usize,
+        rng: &mut R,
+    ) -> Result<(NonMembershipProverKey<E>, NonMembershipVerifierKey<E>), Error> {
+        let (_, gap_config, range_table) = Self::tables(blocklist, limb_bits, limbs, witness_size)?;
+        let (srs_g1, srs_g2) = crate::utils::unsafe_setup_from_rng::<E, R>(
+            gap_config
+                .srs_g1_degree()
+                .max(range_table.subtable.size - 1),
+            gap_config.srs_g2_degree().max(range_table.subtable.size),
+            rng,
+        );
+        Self::keygen(blocklist, limb_bits, limbs, witness_size, srs_g1, &srs_g2)
+    }
+
+    /// Same as `setup` with an existing srs, which has to cover both the gap table and the range subtable
+    pub fn keygen(
+        blocklist: &[u64],
+        limb_bits: u32,
+        limbs: usize,
+        witness_size: usize,
+        srs_g1: Vec<E::G1Affine>,
+        srs_g2: &[E::G2Affine],
+    ) -> Result<(NonMembershipProverKey<E>, NonMembershipVerifierKey<E>), Error> {
+        let (blocklist, gap_config, range_table) =
+            Self::tables(blocklist, limb_bits, limbs, witness_size)?;
+        let bound = 1u64 << (limb_bits * limbs as u32);
+
+        // padding entries have `lo = 2^b`, which no value in range can satisfy
+        let mut gaps: Vec<E::Fr> = gap_bounds(&blocklist, bound)
+            .into_iter()
+            .map(|(lo, hi)| encode_gap(lo, hi))
+            .collect();
+        let table_size = gap_config.table_size();
+        let padding = (gaps.len() as u64..table_size as u64).map(|hi| encode_gap(bound, hi));
+        gaps.extend(padding);
+
+        let (gaps_pk, gaps_vk) = gap_config.keygen(srs_g1.clone(), srs_g2, Table::new(&gaps)?)?;
+        let (range_pk, range_vk) = DecomposableCq::<E, FS>::keygen(
+            DecomposableTable::range(limb_bits, limbs)?,
+            witness_size,
+            srs_g1,
+            srs_g2,
+        )?;
+
+        Ok((
+            NonMembershipProverKey {
+                blocklist,
+                gaps: gaps_pk,
+                range: range_pk,
+                range_table,
+            },
+            NonMembershipVerifierKey {
+                gaps: gaps_vk,
+                range: range_vk,
+            },
+        ))
+    }
+
+    /// Sorted blocklist, config of the gap table and range subtable
+    fn tables(
+        blocklist: &[u64],
+        limb_bits: u32,
+        limbs: usize,
+        witness_size: usize,
+    ) -> Result<(Vec<u64>, CqConfig<E, FS>, DecomposableTable<E::Fr>), Error> {
+        let bits = limb_bits * limbs as u32;
+        if bits > MAX_RANGE_BITS {
+            return Err(Error::RangeTooLarge(MAX_RANGE_BITS, bits));
+        }
+        let bound = 1u64 << bits;
+
+        let mut blocklist = blocklist.to_vec();
+        blocklist.sort_unstable();
+        blocklist.dedup();
+        if let Some(&value) = blocklist.iter().find(|&&value| value >= bound) {
+            return Err(Error::ValueOutOfRange(format!("{}", value)));
+        }
+
+        let gaps = gap_bounds(&blocklist, bound).len();
+        let gap_config = CqConfig::<E, FS>::builder()
+            .table_size(gaps.next_power_of_two().max(witness_size))
+            .witness_size(witness_size)
+            .build()?;
+        let range_table = DecomposableTable::<E::Fr>::range(limb_bits, limbs)?;
+        Ok((blocklist, gap_config, range_table))
+    }
+
+    /// Commits to `values` and proves that none of them is blocklisted
+    pub fn prove(
+        pk: &NonMembershipProverKey<E>,
+        values: &[u64],
+    ) -> Result<(Statement<E>, NonMembershipProof<E>), Error> {
+        let limb_bits = pk
+            .range_table
+            .limb_bits
+            .ok_or(Error::MissingParameter("limb_bits"))?;
+        let bound = 1u64 << (limb_bits * pk.range_table.limbs() as u32);
+
+        let mut lo = Vec::with_capacity(values.len());
+        let mut hi = Vec::with_capacity(values.len());
+        for &value in values {
+            if value >= bound {
+                return Err(Error::ValueOutOfRange(format!("{}", value)));
+            }
+            let (gap_lo, gap_hi) = match pk.blocklist.binary_search(&value) {
+                Ok(_) => return Err(Error::ValueInTable(format!("{}", value))),
+                Err(i) => gap_of(&pk.blocklist, i, bound).expect("the value is in the gap"),
+            };
+            lo.push(gap_lo);
+            hi.push(gap_hi);
+        }
+
+        let statement = commit(&pk.gaps, &to_field(values))?;
+        let lo_cm = commit(&pk.gaps, &to_field(&lo))?.f;
+        let hi_cm = commit(&pk.gaps, &to_field(&hi))?.f;
+
+        let encoded: Vec<E::Fr> = lo
+            .iter()
+            .zip(&hi)
+            .map(|(&l, &h)| encode_gap(l, h))
+            .collect();
+        let (_, gap_proof) = Cq::<E, FS>::prove(&pk.gaps, &encoded)?;
+        let lower: Vec<E::Fr> = values
+            .iter()
+            .zip(&lo)
+            .map(|(&v, &l)| (v - l).into())
+            .collect();
+        let (_, lower_proof) = DecomposableCq::<E, FS>::prove(&pk.range, &pk.range_table, &lower)?;
+        let upper: Vec<E::Fr> = values
+            .iter()
+            .zip(&hi)
+            .map(|(&v, &h)| (h - v).into())
+            .collect();
+        let (_, upper_proof) = DecomposableCq::<E, FS>::prove(&pk.range, &pk.range_table, &upper)?;
+
+        Ok((
+            statement,
+            NonMembershipProof {
+                lo_cm,
+                hi_cm,
+                gap_proof,
+                lower_proof,
+                upper_proof,
+            },
+        ))
+    }
+
+    pub fn verify(
+        vk: &NonMembershipVerifierKey<E>,
+        statement: &Statement<E>,
+        proof: &NonMembershipProof<E>,
+    ) -> Result<(), Error> {
+        let gap = proof.lo_cm.into_projective() + proof.hi_cm.mul(shift::<E>());
+        let lower = statement.f.into_projective() - proof.lo_cm.into_projective();
+        let upper = proof.hi_cm.into_projective() - statement.f.into_projective();
+
+        // all cq proofs are checked with one final exponentiation
+        let gap_check = Cq::<E, FS>::pairing_equation(
+            &vk.gaps,
+            &Statement::new(gap.into_affine()),
+            &proof.gap_proof,
+        )?;
+        let mut checks = vec![(
+            gap_check,
+            Error::Verification(VerificationError::BatchedPairingFailed),
+        )];
+        checks.extend(DecomposableCq::<E, FS>::pairing_checks(
+            &vk.range,
+            &Statement::new(lower.into_affine()),
+            &proof.lower_proof,
+        )?);
+        checks.extend(DecomposableCq::<E, FS>::pairing_checks(
+            &vk.range,
+            &Statement::new(upper.into_affine()),
+            &proof.upper_proof,
+        )?);
+        verify_folded::<E, FS>(checks)
+    }
+}
+
+/// Bounds of the non empty gaps between consecutive entries of the sorted `blocklist`
+fn gap_bounds(blocklist: &[u64], bound: u64) -> Vec<(u64, u64)> {
+    (0..=blocklist.len())
+        .filter_map(|i| gap_of(blocklist, i, bound))
+        .collect()
+}
+
+/// Gap in front of `blocklist[i]`, the last gap ends at `bound - 1`
+fn gap_of(blocklist: &[u64], i: usize, bound: u64) -> Option<(u64, u64)> {
+    let lo = if i == 0 { 0 } else { blocklist[i - 1] + 1 };
+    let hi = blocklist.get(i).map_or(bound, |&s| s).checked_sub(1)?;
+    (lo <= hi).then(|| (lo, hi))
+}
+
+fn shift<E: PairingEngine>() -> E::Fr {
+    E::Fr::from(1u128 << 64)
+}
+
+fn encode_gap<F: From<u128>>(lo: u64, hi: u64) -> F {
+    F::from(lo as u128 + ((hi as u128) << 64))
+}
+
+fn to_field<F: From<u64>>(values: &[u64]) -> Vec<F> {
+    values.iter().map(|&v| F::from(v)).collect()
+}
+
+/// Commitment over the srs shared by both keys
+fn commit<E: PairingEngine>(
+    key: &CqProverKey<E>,
+    values: &Vec<E::Fr>,
+) -> Result<Statement<E>, Error> {
+    let witness = Witness::new(values)?;
+    Ok(Statement::from_witness(&key.pk, &witness))
+}
+
+#[cfg(test)]
+mod non_membership_tests {
+    use ark_bn254::Bn254;
+    use ark_std::test_rng;
+    use rand_chacha::ChaChaRng;
+    use sha3::Keccak256;
+
+    use crate::{data_structures::Statement, error::Error, rng::SimpleHashFiatShamirRng};
+
+    use super::NonMembership;
+
+    type FS = SimpleHashFiatShamirRng<Keccak256, ChaChaRng>;
+
+    #[test]
+    fn test_blocklist_exclusion() {
+        let mut rng = test_rng();
+
+        // blocklist in [0, 2^12)
+        let blocklist = [0, 17, 18, 256, 4095];
+        let (pk, vk) = NonMembership::<Bn254, FS>::setup(&blocklist, 3, 4, 4, &mut rng).unwrap();
+
+        let (statement, proof) =
+            NonMembership::<Bn254, FS>::prove(&pk, &[1, 16, 19, 4094]).unwrap();
+        assert!(NonMembership::<Bn254, FS>::verify(&vk, &statement, &proof).is_ok());
+
+        let res = NonMembership::<Bn254, FS>::prove(&pk, &[1, 16, 256, 4094]);
+        assert_eq!(res.err(), Some(Error::ValueInTable("256".into())));
+
+        let (other, _) = NonMembership::<Bn254, FS>::prove(&pk, &[2, 16, 19, 4094]).unwrap();
+        assert!(NonMembership::<Bn254, FS>::verify(&vk, &other, &proof).is_err());
+        assert!(
+            NonMembership::<Bn254, FS>::verify(&vk, &Statement::new(proof.lo_cm), &proof).is_err()
+        );
+    }
+}
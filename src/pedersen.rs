@@ -0,0 +1,289 @@
+//! Range proofs about values held under Pedersen commitments.
+//!
+//! The values `v_j` sit in commitments `P_j = v_j·G + r_j·H`. The prover commits to the column of all values with a
+//! hiding statement `C = sum_j v_j·[L_j(x)]_1 + s0·[zH(x)]_1 + s1·[x·zH(x)]_1` and proves with a sigma protocol
+//! that `C` and the `P_j` open to the same `v_j`. The range proof is then an ordinary cq proof for `C`, so nothing
+//! in the bridge depends on the table.
+
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{marker::PhantomData, rand::RngCore, vec, vec::Vec, UniformRand};
+
+use crate::{
+    cq::{Cq, CqProverKey, CqVerifierKey},
+    data_structures::{Proof, Statement, StatementBlinder},
+    error::Error,
+    kzg::{Kzg, LagrangeSrs},
+    rng::FiatShamirRng,
+    transcript::{Transcript, TranscriptOracle},
+    PROTOCOL_NAME, PROTOCOL_VERSION,
+};
+
+/// Generators of the Pedersen commitments, nobody may know the discrete log of `h` to the base `g`
+#[derive(Clone, Copy, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PedersenParams<E: PairingEngine> {
+    pub g: E::G1Affine,
+    pub h: E::G1Affine,
+}
+
+impl<E: PairingEngine> PedersenParams<E> {
+    pub fn new(g: E::G1Affine, h: E::G1Affine) -> Self {
+        Self { g, h }
+    }
+
+    pub fn commit(&self, value: E::Fr, randomness: E::Fr) -> E::G1Affine {
+        (self.g.mul(value) + self.h.mul(randomness)).into_affine()
+    }
+}
+
+/// Bases of hiding witness commitments, derived from the srs of the range key
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct BridgeKey<E: PairingEngine> {
+    /// [L_j(x)]_1 over the witness domain
+    pub(crate) lagrange: Vec<E::G1Affine>,
+    /// [zH(x)]_1 and [x·zH(x)]_1
+    pub(crate) zh: Vec<E::G1Affine>,
+}
+
+impl<E: PairingEngine> BridgeKey<E> {
+    pub fn new(srs_g1: &[E::G1Affine], witness_size: usize) -> Result<Self, Error> {
+        if srs_g1.len() < witness_size + 2 {
+            return Err(Error::SrsG1TooSmall(witness_size + 2, srs_g1.len()));
+        }
+        let lagrange = LagrangeSrs::new(srs_g1, witness_size)?;
+        Self::from_lagrange(srs_g1, &lagrange)
+    }
+
+    /// Same as `new` with a precomputed `lagrange` over the witness domain
+    pub fn from_lagrange(srs_g1: &[E::G1Affine], lagrange: &LagrangeSrs<E>) -> Result<Self, Error> {
+        let n = lagrange.domain_size();
+        if srs_g1.len() < n + 2 {
+            return Err(Error::SrsG1TooSmall(n + 2, srs_g1.len()));
+        }
+        let zh = vec![
+            (srs_g1[n].into_projective() - srs_g1[0].into_projective()).into_affine(),
+            (srs_g1[n + 1].into_projective() - srs_g1[1].into_projective()).into_affine(),
+        ];
+
+        Ok(Self {
+            lagrange: lagrange.ls.clone(),
+            zh,
+        })
+    }
+}
+
+/// Sigma proof that the hiding statement and the Pedersen commitments open to the same values
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct EqualOpeningProof<E: PairingEngine> {
+    /// first messages for the Pedersen commitments and the statement
+    pub t: Vec<E::G1Affine>,
+    pub u: E::G1Affine,
+    pub z_values: Vec<E::Fr>,
+    pub z_randomness: Vec<E::Fr>,
+    pub z_s0: E::Fr,
+    pub z_s1: E::Fr,
+}
+
+pub struct PedersenBridge<E: PairingEngine, FS: FiatShamirRng> {
+    _e: PhantomData<E>,
+    _fs: PhantomData<FS>,
+}
+
+impl<E: PairingEngine, FS: FiatShamirRng> PedersenBridge<E, FS> {
+    /// Proves that the openings `(values[j], randomness[j])` of Pedersen commitments are in the table of `pk`.
+    /// Returns the hiding statement, its link to the Pedersen commitments and its cq proof.
+    pub fn prove<R: RngCore>(
+        params: &PedersenParams<E>,
+        bridge: &BridgeKey<E>,
+        pk: &CqProverKey<E>,
+        values: &Vec<E::Fr>,
+        randomness: &[E::Fr],
+        rng: &mut R,
+    ) -> Result<(Statement<E>, EqualOpeningProof<E>, Proof<E>), Error> {
+        let n = bridge.lagrange.len();
+        if values.len() != n || randomness.len() != n {
+            return Err(Error::WitnessSizeMismatch(
+                n,
+                values.len().max(randomness.len()),
+            ));
+        }
+        let pedersen: Vec<E::G1Affine> = values
+            .iter()
+            .zip(randomness)
+            .map(|(v, r)| params.commit(*v, *r))
+            .collect();
+
+        let (statement, blinder) = Cq::<E, FS>::commit_hiding(pk, values, rng)?;
+        let link = Self::prove_equal_openings(
+            params, bridge, &pedersen, &statement, values, randomness, &blinder, rng,
+        );
+        let proof = Cq::<E, FS>::prove_hiding(pk, &statement, values, blinder, rng)?;
+
+        Ok((statement, link, proof))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn prove_equal_openings<R: RngCore>(
+        params: &PedersenParams<E>,
+        bridge: &BridgeKey<E>,
+        pedersen: &[E::G1Affine],
+        statement: &Statement<E>,
+        values: &[E::Fr],
+        randomness: &[E::Fr],
+        blinder: &StatementBlinder<E::Fr>,
+        rng: &mut R,
+    ) -> EqualOpeningProof<E> {
+        let n = values.len();
+        let a: Vec<E::Fr> = (0..n).map(|_| E::Fr::rand(rng)).collect();
+        let b: Vec<E::Fr> = (0..n).map(|_| E::Fr::rand(rng)).collect();
+        let c = (E::Fr::rand(rng), E::Fr::rand(rng));
+
+        let t: Vec<E::G1Affine> = a
+            .iter()
+            .zip(&b)
+            .map(|(a, b)| params.commit(*a, *b))
+            .collect();
+        let u = statement_combination(bridge, &a, c);
+
+        let e = link_challenge::<E, FS>(pedersen, statement, &t, &u);
+        let respond = |mask: &[E::Fr], secret: &[E::Fr]| -> Vec<E::Fr> {
+            mask.iter().zip(secret).map(|(m, s)| *m + e * s).collect()
+        };
+
+        EqualOpeningProof {
+            z_values: respond(&a, values),
+            z_randomness: respond(&b, randomness),
+            z_s0: c.0 + e * blinder.r0,
+            z_s1: c.1 + e * blinder.r1,
+            t,
+            u,
+        }
+    }
+
+    pub fn verify(
+        params: &PedersenParams<E>,
+        bridge: &BridgeKey<E>,
+        vk: &CqVerifierKey<E>,
+        pedersen: &[E::G1Affine],
+        statement: &Statement<E>,
+        link: &EqualOpeningProof<E>,
+        proof: &Proof<E>,
+    ) -> Result<(), Error> {
+        let n = bridge.lagrange.len();
+        for len in [
+            pedersen.len(),
+            link.t.len(),
+            link.z_values.len(),
+            link.z_randomness.len(),
+        ] {
+            if len != n {
+                return Err(Error::WitnessSizeMismatch(n, len));
+            }
+        }
+
+        let e = link_challenge::<E, FS>(pedersen, statement, &link.t, &link.u);
+        for j in 0..n {
+            let lhs = params.commit(link.z_values[j], link.z_randomness[j]);
+            if lhs.into_projective() != link.t[j].into_projective() + pedersen[j].mul(e) {
+                return Err(Error::PedersenLinkFailed);
+            }
+        }
+        let lhs = statement_combination(bridge, &link.z_values, (link.z_s0, link.z_s1));
+        if lhs.into_projective() != link.u.into_projective() + statement.f.mul(e) {
+            return Err(Error::PedersenLinkFailed);
+        }
+
+        Cq::<E, FS>::verify(vk, statement, proof)
+    }
+}
+
+/// `sum_j v_j·[L_j(x)]_1 + s0·[zH(x)]_1 + s1·[x·zH(x)]_1`
+fn statement_combination<E: PairingEngine>(
+    bridge: &BridgeKey<E>,
+    values: &[E::Fr],
+    (s0, s1): (E::Fr, E::Fr),
+) -> E::G1Affine {
+    let lagrange = Kzg::<E>::msm_g1(&bridge.lagrange, values);
+    (lagrange + bridge.zh[0].mul(s0) + bridge.zh[1].mul(s1)).into_affine()
+}
+
+fn link_challenge<E: PairingEngine, FS: FiatShamirRng>(
+    pedersen: &[E::G1Affine],
+    statement: &Statement<E>,
+    t: &[E::G1Affine],
+    u: &E::G1Affine,
+) -> E::Fr {
+    let mut transcript = TranscriptOracle::<FS>::initialize(&PROTOCOL_NAME);
+    Transcript::<E>::begin_round(&mut transcript, PROTOCOL_VERSION, b"pedersen-link");
+    for p in pedersen {
+        Transcript::<E>::absorb_g1(&mut transcript, b"p", p);
+    }
+    Transcript::<E>::absorb_g1(&mut transcript, b"f", &statement.f);
+    for t_j in t {
+        Transcript::<E>::absorb_g1(&mut transcript, b"t", t_j);
+    }
+    Transcript::<E>::absorb_g1(&mut transcript, b"u", u);
+    Transcript::<E>::squeeze_challenge(&mut transcript, b"e")
+}
+
+#[cfg(test)]
+mod pedersen_tests {
+    use ark_bn254::{Bn254, Fr, G1Affine};
+    use ark_ec::{AffineCurve, ProjectiveCurve};
+    use ark_std::{test_rng, vec::Vec, UniformRand};
+    use rand_chacha::ChaChaRng;
+    use sha3::Keccak256;
+
+    use crate::{
+        config::CqConfig, error::Error, rng::SimpleHashFiatShamirRng, table::Table, utils::to_field,
+    };
+
+    use super::{BridgeKey, PedersenBridge, PedersenParams};
+
+    type FS = SimpleHashFiatShamirRng<Keccak256, ChaChaRng>;
+
+    #[test]
+    fn test_pedersen_range() {
+        let mut rng = test_rng();
+        let range = Table::new(&to_field(&[0, 1, 2, 3, 4, 5, 6, 7])).unwrap();
+        let config = CqConfig::<Bn254, FS>::builder()
+            .table_size(8)
+            .witness_size(4)
+            .zk(true)
+            .build()
+            .unwrap();
+        let (pk, vk) = config.unsafe_setup(range, &mut rng).unwrap();
+        let bridge = BridgeKey::new(&pk.pk.srs_g1, 4).unwrap();
+
+        let g = G1Affine::prime_subgroup_generator();
+        let params = PedersenParams::<Bn254>::new(g, g.mul(Fr::rand(&mut rng)).into_affine());
+        let values = to_field(&[3, 0, 7, 5]);
+        let randomness: Vec<Fr> = (0..4).map(|_| Fr::rand(&mut rng)).collect();
+        let pedersen: Vec<G1Affine> = values
+            .iter()
+            .zip(&randomness)
+            .map(|(v, r)| params.commit(*v, *r))
+            .collect();
+
+        let (statement, link, proof) = PedersenBridge::<Bn254, FS>::prove(
+            &params,
+            &bridge,
+            &pk,
+            &values,
+            &randomness,
+            &mut rng,
+        )
+        .unwrap();
+        assert!(PedersenBridge::<Bn254, FS>::verify(
+            &params, &bridge, &vk, &pedersen, &statement, &link, &proof
+        )
+        .is_ok());
+
+        let mut other = pedersen.clone();
+        other[1] = params.commit(Fr::from(1u64), randomness[1]);
+        let res = PedersenBridge::<Bn254, FS>::verify(
+            &params, &bridge, &vk, &other, &statement, &link, &proof,
+        );
+        assert_eq!(res, Err(Error::PedersenLinkFailed));
+    }
+}
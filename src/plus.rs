@@ -0,0 +1,502 @@
+//! cq+, a variant with one G1 element less in the proof.
+//!
+//! The opening of A at 0 and the batched opening at `gamma` share a single witness `W = A0 + pi_gamma`,
+//! since `(A(X) - A(0))·(X - gamma) + X·(C(X) - v) = X·(X - gamma)·W(X)` only holds if both openings do.
+//! The verifier checks it against `[x^2]_2`, so proofs are 7 G1 and 3 field elements at the cost of one
+//! more pairing in the batch.
+
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{Field, One};
+use ark_poly::EvaluationDomain;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use ark_std::{iter, marker::PhantomData, ops::Neg, vec, vec::Vec};
+
+use crate::{
+    data_structures::{ProvingKey, Statement, Witness},
+    error::{Error, VerificationError},
+    indexer::{CommonPreprocessedInput, IndexView},
+    prover::{Prover, ProverFirstMessage, ProverSecondMessage, State},
+    rng::FiatShamirRng,
+    table::Table,
+    transcript::{
+        absorb_first_message, absorb_public_input, absorb_second_message, Transcript,
+        TranscriptOracle,
+    },
+    utils::domain,
+    verifier::{Challenges, VerifierKey},
+    PROTOCOL_NAME, PROTOCOL_VERSION,
+};
+
+/// Key of the plain protocol plus `[x^2]_2`
+#[derive(Clone)]
+pub struct PlusVerifierKey<E: PairingEngine> {
+    pub(crate) vk: VerifierKey<E>,
+    pub(crate) x_sq_2: E::G2Affine,
+    pub(crate) x_sq: E::G2Prepared,
+}
+
+impl<E: PairingEngine> PlusVerifierKey<E> {
+    /// `VerifierKey::for_table` of a plain key plus `[x^2]_2`, failing instead of panicking on a short `srs_g2`
+    pub fn for_table(
+        srs_g2: &[E::G2Affine],
+        table_size: usize,
+        witness_size: usize,
+    ) -> Result<Self, Error> {
+        let vk = VerifierKey::for_table(srs_g2, table_size, witness_size, false)?;
+        let x_sq_2 = *srs_g2.get(2).ok_or(Error::SrsG2TooSmall(3, srs_g2.len()))?;
+        Ok(Self::from_parts(vk, x_sq_2))
+    }
+
+    fn from_parts(vk: VerifierKey<E>, x_sq_2: E::G2Affine) -> Self {
+        Self {
+            vk,
+            x_sq_2,
+            x_sq: x_sq_2.into(),
+        }
+    }
+
+    pub fn vk(&self) -> &VerifierKey<E> {
+        &self.vk
+    }
+}
+
+// like `VerifierKey`, the prepared point is recomputed on deserialization
+impl<E: PairingEngine> CanonicalSerialize for PlusVerifierKey<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.vk.serialize(&mut writer)?;
+        self.x_sq_2.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.vk.serialized_size() + self.x_sq_2.serialized_size()
+    }
+
+    fn serialize_uncompressed<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.vk.serialize_uncompressed(&mut writer)?;
+        self.x_sq_2.serialize_uncompressed(&mut writer)
+    }
+
+    fn uncompressed_size(&self) -> usize {
+        self.vk.uncompressed_size() + self.x_sq_2.uncompressed_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for PlusVerifierKey<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let vk = VerifierKey::deserialize(&mut reader)?;
+        let x_sq_2 = E::G2Affine::deserialize(&mut reader)?;
+        Ok(Self::from_parts(vk, x_sq_2))
+    }
+
+    fn deserialize_uncompressed<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let vk = VerifierKey::deserialize_uncompressed(&mut reader)?;
+        let x_sq_2 = E::G2Affine::deserialize_uncompressed(&mut reader)?;
+        Ok(Self::from_parts(vk, x_sq_2))
+    }
+}
+
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PlusThirdMessage<E: PairingEngine> {
+    pub b0_at_gamma: E::Fr,
+    pub f_at_gamma: E::Fr,
+    pub a_at_zero: E::Fr,
+    /// `[A0(x) + pi_gamma(x)]_1`
+    pub w_cm: E::G1Affine,
+}
+
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct PlusProof<E: PairingEngine> {
+    pub(crate) version: u8,
+    pub(crate) first_msg: ProverFirstMessage<E>,
+    pub(crate) second_msg: ProverSecondMessage<E>,
+    pub(crate) third_msg: PlusThirdMessage<E>,
+}
+
+impl<E: PairingEngine> PlusProof<E> {
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    pub fn first_msg(&self) -> &ProverFirstMessage<E> {
+        &self.first_msg
+    }
+
+    pub fn second_msg(&self) -> &ProverSecondMessage<E> {
+        &self.second_msg
+    }
+
+    pub fn third_msg(&self) -> &PlusThirdMessage<E> {
+        &self.third_msg
+    }
+}
+
+fn absorb_plus_public_input<E: PairingEngine, T: Transcript<E>>(
+    transcript: &mut T,
+    vk: &PlusVerifierKey<E>,
+    common: &CommonPreprocessedInput<E>,
+    statement: &Statement<E>,
+) {
+    absorb_public_input(transcript, &vk.vk, common, statement);
+    transcript.absorb_g2(b"x_sq", &vk.x_sq_2);
+}
+
+fn absorb_plus_third_message<E: PairingEngine, T: Transcript<E>>(
+    transcript: &mut T,
+    msg: &PlusThirdMessage<E>,
+) {
+    transcript.begin_round(PROTOCOL_VERSION, b"round-3-plus");
+    transcript.absorb_field(b"b0_at_gamma", &msg.b0_at_gamma);
+    transcript.absorb_field(b"f_at_gamma", &msg.f_at_gamma);
+    transcript.absorb_field(b"a_at_zero", &msg.a_at_zero);
+    transcript.absorb_g1(b"w", &msg.w_cm);
+}
+
+/// Prover of cq+, rounds 1 and 2 are the ones of [`Prover`]
+pub struct PlusProver<E: PairingEngine, FS> {
+    _e: PhantomData<E>,
+    _fs: PhantomData<FS>,
+}
+
+impl<E: PairingEngine, FS: FiatShamirRng> PlusProver<E, FS> {
+    pub fn prove<'a>(
+        pk: &'a ProvingKey<E>,
+        vk: &PlusVerifierKey<E>,
+        index: &'a dyn IndexView<E>,
+        table: &'a Table<E::Fr>,
+        witness: &'a Witness<E::Fr>,
+        statement: &Statement<E>,
+    ) -> Result<PlusProof<E>, Error> {
+        let mut transcipt = TranscriptOracle::<FS>::initialize(&PROTOCOL_NAME);
+        PlusProver::<E, TranscriptOracle<FS>>::prove_with_transcript(
+            &mut transcipt,
+            pk,
+            vk,
+            index,
+            table,
+            witness,
+            statement,
+        )
+    }
+}
+
+impl<E: PairingEngine, T: Transcript<E>> PlusProver<E, T> {
+    pub fn prove_with_transcript<'a>(
+        transcript: &mut T,
+        pk: &'a ProvingKey<E>,
+        vk: &PlusVerifierKey<E>,
+        index: &'a dyn IndexView<E>,
+        table: &'a Table<E::Fr>,
+        witness: &'a Witness<E::Fr>,
+        statement: &Statement<E>,
+    ) -> Result<PlusProof<E>, Error> {
+        let f_len = witness.committed_f().coeffs.len();
+        if pk.srs_g1.len() < f_len {
+            return Err(Error::SrsG1TooSmall(f_len, pk.srs_g1.len()));
+        }
+        let mut state = State::new(pk, index, table, witness)?.for_key(&vk.vk)?;
+
+        absorb_plus_public_input(transcript, vk, index.common(), statement);
+
+        let first_msg = Prover::<E, T>::round_1(&mut state)?;
+        absorb_first_message(transcript, &first_msg);
+
+        let beta = transcript.squeeze_challenge(b"beta");
+
+        let second_msg = Prover::<E, T>::round_2(&mut state, beta)?;
+        absorb_second_message(transcript, &second_msg);
+
+        let gamma = transcript.squeeze_challenge(b"gamma");
+        let eta = transcript.squeeze_challenge(b"eta");
+
+        let third_msg = Self::round_3(&mut state, gamma, eta)?;
+        absorb_plus_third_message(transcript, &third_msg);
+        transcript.squeeze_challenge(b"u");
+
+        Ok(PlusProof {
+            version: PROTOCOL_VERSION,
+            first_msg,
+            second_msg,
+            third_msg,
+        })
+    }
+
+    /// Round 3 of [`Prover`] with both opening proofs merged
+    pub fn round_3(
+        state: &mut State<E>,
+        gamma: E::Fr,
+        eta: E::Fr,
+    ) -> Result<PlusThirdMessage<E>, Error> {
+        let third_msg = Prover::<E, T>::round_3(state, gamma, eta)?;
+        Ok(PlusThirdMessage {
+            b0_at_gamma: third_msg.b0_at_gamma,
+            f_at_gamma: third_msg.f_at_gamma,
+            a_at_zero: third_msg.a_at_zero,
+            w_cm: third_msg.a0_cm + third_msg.pi_gamma,
+        })
+    }
+}
+
+/// Verifier of cq+
+pub struct PlusVerifier<E: PairingEngine, FS> {
+    _e: PhantomData<E>,
+    _fs: PhantomData<FS>,
+}
+
+impl<E: PairingEngine, FS: FiatShamirRng> PlusVerifier<E, FS> {
+    pub fn verify(
+        vk: &PlusVerifierKey<E>,
+        common: &CommonPreprocessedInput<E>,
+        statement: &Statement<E>,
+        proof: &PlusProof<E>,
+    ) -> Result<(), Error> {
+        let mut transcipt = TranscriptOracle::<FS>::initialize(&PROTOCOL_NAME);
+        PlusVerifier::<E, TranscriptOracle<FS>>::verify_with_transcript(
+            &mut transcipt,
+            vk,
+            common,
+            statement,
+            proof,
+        )
+    }
+}
+
+impl<E: PairingEngine, T: Transcript<E>> PlusVerifier<E, T> {
+    pub fn verify_with_transcript(
+        transcript: &mut T,
+        vk: &PlusVerifierKey<E>,
+        common: &CommonPreprocessedInput<E>,
+        statement: &Statement<E>,
+        proof: &PlusProof<E>,
+    ) -> Result<(), Error> {
+        absorb_plus_public_input(transcript, vk, common, statement);
+        absorb_first_message(transcript, &proof.first_msg);
+        let beta = transcript.squeeze_challenge(b"beta");
+        absorb_second_message(transcript, &proof.second_msg);
+        let gamma = transcript.squeeze_challenge(b"gamma");
+        let eta = transcript.squeeze_challenge(b"eta");
+        absorb_plus_third_message(transcript, &proof.third_msg);
+        let u = transcript.squeeze_challenge(b"u");
+
+        let challenges = Challenges {
+            beta,
+            gamma,
+            eta,
+            u,
+        };
+        Self::verify_with_challenges(vk, common, statement, proof, &challenges)
+    }
+
+    pub fn verify_with_challenges(
+        vk: &PlusVerifierKey<E>,
+        common: &CommonPreprocessedInput<E>,
+        statement: &Statement<E>,
+        proof: &PlusProof<E>,
+        challenges: &Challenges<E::Fr>,
+    ) -> Result<(), Error> {
+        if proof.version != PROTOCOL_VERSION {
+            return Err(Error::Verification(
+                VerificationError::UnsupportedProtocolVersion(proof.version),
+            ));
+        }
+        if proof.second_msg.g_cm.is_some() {
+            return Err(Error::Verification(VerificationError::ZkModeMismatch(
+                false,
+            )));
+        }
+        if proof.second_msg.a_shift_cm.is_some() != vk.vk.x_pow_a_bound.is_some() {
+            return Err(Error::Verification(VerificationError::ProofShapeMismatch));
+        }
+
+        let Challenges {
+            beta,
+            gamma,
+            eta,
+            u,
+        } = *challenges;
+        let u_powers: Vec<E::Fr> = iter::successors(Some(u), |u_pow| Some(*u_pow * u))
+            .take(3)
+            .collect();
+
+        let g_1 = E::G1Affine::prime_subgroup_generator();
+        let g_2 = E::G2Affine::prime_subgroup_generator();
+        let (first, second, third) = (&proof.first_msg, &proof.second_msg, &proof.third_msg);
+
+        let witness_domain = domain::<E::Fr>(vk.vk.witness_size)?;
+        let n_table = E::Fr::from(vk.vk.table_size as u64);
+        let n = E::Fr::from(vk.vk.witness_size as u64);
+        let n_inv = n
+            .inverse()
+            .ok_or(Error::WitnessSizeTooSmall(vk.vk.witness_size))?;
+
+        let b0 = n_table * third.a_at_zero * n_inv;
+        let b_at_gamma = third.b0_at_gamma * gamma + b0;
+        let zh_at_gamma_inv = witness_domain
+            .evaluate_vanishing_polynomial(gamma)
+            .inverse()
+            .ok_or(Error::Verification(VerificationError::GammaInDomain))?;
+        let qb_at_gamma = (b_at_gamma * (third.f_at_gamma + beta) - E::Fr::one()) * zh_at_gamma_inv;
+
+        // C - v, the batched polynomials of the opening at gamma
+        let v = third.b0_at_gamma + eta * third.f_at_gamma + eta * eta * qb_at_gamma;
+        let mut c = statement.f.mul(eta) + second.qb_cm.mul(eta * eta) + g_1.mul(-v);
+        c.add_assign_mixed(&second.b0_cm);
+        // A - A(0)
+        let a_pt = second.a_cm + g_1.mul(-third.a_at_zero).into_affine();
+
+        // e(A - A(0), [x - gamma]) · e(C - v, [x]) = e(W, [x^2 - gamma·x]), batched with the well formation of
+        // A (power 1) and the degree bound of B0 (power u)
+        let lhs_1 = (first.m_cm.neg().into_projective()
+            + second.p_cm.mul(-u_powers[0])
+            + a_pt.mul(-gamma * u_powers[1]))
+        .into_affine();
+        let lhs_x = ((c + a_pt.into_projective() + third.w_cm.mul(gamma)).into_affine())
+            .mul(u_powers[1])
+            .into_affine();
+        let lhs_x_sq = third.w_cm.mul(-u_powers[1]).into_affine();
+        let beta_2 = g_2.mul(beta).into_affine();
+
+        let mut pairs: Vec<(E::G1Prepared, E::G2Prepared)> = vec![
+            (lhs_1.into(), vk.vk.g.clone()),
+            (lhs_x.into(), vk.vk.x.clone()),
+            (lhs_x_sq.into(), vk.x_sq.clone()),
+            (
+                second.b0_cm.mul(u_powers[0]).into_affine().into(),
+                vk.vk.x_pow_b0_bound.clone(),
+            ),
+            (second.qa_cm.neg().into(), common.zv.clone()),
+            (second.a_cm.into(), (common.t_2 + beta_2).into()),
+        ];
+        // degree bound of A for witnesses larger than the table, power u^3
+        if let (Some(a_shift_cm), Some(x_pow_a_bound)) = (second.a_shift_cm, &vk.vk.x_pow_a_bound) {
+            pairs.push((
+                second.a_cm.mul(u_powers[2]).into_affine().into(),
+                x_pow_a_bound.clone(),
+            ));
+            pairs.push((
+                a_shift_cm.mul(-u_powers[2]).into_affine().into(),
+                vk.vk.g.clone(),
+            ));
+        }
+        let res = E::product_of_pairings(&pairs);
+
+        if res != E::Fqk::one() {
+            return Err(Error::Verification(VerificationError::BatchedPairingFailed));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod plus_tests {
+    use ark_bn254::{Bn254, Fr};
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+    use ark_std::{rand::rngs::StdRng, test_rng};
+    use rand_chacha::ChaChaRng;
+    use sha3::Keccak256;
+
+    use crate::{
+        data_structures::{ProvingKey, Statement, Witness},
+        error::{Error, VerificationError},
+        indexer::{CommonPreprocessedInput, Index},
+        prover::Prover,
+        rng::SimpleHashFiatShamirRng,
+        table::Table,
+        utils::{to_field, unsafe_setup_from_rng},
+        verifier::Verifier,
+    };
+
+    use super::{PlusProver, PlusVerifier, PlusVerifierKey};
+
+    type FS = SimpleHashFiatShamirRng<Keccak256, ChaChaRng>;
+
+    #[test]
+    fn test_plus_roundtrip() {
+        let n = 8;
+        let mut rng = test_rng();
+
+        let (srs_g1, srs_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(n - 1, n, &mut rng);
+        let pk = ProvingKey::<Bn254>::new(srs_g1);
+        let table = Table::new(&to_field(&[1, 5, 10, 15, 20, 25, 30, 35])).unwrap();
+        let index = Index::<Bn254>::gen(&pk.srs_g1, &srs_g2, &table).unwrap();
+        let witness = Witness::<Fr>::new(&to_field(&[5, 15, 20, 35])).unwrap();
+        let statement = Statement::from_witness(&pk, &witness);
+        let vk = PlusVerifierKey::<Bn254>::for_table(&srs_g2, table.size, witness.size).unwrap();
+
+        let mut proof =
+            PlusProver::<Bn254, FS>::prove(&pk, &vk, &index, &table, &witness, &statement).unwrap();
+        let res = PlusVerifier::<Bn254, FS>::verify(&vk, &index.common, &statement, &proof);
+        assert!(res.is_ok());
+
+        // one G1 element less than the baseline
+        let baseline =
+            Prover::<Bn254, FS>::prove(&pk, &vk.vk, &index, &table, &witness, &statement).unwrap();
+        assert_eq!(
+            proof.serialized_size() + proof.third_msg.w_cm.serialized_size(),
+            baseline.serialized_size()
+        );
+
+        // W has to cover both openings
+        let w_cm = proof.third_msg.w_cm;
+        proof.third_msg.w_cm = w_cm + -baseline.third_msg.a0_cm;
+        let res = PlusVerifier::<Bn254, FS>::verify(&vk, &index.common, &statement, &proof);
+        assert_eq!(
+            res,
+            Err(Error::Verification(VerificationError::BatchedPairingFailed))
+        );
+        proof.third_msg.w_cm = w_cm;
+        proof.third_msg.a_at_zero += Fr::from(1u64);
+        let res = PlusVerifier::<Bn254, FS>::verify(&vk, &index.common, &statement, &proof);
+        assert_eq!(
+            res,
+            Err(Error::Verification(VerificationError::BatchedPairingFailed))
+        );
+    }
+
+    #[test]
+    fn test_for_table() {
+        let n = 8;
+        let mut rng = test_rng();
+
+        let (_, srs_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(n - 1, n, &mut rng);
+        assert!(PlusVerifierKey::<Bn254>::for_table(&srs_g2, n, 4).is_ok());
+        let res = PlusVerifierKey::<Bn254>::for_table(&srs_g2[..n], n, 4);
+        assert_eq!(res.err(), Some(Error::SrsG2TooSmall(n + 1, n)));
+        // witnesses above twice the table need x^(n-N) in G2 for the degree bound of A
+        let res = PlusVerifierKey::<Bn254>::for_table(&srs_g2[..4], 2, 8);
+        assert_eq!(res.err(), Some(Error::SrsG2TooSmall(7, 4)));
+    }
+
+    /// prepared points are rebuilt when keys are read back
+    #[test]
+    fn test_prepared_key_roundtrip() {
+        let n = 8;
+        let mut rng = test_rng();
+
+        let (srs_g1, srs_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(n - 1, n, &mut rng);
+        let pk = ProvingKey::<Bn254>::new(srs_g1);
+        let table = Table::new(&to_field(&[1, 5, 10, 15, 20, 25, 30, 35])).unwrap();
+        let index = Index::<Bn254>::gen(&pk.srs_g1, &srs_g2, &table).unwrap();
+        let witness = Witness::<Fr>::new(&to_field(&[5, 15, 20, 35])).unwrap();
+        let statement = Statement::from_witness(&pk, &witness);
+        let vk = PlusVerifierKey::<Bn254>::for_table(&srs_g2, table.size, witness.size).unwrap();
+        let proof =
+            PlusProver::<Bn254, FS>::prove(&pk, &vk, &index, &table, &witness, &statement).unwrap();
+
+        let mut vk_bytes = Vec::new();
+        vk.serialize(&mut vk_bytes).unwrap();
+        let mut common_bytes = Vec::new();
+        index
+            .common
+            .serialize_uncompressed(&mut common_bytes)
+            .unwrap();
+        let vk = PlusVerifierKey::<Bn254>::deserialize(&vk_bytes[..]).unwrap();
+        let common = CommonPreprocessedInput::deserialize_uncompressed(&common_bytes[..]).unwrap();
+
+        let res = PlusVerifier::<Bn254, FS>::verify(&vk, &common, &statement, &proof);
+        assert!(res.is_ok());
+        let baseline =
+            Prover::<Bn254, FS>::prove(&pk, &vk.vk, &index, &table, &witness, &statement).unwrap();
+        let res = Verifier::<Bn254, FS>::verify(&vk.vk, &common, &statement, &baseline);
+        assert!(res.is_ok());
+    }
+}
@@ -0,0 +1,122 @@
+//! Interoperability with ark-poly-commit's KZG10, so the same G1 powers can back both
+//! cqext and an arkworks `PolynomialCommitment` stack.
+//!
+//! `UniversalParams` only carries `[1]_2` and `[x]_2`, while cq needs G2 powers up to the table size,
+//! so only the G1 side can be shared; the G2 powers still come from the cq srs.
+
+use ark_ec::PairingEngine;
+use ark_poly::univariate::DensePolynomial;
+use ark_poly_commit::kzg10::{Commitment, Powers, UniversalParams};
+use ark_std::{borrow::Cow, vec, vec::Vec};
+
+use crate::{error::Error, kzg::Kzg};
+
+/// G1 powers of `params` in the layout expected by `Index::gen` and `ProvingKey`
+pub fn srs_g1_from_universal_params<E: PairingEngine>(
+    params: &UniversalParams<E>,
+) -> Vec<E::G1Affine> {
+    params.powers_of_g.clone()
+}
+
+/// Checks that `params` and the cq srs were generated from the same secret. `params` has to cover all of
+/// `srs_g1`, which must not be empty, and reach x in G2.
+pub fn check_universal_params<E: PairingEngine>(
+    params: &UniversalParams<E>,
+    srs_g1: &[E::G1Affine],
+    srs_g2: &[E::G2Affine],
+) -> Result<(), Error> {
+    if srs_g1.is_empty() {
+        return Err(Error::SrsG1TooSmall(1, 0));
+    }
+    if params.powers_of_g.len() < srs_g1.len() {
+        return Err(Error::SrsG1TooSmall(srs_g1.len(), params.powers_of_g.len()));
+    }
+    if srs_g2.len() < 2 {
+        return Err(Error::SrsG2TooSmall(2, srs_g2.len()));
+    }
+    if params.powers_of_g[..srs_g1.len()] != srs_g1[..]
+        || params.h != srs_g2[0]
+        || params.beta_h != srs_g2[1]
+    {
+        return Err(Error::SrsMismatch);
+    }
+    Ok(())
+}
+
+/// Borrows the cq G1 powers as non-hiding KZG10 `Powers`
+pub fn powers_from_srs_g1<E: PairingEngine>(srs_g1: &[E::G1Affine]) -> Powers<'_, E> {
+    Powers {
+        powers_of_g: Cow::Borrowed(srs_g1),
+        powers_of_gamma_g: Cow::Owned(vec![]),
+    }
+}
+
+/// Commits with cqext, wrapped as a KZG10 commitment
+pub fn commit<E: PairingEngine>(
+    srs_g1: &[E::G1Affine],
+    poly: &DensePolynomial<E::Fr>,
+) -> Commitment<E> {
+    Commitment(Kzg::<E>::commit_g1(srs_g1, poly).into())
+}
+
+#[cfg(test)]
+mod poly_commit_tests {
+    use ark_bn254::{Bn254, Fr};
+    use ark_poly::{univariate::DensePolynomial, UVPolynomial};
+    use ark_poly_commit::kzg10::KZG10;
+    use ark_std::{rand::rngs::StdRng, test_rng};
+
+    use crate::{error::Error, utils::unsafe_setup_from_rng};
+
+    use super::{check_universal_params, commit, powers_from_srs_g1, srs_g1_from_universal_params};
+
+    type Kzg10 = KZG10<Bn254, DensePolynomial<Fr>>;
+
+    #[test]
+    fn test_commitments_agree() {
+        let n = 16;
+        let mut rng = test_rng();
+
+        let (srs_g1, _) = unsafe_setup_from_rng::<Bn254, StdRng>(n - 1, 1, &mut rng);
+        let poly = DensePolynomial::<Fr>::rand(n - 1, &mut rng);
+
+        let powers = powers_from_srs_g1::<Bn254>(&srs_g1);
+        let (pc_cm, _) = Kzg10::commit(&powers, &poly, None, None).unwrap();
+
+        assert_eq!(pc_cm, commit::<Bn254>(&srs_g1, &poly));
+    }
+
+    #[test]
+    fn test_srs_from_universal_params() {
+        let n = 16;
+        let mut rng = test_rng();
+
+        let params = Kzg10::setup(n - 1, false, &mut rng).unwrap();
+        let srs_g1 = srs_g1_from_universal_params(&params);
+        assert_eq!(srs_g1.len(), n);
+
+        let (other_g1, other_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(n - 1, n, &mut rng);
+        assert!(check_universal_params(&params, &other_g1, &other_g2).is_err());
+
+        // the whole cq srs is compared, params shorter than it or empty powers are rejected
+        let srs_g2 = vec![params.h, params.beta_h];
+        assert!(check_universal_params(&params, &srs_g1, &srs_g2).is_ok());
+        assert!(check_universal_params(&params, &srs_g1[..4], &srs_g2).is_ok());
+        let mut longer = srs_g1.clone();
+        longer.push(other_g1[0]);
+        assert_eq!(
+            check_universal_params(&params, &longer, &srs_g2),
+            Err(Error::SrsG1TooSmall(n + 1, n))
+        );
+        assert_eq!(
+            check_universal_params(&params, &[], &srs_g2),
+            Err(Error::SrsG1TooSmall(1, 0))
+        );
+        let mut wrong_last = srs_g1.clone();
+        wrong_last[n - 1] = other_g1[n - 1];
+        assert_eq!(
+            check_universal_params(&params, &wrong_last, &srs_g2),
+            Err(Error::SrsMismatch)
+        );
+    }
+}
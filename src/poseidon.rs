@@ -0,0 +1,343 @@
+//! Poseidon sponge over the scalar field as a `FiatShamirRng`, so the transcript can be re-derived inside an
+//! arithmetic circuit over the same field.
+//!
+//! Round constants and the MDS matrix are derived with the Grain LFSR of the Poseidon paper, appendix F, in
+//! the same way as arkworks' `find_poseidon_ark_and_mds`. The sponge has width 3, rate 2 and capacity 1.
+
+use ark_ff::{BigInteger, FpParameters, PrimeField, ToBytes};
+use ark_std::{
+    rand::{Error, RngCore},
+    vec,
+    vec::Vec,
+};
+
+use crate::rng::FiatShamirRng;
+
+const WIDTH: usize = 3;
+const RATE: usize = 2;
+const CAPACITY: usize = WIDTH - RATE;
+/// Bytes taken from each squeezed element, a uniform element mod p is within 2^-126 of uniform on them
+const SQUEEZE_BYTES: usize = 16;
+
+/// Poseidon parameters of a scalar field at the 128 bit security level
+pub trait PoseidonField: PrimeField {
+    /// Smallest `alpha` with `gcd(alpha, p - 1) = 1`
+    const ALPHA: u64;
+    const FULL_ROUNDS: usize;
+    const PARTIAL_ROUNDS: usize;
+}
+
+impl PoseidonField for ark_bn254::Fr {
+    const ALPHA: u64 = 5;
+    const FULL_ROUNDS: usize = 8;
+    const PARTIAL_ROUNDS: usize = 57;
+}
+
+impl PoseidonField for ark_bls12_381::Fr {
+    const ALPHA: u64 = 5;
+    const FULL_ROUNDS: usize = 8;
+    const PARTIAL_ROUNDS: usize = 57;
+}
+
+impl PoseidonField for ark_bls12_377::Fr {
+    const ALPHA: u64 = 17;
+    const FULL_ROUNDS: usize = 8;
+    const PARTIAL_ROUNDS: usize = 31;
+}
+
+/// The Grain LFSR used to generate Poseidon parameters
+struct GrainLfsr {
+    state: [bool; 80],
+    head: usize,
+    prime_bits: usize,
+}
+
+impl GrainLfsr {
+    fn new(prime_bits: usize, full_rounds: usize, partial_rounds: usize) -> Self {
+        let mut state = [false; 80];
+        let mut write = |from: usize, to: usize, mut value: usize| {
+            for bit in state[from..=to].iter_mut().rev() {
+                *bit = value & 1 == 1;
+                value >>= 1;
+            }
+        };
+        // b0, b1 encode a prime field and b2..b5 the x^alpha s-box
+        write(0, 1, 1);
+        write(2, 5, 0);
+        write(6, 17, prime_bits);
+        write(18, 29, WIDTH);
+        write(30, 39, full_rounds);
+        write(40, 49, partial_rounds);
+        write(50, 79, (1 << 30) - 1);
+
+        let mut lfsr = Self {
+            state,
+            head: 0,
+            prime_bits,
+        };
+        for _ in 0..160 {
+            lfsr.update();
+        }
+        lfsr
+    }
+
+    fn update(&mut self) -> bool {
+        let bit = [62, 51, 38, 23, 13, 0].iter().fold(false, |acc, offset| {
+            acc ^ self.state[(self.head + offset) % 80]
+        });
+        self.state[self.head] = bit;
+        self.head = (self.head + 1) % 80;
+        bit
+    }
+
+    /// Most significant bit first, each output bit is kept only if the bit before it is set
+    fn bits(&mut self) -> Vec<bool> {
+        (0..self.prime_bits)
+            .map(|_| {
+                while !self.update() {
+                    self.update();
+                }
+                self.update()
+            })
+            .collect()
+    }
+
+    fn field_element_rejection<F: PrimeField>(&mut self) -> F {
+        loop {
+            let mut bits = self.bits();
+            bits.reverse();
+            if let Some(f) = F::from_repr(F::BigInt::from_bits_le(&bits)) {
+                return f;
+            }
+        }
+    }
+
+    fn field_element_mod_p<F: PrimeField>(&mut self) -> F {
+        let mut bits = self.bits();
+        bits.reverse();
+        let bytes: Vec<u8> = bits
+            .chunks(8)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .enumerate()
+                    .fold(0u8, |byte, (i, &bit)| byte | (u8::from(bit) << i))
+            })
+            .collect();
+        F::from_le_bytes_mod_order(&bytes)
+    }
+}
+
+struct PoseidonParameters<F: PoseidonField> {
+    /// one row of `WIDTH` constants per round
+    ark: Vec<[F; WIDTH]>,
+    mds: [[F; WIDTH]; WIDTH],
+}
+
+impl<F: PoseidonField> PoseidonParameters<F> {
+    fn new() -> Self {
+        let prime_bits = <F::Params as FpParameters>::MODULUS_BITS as usize;
+        let mut lfsr = GrainLfsr::new(prime_bits, F::FULL_ROUNDS, F::PARTIAL_ROUNDS);
+
+        let ark = (0..F::FULL_ROUNDS + F::PARTIAL_ROUNDS)
+            .map(|_| [(); WIDTH].map(|_| lfsr.field_element_rejection::<F>()))
+            .collect();
+
+        // cauchy matrix 1 / (x_i + y_j)
+        let xs = [(); WIDTH].map(|_| lfsr.field_element_mod_p::<F>());
+        let ys = [(); WIDTH].map(|_| lfsr.field_element_mod_p::<F>());
+        let mds = xs.map(|x| ys.map(|y| (x + y).inverse().expect("x_i + y_j is zero")));
+
+        Self { ark, mds }
+    }
+
+    fn permute(&self, state: &mut [F; WIDTH]) {
+        let half_full = F::FULL_ROUNDS / 2;
+        for (round, constants) in self.ark.iter().enumerate() {
+            for (s, c) in state.iter_mut().zip(constants) {
+                *s += c;
+            }
+
+            let full = round < half_full || round >= half_full + F::PARTIAL_ROUNDS;
+            let sbox_width = if full { WIDTH } else { 1 };
+            for s in state[..sbox_width].iter_mut() {
+                *s = s.pow([F::ALPHA]);
+            }
+
+            let mixed = self.mds.map(|row| {
+                row.iter()
+                    .zip(state.iter())
+                    .fold(F::zero(), |acc, (m, s)| acc + *m * s)
+            });
+            *state = mixed;
+        }
+    }
+}
+
+/// Duplex Poseidon sponge, absorbed bytes are packed into field elements and squeezed elements are
+/// turned back into bytes for `FiatShamirRng` consumers
+pub struct PoseidonFiatShamirRng<F: PoseidonField> {
+    params: PoseidonParameters<F>,
+    state: [F; WIDTH],
+    squeezed: Vec<u8>,
+}
+
+impl<F: PoseidonField> PoseidonFiatShamirRng<F> {
+    /// Bytes packed into a single field element, strictly below the modulus
+    fn chunk_bytes() -> usize {
+        (<F::Params as FpParameters>::MODULUS_BITS as usize - 1) / 8
+    }
+
+    fn absorb_elements(&mut self, elements: &[F]) {
+        for chunk in elements.chunks(RATE) {
+            for (s, e) in self.state[CAPACITY..].iter_mut().zip(chunk) {
+                *s += e;
+            }
+            self.params.permute(&mut self.state);
+        }
+    }
+
+    /// Absorbs the length of `bytes` followed by its little endian chunks
+    fn absorb_bytes(&mut self, bytes: &[u8]) {
+        let mut elements = vec![F::from(bytes.len() as u64)];
+        elements.extend(
+            bytes
+                .chunks(Self::chunk_bytes())
+                .map(F::from_le_bytes_mod_order),
+        );
+        self.absorb_elements(&elements);
+        self.squeezed.clear();
+    }
+
+    fn squeeze_block(&mut self) {
+        self.params.permute(&mut self.state);
+        let mut block = Vec::with_capacity(RATE * SQUEEZE_BYTES);
+        for s in &self.state[CAPACITY..] {
+            block.extend_from_slice(&s.into_repr().to_bytes_le()[..SQUEEZE_BYTES]);
+        }
+        // bytes are consumed from the back
+        block.reverse();
+        self.squeezed = block;
+    }
+}
+
+impl<F: PoseidonField> RngCore for PoseidonFiatShamirRng<F> {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for byte in dest.iter_mut() {
+            if self.squeezed.is_empty() {
+                self.squeeze_block();
+            }
+            *byte = self.squeezed.pop().expect("a block was just squeezed");
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl<F: PoseidonField> FiatShamirRng for PoseidonFiatShamirRng<F> {
+    fn initialize<'a, T: 'a + ToBytes>(initial_input: &'a T) -> Self {
+        let mut rng = Self {
+            params: PoseidonParameters::new(),
+            state: [F::zero(); WIDTH],
+            squeezed: Vec::new(),
+        };
+        rng.absorb(initial_input);
+        rng
+    }
+
+    fn absorb<'a, T: 'a + ToBytes>(&mut self, new_input: &'a T) {
+        let mut bytes = Vec::new();
+        new_input
+            .write(&mut bytes)
+            .expect("failed to convert to bytes");
+        self.absorb_bytes(&bytes);
+    }
+}
+
+#[cfg(test)]
+mod poseidon_tests {
+    use ark_bls12_377::Bls12_377;
+    use ark_bn254::{Bn254, Fr};
+    use ark_ff::{Field, UniformRand, Zero};
+    use ark_std::{rand::RngCore, test_rng};
+
+    use crate::{cq::Cq, rng::FiatShamirRng, utils::to_field};
+
+    use super::{PoseidonFiatShamirRng, PoseidonParameters, WIDTH};
+
+    #[test]
+    fn test_parameters() {
+        let params = PoseidonParameters::<Fr>::new();
+        assert_eq!(params.ark.len(), 65);
+
+        // the cauchy matrix is invertible, so the zero state is the only one mapped to zero by the mds
+        let det = {
+            let m = params.mds;
+            m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+                - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+                + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+        };
+        assert!(!det.is_zero());
+
+        let mut state = [Fr::from(1u64), Fr::from(2u64), Fr::zero()];
+        let mut again = state;
+        params.permute(&mut state);
+        PoseidonParameters::<Fr>::new().permute(&mut again);
+        assert_eq!(state, again);
+        assert_ne!(state, [Fr::from(1u64), Fr::from(2u64), Fr::zero()]);
+        assert_eq!(state.len(), WIDTH);
+    }
+
+    #[test]
+    fn test_transcript_binding() {
+        let squeeze = |inputs: &[&[u8]]| {
+            let mut rng = PoseidonFiatShamirRng::<Fr>::initialize(&inputs[0].to_vec());
+            for input in &inputs[1..] {
+                rng.absorb(&input.to_vec());
+            }
+            Fr::rand(&mut rng)
+        };
+
+        let challenge = squeeze(&[b"cq", b"message"]);
+        assert_eq!(challenge, squeeze(&[b"cq", b"message"]));
+        assert_ne!(challenge, squeeze(&[b"cq", b"messagf"]));
+        // the length prefix separates inputs that only differ by trailing zeros
+        assert_ne!(challenge, squeeze(&[b"cq", b"message\0"]));
+
+        let mut rng = PoseidonFiatShamirRng::<Fr>::initialize(&b"cq".to_vec());
+        let first = rng.next_u64();
+        assert_ne!(first, rng.next_u64());
+        assert!(!Fr::rand(&mut rng).square().is_zero());
+    }
+
+    #[test]
+    fn test_cq_with_poseidon_transcript() {
+        let mut rng = test_rng();
+        let table_values = to_field(&[1, 5, 10, 15, 20, 25, 30, 35]);
+
+        type Bn254Cq = Cq<Bn254, PoseidonFiatShamirRng<Fr>>;
+        let (pk, vk) = Bn254Cq::setup(&table_values, 4, &mut rng).unwrap();
+        let (statement, proof) = Bn254Cq::prove(&pk, &to_field(&[5, 15, 20, 35])).unwrap();
+        assert!(Bn254Cq::verify(&vk, &statement, &proof).is_ok());
+
+        type BlsCq = Cq<Bls12_377, PoseidonFiatShamirRng<ark_bls12_377::Fr>>;
+        let (pk, vk) = BlsCq::setup(&to_field(&[1, 2, 3, 4]), 2, &mut rng).unwrap();
+        let (statement, proof) = BlsCq::prove(&pk, &to_field(&[2, 4])).unwrap();
+        assert!(BlsCq::verify(&vk, &statement, &proof).is_ok());
+    }
+}
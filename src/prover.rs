@@ -1,33 +1,92 @@
-use std::{collections::BTreeMap, marker::PhantomData};
-
 use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
-use ark_ff::{Field, One, ToBytes, Zero};
+use ark_ff::{batch_inversion, Field, One, ToBytes, Zero};
 use ark_poly::{
     univariate::DensePolynomial, EvaluationDomain, GeneralEvaluationDomain, Polynomial,
     UVPolynomial,
 };
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{
+    cfg_iter, cfg_iter_mut, collections::BTreeMap, format, io, marker::PhantomData, mem,
+    rand::RngCore, vec, vec::Vec, UniformRand,
+};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use zeroize::Zeroize;
 
 use crate::{
     data_structures::{Proof, ProvingKey, Statement, Witness},
-    error::Error,
-    indexer::Index,
+    error::{Error, VerificationError},
+    indexer::IndexView,
     kzg::Kzg,
+    metrics::{PeakWindow, ProverMetrics, Timer},
     rng::FiatShamirRng,
     table::Table,
-    transcript::TranscriptOracle,
+    transcript::{
+        absorb_first_message, absorb_public_input, absorb_second_message, absorb_third_message,
+        Transcript, TranscriptOracle,
+    },
+    utils::domain,
+    verifier::{a_bound_shift, b0_bound_shift, exact_srs_degree, VerifierKey},
     PROTOCOL_NAME,
 };
 
-pub struct Prover<E: PairingEngine, FS: FiatShamirRng> {
+/// Prover of the protocol, `FS` is the Fiat-Shamir rng of `prove` and `prove_with_metrics`, or the
+/// [`Transcript`] of `prove_with_transcript`
+pub struct Prover<E: PairingEngine, FS> {
     _e: PhantomData<E>,
     _fs: PhantomData<FS>,
 }
 
+/// Values of a proof that only depend on the key, the index and the table
+struct TableData<E: PairingEngine> {
+    domain: GeneralEvaluationDomain<E::Fr>,
+    n_table_inv: E::Fr,
+    /// [zV(x)]_1 and [x·zV(x)]_1, if the srs is large enough for zk proofs
+    zv_1: Option<(E::G1Affine, E::G1Affine)>,
+    /// [T(x)]_1 and [x·T(x)]_1, if the index has them
+    t_1: Option<(E::G1Affine, E::G1Affine)>,
+}
+
+impl<E: PairingEngine> Clone for TableData<E> {
+    fn clone(&self) -> Self {
+        Self {
+            domain: self.domain.clone(),
+            n_table_inv: self.n_table_inv,
+            zv_1: self.zv_1,
+            t_1: self.t_1,
+        }
+    }
+}
+
+impl<E: PairingEngine> TableData<E> {
+    fn new(
+        pk: &ProvingKey<E>,
+        index: &dyn IndexView<E>,
+        table: &Table<E::Fr>,
+    ) -> Result<Self, Error> {
+        let domain = domain::<E::Fr>(table.size)?;
+        let srs = &pk.srs_g1;
+        let n = table.size;
+        let zv_1 = (srs.len() >= n + 2).then(|| (srs[n] + -srs[0], srs[n + 1] + -srs[1]));
+        let n_table_inv = domain
+            .size_as_field_element()
+            .inverse()
+            .ok_or(Error::TableSizeNotPow2(n))?;
+        Ok(Self {
+            domain,
+            n_table_inv,
+            zv_1,
+            t_1: index.t_1().ok(),
+        })
+    }
+}
+
 pub struct State<'a, E: PairingEngine> {
     pk: &'a ProvingKey<E>,
-    index: &'a Index<E>,
+    index: &'a dyn IndexView<E>,
     table: &'a Table<E::Fr>,
     witness: &'a Witness<E::Fr>,
+    table_data: TableData<E>,
 
     // captured in round_1
     m_sparse: Option<BTreeMap<usize, E::Fr>>,
@@ -37,20 +96,69 @@ pub struct State<'a, E: PairingEngine> {
     qb: Option<DensePolynomial<E::Fr>>,
     a_sparse: Option<BTreeMap<usize, E::Fr>>,
     a_at_zero: Option<E::Fr>,
+
+    // sampled in `blinded`
+    blinders: Option<Blinders<E::Fr>>,
+
+    // coefficients per msm, set in `msm_chunk`
+    msm_chunk: Option<usize>,
+
+    // highest power of the srs of the verifier key, set in `for_key`
+    srs_degree: Option<usize>,
+
+    // set in `validated`
+    validate: bool,
+
+    metrics: ProverMetrics,
+}
+
+/// Randomness of a zk proof. `b = N·a / n` and `t = n·s / N` keep the sums over the table and the witness
+/// domain equal and cancel the blinders in the x^N coefficient of G. Wiped on drop.
+struct Blinders<F: Zeroize> {
+    /// M(X) + m·zV(X)
+    m: F,
+    /// A(X) + (a + t·X)·zV(X)
+    a: F,
+    t: F,
+    /// B(X) + (b + s·X)·zH(X)
+    b: F,
+    s: F,
+}
+
+impl<F: Zeroize> Drop for Blinders<F> {
+    fn drop(&mut self) {
+        self.m.zeroize();
+        self.a.zeroize();
+        self.t.zeroize();
+        self.b.zeroize();
+        self.s.zeroize();
+    }
 }
 
 impl<'a, E: PairingEngine> State<'a, E> {
     pub fn new(
         pk: &'a ProvingKey<E>,
-        index: &'a Index<E>,
+        index: &'a dyn IndexView<E>,
+        table: &'a Table<E::Fr>,
+        witness: &'a Witness<E::Fr>,
+    ) -> Result<Self, Error> {
+        let table_data = TableData::new(pk, index, table)?;
+        Ok(Self::with_table_data(pk, index, table, witness, table_data))
+    }
+
+    fn with_table_data(
+        pk: &'a ProvingKey<E>,
+        index: &'a dyn IndexView<E>,
         table: &'a Table<E::Fr>,
         witness: &'a Witness<E::Fr>,
+        table_data: TableData<E>,
     ) -> Self {
         Self {
             pk,
             index,
             table,
             witness,
+            table_data,
 
             m_sparse: None,
 
@@ -58,48 +166,172 @@ impl<'a, E: PairingEngine> State<'a, E> {
             qb: None,
             a_sparse: None,
             a_at_zero: None,
+
+            blinders: None,
+
+            msm_chunk: None,
+
+            srs_degree: None,
+
+            validate: false,
+
+            metrics: ProverMetrics::default(),
+        }
+    }
+
+    /// Samples blinders for a zk proof, which needs the srs to reach x^(N+1) in G1 and a witness no larger than
+    /// the table
+    pub fn blinded<R: RngCore>(mut self, rng: &mut R) -> Result<Self, Error> {
+        if self.witness.size > self.table.size {
+            return Err(Error::WitnessLargerThanTable(
+                self.witness.size,
+                self.table.size,
+            ));
+        }
+        let srs_len = self.table.size + 2;
+        if self.pk.srs_g1.len() < srs_len {
+            return Err(Error::SrsG1TooSmall(srs_len, self.pk.srs_g1.len()));
+        }
+
+        let n_table = E::Fr::from(self.table.size as u64);
+        let n = E::Fr::from(self.witness.size as u64);
+        let n_inv = n
+            .inverse()
+            .ok_or(Error::WitnessSizeNotPow2(self.witness.size))?;
+        let m = E::Fr::rand(rng);
+        let a = E::Fr::rand(rng);
+        let s = E::Fr::rand(rng);
+        self.blinders = Some(Blinders {
+            m,
+            a,
+            t: n * s * self.table_data.n_table_inv,
+            b: n_table * a * n_inv,
+            s,
+        });
+        Ok(self)
+    }
+
+    /// Splits every commitment into msms of at most `chunk` coefficients, proofs are the same as without it.
+    /// This caps the scalars converted for one msm, not the memory of the proof: the witness, the counts and the
+    /// polynomials of the rounds are still held in full.
+    pub fn msm_chunk(mut self, chunk: usize) -> Self {
+        self.msm_chunk = Some(chunk.max(1));
+        self
+    }
+
+    /// Shifts the degree bounds to the highest power of the srs of `vk`, which the proving key has to reach
+    pub(crate) fn for_key(mut self, vk: &VerifierKey<E>) -> Result<Self, Error> {
+        if self.pk.srs_g1.len() <= vk.srs_degree {
+            return Err(Error::SrsG1TooSmall(
+                vk.srs_degree + 1,
+                self.pk.srs_g1.len(),
+            ));
+        }
+        let exact = exact_srs_degree(self.table.size, self.witness.size, self.blinders.is_some());
+        if vk.srs_degree < exact {
+            return Err(Error::SrsG1TooSmall(exact + 1, vk.srs_degree + 1));
+        }
+        self.srs_degree = Some(vk.srs_degree);
+        Ok(self)
+    }
+
+    /// Highest power of the srs, the one of an srs of exactly the needed size if no key was given
+    fn srs_degree(&self) -> usize {
+        self.srs_degree.unwrap_or_else(|| {
+            exact_srs_degree(self.table.size, self.witness.size, self.blinders.is_some())
+        })
+    }
+
+    /// Checks the witness against the table and the statement before the first round, so that a bad witness
+    /// fails with the position of its first bad value instead of inside a round or at verification. Costs one
+    /// table lookup per value and the msm of the statement.
+    pub fn validated(mut self) -> Self {
+        self.validate = true;
+        self
+    }
+
+    /// [x^shift·poly(x)]_1
+    fn commit(&self, poly: &DensePolynomial<E::Fr>, shift: usize) -> Result<E::G1Affine, Error> {
+        let len = shift + poly.coeffs.len();
+        if self.pk.srs_g1.len() < len {
+            return Err(Error::SrsG1TooSmall(len, self.pk.srs_g1.len()));
+        }
+        let srs = &self.pk.srs_g1[shift..];
+        let cm = match self.msm_chunk {
+            Some(chunk) => Kzg::<E>::commit_g1_chunked(srs, poly, chunk)?,
+            None => Kzg::<E>::commit_g1(srs, poly),
+        };
+        Ok(cm.into())
+    }
+
+    /// [zV(x)]_1 and [x·zV(x)]_1
+    fn zv_1(&self) -> (E::G1Affine, E::G1Affine) {
+        self.table_data
+            .zv_1
+            .expect("blinded checks that the srs reaches x^(N+1)")
+    }
+
+    /// [T(x)]_1 and [x·T(x)]_1
+    fn t_1(&self) -> Result<(E::G1Affine, E::G1Affine), Error> {
+        match self.table_data.t_1 {
+            Some(t_1) => Ok(t_1),
+            None => self.index.t_1(),
         }
     }
 }
 
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
 pub struct ProverFirstMessage<E: PairingEngine> {
-    pub(crate) m_cm: E::G1Affine,
+    pub m_cm: E::G1Affine,
 }
 
 impl<E: PairingEngine> ToBytes for ProverFirstMessage<E> {
-    fn write<W: std::io::Write>(&self, mut w: W) -> std::io::Result<()> {
+    fn write<W: io::Write>(&self, mut w: W) -> io::Result<()> {
         self.m_cm.write(&mut w)
     }
 }
 
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
 pub struct ProverSecondMessage<E: PairingEngine> {
-    pub(crate) a_cm: E::G1Affine,
-    pub(crate) qa_cm: E::G1Affine,
-    pub(crate) b0_cm: E::G1Affine,
-    pub(crate) qb_cm: E::G1Affine,
-    pub(crate) p_cm: E::G1Affine,
+    pub a_cm: E::G1Affine,
+    pub qa_cm: E::G1Affine,
+    pub b0_cm: E::G1Affine,
+    pub qb_cm: E::G1Affine,
+    pub p_cm: E::G1Affine,
+    /// [x^2·G(x)]_1 of zk proofs, G = N·(A(X) - A(0)) - n·P(X) has degree below N
+    pub g_cm: Option<E::G1Affine>,
+    /// [x^(n-N)·A(x)]_1 of proofs for witnesses larger than the table, where the srs doesn't bound A below N
+    pub a_shift_cm: Option<E::G1Affine>,
 }
 
 impl<E: PairingEngine> ToBytes for ProverSecondMessage<E> {
-    fn write<W: std::io::Write>(&self, mut w: W) -> std::io::Result<()> {
+    fn write<W: io::Write>(&self, mut w: W) -> io::Result<()> {
         self.a_cm.write(&mut w)?;
         self.qa_cm.write(&mut w)?;
         self.b0_cm.write(&mut w)?;
         self.qb_cm.write(&mut w)?;
-        self.p_cm.write(&mut w)
+        self.p_cm.write(&mut w)?;
+        if let Some(g_cm) = &self.g_cm {
+            g_cm.write(&mut w)?;
+        }
+        match &self.a_shift_cm {
+            Some(a_shift_cm) => a_shift_cm.write(&mut w),
+            None => Ok(()),
+        }
     }
 }
 
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
 pub struct ProverThirdMessage<E: PairingEngine> {
-    pub(crate) b0_at_gamma: E::Fr,
-    pub(crate) f_at_gamma: E::Fr,
-    pub(crate) a_at_zero: E::Fr,
-    pub(crate) pi_gamma: E::G1Affine,
-    pub(crate) a0_cm: E::G1Affine,
+    pub b0_at_gamma: E::Fr,
+    pub f_at_gamma: E::Fr,
+    pub a_at_zero: E::Fr,
+    pub pi_gamma: E::G1Affine,
+    pub a0_cm: E::G1Affine,
 }
 
 impl<E: PairingEngine> ToBytes for ProverThirdMessage<E> {
-    fn write<W: std::io::Write>(&self, mut w: W) -> std::io::Result<()> {
+    fn write<W: io::Write>(&self, mut w: W) -> io::Result<()> {
         self.b0_at_gamma.write(&mut w)?;
         self.f_at_gamma.write(&mut w)?;
         self.a_at_zero.write(&mut w)?;
@@ -111,126 +343,433 @@ impl<E: PairingEngine> ToBytes for ProverThirdMessage<E> {
 impl<E: PairingEngine, FS: FiatShamirRng> Prover<E, FS> {
     pub fn prove<'a>(
         pk: &'a ProvingKey<E>,
-        index: &'a Index<E>,
+        vk: &VerifierKey<E>,
+        index: &'a dyn IndexView<E>,
+        table: &'a Table<E::Fr>,
+        witness: &'a Witness<E::Fr>,
+        statement: &Statement<E>,
+    ) -> Result<Proof<E>, Error> {
+        Self::prove_with_metrics(pk, vk, index, table, witness, statement).map(|(proof, _)| proof)
+    }
+
+    /// Same as `prove`, additionally returns per round timings and operation counts
+    pub fn prove_with_metrics<'a>(
+        pk: &'a ProvingKey<E>,
+        vk: &VerifierKey<E>,
+        index: &'a dyn IndexView<E>,
+        table: &'a Table<E::Fr>,
+        witness: &'a Witness<E::Fr>,
+        statement: &Statement<E>,
+    ) -> Result<(Proof<E>, ProverMetrics), Error> {
+        let mut transcipt = TranscriptOracle::<FS>::initialize(&PROTOCOL_NAME);
+        Prover::<E, TranscriptOracle<FS>>::prove_with_transcript(
+            &mut transcipt,
+            pk,
+            vk,
+            index,
+            table,
+            witness,
+            statement,
+        )
+    }
+
+    /// Same as `prove` on a `State::validated` state: a witness value missing from the table fails with
+    /// `WitnessValueNotInTable(position, value)` and a statement of other values with `StatementOpeningMismatch`
+    /// before any round runs
+    pub fn prove_checked<'a>(
+        pk: &'a ProvingKey<E>,
+        vk: &VerifierKey<E>,
+        index: &'a dyn IndexView<E>,
+        table: &'a Table<E::Fr>,
+        witness: &'a Witness<E::Fr>,
+        statement: &Statement<E>,
+    ) -> Result<Proof<E>, Error> {
+        let state = State::new(pk, index, table, witness)?.validated();
+        let mut transcipt = TranscriptOracle::<FS>::initialize(&PROTOCOL_NAME);
+        Prover::<E, TranscriptOracle<FS>>::prove_with_state(&mut transcipt, state, vk, statement)
+            .map(|(proof, _)| proof)
+    }
+
+    /// Zero-knowledge variant of `prove`, the messages are blinded with randomness from `rng`.
+    /// Needs a key from `VerifierKey::for_table(.., true)` and an srs and index reaching x^(N+1) in G1. Proofs
+    /// can't be rerandomized after the fact, call it again for an unlinkable proof of the same statement.
+    pub fn prove_zk<'a, R: RngCore>(
+        pk: &'a ProvingKey<E>,
+        vk: &VerifierKey<E>,
+        index: &'a dyn IndexView<E>,
         table: &'a Table<E::Fr>,
         witness: &'a Witness<E::Fr>,
         statement: &Statement<E>,
+        rng: &mut R,
     ) -> Result<Proof<E>, Error> {
-        let mut state = State::new(pk, index, table, witness);
+        let state = State::new(pk, index, table, witness)?.blinded(rng)?;
         let mut transcipt = TranscriptOracle::<FS>::initialize(&PROTOCOL_NAME);
+        Prover::<E, TranscriptOracle<FS>>::prove_with_state(&mut transcipt, state, vk, statement)
+            .map(|(proof, _)| proof)
+    }
 
-        transcipt.stream_public_input(&index.common, statement);
+    /// Same as `prove` with the msms split by `State::msm_chunk`
+    #[allow(clippy::too_many_arguments)]
+    pub fn prove_with_msm_chunk<'a>(
+        pk: &'a ProvingKey<E>,
+        vk: &VerifierKey<E>,
+        index: &'a dyn IndexView<E>,
+        table: &'a Table<E::Fr>,
+        witness: &'a Witness<E::Fr>,
+        statement: &Statement<E>,
+        msm_chunk: usize,
+    ) -> Result<Proof<E>, Error> {
+        let state = State::new(pk, index, table, witness)?.msm_chunk(msm_chunk);
+        let mut transcipt = TranscriptOracle::<FS>::initialize(&PROTOCOL_NAME);
+        Prover::<E, TranscriptOracle<FS>>::prove_with_state(&mut transcipt, state, vk, statement)
+            .map(|(proof, _)| proof)
+    }
+}
+
+/// Prover for many witnesses against the same key, index and table. The table domain, [zV(x)]_1 and the zk
+/// commitments of the index are computed once in `new` instead of in every proof.
+pub struct ProverContext<'a, E: PairingEngine, FS> {
+    pk: &'a ProvingKey<E>,
+    vk: &'a VerifierKey<E>,
+    index: &'a dyn IndexView<E>,
+    table: &'a Table<E::Fr>,
+    table_data: TableData<E>,
+    _fs: PhantomData<FS>,
+}
+
+impl<'a, E: PairingEngine, FS: FiatShamirRng> ProverContext<'a, E, FS> {
+    /// `vk` is absorbed into the transcript of every proof
+    pub fn new(
+        pk: &'a ProvingKey<E>,
+        vk: &'a VerifierKey<E>,
+        index: &'a dyn IndexView<E>,
+        table: &'a Table<E::Fr>,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            pk,
+            vk,
+            index,
+            table,
+            table_data: TableData::new(pk, index, table)?,
+            _fs: PhantomData,
+        })
+    }
+
+    /// Fresh prover state for `witness`, blind it for zk proofs
+    pub fn state<'w>(&self, witness: &'w Witness<E::Fr>) -> State<'w, E>
+    where
+        'a: 'w,
+    {
+        State::with_table_data(
+            self.pk,
+            self.index,
+            self.table,
+            witness,
+            self.table_data.clone(),
+        )
+    }
+
+    /// Same as `Prover::prove` with the context's key, index and table
+    pub fn prove(
+        &self,
+        witness: &Witness<E::Fr>,
+        statement: &Statement<E>,
+    ) -> Result<Proof<E>, Error> {
+        self.prove_with_state(self.state(witness), statement)
+    }
+
+    /// Same as `Prover::prove_zk` with the context's key, index and table
+    pub fn prove_zk<R: RngCore>(
+        &self,
+        witness: &Witness<E::Fr>,
+        statement: &Statement<E>,
+        rng: &mut R,
+    ) -> Result<Proof<E>, Error> {
+        self.prove_with_state(self.state(witness).blinded(rng)?, statement)
+    }
+
+    fn prove_with_state(
+        &self,
+        state: State<E>,
+        statement: &Statement<E>,
+    ) -> Result<Proof<E>, Error> {
+        let mut transcipt = TranscriptOracle::<FS>::initialize(&PROTOCOL_NAME);
+        Prover::<E, TranscriptOracle<FS>>::prove_with_state(
+            &mut transcipt,
+            state,
+            self.vk,
+            statement,
+        )
+        .map(|(proof, _)| proof)
+    }
+}
 
+impl<E: PairingEngine, T: Transcript<E>> Prover<E, T> {
+    /// Runs the prover on a caller supplied transcript, which is left in the state after the proof
+    pub fn prove_with_transcript<'a>(
+        transcript: &mut T,
+        pk: &'a ProvingKey<E>,
+        vk: &VerifierKey<E>,
+        index: &'a dyn IndexView<E>,
+        table: &'a Table<E::Fr>,
+        witness: &'a Witness<E::Fr>,
+        statement: &Statement<E>,
+    ) -> Result<(Proof<E>, ProverMetrics), Error> {
+        let state = State::new(pk, index, table, witness)?;
+        Self::prove_with_state(transcript, state, vk, statement)
+    }
+
+    /// Runs all rounds on a fresh `state`, which is blinded for zk proofs
+    pub fn prove_with_state(
+        transcript: &mut T,
+        state: State<E>,
+        vk: &VerifierKey<E>,
+        statement: &Statement<E>,
+    ) -> Result<(Proof<E>, ProverMetrics), Error> {
+        if state.blinders.is_some() != vk.is_zk() {
+            return Err(Error::Verification(VerificationError::ZkModeMismatch(
+                vk.is_zk(),
+            )));
+        }
+        let mut state = state.for_key(vk)?;
+        // a hiding f, times the blinder of B in zk proofs, ends up in QB
+        let f_len = state.witness.committed_f().coeffs.len();
+        let qb_len = f_len + state.blinders.is_some() as usize;
+        if state.pk.srs_g1.len() < qb_len {
+            return Err(Error::SrsG1TooSmall(qb_len, state.pk.srs_g1.len()));
+        }
+
+        if state.validate {
+            state.witness.check_table(state.table)?;
+            statement.check_opening(state.pk, state.witness)?;
+        }
+
+        let peak_window = PeakWindow::open();
+        let start = Timer::start();
+
+        absorb_public_input(transcript, vk, state.index.common(), statement);
+
+        let round_start = Timer::start();
         let first_msg = Self::round_1(&mut state)?;
-        transcipt.stream_first_message(&first_msg);
+        state.metrics.round_1 = round_start.elapsed();
+        absorb_first_message(transcript, &first_msg);
 
-        let beta: E::Fr = transcipt.squeeze_challenge();
+        let beta = transcript.squeeze_challenge(b"beta");
 
+        let round_start = Timer::start();
         let second_msg = Self::round_2(&mut state, beta)?;
-        transcipt.stream_second_message(&second_msg);
+        state.metrics.round_2 = round_start.elapsed();
+        absorb_second_message(transcript, &second_msg);
 
-        let gamma: E::Fr = transcipt.squeeze_challenge();
-        let eta: E::Fr = transcipt.squeeze_challenge();
+        let gamma = transcript.squeeze_challenge(b"gamma");
+        let eta = transcript.squeeze_challenge(b"eta");
 
+        let round_start = Timer::start();
         let third_msg = Self::round_3(&mut state, gamma, eta)?;
+        state.metrics.round_3 = round_start.elapsed();
+        absorb_third_message(transcript, &third_msg);
+        // the verifier's batching challenge, drawn so both sides leave the transcript in the same state
+        transcript.squeeze_challenge(b"u");
 
-        Ok(Proof {
-            first_msg,
-            second_msg,
-            third_msg,
-        })
+        let mut metrics = state.metrics;
+        metrics.total = start.elapsed();
+        metrics.peak_allocation = peak_window.close();
+
+        Ok((Proof::new(first_msg, second_msg, third_msg), metrics))
     }
+}
 
+impl<E: PairingEngine, FS> Prover<E, FS> {
+    /// Commits to the multiplicities of the table values in the witness
     pub fn round_1(state: &mut State<E>) -> Result<ProverFirstMessage<E>, Error> {
         let mut index_multiplicity_mapping = BTreeMap::<usize, E::Fr>::default();
-
-        for fi in &state.witness.f_evals {
-            let index = state.table.value_index_mapping.get(fi);
-            let err_str = format!("{}", fi);
-            let index = index.ok_or(Error::ValueNotInTable(err_str))?;
-            let zero = E::Fr::zero();
-            let multiplicity = index_multiplicity_mapping.entry(*index).or_insert(zero);
+        let mut count = |index: usize| {
+            let multiplicity = index_multiplicity_mapping
+                .entry(index)
+                .or_insert_with(E::Fr::zero);
             *multiplicity += E::Fr::one();
+        };
+
+        match &state.witness.indices {
+            // positions known from `Witness::from_indices`, no table lookups
+            Some(indices) => {
+                for &index in indices {
+                    if index >= state.table.size {
+                        return Err(Error::IndexOutOfTable(index, state.table.size));
+                    }
+                    count(index);
+                }
+            }
+            None => {
+                for fi in &state.witness.f_evals {
+                    let index = state
+                        .table
+                        .value_index_mapping
+                        .get(fi)
+                        .ok_or_else(|| Error::ValueNotInTable(format!("{}", fi)))?;
+                    count(*index);
+                }
+            }
         }
 
-        let mut m_cm = E::G1Affine::zero();
-        for (&index, &multiplicity) in index_multiplicity_mapping.iter() {
-            m_cm = state.index.ls[index]
-                .mul(multiplicity)
-                .add_mixed(&m_cm)
-                .into();
+        let mut m_cm = sparse_msm(&index_multiplicity_mapping, |i| state.index.l(i))?;
+        state.metrics.record_msm(index_multiplicity_mapping.len());
+
+        if let Some(blinders) = &state.blinders {
+            m_cm += state.zv_1().0.mul(blinders.m);
         }
+        let m_cm = m_cm.into_affine();
 
         state.m_sparse = Some(index_multiplicity_mapping);
         Ok(ProverFirstMessage { m_cm })
     }
 
+    /// Commits to A, B0 and their quotients given the verifier challenge `beta`
     pub fn round_2(state: &mut State<E>, beta: E::Fr) -> Result<ProverSecondMessage<E>, Error> {
-        let wtns_domain = GeneralEvaluationDomain::<E::Fr>::new(state.witness.size).unwrap();
+        let wtns_domain = domain::<E::Fr>(state.witness.size)?;
         let m_sparse = state
             .m_sparse
             .as_ref()
             .expect("m is missing from the state");
 
-        let mut a_sparse = BTreeMap::<usize, E::Fr>::default();
-        let mut a_cm = E::G1Affine::zero();
-        let mut qa_cm = E::G1Affine::zero();
-
-        // step 2&3&4: computes A sparse representation, a commitment and qa commitment in single pass
-        for (&index, &multiplicity) in m_sparse.iter() {
-            let a_i = multiplicity * (state.table.values[index] + beta).inverse().unwrap();
-            let _ = a_sparse.insert(index, a_i); // keys are unique so overriding will never occur
-
-            a_cm = state.index.ls[index].mul(a_i).add_mixed(&a_cm).into();
-            qa_cm = state.index.qs[index].mul(a_i).add_mixed(&qa_cm).into();
-        }
-
-        // step 5: compute B(X)
-        let b_evals: Vec<_> = state
-            .witness
-            .f_evals
+        // step 2: compute A sparse representation, with one inversion for all entries
+        let mut denominators: Vec<E::Fr> = m_sparse
+            .keys()
+            .map(|&index| state.table.values[index] + beta)
+            .collect();
+        batch_inversion(&mut denominators);
+        let a_sparse: BTreeMap<usize, E::Fr> = m_sparse
             .iter()
-            .map(|&fi| (fi + beta).inverse().unwrap())
+            .zip(denominators)
+            .map(|((&index, &multiplicity), inverse)| (index, multiplicity * inverse))
             .collect();
-        let b_poly = DensePolynomial::from_coefficients_slice(&wtns_domain.ifft(&b_evals));
 
-        // step 6: compute B0(X)
-        let b0_poly = DensePolynomial::from_coefficients_slice(&b_poly.coeffs[1..]);
+        // step 3&4: commit to A and QA
+        let mut a_cm = sparse_msm(&a_sparse, |i| state.index.l(i))?;
+        let mut qa_cm = sparse_msm(&a_sparse, |i| state.index.q(i))?;
+        state.metrics.record_msm(m_sparse.len());
+        state.metrics.record_msm(m_sparse.len());
+
+        // zk: A(X) + (a + t·X)·zV(X) adds (a + t·X)·(T(X) + beta) - m to QA(X)
+        if let Some(blinders) = &state.blinders {
+            let (zv_1, x_zv_1) = state.zv_1();
+            let (t_1, x_t_1) = state.t_1()?;
+            let srs = &state.pk.srs_g1;
+            a_cm += zv_1.mul(blinders.a) + x_zv_1.mul(blinders.t);
+            qa_cm += t_1.mul(blinders.a)
+                + x_t_1.mul(blinders.t)
+                + srs[0].mul(blinders.a * beta - blinders.m)
+                + srs[1].mul(blinders.t * beta);
+        }
+        let a_cm = a_cm.into_affine();
+        let qa_cm = qa_cm.into_affine();
+
+        // srs past x^(N-1), e.g. for witnesses larger than the table: A(X) is shown to be below x^N with
+        // [x^(D+1-N)·A(x)]_1, from its coefficients over the table domain
+        let zk = state.blinders.is_some();
+        let a_shift_cm = a_bound_shift(state.table.size, state.srs_degree(), zk)
+            .map(|shift| {
+                let mut a_coeffs = vec![E::Fr::zero(); state.table.size];
+                for (&index, &a) in &a_sparse {
+                    a_coeffs[index] = a;
+                }
+                state.table_data.domain.ifft_in_place(&mut a_coeffs);
+                let a_poly = DensePolynomial::from_coefficients_vec(a_coeffs);
+                state.commit(&a_poly, shift)
+            })
+            .transpose()?;
+        if a_shift_cm.is_some() {
+            state.metrics.record_fft(state.table.size);
+            state.metrics.record_msm(state.table.size);
+        }
 
-        // step 7: commit to B0(X)
-        let b0_cm: E::G1Affine = Kzg::<E>::commit_g1(&state.pk.srs_g1, &b0_poly).into();
+        // step 5: compute B(X), the evaluations are inverted together and interpolated in place
+        let mut b_coeffs: Vec<_> = cfg_iter!(state.witness.f_evals)
+            .map(|&fi| fi + beta)
+            .collect();
+        batch_inversion(&mut b_coeffs);
+        wtns_domain.ifft_in_place(&mut b_coeffs);
+        let b_poly = DensePolynomial::from_coefficients_vec(b_coeffs);
+        state.metrics.record_fft(wtns_domain.size());
 
-        // step 8: compute QB(X)
-        let b_coset_evals = wtns_domain.coset_fft(&b_poly);
+        // step 8: compute QB(X), in place over the coset evaluations of B
+        let mut qb_evals = wtns_domain.coset_fft(&b_poly);
         let f_coset_evals = wtns_domain.coset_fft(&state.witness.f);
-        let mut qb_evals: Vec<_> = b_coset_evals
-            .iter()
-            .zip(f_coset_evals.iter())
-            .map(|(&bi, &fi)| bi * (fi + beta) - E::Fr::one())
-            .collect();
+        cfg_iter_mut!(qb_evals)
+            .zip(cfg_iter!(f_coset_evals))
+            .for_each(|(qi, &fi)| *qi = *qi * (fi + beta) - E::Fr::one());
+        mem::drop(f_coset_evals);
         wtns_domain.divide_by_vanishing_poly_on_coset_in_place(&mut qb_evals);
-        let qb_poly = DensePolynomial::from_coefficients_slice(&wtns_domain.coset_ifft(&qb_evals));
+        wtns_domain.coset_ifft_in_place(&mut qb_evals);
+        let mut qb_poly = DensePolynomial::from_coefficients_vec(qb_evals);
+        for _ in 0..3 {
+            state.metrics.record_fft(wtns_domain.size());
+        }
+        // hiding statement: f(X) + (r0 + r1·X)·zH(X) adds (r0 + r1·X)·B(X) to QB(X)
+        if let Some(blinder) = &state.witness.blinder {
+            let rho = DensePolynomial::from_coefficients_vec(vec![blinder.r0, blinder.r1]);
+            qb_poly += &(&rho * &b_poly);
+        }
+        // zk: QB(X) + (b + s·X)·(f(X) + beta), with f the committed polynomial
+        if let Some(blinders) = &state.blinders {
+            let rho = DensePolynomial::from_coefficients_vec(vec![blinders.b, blinders.s]);
+            let f_plus_beta =
+                state.witness.committed_f() + &DensePolynomial::from_coefficients_vec(vec![beta]);
+            qb_poly += &(&rho * &f_plus_beta);
+        }
 
-        // step 9: commit to QB(X)
-        let qb_cm: E::G1Affine = Kzg::<E>::commit_g1(&state.pk.srs_g1, &qb_poly).into();
+        // step 6: compute B0(X) in the buffer of B(X), which is done once QB(X) is known, for zk proofs also the one
+        // of B(X) + (b + s·X)·zH(X)
+        let b_at_zero = b_poly.evaluate(&E::Fr::zero());
+        let mut b0_coeffs = b_poly.coeffs;
+        if !b0_coeffs.is_empty() {
+            b0_coeffs.remove(0);
+        }
+        let b0_poly = DensePolynomial::from_coefficients_vec(b0_coeffs);
+        let b0_blinded = state.blinders.as_ref().map(|blinders| {
+            let n = state.witness.size;
+            let mut coeffs = b0_poly.coeffs.clone();
+            coeffs.resize(n + 1, E::Fr::zero());
+            coeffs[0] -= blinders.s;
+            coeffs[n - 1] += blinders.b;
+            coeffs[n] += blinders.s;
+            DensePolynomial::from_coefficients_vec(coeffs)
+        });
+        let b0 = b0_blinded.as_ref().unwrap_or(&b0_poly);
 
-        // step 10: compute degree correctness check for B0
-        let mut shifted_coeffs =
-            vec![E::Fr::zero(); state.table.size - 1 - (state.witness.size - 2)];
-        shifted_coeffs.extend_from_slice(&b0_poly);
-        let p_poly = DensePolynomial::from_coefficients_slice(&shifted_coeffs);
-        let p_cm: E::G1Affine = Kzg::<E>::commit_g1(&state.pk.srs_g1, &p_poly).into();
+        // step 7: commit to B0(X)
+        let b0_cm = state.commit(b0, 0)?;
+        state.metrics.record_msm(b0.coeffs.len());
 
-        state.b0 = Some(b0_poly);
-        state.qb = Some(qb_poly);
+        // step 9: commit to QB(X)
+        let qb_cm = state.commit(&qb_poly, 0)?;
+        state.metrics.record_msm(qb_poly.coeffs.len());
+
+        // step 10: compute degree correctness check for B0, P(X) = X^(D-(n-2))·B0(X)
+        let shift = b0_bound_shift(state.srs_degree(), state.witness.size, zk);
+        let p_cm = state.commit(b0, shift)?;
+        state.metrics.record_msm(b0.coeffs.len());
 
         let a_at_zero = {
-            let b_at_zero = b_poly.evaluate(&E::Fr::zero());
             let n = E::Fr::from(state.witness.size as u64);
 
-            let n_table_inv = E::Fr::from(state.table.size as u64).inverse().unwrap();
+            n * b_at_zero * state.table_data.n_table_inv
+        };
+
+        let g_cm = match &state.blinders {
+            Some(blinders) => Some(Self::zk_g_commitment(
+                state, blinders, &a_sparse, &b0_poly, a_at_zero,
+            )?),
+            None => None,
+        };
+
+        state.b0 = Some(b0_blinded.unwrap_or(b0_poly));
+        state.qb = Some(qb_poly);
 
-            n * b_at_zero * n_table_inv
+        // zk: A(0) - a
+        let a_at_zero = match &state.blinders {
+            Some(blinders) => a_at_zero - blinders.a,
+            None => a_at_zero,
         };
 
         state.a_at_zero = Some(a_at_zero);
@@ -246,9 +785,53 @@ impl<E: PairingEngine, FS: FiatShamirRng> Prover<E, FS> {
             b0_cm,
             qb_cm,
             p_cm,
+            g_cm,
+            a_shift_cm,
         })
     }
 
+    /// [x^2·G(x)]_1 from the unblinded A and B0. With the relations between the blinders,
+    /// G = N·(A(X) - A(0)) - n·X^(N-n+1)·(B0(X) - s) - n·s·X, and [x^2·A(x)]_1 follows from
+    /// x·Li(x) = w^i·Li(x) + w^i / N·zV(x) without any extra cached commitments.
+    fn zk_g_commitment(
+        state: &State<E>,
+        blinders: &Blinders<E::Fr>,
+        a_sparse: &BTreeMap<usize, E::Fr>,
+        b0: &DensePolynomial<E::Fr>,
+        a_at_zero: E::Fr,
+    ) -> Result<E::G1Affine, Error> {
+        let table_domain = state.table_data.domain;
+        let n_table = table_domain.size_as_field_element();
+        let n = E::Fr::from(state.witness.size as u64);
+        let srs = &state.pk.srs_g1;
+        let (zv_1, x_zv_1) = state.zv_1();
+
+        let mut zv_coeff = E::Fr::zero();
+        let mut x_zv_coeff = E::Fr::zero();
+        let mut x2_a_sparse = BTreeMap::<usize, E::Fr>::default();
+        for (&index, &a_i) in a_sparse.iter() {
+            let w_i = table_domain.element(index);
+            x2_a_sparse.insert(index, a_i * w_i * w_i);
+            zv_coeff += a_i * w_i * w_i;
+            x_zv_coeff += a_i * w_i;
+        }
+        let mut x2_a = sparse_msm(&x2_a_sparse, |i| state.index.l(i))?;
+        let n_table_inv = state.table_data.n_table_inv;
+        x2_a += zv_1.mul(zv_coeff * n_table_inv) + x_zv_1.mul(x_zv_coeff * n_table_inv);
+        x2_a -= srs[2].mul(a_at_zero);
+
+        // x^2·(X^(N-n+1)·(B0(X) - s) + s·X)
+        let shift = state.table.size - state.witness.size + 3;
+        let mut coeffs = vec![E::Fr::zero(); shift + b0.coeffs.len().max(1)];
+        coeffs[shift..shift + b0.coeffs.len()].copy_from_slice(&b0.coeffs);
+        coeffs[shift] -= blinders.s;
+        coeffs[3] += blinders.s;
+        let x2_b0 = Kzg::<E>::commit_g1(srs, &DensePolynomial::from_coefficients_vec(coeffs));
+
+        Ok((x2_a.into_affine().mul(n_table) - x2_b0.into_affine().mul(n)).into_affine())
+    }
+
+    /// Opens B0 and f at `gamma` batched with `eta`, and proves the evaluation of A at zero
     pub fn round_3(
         state: &mut State<E>,
         gamma: E::Fr,
@@ -261,21 +844,24 @@ impl<E: PairingEngine, FS: FiatShamirRng> Prover<E, FS> {
 
         // step 2: compute openings of b0 and f
         let b0_at_gamma = b0.evaluate(&gamma);
-        let f_at_gamma = state.witness.f.evaluate(&gamma);
+        let f = state.witness.committed_f();
+        let f_at_gamma = f.evaluate(&gamma);
 
         // step 3: compute [A0(X)]_1
-        let mut a0_cm = E::G1Affine::zero();
-        for (&index, &a_i) in a_sparse.iter() {
-            a0_cm = state.index.ls_at_0[index].mul(a_i).add_mixed(&a0_cm).into();
+        let mut a0_cm = sparse_msm(a_sparse, |i| state.index.l_at_0(i))?;
+        state.metrics.record_msm(a_sparse.len());
+
+        // zk: A0(X) + a·X^(N-1) + t·zV(X)
+        if let Some(blinders) = &state.blinders {
+            let x_pow_n_minus_1 = state.pk.srs_g1[state.table.size - 1];
+            a0_cm += x_pow_n_minus_1.mul(blinders.a) + state.zv_1().0.mul(blinders.t);
         }
+        let a0_cm = a0_cm.into_affine();
 
         // step 6: compute openings proof
-        let pi_gamma: E::G1Affine = Kzg::<E>::batch_open_g1(
-            &state.pk.srs_g1,
-            &[b0.clone(), state.witness.f.clone(), qb.clone()],
-            gamma,
-            eta,
-        );
+        let q = Kzg::<E>::batch_open_quotient(&[b0, f, qb], gamma, eta);
+        let pi_gamma = state.commit(&q, 0)?;
+        state.metrics.record_msm(q.coeffs.len());
 
         Ok(ProverThirdMessage {
             b0_at_gamma,
@@ -290,7 +876,7 @@ impl<E: PairingEngine, FS: FiatShamirRng> Prover<E, FS> {
         let m_sparse = state.m_sparse.as_ref().expect("m missing from the state");
         let a_sparse = state.a_sparse.as_ref().expect("a missing from the state");
 
-        let table_domain = GeneralEvaluationDomain::<E::Fr>::new(state.table.size).unwrap();
+        let table_domain = state.table_data.domain;
         let zv: DensePolynomial<_> = table_domain.vanishing_polynomial().into();
 
         let roots: Vec<_> = table_domain.elements().collect();
@@ -307,7 +893,7 @@ impl<E: PairingEngine, FS: FiatShamirRng> Prover<E, FS> {
         }
 
         let mut table_poly =
-            DensePolynomial::from_coefficients_slice(&table_domain.ifft(&state.table.values));
+            DensePolynomial::from_coefficients_vec(table_domain.ifft(&state.table.values));
 
         table_poly[0] += beta;
         let mut num = &a_poly * &table_poly;
@@ -318,6 +904,19 @@ impl<E: PairingEngine, FS: FiatShamirRng> Prover<E, FS> {
     }
 }
 
+/// `sum_i v_i·base(i)` over the entries `(i, v_i)` of a sparse vector, with a single MSM
+fn sparse_msm<E: PairingEngine>(
+    sparse: &BTreeMap<usize, E::Fr>,
+    base: impl Fn(usize) -> Result<E::G1Affine, Error>,
+) -> Result<E::G1Projective, Error> {
+    let bases = sparse
+        .keys()
+        .map(|&i| base(i))
+        .collect::<Result<Vec<_>, _>>()?;
+    let scalars: Vec<E::Fr> = sparse.values().copied().collect();
+    Ok(Kzg::<E>::msm_g1(&bases, &scalars))
+}
+
 #[cfg(test)]
 mod prover_rounds_tests {
     use std::ops::Neg;
@@ -325,21 +924,26 @@ mod prover_rounds_tests {
     use ark_bn254::{Bn254, Fq12, Fr, G1Affine, G2Affine};
     use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
     use ark_ff::{Field, One, UniformRand};
-    use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
+    use ark_poly::{
+        univariate::DensePolynomial, EvaluationDomain, GeneralEvaluationDomain, UVPolynomial,
+    };
+    use ark_serialize::CanonicalSerialize;
     use ark_std::{rand::rngs::StdRng, test_rng};
     use rand_chacha::ChaChaRng;
     use sha3::Keccak256;
 
     use crate::{
         data_structures::{ProvingKey, Statement, Witness},
+        error::Error,
         indexer::Index,
         kzg::Kzg,
         rng::SimpleHashFiatShamirRng,
         table::Table,
         utils::{to_field, unsafe_setup_from_rng},
+        verifier::{Verifier, VerifierKey},
     };
 
-    use super::{Prover, ProverSecondMessage, ProverThirdMessage, State};
+    use super::{Prover, ProverContext, ProverSecondMessage, ProverThirdMessage, State};
 
     type FS = SimpleHashFiatShamirRng<Keccak256, ChaChaRng>;
 
@@ -356,7 +960,7 @@ mod prover_rounds_tests {
         let table_values = vec![1, 5, 10, 15, 20, 25, 30, 35];
         let table = Table::new(&to_field(&table_values)).unwrap();
 
-        let index = Index::<Bn254>::gen(&pk.srs_g1, &srs_g2, &table);
+        let index = Index::<Bn254>::gen(&pk.srs_g1, &srs_g2, &table).unwrap();
 
         let witness_values = vec![5, 15, 20, 35];
         let witness = Witness::<Fr>::new(&to_field(&witness_values)).unwrap();
@@ -365,7 +969,145 @@ mod prover_rounds_tests {
             f: Kzg::<Bn254>::commit_g1(&pk.srs_g1, &witness.f).into(),
         };
 
-        let _ = Prover::<Bn254, FS>::prove(&pk, &index, &table, &witness, &statement).unwrap();
+        let vk = VerifierKey::<Bn254>::for_table(&srs_g2, table.size, witness.size, false).unwrap();
+        let _ = Prover::<Bn254, FS>::prove(&pk, &vk, &index, &table, &witness, &statement).unwrap();
+    }
+
+    #[test]
+    fn test_prove_with_msm_chunk() {
+        let n = 8;
+        let mut rng = test_rng();
+
+        let (srs_g1, srs_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(n - 1, n, &mut rng);
+        let pk = ProvingKey { srs_g1 };
+
+        let table_values = vec![1, 5, 10, 15, 20, 25, 30, 35];
+        let table = Table::new(&to_field(&table_values)).unwrap();
+
+        let index = Index::<Bn254>::gen(&pk.srs_g1, &srs_g2, &table).unwrap();
+
+        let witness_values = vec![5, 15, 20, 35];
+        let witness = Witness::<Fr>::new(&to_field(&witness_values)).unwrap();
+
+        let statement = Statement::<Bn254> {
+            f: Kzg::<Bn254>::commit_g1(&pk.srs_g1, &witness.f).into(),
+        };
+
+        let vk = VerifierKey::<Bn254>::for_table(&srs_g2, table.size, witness.size, false).unwrap();
+        let proof =
+            Prover::<Bn254, FS>::prove(&pk, &vk, &index, &table, &witness, &statement).unwrap();
+        // two scalars per msm
+        let chunked = Prover::<Bn254, FS>::prove_with_msm_chunk(
+            &pk, &vk, &index, &table, &witness, &statement, 2,
+        )
+        .unwrap();
+        assert!(Verifier::<Bn254, FS>::verify(&vk, index.common(), &statement, &chunked).is_ok());
+
+        let (mut expected, mut actual) = (Vec::new(), Vec::new());
+        proof.serialize(&mut expected).unwrap();
+        chunked.serialize(&mut actual).unwrap();
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn test_prove_checked() {
+        let mut rng = test_rng();
+        let (srs_g1, srs_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(7, 8, &mut rng);
+        let pk = ProvingKey { srs_g1 };
+        let table = Table::new(&to_field(&[1, 5, 10, 15, 20, 25, 30, 35])).unwrap();
+        let index = Index::<Bn254>::gen(&pk.srs_g1, &srs_g2, &table).unwrap();
+        let vk = VerifierKey::<Bn254>::for_table(&srs_g2, table.size, 4, false).unwrap();
+
+        let witness = Witness::<Fr>::new(&to_field(&[5, 15, 21, 35])).unwrap();
+        let statement = Statement::from_witness(&pk, &witness);
+        let res =
+            Prover::<Bn254, FS>::prove_checked(&pk, &vk, &index, &table, &witness, &statement);
+        let expected = Error::WitnessValueNotInTable(2, format!("{}", Fr::from(21u64)));
+        assert_eq!(res.err(), Some(expected));
+
+        // positions into another table give values the prover would otherwise never look up
+        let other = Table::new(&to_field(&[2, 5, 10, 15, 20, 25, 30, 35])).unwrap();
+        let witness = Witness::from_indices(&other, &[1, 0, 2, 3]).unwrap();
+        let statement = Statement::from_witness(&pk, &witness);
+        let res =
+            Prover::<Bn254, FS>::prove_checked(&pk, &vk, &index, &table, &witness, &statement);
+        let expected = Error::WitnessValueNotInTable(1, format!("{}", Fr::from(2u64)));
+        assert_eq!(res.err(), Some(expected));
+
+        let witness = Witness::<Fr>::new(&to_field(&[5, 15, 20, 35])).unwrap();
+        let wrong =
+            Statement::from_witness(&pk, &Witness::new(&to_field(&[5, 15, 20, 30])).unwrap());
+        let res = Prover::<Bn254, FS>::prove_checked(&pk, &vk, &index, &table, &witness, &wrong);
+        assert_eq!(res.err(), Some(Error::StatementOpeningMismatch));
+        let statement = Statement::from_witness(&pk, &witness);
+        let proof =
+            Prover::<Bn254, FS>::prove_checked(&pk, &vk, &index, &table, &witness, &statement)
+                .unwrap();
+        assert!(Verifier::<Bn254, FS>::verify(&vk, index.common(), &statement, &proof).is_ok());
+    }
+
+    #[test]
+    fn test_prover_context() {
+        let n = 8;
+        let mut rng = test_rng();
+
+        let (srs_g1, srs_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(n - 1, n, &mut rng);
+        let pk = ProvingKey { srs_g1 };
+
+        let table_values = vec![1, 5, 10, 15, 20, 25, 30, 35];
+        let table = Table::new(&to_field(&table_values)).unwrap();
+
+        let index = Index::<Bn254>::gen(&pk.srs_g1, &srs_g2, &table).unwrap();
+        let vk = VerifierKey::<Bn254>::for_table(&srs_g2, table.size, 4, false).unwrap();
+        let ctx = ProverContext::<Bn254, FS>::new(&pk, &vk, &index, &table).unwrap();
+
+        for witness_values in [vec![5, 15, 20, 35], vec![1, 1, 30, 10]] {
+            let witness = Witness::<Fr>::new(&to_field(&witness_values)).unwrap();
+            let statement = Statement::<Bn254> {
+                f: Kzg::<Bn254>::commit_g1(&pk.srs_g1, &witness.f).into(),
+            };
+
+            let proof = ctx.prove(&witness, &statement).unwrap();
+            let expected =
+                Prover::<Bn254, FS>::prove(&pk, &vk, &index, &table, &witness, &statement).unwrap();
+            let (mut actual_bytes, mut expected_bytes) = (Vec::new(), Vec::new());
+            proof.serialize(&mut actual_bytes).unwrap();
+            expected.serialize(&mut expected_bytes).unwrap();
+            assert_eq!(actual_bytes, expected_bytes);
+        }
+    }
+
+    #[test]
+    fn test_prove_with_metrics() {
+        let n = 8;
+        let mut rng = test_rng();
+
+        let (srs_g1, srs_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(n - 1, n, &mut rng);
+        let pk = ProvingKey { srs_g1 };
+
+        let table_values = vec![1, 5, 10, 15, 20, 25, 30, 35];
+        let table = Table::new(&to_field(&table_values)).unwrap();
+
+        let index = Index::<Bn254>::gen(&pk.srs_g1, &srs_g2, &table).unwrap();
+
+        let witness_values = vec![5, 15, 20, 35];
+        let witness = Witness::<Fr>::new(&to_field(&witness_values)).unwrap();
+
+        let statement = Statement::<Bn254> {
+            f: Kzg::<Bn254>::commit_g1(&pk.srs_g1, &witness.f).into(),
+        };
+
+        let vk = VerifierKey::<Bn254>::for_table(&srs_g2, table.size, witness.size, false).unwrap();
+        let (_, metrics) =
+            Prover::<Bn254, FS>::prove_with_metrics(&pk, &vk, &index, &table, &witness, &statement)
+                .unwrap();
+
+        // m, a, qa, b0, qb, p, a0 and the batched opening
+        assert_eq!(metrics.msm_count, 8);
+        // ifft of B and the coset transforms for QB
+        assert_eq!(metrics.fft_sizes, vec![4; 4]);
+        assert!(metrics.total >= metrics.round_1 + metrics.round_2 + metrics.round_3);
+        assert_eq!(metrics.peak_allocation, None);
     }
 
     #[test]
@@ -379,12 +1121,12 @@ mod prover_rounds_tests {
         let table_values = vec![1, 5, 10, 15, 20, 25, 30, 35];
         let table = Table::new(&to_field(&table_values)).unwrap();
 
-        let index = Index::<Bn254>::gen(&pk.srs_g1, &srs_g2, &table);
+        let index = Index::<Bn254>::gen(&pk.srs_g1, &srs_g2, &table).unwrap();
 
         let witness_values = vec![5, 15, 20, 35];
         let witness = Witness::<Fr>::new(&to_field(&witness_values)).unwrap();
 
-        let mut state = State::new(&pk, &index, &table, &witness);
+        let mut state = State::new(&pk, &index, &table, &witness).unwrap();
 
         let res = Prover::<Bn254, FS>::round_1(&mut state);
         assert!(res.is_ok());
@@ -396,6 +1138,14 @@ mod prover_rounds_tests {
         let multiplicities = vec![Fr::one(), Fr::one(), Fr::one(), Fr::one()];
         let m_values: Vec<Fr> = state.m_sparse.as_ref().unwrap().values().copied().collect();
         assert_eq!(multiplicities, m_values);
+
+        // the same witness by its table positions skips the lookups
+        let sparse =
+            Witness::<Fr>::from_multiplicities(&table, &[(1, 1), (3, 1), (4, 1), (7, 1)]).unwrap();
+        let mut sparse_state = State::new(&pk, &index, &table, &sparse).unwrap();
+        let first_msg = Prover::<Bn254, FS>::round_1(&mut sparse_state).unwrap();
+        assert_eq!(first_msg.m_cm, res.unwrap().m_cm);
+        assert_eq!(sparse_state.m_sparse, state.m_sparse);
     }
 
     #[test]
@@ -409,12 +1159,12 @@ mod prover_rounds_tests {
         let table_values = vec![1, 5, 10, 15, 20, 25, 30, 35];
         let table = Table::new(&to_field(&table_values)).unwrap();
 
-        let index = Index::<Bn254>::gen(&pk.srs_g1, &srs_g2, &table);
+        let index = Index::<Bn254>::gen(&pk.srs_g1, &srs_g2, &table).unwrap();
 
         let witness_values = vec![5, 15, 20, 35];
         let witness = Witness::<Fr>::new(&to_field(&witness_values)).unwrap();
 
-        let mut state = State::new(&pk, &index, &table, &witness);
+        let mut state = State::new(&pk, &index, &table, &witness).unwrap();
 
         let m_cm = Prover::<Bn254, FS>::round_1(&mut state).unwrap().m_cm;
 
@@ -426,6 +1176,8 @@ mod prover_rounds_tests {
             b0_cm,
             qb_cm: _,
             p_cm,
+            g_cm: _,
+            a_shift_cm: _,
         } = second_msg;
 
         // check well formation of A
@@ -465,7 +1217,7 @@ mod prover_rounds_tests {
         let table_values = vec![1, 5, 10, 15, 20, 25, 30, 35];
         let table = Table::new(&to_field(&table_values)).unwrap();
 
-        let index = Index::<Bn254>::gen(&pk.srs_g1, &srs_g2, &table);
+        let index = Index::<Bn254>::gen(&pk.srs_g1, &srs_g2, &table).unwrap();
 
         let witness_values = vec![5, 15, 20, 35];
         let witness = Witness::<Fr>::new(&to_field(&witness_values)).unwrap();
@@ -474,7 +1226,7 @@ mod prover_rounds_tests {
             f: Kzg::<Bn254>::commit_g1(&pk.srs_g1, &witness.f).into(),
         };
 
-        let mut state = State::new(&pk, &index, &table, &witness);
+        let mut state = State::new(&pk, &index, &table, &witness).unwrap();
 
         let _ = Prover::<Bn254, FS>::round_1(&mut state).unwrap();
 
@@ -486,6 +1238,8 @@ mod prover_rounds_tests {
             b0_cm,
             qb_cm,
             p_cm: _,
+            g_cm: _,
+            a_shift_cm: _,
         } = second_msg;
 
         let gamma = Fr::rand(&mut rng);
@@ -547,4 +1301,23 @@ mod prover_rounds_tests {
             assert_eq!(p1, p2);
         }
     }
+
+    #[test]
+    fn test_commit_past_srs() {
+        let mut rng = test_rng();
+        let (srs_g1, srs_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(7, 8, &mut rng);
+        let pk = ProvingKey { srs_g1 };
+        let table = Table::new(&to_field(&[1, 5, 10, 15, 20, 25, 30, 35])).unwrap();
+        let index = Index::<Bn254>::gen(&pk.srs_g1, &srs_g2, &table).unwrap();
+        let witness = Witness::<Fr>::new(&to_field(&[5, 15, 20, 35])).unwrap();
+        let state = State::new(&pk, &index, &table, &witness).unwrap();
+
+        // 4 coefficients fit from x^4 on, not from x^5
+        let poly = DensePolynomial::from_coefficients_vec(to_field(&[1, 2, 3, 4]));
+        assert!(state.commit(&poly, 4).is_ok());
+        assert_eq!(
+            state.commit(&poly, 5).err(),
+            Some(Error::SrsG1TooSmall(9, 8))
+        );
+    }
 }
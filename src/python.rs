@@ -0,0 +1,166 @@
+//! Python module over the BN254 `Cq` facade, built with maturin (see `pyproject.toml`).
+//!
+//! ```python
+//! import cqext
+//! pk, vk = cqext.setup([1, 5, 10, 15, 20, 25, 30, 35], witness_size=4, seed=42)
+//! statement, proof = cqext.prove(pk, [5, 15, 20, 35])
+//! assert cqext.verify(vk, statement, cqext.Proof.from_bytes(proof.to_bytes()))
+//! ```
+
+use ark_bn254::{Bn254, Fr};
+use ark_ff::{BigInteger, PrimeField};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+#[cfg(any(test, feature = "test-utils"))]
+use ark_std::rand::{rngs::StdRng, SeedableRng};
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyBytes};
+use rand_chacha::ChaChaRng;
+use sha3::Keccak256;
+
+use crate::{
+    cq::{Cq, CqProverKey, CqVerifierKey},
+    data_structures::{Proof, Statement},
+    encoding::{Artifact, Compression},
+    error::Error,
+    rng::SimpleHashFiatShamirRng,
+};
+
+type FS = SimpleHashFiatShamirRng<Keccak256, ChaChaRng>;
+
+fn value_error(e: Error) -> PyErr {
+    PyValueError::new_err(format!("{:?}", e))
+}
+
+/// Converts python ints to field elements, rejecting negative values and values above the modulus
+fn to_field_elements(values: Vec<&PyAny>) -> PyResult<Vec<Fr>> {
+    values
+        .into_iter()
+        .map(|value| {
+            let bytes: Vec<u8> = value.call_method1("to_bytes", (32, "little"))?.extract()?;
+            let element = Fr::from_le_bytes_mod_order(&bytes);
+            if element.into_repr().to_bytes_le() != bytes {
+                return Err(PyValueError::new_err(
+                    "value is not smaller than the field modulus",
+                ));
+            }
+            Ok(element)
+        })
+        .collect()
+}
+
+fn to_bytes<T: CanonicalSerialize>(value: &T) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(value.serialized_size());
+    value
+        .serialize(&mut bytes)
+        .expect("serializing into a vec can't fail");
+    bytes
+}
+
+fn from_bytes<T: CanonicalDeserialize>(mut bytes: &[u8]) -> PyResult<T> {
+    let value =
+        T::deserialize(&mut bytes).map_err(|e| PyValueError::new_err(format!("{:?}", e)))?;
+    if !bytes.is_empty() {
+        return Err(PyValueError::new_err("trailing bytes"));
+    }
+    Ok(value)
+}
+
+#[pyclass(name = "ProverKey")]
+pub struct PyProverKey(CqProverKey<Bn254>);
+
+#[pyclass(name = "VerifierKey")]
+pub struct PyVerifierKey(CqVerifierKey<Bn254>);
+
+#[pyclass(name = "Statement")]
+pub struct PyStatement(Statement<Bn254>);
+
+#[pyclass(name = "Proof")]
+pub struct PyProof(Proof<Bn254>);
+
+#[pymethods]
+impl PyProverKey {
+    fn to_bytes<'py>(&self, py: Python<'py>) -> &'py PyBytes {
+        PyBytes::new(py, &to_bytes(&self.0))
+    }
+
+    #[staticmethod]
+    fn from_bytes(bytes: &[u8]) -> PyResult<Self> {
+        Ok(Self(from_bytes(bytes)?))
+    }
+}
+
+#[pymethods]
+impl PyVerifierKey {
+    fn to_bytes<'py>(&self, py: Python<'py>) -> &'py PyBytes {
+        PyBytes::new(py, &to_bytes(&self.0))
+    }
+
+    #[staticmethod]
+    fn from_bytes(bytes: &[u8]) -> PyResult<Self> {
+        Ok(Self(from_bytes(bytes)?))
+    }
+}
+
+#[pymethods]
+impl PyStatement {
+    fn to_bytes<'py>(&self, py: Python<'py>) -> &'py PyBytes {
+        PyBytes::new(py, &self.0.encode(Compression::Compressed))
+    }
+
+    #[staticmethod]
+    fn from_bytes(bytes: &[u8]) -> PyResult<Self> {
+        Ok(Self(Statement::decode(bytes).map_err(value_error)?))
+    }
+}
+
+#[pymethods]
+impl PyProof {
+    fn to_bytes<'py>(&self, py: Python<'py>) -> &'py PyBytes {
+        PyBytes::new(py, &self.0.encode(Compression::Compressed))
+    }
+
+    #[staticmethod]
+    fn from_bytes(bytes: &[u8]) -> PyResult<Self> {
+        Ok(Self(Proof::decode(bytes).map_err(value_error)?))
+    }
+}
+
+/// Indexes a table for witnesses of `witness_size`, the srs is sampled from `seed` and is not secure, only built with the
+/// `test-utils` feature
+#[cfg(any(test, feature = "test-utils"))]
+#[pyfunction]
+fn setup(
+    table: Vec<&PyAny>,
+    witness_size: usize,
+    seed: u64,
+) -> PyResult<(PyProverKey, PyVerifierKey)> {
+    let table_values = to_field_elements(table)?;
+    let mut rng = StdRng::seed_from_u64(seed);
+    let (pk, vk) =
+        Cq::<Bn254, FS>::setup(&table_values, witness_size, &mut rng).map_err(value_error)?;
+    Ok((PyProverKey(pk), PyVerifierKey(vk)))
+}
+
+#[pyfunction]
+fn prove(pk: &PyProverKey, witness: Vec<&PyAny>) -> PyResult<(PyStatement, PyProof)> {
+    let witness_values = to_field_elements(witness)?;
+    let (statement, proof) = Cq::<Bn254, FS>::prove(&pk.0, &witness_values).map_err(value_error)?;
+    Ok((PyStatement(statement), PyProof(proof)))
+}
+
+#[pyfunction]
+fn verify(vk: &PyVerifierKey, statement: &PyStatement, proof: &PyProof) -> bool {
+    Cq::<Bn254, FS>::verify(&vk.0, &statement.0, &proof.0).is_ok()
+}
+
+#[pymodule]
+fn cqext(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_class::<PyProverKey>()?;
+    m.add_class::<PyVerifierKey>()?;
+    m.add_class::<PyStatement>()?;
+    m.add_class::<PyProof>()?;
+    #[cfg(any(test, feature = "test-utils"))]
+    m.add_function(wrap_pyfunction!(setup, m)?)?;
+    m.add_function(wrap_pyfunction!(prove, m)?)?;
+    m.add_function(wrap_pyfunction!(verify, m)?)?;
+    Ok(())
+}
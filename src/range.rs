@@ -0,0 +1,660 @@
+//! Range proofs over integers: cq lookups into the table `[0, 2^bits)`, where every value is its own index.
+
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{FftField, FftParameters};
+use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+#[cfg(any(test, feature = "test-utils"))]
+use ark_std::rand::RngCore;
+use ark_std::{format, marker::PhantomData, vec, vec::Vec};
+
+use crate::{
+    config::CqConfig,
+    cq::{Cq, CqProverKey, CqVerifierKey},
+    data_structures::{Proof, ProvingKey, Statement, Witness},
+    decomposable::{
+        DecomposableCq, DecomposableProverKey, DecomposableTable, DecomposableVerifierKey,
+        DecomposedProof,
+    },
+    error::{Error, VerificationError},
+    indexer::{Index, IndexView},
+    kzg::Kzg,
+    prover::Prover,
+    rng::FiatShamirRng,
+    table::Table,
+    transcript::{Transcript, TranscriptOracle},
+    verifier::{verify_folded, PairingEquation, VerifierKey},
+    PROTOCOL_NAME, PROTOCOL_VERSION,
+};
+
+/// srs and verifier key of a range table, its index is kept apart so it can also be memory mapped
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct RangeProverKey<E: PairingEngine> {
+    pub pk: ProvingKey<E>,
+    pub vk: VerifierKey<E>,
+}
+
+/// Aggregated range proof for values committed one by one
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct BatchRangeProof<E: PairingEngine> {
+    /// commitment to the column of all values
+    pub g_cm: E::G1Affine,
+    pub proof: Proof<E>,
+    /// opening of `g` at `z`
+    pub pi_z: E::G1Affine,
+}
+
+/// `a <= b` as range proofs of `a`, `b` and `b - a`
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct LeqProof<E: PairingEngine> {
+    pub a_proof: Proof<E>,
+    pub b_proof: Proof<E>,
+    pub diff_proof: Proof<E>,
+}
+
+/// Claim that all `size` evaluations on the witness domain of the polynomial committed in `commitment` are in
+/// `[0, bound)`
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct VectorRangeStatement<E: PairingEngine> {
+    pub commitment: E::G1Affine,
+    pub size: usize,
+    pub bound: u64,
+}
+
+/// Lookups of a `VectorRangeStatement` into `[0, 2^k)` with `2^k = bound.next_power_of_two()`, a bound that is not
+/// a power of two additionally looks up the column shifted by `2^k - bound`
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct VectorRangeProof<E: PairingEngine> {
+    pub proof: Proof<E>,
+    pub shifted_proof: Option<Proof<E>>,
+}
+
+/// Limb layout of `[0, 2^range_bits)` as `limbs` lookups into `[0, 2^limb_bits)`, see `RangePlan::new`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RangePlan {
+    pub limb_bits: u32,
+    pub limbs: usize,
+    /// estimated group operations of indexing the subtable, about `N·log N`
+    pub setup_cost: u64,
+    /// estimated group operations of one proof, about `n·log n` per limb
+    pub prove_cost: u64,
+}
+
+impl RangePlan {
+    /// Cheapest layout for `proofs` proofs of `lookups` values each, among limb sizes that divide `range_bits`,
+    /// cover the witness and fit the domains of `F`. Many proofs favour large limbs, few proofs small tables.
+    pub fn new<F: FftField>(range_bits: u32, lookups: usize, proofs: usize) -> Result<Self, Error> {
+        if !lookups.is_power_of_two() {
+            return Err(Error::WitnessSizeNotPow2(lookups));
+        }
+        let max_limb_bits = (F::FftParams::TWO_ADICITY - 1).min(usize::BITS - 1);
+        let log_n = (lookups.trailing_zeros() as u64).max(1);
+
+        (1..=range_bits.min(max_limb_bits))
+            .filter(|limb_bits| range_bits % limb_bits == 0 && 1usize << limb_bits >= lookups)
+            .map(|limb_bits| {
+                let limbs = (range_bits / limb_bits) as usize;
+                Self {
+                    limb_bits,
+                    limbs,
+                    setup_cost: (1u64 << limb_bits) * limb_bits as u64,
+                    prove_cost: limbs as u64 * lookups as u64 * log_n,
+                }
+            })
+            .min_by_key(|plan| plan.total_cost(proofs))
+            .ok_or(Error::WitnessLargerThanTable(
+                lookups,
+                1usize << range_bits.min(max_limb_bits),
+            ))
+    }
+
+    pub fn total_cost(&self, proofs: usize) -> u64 {
+        self.setup_cost
+            .saturating_add(self.prove_cost.saturating_mul(proofs as u64))
+    }
+
+    /// Indexes the subtable of the plan, see `RangeProver::setup_wide`
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn setup<E: PairingEngine, FS: FiatShamirRng, R: RngCore>(
+        &self,
+        witness_size: usize,
+        rng: &mut R,
+    ) -> Result<(DecomposableProverKey<E>, DecomposableVerifierKey<E>), Error> {
+        RangeProver::<E, FS>::setup_wide(self.limb_bits, self.limbs, witness_size, rng)
+    }
+}
+
+pub struct RangeProver<E: PairingEngine, FS: FiatShamirRng> {
+    _e: PhantomData<E>,
+    _fs: PhantomData<FS>,
+}
+
+impl<E: PairingEngine, FS: FiatShamirRng> RangeProver<E, FS> {
+    /// Indexes `[0, 2^bits)` with an srs sampled from `rng`, the caller must not be trusted by the verifier
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn setup<R: RngCore>(
+        bits: u32,
+        witness_size: usize,
+        rng: &mut R,
+    ) -> Result<(RangeProverKey<E>, Index<E>, CqVerifierKey<E>), Error> {
+        let table = range_table(bits)?;
+        let config = CqConfig::<E, FS>::builder()
+            .table_size(table.size)
+            .witness_size(witness_size)
+            .build()?;
+        let (CqProverKey { pk, vk, index, .. }, verifier_key) = config.unsafe_setup(table, rng)?;
+
+        Ok((RangeProverKey { pk, vk }, index, verifier_key))
+    }
+
+    /// Indexes `[0, 2^bits)` with an existing srs
+    pub fn keygen(
+        bits: u32,
+        witness_size: usize,
+        srs_g1: Vec<E::G1Affine>,
+        srs_g2: &[E::G2Affine],
+    ) -> Result<(RangeProverKey<E>, Index<E>, CqVerifierKey<E>), Error> {
+        let table = range_table(bits)?;
+        let config = CqConfig::<E, FS>::builder()
+            .table_size(table.size)
+            .witness_size(witness_size)
+            .build()?;
+        let (CqProverKey { pk, vk, index, .. }, verifier_key) =
+            config.keygen(srs_g1, srs_g2, table)?;
+
+        Ok((RangeProverKey { pk, vk }, index, verifier_key))
+    }
+
+    /// Commits to `values` and proves that they are in `[0, 2^bits)`
+    pub fn prove(
+        pk: &RangeProverKey<E>,
+        index: &dyn IndexView<E>,
+        values: &[u64],
+        bits: u32,
+    ) -> Result<(E::G1Affine, Proof<E>), Error> {
+        let (_, statement, proof) = Self::prove_column(pk, index, values, bits)?;
+        Ok((statement.f, proof))
+    }
+
+    fn prove_column(
+        pk: &RangeProverKey<E>,
+        index: &dyn IndexView<E>,
+        values: &[u64],
+        bits: u32,
+    ) -> Result<(Witness<E::Fr>, Statement<E>, Proof<E>), Error> {
+        let table = range_table(bits)?;
+        if table.size != pk.vk.table_size {
+            return Err(Error::TableSizeMismatch(pk.vk.table_size, table.size));
+        }
+        if let Some(value) = values.iter().find(|&&value| value >> bits != 0) {
+            return Err(Error::ValueOutOfRange(format!("{}", value)));
+        }
+
+        let indices: Vec<usize> = values.iter().map(|&value| value as usize).collect();
+        let witness = Witness::from_indices(&table, &indices)?;
+        let statement = Statement::from_witness(&pk.pk, &witness);
+        let proof = Prover::<E, FS>::prove(&pk.pk, &pk.vk, index, &table, &witness, &statement)?;
+
+        Ok((witness, statement, proof))
+    }
+
+    /// Proves `a_i <= b_i` for the columns `a` and `b`: both are proven in `[0, 2^bits)` and `[b]_1 - [a]_1` is
+    /// proven in `[0, 2^bits)` as well. The range proofs of `a` and `b` rule out wraparound, a difference in range
+    /// can then only come from an ordered pair since `2^bits` is far below the field size.
+    pub fn prove_leq(
+        pk: &RangeProverKey<E>,
+        index: &dyn IndexView<E>,
+        a: &[u64],
+        b: &[u64],
+        bits: u32,
+    ) -> Result<(E::G1Affine, E::G1Affine, LeqProof<E>), Error> {
+        if a.len() != b.len() {
+            return Err(Error::WitnessSizeMismatch(a.len(), b.len()));
+        }
+        if let Some(i) = a.iter().zip(b).position(|(a_i, b_i)| a_i > b_i) {
+            return Err(Error::NotLessOrEqual(i));
+        }
+        let diff: Vec<u64> = a.iter().zip(b).map(|(a_i, b_i)| b_i - a_i).collect();
+
+        let (a_cm, a_proof) = Self::prove(pk, index, a, bits)?;
+        let (b_cm, b_proof) = Self::prove(pk, index, b, bits)?;
+        let (_, diff_proof) = Self::prove(pk, index, &diff, bits)?;
+
+        Ok((
+            a_cm,
+            b_cm,
+            LeqProof {
+                a_proof,
+                b_proof,
+                diff_proof,
+            },
+        ))
+    }
+
+    pub fn verify_leq(
+        vk: &CqVerifierKey<E>,
+        a_commitment: &E::G1Affine,
+        b_commitment: &E::G1Affine,
+        proof: &LeqProof<E>,
+    ) -> Result<(), Error> {
+        let diff = (b_commitment.into_projective() - a_commitment.into_projective()).into_affine();
+        Self::verify(vk, a_commitment, &proof.a_proof)?;
+        Self::verify(vk, b_commitment, &proof.b_proof)?;
+        Self::verify(vk, &diff, &proof.diff_proof)
+    }
+
+    /// Proves `values` in `[-2^(bits-1), 2^(bits-1))`, negative values are committed as `p - |v|`. The table is the
+    /// unsigned `[0, 2^bits)` and the lookup is on `v + 2^(bits-1)`, whose commitment is `[f]_1 + 2^(bits-1)·[1]_1`
+    /// since the constant polynomial takes the shift everywhere on the witness domain.
+    pub fn prove_signed(
+        pk: &RangeProverKey<E>,
+        index: &dyn IndexView<E>,
+        values: &[i64],
+        bits: u32,
+    ) -> Result<(E::G1Affine, Proof<E>), Error> {
+        if bits == 0 || bits > 64 {
+            return Err(Error::RangeTooLarge(64, bits));
+        }
+        let half = 1i128 << (bits - 1);
+        let shifted = values
+            .iter()
+            .map(|&value| {
+                let shifted = value as i128 + half;
+                if !(0..2 * half).contains(&shifted) {
+                    return Err(Error::ValueOutOfRange(format!("{}", value)));
+                }
+                Ok(shifted as u64)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let (shifted_cm, proof) = Self::prove(pk, index, &shifted, bits)?;
+        let shift = pk.pk.srs_g1[0].mul(signed_shift::<E>(bits));
+        Ok(((shifted_cm.into_projective() - shift).into_affine(), proof))
+    }
+
+    pub fn verify_signed(
+        vk: &CqVerifierKey<E>,
+        commitment: &E::G1Affine,
+        proof: &Proof<E>,
+        bits: u32,
+    ) -> Result<(), Error> {
+        if bits == 0 || bits > 64 {
+            return Err(Error::RangeTooLarge(64, bits));
+        }
+        let shift = E::G1Affine::prime_subgroup_generator().mul(signed_shift::<E>(bits));
+        let shifted = commitment.into_projective() + shift;
+        Self::verify(vk, &shifted.into_affine(), proof)
+    }
+
+    /// Commitment `v·[1]_1` to a single value, as aggregated by `prove_batch`. It is binding but not hiding, and
+    /// small values can be found by brute force.
+    pub fn commit_value(value: u64) -> E::G1Affine {
+        E::G1Affine::prime_subgroup_generator()
+            .mul(E::Fr::from(value))
+            .into_affine()
+    }
+
+    /// Commits to the column `values` and proves all of it in `[0, bound)`, with a single cq proof if `bound` is
+    /// the table size and two otherwise. The key must index `[0, bound.next_power_of_two())`.
+    pub fn prove_vector(
+        pk: &RangeProverKey<E>,
+        index: &dyn IndexView<E>,
+        values: &[u64],
+        bound: u64,
+    ) -> Result<(VectorRangeStatement<E>, VectorRangeProof<E>), Error> {
+        let bits = vector_range_bits(bound)?;
+        if let Some(value) = values.iter().find(|&&value| value >= bound) {
+            return Err(Error::ValueOutOfRange(format!("{}", value)));
+        }
+
+        let (commitment, proof) = Self::prove(pk, index, values, bits)?;
+        let shift = (1u64 << bits) - bound;
+        let shifted_proof = if shift == 0 {
+            None
+        } else {
+            let shifted: Vec<u64> = values.iter().map(|&value| value + shift).collect();
+            Some(Self::prove(pk, index, &shifted, bits)?.1)
+        };
+
+        Ok((
+            VectorRangeStatement {
+                commitment,
+                size: values.len(),
+                bound,
+            },
+            VectorRangeProof {
+                proof,
+                shifted_proof,
+            },
+        ))
+    }
+
+    pub fn verify_vector(
+        vk: &CqVerifierKey<E>,
+        statement: &VectorRangeStatement<E>,
+        proof: &VectorRangeProof<E>,
+    ) -> Result<(), Error> {
+        let bits = vector_range_bits(statement.bound)?;
+        if statement.size != vk.vk.witness_size {
+            return Err(Error::WitnessSizeMismatch(
+                vk.vk.witness_size,
+                statement.size,
+            ));
+        }
+        if 1usize << bits != vk.vk.table_size {
+            return Err(Error::TableSizeMismatch(vk.vk.table_size, 1usize << bits));
+        }
+
+        Self::verify(vk, &statement.commitment, &proof.proof)?;
+        let shift = (1u64 << bits) - statement.bound;
+        match (shift, &proof.shifted_proof) {
+            (0, None) => Ok(()),
+            (shift, Some(shifted_proof)) if shift != 0 => {
+                let shift = E::G1Affine::prime_subgroup_generator().mul(E::Fr::from(shift));
+                let shifted = statement.commitment.into_projective() + shift;
+                Self::verify(vk, &shifted.into_affine(), shifted_proof)
+            }
+            _ => Err(Error::Verification(VerificationError::ProofShapeMismatch)),
+        }
+    }
+
+    /// One proof that each of up to `witness_size` values is in `[0, 2^bits)`, returned with their `commit_value`
+    /// commitments. The values are laid out as one witness column `g` with a single cq proof, linked to the
+    /// commitments by opening `g` at a challenge `z`: `g(z) = sum_j L_j(z)·v_j` is checked in the exponent.
+    pub fn prove_batch(
+        pk: &RangeProverKey<E>,
+        index: &dyn IndexView<E>,
+        values: &[u64],
+        bits: u32,
+    ) -> Result<(Vec<E::G1Affine>, BatchRangeProof<E>), Error> {
+        let witness_size = pk.vk.witness_size;
+        if values.len() > witness_size {
+            return Err(Error::WitnessSizeMismatch(witness_size, values.len()));
+        }
+        let commitments: Vec<E::G1Affine> = values.iter().map(|&v| Self::commit_value(v)).collect();
+
+        let mut column = values.to_vec();
+        column.resize(witness_size, 0);
+        let (witness, statement, proof) = Self::prove_column(pk, index, &column, bits)?;
+        let g_cm = statement.f;
+
+        let z = batch_challenge::<E, FS>(&commitments, &g_cm);
+        let (_, pi_z) = Kzg::<E>::open_g1(&pk.pk.srs_g1, &witness.f, z);
+
+        Ok((commitments, BatchRangeProof { g_cm, proof, pi_z }))
+    }
+
+    pub fn verify_batch(
+        vk: &CqVerifierKey<E>,
+        commitments: &[E::G1Affine],
+        proof: &BatchRangeProof<E>,
+    ) -> Result<(), Error> {
+        let witness_size = vk.vk.witness_size;
+        if commitments.len() > witness_size {
+            return Err(Error::WitnessSizeMismatch(witness_size, commitments.len()));
+        }
+        let range_check =
+            Cq::<E, FS>::pairing_equation(vk, &Statement::new(proof.g_cm), &proof.proof)?;
+
+        // e([g]_1 - sum_j L_j(z)·C_j + z·pi, [1]_2) = e(pi, [x]_2)
+        let z = batch_challenge::<E, FS>(commitments, &proof.g_cm);
+        let domain = GeneralEvaluationDomain::<E::Fr>::new(witness_size).unwrap();
+        let lagrange_at_z = domain.evaluate_all_lagrange_coefficients(z);
+        let g_at_z = Kzg::<E>::msm_g1(commitments, &lagrange_at_z);
+        let lhs = proof.g_cm.into_projective() - g_at_z + proof.pi_z.mul(z);
+
+        let link = PairingEquation {
+            pairs: vec![
+                (lhs.into_affine(), vk.vk.g.clone()),
+                (-proof.pi_z, vk.vk.x.clone()),
+            ],
+            challenge: z,
+        };
+
+        verify_folded::<E, FS>(vec![
+            (
+                range_check,
+                Error::Verification(VerificationError::BatchedPairingFailed),
+            ),
+            (link, Error::BatchLinkFailed),
+        ])
+    }
+
+    /// Key for `[0, 2^(limb_bits·limbs))` as limbs in `[0, 2^limb_bits)`, see `DecomposableCq`
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn setup_wide<R: RngCore>(
+        limb_bits: u32,
+        limbs: usize,
+        witness_size: usize,
+        rng: &mut R,
+    ) -> Result<(DecomposableProverKey<E>, DecomposableVerifierKey<E>), Error> {
+        DecomposableCq::<E, FS>::setup(
+            DecomposableTable::range(limb_bits, limbs)?,
+            witness_size,
+            rng,
+        )
+    }
+
+    /// Commits to `values` and proves that they are in `[0, 2^(limb_bits·limbs))` with one lookup per limb and
+    /// the recomposition `v = sum_i limb_i·2^(limb_bits·i)` on the commitments
+    pub fn prove_wide(
+        pk: &DecomposableProverKey<E>,
+        values: &[u128],
+        limb_bits: u32,
+    ) -> Result<(E::G1Affine, DecomposedProof<E>), Error> {
+        let table = DecomposableTable::range(limb_bits, pk.weights.len())?;
+        let values: Vec<E::Fr> = values.iter().map(|&value| E::Fr::from(value)).collect();
+        let (statement, proof) = DecomposableCq::<E, FS>::prove(pk, &table, &values)?;
+
+        Ok((statement.f, proof))
+    }
+
+    pub fn verify_wide(
+        vk: &DecomposableVerifierKey<E>,
+        commitment: &E::G1Affine,
+        proof: &DecomposedProof<E>,
+    ) -> Result<(), Error> {
+        DecomposableCq::<E, FS>::verify(vk, &Statement::new(*commitment), proof)
+    }
+
+    pub fn verify(
+        vk: &CqVerifierKey<E>,
+        commitment: &E::G1Affine,
+        proof: &Proof<E>,
+    ) -> Result<(), Error> {
+        Cq::<E, FS>::verify(vk, &Statement::new(*commitment), proof)
+    }
+}
+
+/// `z` binds the opening of `g` to the commitments of the batch
+fn batch_challenge<E: PairingEngine, FS: FiatShamirRng>(
+    commitments: &[E::G1Affine],
+    g_cm: &E::G1Affine,
+) -> E::Fr {
+    let mut transcript = TranscriptOracle::<FS>::initialize(&PROTOCOL_NAME);
+    Transcript::<E>::begin_round(&mut transcript, PROTOCOL_VERSION, b"batch-range");
+    for commitment in commitments {
+        Transcript::<E>::absorb_g1(&mut transcript, b"c", commitment);
+    }
+    Transcript::<E>::absorb_g1(&mut transcript, b"g", g_cm);
+    Transcript::<E>::squeeze_challenge(&mut transcript, b"z")
+}
+
+/// `k` with `2^k = bound.next_power_of_two()`
+fn vector_range_bits(bound: u64) -> Result<u32, Error> {
+    if bound == 0 || bound > 1 << (usize::BITS - 2) {
+        return Err(Error::ValueOutOfRange(format!("{}", bound)));
+    }
+    Ok(bound.next_power_of_two().trailing_zeros())
+}
+
+/// `2^(bits-1)`, the offset of the signed range
+fn signed_shift<E: PairingEngine>(bits: u32) -> E::Fr {
+    E::Fr::from(1u64 << (bits - 1))
+}
+
+/// Field encoding of a signed value, negative values map to `p - |v|`
+pub fn signed_to_field<E: PairingEngine>(value: i64) -> E::Fr {
+    let abs = E::Fr::from(value.unsigned_abs());
+    if value < 0 {
+        -abs
+    } else {
+        abs
+    }
+}
+
+/// `[0, 2^bits)`, `bits` must leave room for the table in a `usize`
+fn range_table<E: PairingEngine>(bits: u32) -> Result<Table<E::Fr>, Error> {
+    if bits >= usize::BITS {
+        return Err(Error::RangeTooLarge(usize::BITS - 1, bits));
+    }
+    Table::range(0..1u64 << bits)
+}
+
+#[cfg(test)]
+mod range_tests {
+    use ark_bn254::{Bn254, Fr};
+    use ark_std::{test_rng, vec::Vec};
+    use rand_chacha::ChaChaRng;
+    use sha3::Keccak256;
+
+    use crate::{
+        data_structures::{Statement, Witness},
+        error::{Error, VerificationError},
+        rng::SimpleHashFiatShamirRng,
+    };
+
+    use super::{signed_to_field, RangePlan, RangeProver, VectorRangeStatement};
+
+    type FS = SimpleHashFiatShamirRng<Keccak256, ChaChaRng>;
+
+    #[test]
+    fn test_range_prover() {
+        let mut rng = test_rng();
+        let (pk, index, vk) = RangeProver::<Bn254, FS>::setup(4, 4, &mut rng).unwrap();
+
+        let (commitment, proof) =
+            RangeProver::<Bn254, FS>::prove(&pk, &index, &[0, 3, 9, 15], 4).unwrap();
+        assert!(RangeProver::<Bn254, FS>::verify(&vk, &commitment, &proof).is_ok());
+
+        let res = RangeProver::<Bn254, FS>::prove(&pk, &index, &[0, 3, 16, 15], 4);
+        assert_eq!(res.err(), Some(Error::ValueOutOfRange("16".into())));
+        let res = RangeProver::<Bn254, FS>::prove(&pk, &index, &[0, 3, 9, 15], 5);
+        assert_eq!(res.err(), Some(Error::TableSizeMismatch(16, 32)));
+    }
+    #[test]
+    fn test_wide_range() {
+        let mut rng = test_rng();
+        // [0, 2^64) with a table of 2^4 entries
+        let (pk, vk) = RangeProver::<Bn254, FS>::setup_wide(4, 16, 4, &mut rng).unwrap();
+
+        let values = [0, 1 << 32, u64::MAX as u128, 12345];
+        let (commitment, proof) = RangeProver::<Bn254, FS>::prove_wide(&pk, &values, 4).unwrap();
+        assert!(RangeProver::<Bn254, FS>::verify_wide(&vk, &commitment, &proof).is_ok());
+
+        let res = RangeProver::<Bn254, FS>::prove_wide(&pk, &[0, 1 << 64, 2, 3], 4);
+        assert!(matches!(res.err(), Some(Error::ValueNotInTable(_))));
+    }
+    #[test]
+    fn test_batch_range() {
+        let mut rng = test_rng();
+        let (pk, index, vk) = RangeProver::<Bn254, FS>::setup(4, 8, &mut rng).unwrap();
+
+        let (commitments, proof) =
+            RangeProver::<Bn254, FS>::prove_batch(&pk, &index, &[3, 0, 15, 7, 9], 4).unwrap();
+        assert_eq!(commitments[2], RangeProver::<Bn254, FS>::commit_value(15));
+        assert!(RangeProver::<Bn254, FS>::verify_batch(&vk, &commitments, &proof).is_ok());
+
+        let mut swapped = commitments.clone();
+        swapped.swap(0, 1);
+        let res = RangeProver::<Bn254, FS>::verify_batch(&vk, &swapped, &proof);
+        assert_eq!(res, Err(Error::BatchLinkFailed));
+    }
+    #[test]
+    fn test_leq() {
+        let mut rng = test_rng();
+        let (pk, index, vk) = RangeProver::<Bn254, FS>::setup(4, 4, &mut rng).unwrap();
+
+        let (a_cm, b_cm, proof) =
+            RangeProver::<Bn254, FS>::prove_leq(&pk, &index, &[0, 3, 9, 15], &[2, 3, 15, 15], 4)
+                .unwrap();
+        assert!(RangeProver::<Bn254, FS>::verify_leq(&vk, &a_cm, &b_cm, &proof).is_ok());
+        assert!(RangeProver::<Bn254, FS>::verify_leq(&vk, &b_cm, &a_cm, &proof).is_err());
+
+        let res =
+            RangeProver::<Bn254, FS>::prove_leq(&pk, &index, &[0, 4, 9, 15], &[2, 3, 15, 15], 4);
+        assert_eq!(res.err(), Some(Error::NotLessOrEqual(1)));
+    }
+    #[test]
+    fn test_signed_range() {
+        let mut rng = test_rng();
+        // [-8, 8)
+        let (pk, index, vk) = RangeProver::<Bn254, FS>::setup(4, 4, &mut rng).unwrap();
+
+        let values = [-8, -1, 0, 7];
+        let (commitment, proof) =
+            RangeProver::<Bn254, FS>::prove_signed(&pk, &index, &values, 4).unwrap();
+        assert!(RangeProver::<Bn254, FS>::verify_signed(&vk, &commitment, &proof, 4).is_ok());
+
+        // the commitment is the plain one to the field encodings
+        let encoded: Vec<Fr> = values
+            .iter()
+            .map(|&v| signed_to_field::<Bn254>(v))
+            .collect();
+        let witness = Witness::new(&encoded).unwrap();
+        assert_eq!(
+            Statement::<Bn254>::from_witness(&pk.pk, &witness).f,
+            commitment
+        );
+
+        let res = RangeProver::<Bn254, FS>::prove_signed(&pk, &index, &[-9, -1, 0, 7], 4);
+        assert_eq!(res.err(), Some(Error::ValueOutOfRange("-9".into())));
+        let res = RangeProver::<Bn254, FS>::prove_signed(&pk, &index, &[-8, -1, 0, 8], 4);
+        assert_eq!(res.err(), Some(Error::ValueOutOfRange("8".into())));
+    }
+    #[test]
+    fn test_plan() {
+        // one proof: small tables win, many proofs: few limbs win
+        let plan = RangePlan::new::<Fr>(32, 4, 1).unwrap();
+        assert_eq!((plan.limb_bits, plan.limbs), (4, 8));
+        let plan = RangePlan::new::<Fr>(32, 4, 1 << 20).unwrap();
+        assert_eq!((plan.limb_bits, plan.limbs), (16, 2));
+        // limbs must cover the witness
+        let plan = RangePlan::new::<Fr>(64, 1 << 10, 1).unwrap();
+        assert_eq!((plan.limb_bits, plan.limbs), (16, 4));
+        assert!(RangePlan::new::<Fr>(6, 1 << 10, 1).is_err());
+
+        let mut rng = test_rng();
+        let plan = RangePlan::new::<Fr>(12, 4, 1).unwrap();
+        let (pk, vk) = plan.setup::<Bn254, FS, _>(4, &mut rng).unwrap();
+        let (commitment, proof) =
+            RangeProver::<Bn254, FS>::prove_wide(&pk, &[0, 1, 4095, 77], plan.limb_bits).unwrap();
+        assert!(RangeProver::<Bn254, FS>::verify_wide(&vk, &commitment, &proof).is_ok());
+    }
+    #[test]
+    fn test_vector_range() {
+        let mut rng = test_rng();
+        let (pk, index, vk) = RangeProver::<Bn254, FS>::setup(4, 4, &mut rng).unwrap();
+
+        // [0, 10) needs the shifted lookup, [0, 16) is the table itself
+        let (statement, proof) =
+            RangeProver::<Bn254, FS>::prove_vector(&pk, &index, &[0, 9, 3, 5], 10).unwrap();
+        assert!(proof.shifted_proof.is_some());
+        assert!(RangeProver::<Bn254, FS>::verify_vector(&vk, &statement, &proof).is_ok());
+        let (full, full_proof) =
+            RangeProver::<Bn254, FS>::prove_vector(&pk, &index, &[0, 15, 3, 5], 16).unwrap();
+        assert!(full_proof.shifted_proof.is_none());
+        assert!(RangeProver::<Bn254, FS>::verify_vector(&vk, &full, &full_proof).is_ok());
+
+        let res = RangeProver::<Bn254, FS>::prove_vector(&pk, &index, &[0, 10, 3, 5], 10);
+        assert_eq!(res.err(), Some(Error::ValueOutOfRange("10".into())));
+
+        // a proof for [0, 16) doesn't show [0, 10)
+        let narrowed = VectorRangeStatement { bound: 10, ..full };
+        let res = RangeProver::<Bn254, FS>::verify_vector(&vk, &narrowed, &full_proof);
+        assert_eq!(
+            res,
+            Err(Error::Verification(VerificationError::ProofShapeMismatch))
+        );
+    }
+}
@@ -0,0 +1,277 @@
+//! On disk store of preprocessed tables, keyed by the table commitment [T(x)]_2, for proving services that handle
+//! many tables.
+//!
+//! Every entry is one file `<keccak of [T(x)]_2>.cqtable` in the registry directory:
+//!
+//! | bytes       | content                                                 |
+//! |-------------|---------------------------------------------------------|
+//! | 0..32       | Keccak256 of everything after it                        |
+//! | 32..40      | length of the index encoding, little endian             |
+//! | then        | versioned encoding of the [`Index`], with the common input |
+//! | then        | compressed table values                                 |
+//!
+//! Names are files `<name>.name` holding the hex id of an entry. Entries are written to a temporary file and
+//! renamed, so a crash never leaves a partial entry behind. Reading checks the checksum and that the index is the
+//! one of the requested commitment; `get_checked` also recomputes [T(x)]_2 from the table values.
+
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use sha3::{Digest, Keccak256};
+
+use crate::{
+    encoding::{Artifact, Compression, CurveId},
+    error::Error,
+    indexer::{CommonPreprocessedInput, Index},
+    table::Table,
+};
+
+const CHECKSUM_BYTES: usize = 32;
+const ENTRY_EXTENSION: &str = "cqtable";
+const NAME_EXTENSION: &str = "name";
+
+fn io_err(e: std::io::Error) -> Error {
+    Error::Io(e.to_string())
+}
+
+fn serialization_err(e: ark_serialize::SerializationError) -> Error {
+    Error::Serialization(format!("{:?}", e))
+}
+
+/// Writes to a temporary file next to `path` and renames it over `path`
+fn write_atomic(path: &Path, bytes: &[u8]) -> Result<(), Error> {
+    let tmp = path.with_extension("tmp");
+    let mut file = File::create(&tmp).map_err(io_err)?;
+    file.write_all(bytes).map_err(io_err)?;
+    file.sync_all().map_err(io_err)?;
+    fs::rename(&tmp, path).map_err(io_err)
+}
+
+/// A table with its index, as stored in a [`TableRegistry`]
+pub struct TableEntry<E: CurveId> {
+    pub table: Table<E::Fr>,
+    pub index: Index<E>,
+}
+
+impl<E: CurveId> TableEntry<E> {
+    pub fn common(&self) -> &CommonPreprocessedInput<E> {
+        self.index.common()
+    }
+}
+
+/// Directory of [`TableEntry`] files, see the module documentation for the layout
+pub struct TableRegistry<E: CurveId> {
+    dir: PathBuf,
+    _e: std::marker::PhantomData<E>,
+}
+
+impl<E: CurveId> TableRegistry<E> {
+    /// Opens the registry in `dir`, creating the directory if needed
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<Self, Error> {
+        fs::create_dir_all(&dir).map_err(io_err)?;
+        Ok(Self {
+            dir: dir.as_ref().to_path_buf(),
+            _e: std::marker::PhantomData,
+        })
+    }
+
+    /// Hex id of the entry of a table commitment, the file name of the entry
+    pub fn id(commitment: &E::G2Affine) -> String {
+        let mut bytes = vec![];
+        commitment
+            .serialize(&mut bytes)
+            .expect("serializing into a vec can't fail");
+        hex::encode(Keccak256::digest(&bytes))
+    }
+
+    fn entry_path(&self, id: &str) -> PathBuf {
+        self.dir.join(id).with_extension(ENTRY_EXTENSION)
+    }
+
+    fn name_path(&self, name: &str) -> Result<PathBuf, Error> {
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(Error::InvalidEncoding("table names are [A-Za-z0-9_-]+"));
+        }
+        Ok(self.dir.join(name).with_extension(NAME_EXTENSION))
+    }
+
+    /// Stores `table` and its `index`, replacing an entry of the same commitment, and returns the commitment
+    pub fn insert(&self, table: &Table<E::Fr>, index: &Index<E>) -> Result<E::G2Affine, Error> {
+        if index.qs.len() != table.size {
+            return Err(Error::TableSizeMismatch(index.qs.len(), table.size));
+        }
+        let encoded_index = index.encode(Compression::Compressed);
+        let mut body = (encoded_index.len() as u64).to_le_bytes().to_vec();
+        body.extend_from_slice(&encoded_index);
+        table.serialize(&mut body).map_err(serialization_err)?;
+
+        let mut bytes = Keccak256::digest(&body).to_vec();
+        bytes.extend_from_slice(&body);
+        let commitment = index.common().t_2;
+        write_atomic(&self.entry_path(&Self::id(&commitment)), &bytes)?;
+        Ok(commitment)
+    }
+
+    /// The entry of `commitment`, `None` if the registry has none
+    pub fn get(&self, commitment: &E::G2Affine) -> Result<Option<TableEntry<E>>, Error> {
+        let bytes = match fs::read(self.entry_path(&Self::id(commitment))) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(io_err(e)),
+        };
+        if bytes.len() < CHECKSUM_BYTES + 8 {
+            return Err(Error::InvalidEncoding("truncated registry entry"));
+        }
+        let (checksum, body) = bytes.split_at(CHECKSUM_BYTES);
+        if Keccak256::digest(body).as_slice() != checksum {
+            return Err(Error::InvalidEncoding("registry entry checksum mismatch"));
+        }
+        let mut len = [0u8; 8];
+        len.copy_from_slice(&body[..8]);
+        let len = u64::from_le_bytes(len) as usize;
+        if body.len() - 8 < len {
+            return Err(Error::InvalidEncoding("truncated registry entry"));
+        }
+        let index = Index::<E>::decode(&body[8..8 + len])?;
+        let table = Table::<E::Fr>::deserialize(&body[8 + len..]).map_err(serialization_err)?;
+        if index.common().t_2 != *commitment {
+            return Err(Error::InvalidEncoding(
+                "registry entry of another commitment",
+            ));
+        }
+        if index.qs.len() != table.size {
+            return Err(Error::TableSizeMismatch(index.qs.len(), table.size));
+        }
+        Ok(Some(TableEntry { table, index }))
+    }
+
+    /// `get`, also checking that the table values commit to `commitment` under `srs_g2`, about N G2
+    /// multiplications
+    pub fn get_checked(
+        &self,
+        commitment: &E::G2Affine,
+        srs_g2: &[E::G2Affine],
+    ) -> Result<Option<TableEntry<E>>, Error> {
+        let entry = match self.get(commitment)? {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        entry.common().check_table(srs_g2, &entry.table)?;
+        Ok(Some(entry))
+    }
+
+    /// Removes the entry of `commitment`, returns whether there was one. Names pointing to it are kept and
+    /// resolve to a missing entry.
+    pub fn remove(&self, commitment: &E::G2Affine) -> Result<bool, Error> {
+        match fs::remove_file(self.entry_path(&Self::id(commitment))) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(io_err(e)),
+        }
+    }
+
+    /// Ids of all entries, in no particular order
+    pub fn ids(&self) -> Result<Vec<String>, Error> {
+        let mut ids = vec![];
+        for entry in fs::read_dir(&self.dir).map_err(io_err)? {
+            let path = entry.map_err(io_err)?.path();
+            if path.extension().map_or(false, |ext| ext == ENTRY_EXTENSION) {
+                if let Some(id) = path.file_stem().and_then(|stem| stem.to_str()) {
+                    ids.push(id.to_string());
+                }
+            }
+        }
+        Ok(ids)
+    }
+
+    /// Points `name` at the entry of `commitment`, replacing an earlier target
+    pub fn set_name(&self, name: &str, commitment: &E::G2Affine) -> Result<(), Error> {
+        let path = self.name_path(name)?;
+        let mut bytes = vec![];
+        commitment
+            .serialize(&mut bytes)
+            .map_err(serialization_err)?;
+        write_atomic(&path, hex::encode(bytes).as_bytes())
+    }
+
+    /// Commitment `name` points at, `None` for an unknown name
+    pub fn resolve(&self, name: &str) -> Result<Option<E::G2Affine>, Error> {
+        let text = match fs::read_to_string(self.name_path(name)?) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(io_err(e)),
+        };
+        let bytes = hex::decode(text.trim())
+            .map_err(|_| Error::InvalidEncoding("registry name is not hex"))?;
+        E::G2Affine::deserialize(&bytes[..])
+            .map(Some)
+            .map_err(serialization_err)
+    }
+}
+
+#[cfg(test)]
+mod registry_tests {
+    use std::fs;
+
+    use ark_bn254::{Bn254, Fr};
+    use ark_std::{rand::rngs::StdRng, test_rng};
+
+    use crate::{
+        error::Error,
+        indexer::Index,
+        table::Table,
+        utils::{to_field, unsafe_setup_from_rng},
+    };
+
+    use super::TableRegistry;
+
+    #[test]
+    fn test_table_registry() {
+        let mut rng = test_rng();
+        let (srs_g1, srs_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(7, 8, &mut rng);
+        let dir = std::env::temp_dir().join(format!("cqext-registry-{}", std::process::id()));
+        let registry = TableRegistry::<Bn254>::open(&dir).unwrap();
+
+        let table = Table::<Fr>::new(&to_field(&[1, 5, 10, 15, 20, 25, 30, 35])).unwrap();
+        let index = Index::<Bn254>::gen(&srs_g1, &srs_g2, &table).unwrap();
+        let commitment = registry.insert(&table, &index).unwrap();
+        registry.set_name("allowlist", &commitment).unwrap();
+
+        let resolved = registry.resolve("allowlist").unwrap().unwrap();
+        let entry = registry.get_checked(&resolved, &srs_g2).unwrap().unwrap();
+        assert_eq!(entry.table.values(), table.values());
+        assert_eq!(entry.index.qs, index.qs);
+        assert_eq!(
+            registry.ids().unwrap(),
+            vec![TableRegistry::<Bn254>::id(&commitment)]
+        );
+        assert!(registry.resolve("other").unwrap().is_none());
+        assert!(registry.set_name("../escape", &commitment).is_err());
+
+        // a flipped bit anywhere in the entry is caught by the checksum
+        let path = dir.join(format!(
+            "{}.cqtable",
+            TableRegistry::<Bn254>::id(&commitment)
+        ));
+        let mut bytes = fs::read(&path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        fs::write(&path, &bytes).unwrap();
+        assert_eq!(
+            registry.get(&commitment).err(),
+            Some(Error::InvalidEncoding("registry entry checksum mismatch"))
+        );
+
+        assert!(registry.remove(&commitment).unwrap());
+        assert!(registry.get(&commitment).unwrap().is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
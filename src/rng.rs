@@ -1,8 +1,17 @@
-use ark_ff::{FromBytes, ToBytes};
+use ark_ff::{FromBytes, PrimeField, ToBytes};
 use ark_std::convert::From;
 use ark_std::marker::PhantomData;
 use ark_std::rand::{RngCore, SeedableRng};
-use sha3::Digest;
+use ark_std::vec::Vec;
+use blake2::{Blake2b512, Blake2s256};
+use sha2::{Sha256, Sha512};
+use sha3::digest::{core_api::BlockSizeUser, ExtendableOutput, Output, Update, XofReader};
+use sha3::{Digest, Keccak256, Sha3_256, Shake128, Shake256};
+
+use crate::hash_to_field::{hash_to_field, hash_to_field_xof};
+
+/// Domain separation tag of challenges derived with `hash_to_field`
+pub const CHALLENGE_DST: &[u8] = b"CQ-1.0-challenge";
 
 /// An RNG suitable for Fiat-Shamir transforms
 pub trait FiatShamirRng: RngCore {
@@ -10,6 +19,20 @@ pub trait FiatShamirRng: RngCore {
     fn initialize<'a, T: 'a + ToBytes>(initial_input: &'a T) -> Self;
     /// Absorb new inputs into state
     fn absorb<'a, T: 'a + ToBytes>(&mut self, new_input: &'a T);
+    /// Draw a challenge, sampled with `F::rand` unless the rng hashes to the field directly
+    fn squeeze_field<F: PrimeField>(&mut self) -> F
+    where
+        Self: Sized,
+    {
+        F::rand(self)
+    }
+}
+
+/// Message hashed to the field for the `counter`-th challenge since the last absorb
+fn challenge_message(seed: &[u8; 32], counter: &mut u64) -> Vec<u8> {
+    let msg = [&seed[..], &counter.to_be_bytes()].concat();
+    *counter += 1;
+    msg
 }
 
 /// A simple `FiatShamirRng` that refreshes its seed by hashing together the previous seed
@@ -17,6 +40,7 @@ pub trait FiatShamirRng: RngCore {
 pub struct SimpleHashFiatShamirRng<D: Digest, R: RngCore + SeedableRng> {
     r: R,
     seed: [u8; 32],
+    challenges: u64,
     #[doc(hidden)]
     digest: PhantomData<D>,
 }
@@ -44,7 +68,8 @@ impl<D: Digest, R: RngCore + SeedableRng> RngCore for SimpleHashFiatShamirRng<D,
     }
 }
 
-impl<D: Digest, R: RngCore + SeedableRng> FiatShamirRng for SimpleHashFiatShamirRng<D, R>
+impl<D: Digest + BlockSizeUser, R: RngCore + SeedableRng> FiatShamirRng
+    for SimpleHashFiatShamirRng<D, R>
 where
     R::Seed: From<[u8; 32]>,
 {
@@ -61,6 +86,7 @@ where
         Self {
             r,
             seed,
+            challenges: 0,
             digest: PhantomData,
         }
     }
@@ -76,5 +102,316 @@ where
         bytes.extend_from_slice(&self.seed);
         self.seed = FromBytes::read(D::digest(&bytes).as_ref()).expect("failed to get [u8; 32]");
         self.r = R::from_seed(<R::Seed>::from(self.seed));
+        self.challenges = 0;
+    }
+
+    /// `hash_to_field(self.seed || i)` with `expand_message_xmd` over `D`, `i` counting the challenges
+    /// since the last absorb as a big endian u64
+    #[inline]
+    fn squeeze_field<F: PrimeField>(&mut self) -> F {
+        let msg = challenge_message(&self.seed, &mut self.challenges);
+        hash_to_field::<F, D>(&msg, CHALLENGE_DST, 1)[0]
+    }
+}
+
+/// Hash usable in a transcript, with a constant separating its transcripts from those of other hashes
+pub trait TranscriptDigest {
+    const DOMAIN: &'static [u8];
+}
+
+macro_rules! transcript_digest {
+    ($($hash:ty => $domain:literal),* $(,)?) => {
+        $(impl TranscriptDigest for $hash {
+            const DOMAIN: &'static [u8] = $domain;
+        })*
+    };
+}
+
+transcript_digest! {
+    Keccak256 => b"cqext-fs-keccak256",
+    Sha3_256 => b"cqext-fs-sha3-256",
+    Sha256 => b"cqext-fs-sha256",
+    Sha512 => b"cqext-fs-sha512",
+    Blake2s256 => b"cqext-fs-blake2s256",
+    Blake2b512 => b"cqext-fs-blake2b512",
+    Shake128 => b"cqext-fs-shake128",
+    Shake256 => b"cqext-fs-shake256",
+}
+
+/// [`SimpleHashFiatShamirRng`] with `D::DOMAIN` prepended to the initial input, so transcripts over
+/// different hashes never share a seed.
+pub struct DomainSeparatedFiatShamirRng<D: Digest + TranscriptDigest, R: RngCore + SeedableRng>(
+    SimpleHashFiatShamirRng<D, R>,
+);
+
+impl<D: Digest + TranscriptDigest, R: RngCore + SeedableRng> RngCore
+    for DomainSeparatedFiatShamirRng<D, R>
+{
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    #[inline]
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    #[inline]
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest);
+    }
+
+    #[inline]
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ark_std::rand::Error> {
+        self.0.try_fill_bytes(dest)
+    }
+}
+
+impl<D: Digest + BlockSizeUser + TranscriptDigest, R: RngCore + SeedableRng> FiatShamirRng
+    for DomainSeparatedFiatShamirRng<D, R>
+where
+    R::Seed: From<[u8; 32]>,
+{
+    /// `self.seed = H(D::DOMAIN || initial_input)`
+    #[inline]
+    fn initialize<'a, T: 'a + ToBytes>(initial_input: &'a T) -> Self {
+        let mut bytes = D::DOMAIN.to_vec();
+        initial_input
+            .write(&mut bytes)
+            .expect("failed to convert to bytes");
+        Self(SimpleHashFiatShamirRng::initialize(&bytes))
+    }
+
+    #[inline]
+    fn absorb<'a, T: 'a + ToBytes>(&mut self, new_input: &'a T) {
+        self.0.absorb(new_input);
+    }
+
+    #[inline]
+    fn squeeze_field<F: PrimeField>(&mut self) -> F {
+        self.0.squeeze_field()
+    }
+}
+
+const XOF_SEED_TAG: u8 = 0;
+const XOF_EXPAND_TAG: u8 = 1;
+
+/// `FiatShamirRng` over an extendable output function, the challenges are read straight from the XOF
+/// instead of from an rng seeded with a digest.
+///
+/// `self.seed = X(X::DOMAIN || 0 || input || self.seed)` truncated to 32 bytes on every absorb, and the
+/// output stream is `X(X::DOMAIN || 1 || self.seed)`.
+pub struct XofFiatShamirRng<X: ExtendableOutput + Update + Default + TranscriptDigest> {
+    seed: [u8; 32],
+    reader: X::Reader,
+    challenges: u64,
+}
+
+impl<X: ExtendableOutput + Update + Default + TranscriptDigest> XofFiatShamirRng<X> {
+    fn xof(tag: u8, parts: &[&[u8]]) -> X::Reader {
+        let mut hasher = X::default();
+        hasher.update(X::DOMAIN);
+        hasher.update(&[tag]);
+        for part in parts {
+            hasher.update(part);
+        }
+        hasher.finalize_xof()
+    }
+
+    fn reseed(&mut self, input: &[u8]) {
+        let mut reader = Self::xof(XOF_SEED_TAG, &[input, &self.seed]);
+        reader.read(&mut self.seed);
+        self.reader = Self::xof(XOF_EXPAND_TAG, &[&self.seed]);
+        self.challenges = 0;
+    }
+}
+
+impl<X: ExtendableOutput + Update + Default + TranscriptDigest> RngCore for XofFiatShamirRng<X> {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.reader.read(dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ark_std::rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl<X: ExtendableOutput + Update + Default + TranscriptDigest> FiatShamirRng
+    for XofFiatShamirRng<X>
+{
+    fn initialize<'a, T: 'a + ToBytes>(initial_input: &'a T) -> Self {
+        let mut bytes = Vec::new();
+        initial_input
+            .write(&mut bytes)
+            .expect("failed to convert to bytes");
+        let mut seed = [0u8; 32];
+        Self::xof(XOF_SEED_TAG, &[&bytes]).read(&mut seed);
+        Self {
+            seed,
+            reader: Self::xof(XOF_EXPAND_TAG, &[&seed]),
+            challenges: 0,
+        }
+    }
+
+    fn absorb<'a, T: 'a + ToBytes>(&mut self, new_input: &'a T) {
+        let mut bytes = Vec::new();
+        new_input
+            .write(&mut bytes)
+            .expect("failed to convert to bytes");
+        self.reseed(&bytes);
+    }
+
+    /// `hash_to_field(self.seed || i)` with `expand_message_xof` over `X`
+    fn squeeze_field<F: PrimeField>(&mut self) -> F {
+        let msg = challenge_message(&self.seed, &mut self.challenges);
+        hash_to_field_xof::<F, X>(&msg, CHALLENGE_DST, 1)[0]
+    }
+}
+
+/// Counter mode rng over a digest.
+///
+/// Block `i` is `D(seed || i)` with `i` as a big endian u64, and words are read from the blocks little
+/// endian.
+pub struct CounterRng<D: Digest> {
+    seed: [u8; 32],
+    counter: u64,
+    block: Output<D>,
+    pos: usize,
+}
+
+/// Counter mode rng over Keccak256, cheap to replicate in the EVM.
+///
+/// Sampling a 4 limb field element consumes exactly one block.
+pub type KeccakCounterRng = CounterRng<Keccak256>;
+
+impl<D: Digest> CounterRng<D> {
+    fn next_byte(&mut self) -> u8 {
+        if self.pos == self.block.len() {
+            let mut input = self.seed.to_vec();
+            input.extend_from_slice(&self.counter.to_be_bytes());
+            self.block = D::digest(&input);
+            self.counter += 1;
+            self.pos = 0;
+        }
+        self.pos += 1;
+        self.block[self.pos - 1]
+    }
+}
+
+impl<D: Digest> SeedableRng for CounterRng<D> {
+    type Seed = [u8; 32];
+
+    fn from_seed(seed: [u8; 32]) -> Self {
+        let block = Output::<D>::default();
+        Self {
+            seed,
+            counter: 0,
+            pos: block.len(),
+            block,
+        }
+    }
+}
+
+impl<D: Digest> RngCore for CounterRng<D> {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for byte in dest.iter_mut() {
+            *byte = self.next_byte();
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ark_std::rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod rng_tests {
+    use ark_bn254::{Bn254, Fr};
+    use ark_std::{rand::RngCore, test_rng};
+    use blake2::Blake2s256;
+    use rand_chacha::ChaChaRng;
+    use sha2::Sha256;
+    use sha3::{Shake128, Shake256};
+
+    use crate::{cq::Cq, utils::to_field};
+
+    use super::{DomainSeparatedFiatShamirRng, FiatShamirRng, XofFiatShamirRng};
+
+    fn squeeze<R: FiatShamirRng>(inputs: &[&[u8]]) -> u64 {
+        let mut rng = R::initialize(&inputs[0].to_vec());
+        for input in &inputs[1..] {
+            rng.absorb(&input.to_vec());
+        }
+        rng.next_u64()
+    }
+
+    #[test]
+    fn test_domain_separation() {
+        type Sha = DomainSeparatedFiatShamirRng<Sha256, ChaChaRng>;
+        type Blake = DomainSeparatedFiatShamirRng<Blake2s256, ChaChaRng>;
+        let inputs: &[&[u8]] = &[b"cq", b"message"];
+        assert_eq!(squeeze::<Sha>(inputs), squeeze::<Sha>(inputs));
+        assert_ne!(squeeze::<Sha>(inputs), squeeze::<Blake>(inputs));
+        assert_ne!(squeeze::<Sha>(inputs), squeeze::<Sha>(&[b"cq", b"messagf"]));
+
+        type Shake = XofFiatShamirRng<Shake128>;
+        assert_eq!(squeeze::<Shake>(inputs), squeeze::<Shake>(inputs));
+        assert_ne!(
+            squeeze::<Shake>(inputs),
+            squeeze::<XofFiatShamirRng<Shake256>>(inputs)
+        );
+        assert_ne!(
+            squeeze::<Shake>(inputs),
+            squeeze::<Shake>(&[b"cq", b"messagf"])
+        );
+
+        // challenge expansion keeps reading the xof
+        let mut rng = Shake::initialize(&b"cq".to_vec());
+        let first = rng.next_u64();
+        assert_ne!(first, rng.next_u64());
+    }
+
+    #[test]
+    fn test_cq_with_digests() {
+        let mut rng = test_rng();
+        let table_values = to_field(&[1, 5, 10, 15, 20, 25, 30, 35]);
+        let witness = to_field::<Fr>(&[5, 15, 20, 35]);
+
+        type ShaCq = Cq<Bn254, DomainSeparatedFiatShamirRng<Sha256, ChaChaRng>>;
+        let (pk, vk) = ShaCq::setup(&table_values, 4, &mut rng).unwrap();
+        let (statement, proof) = ShaCq::prove(&pk, &witness).unwrap();
+        assert!(ShaCq::verify(&vk, &statement, &proof).is_ok());
+
+        type ShakeCq = Cq<Bn254, XofFiatShamirRng<Shake256>>;
+        let (pk, vk) = ShakeCq::setup(&table_values, 4, &mut rng).unwrap();
+        let (statement, proof) = ShakeCq::prove(&pk, &witness).unwrap();
+        assert!(ShakeCq::verify(&vk, &statement, &proof).is_ok());
     }
 }
@@ -0,0 +1,281 @@
+//! Lookups restricted to a contiguous segment `[lo, hi)` of the positions of a larger table, e.g. opcodes
+//! `0..64` of a 2^16 table, with the segment part of the statement.
+//!
+//! The table is indexed once as the two column table `(T_i, i)`. The prover commits to the position `p` of every
+//! witness value and shows
+//!
+//! - `(f, p)` is a row of the table, with a [`MultiColumnCq`] lookup,
+//! - `p - lo` and `hi - 1 - p` are in `[0, 2^b)`, `2^b >= N`, with `DecomposableCq` range lookups.
+//!
+//! The range statements are `[p]_1 - lo·[1]_1` and `(hi - 1)·[1]_1 - [p]_1`, so the verifier derives them from the
+//! bounds itself. Positions are below N, so neither difference can wrap around the field.
+
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+#[cfg(any(test, feature = "test-utils"))]
+use ark_std::rand::RngCore;
+use ark_std::{format, marker::PhantomData, vec, vec::Vec};
+
+use crate::{
+    config::CqConfig,
+    data_structures::{Proof, Statement},
+    decomposable::{
+        DecomposableCq, DecomposableProverKey, DecomposableTable, DecomposableVerifierKey,
+        DecomposedProof,
+    },
+    error::{Error, VerificationError},
+    multi_column::{
+        MultiColumnCq, MultiColumnProverKey, MultiColumnStatement, MultiColumnTable,
+        MultiColumnVerifierKey,
+    },
+    rng::FiatShamirRng,
+    table::Table,
+    verifier::verify_folded,
+};
+
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct SegmentProverKey<E: PairingEngine> {
+    pub table: Table<E::Fr>,
+    /// key of the `(T_i, i)` table
+    pub lookup: MultiColumnProverKey<E>,
+    pub range: DecomposableProverKey<E>,
+    pub range_table: DecomposableTable<E::Fr>,
+}
+
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct SegmentVerifierKey<E: PairingEngine> {
+    pub lookup: MultiColumnVerifierKey<E>,
+    pub range: DecomposableVerifierKey<E>,
+}
+
+/// Witness commitment and the segment `[lo, hi)` its values are claimed to come from
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct SegmentStatement<E: PairingEngine> {
+    pub f: E::G1Affine,
+    pub lo: u64,
+    pub hi: u64,
+}
+
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct SegmentProof<E: PairingEngine> {
+    /// commitment to the positions of the witness values
+    pub p_cm: E::G1Affine,
+    /// `(f, p)` is a row of `(T_i, i)`
+    pub lookup_proof: Proof<E>,
+    /// `p - lo` is in range
+    pub lower_proof: DecomposedProof<E>,
+    /// `hi - 1 - p` is in range
+    pub upper_proof: DecomposedProof<E>,
+}
+
+pub struct SegmentCq<E: PairingEngine, FS: FiatShamirRng> {
+    _e: PhantomData<E>,
+    _fs: PhantomData<FS>,
+}
+
+impl<E: PairingEngine, FS: FiatShamirRng> SegmentCq<E, FS> {
+    /// Indexes `table` and the range subtable of `2^limb_bits` entries with one srs sampled from `rng`
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn setup<R: RngCore>(
+        table: Table<E::Fr>,
+        limb_bits: u32,
+        witness_size: usize,
+        rng: &mut R,
+    ) -> Result<(SegmentProverKey<E>, SegmentVerifierKey<E>), Error> {
+        let (lookup, range, _) = Self::configs(table.size, limb_bits, witness_size)?;
+        let (srs_g1, srs_g2) = crate::utils::unsafe_setup_from_rng::<E, R>(
+            lookup.srs_g1_degree().max(range.srs_g1_degree()),
+            lookup.srs_g2_degree().max(range.srs_g2_degree()),
+            rng,
+        );
+        Self::keygen(table, limb_bits, witness_size, srs_g1, &srs_g2)
+    }
+
+    /// Same as `setup` with an existing srs, which has to cover both the table and the range subtable
+    pub fn keygen(
+        table: Table<E::Fr>,
+        limb_bits: u32,
+        witness_size: usize,
+        srs_g1: Vec<E::G1Affine>,
+        srs_g2: &[E::G2Affine],
+    ) -> Result<(SegmentProverKey<E>, SegmentVerifierKey<E>), Error> {
+        let (_, _, limbs) = Self::configs(table.size, limb_bits, witness_size)?;
+        let positions = (0..table.size as u64).map(E::Fr::from).collect();
+        let rows = MultiColumnTable::from_columns(vec![table.values.clone(), positions])?;
+        let (lookup_pk, lookup_vk) =
+            MultiColumnCq::<E, FS>::keygen(rows, witness_size, srs_g1.clone(), srs_g2)?;
+        let range_table = DecomposableTable::range(limb_bits, limbs)?;
+        let (range_pk, range_vk) = DecomposableCq::<E, FS>::keygen(
+            DecomposableTable::range(limb_bits, limbs)?,
+            witness_size,
+            srs_g1,
+            srs_g2,
+        )?;
+
+        Ok((
+            SegmentProverKey {
+                table,
+                lookup: lookup_pk,
+                range: range_pk,
+                range_table,
+            },
+            SegmentVerifierKey {
+                lookup: lookup_vk,
+                range: range_vk,
+            },
+        ))
+    }
+
+    /// Configs of the table and the range subtable, and the number of limbs covering the positions
+    fn configs(
+        table_size: usize,
+        limb_bits: u32,
+        witness_size: usize,
+    ) -> Result<(CqConfig<E, FS>, CqConfig<E, FS>, usize), Error> {
+        let lookup = CqConfig::<E, FS>::builder()
+            .table_size(table_size)
+            .witness_size(witness_size)
+            .build()?;
+        if limb_bits == 0 || limb_bits >= 32 {
+            return Err(Error::RangeTooLarge(31, limb_bits));
+        }
+        let bits = table_size.trailing_zeros();
+        let limbs = ((bits + limb_bits - 1) / limb_bits).max(1) as usize;
+        let range = CqConfig::<E, FS>::builder()
+            .table_size(1 << limb_bits)
+            .witness_size(witness_size)
+            .build()?;
+        Ok((lookup, range, limbs))
+    }
+
+    /// Commits to `values` and proves that each of them is at a position in `[lo, hi)`
+    pub fn prove(
+        pk: &SegmentProverKey<E>,
+        values: &[E::Fr],
+        lo: u64,
+        hi: u64,
+    ) -> Result<(SegmentStatement<E>, SegmentProof<E>), Error> {
+        check_segment(lo, hi, pk.table.size)?;
+        let positions = values
+            .iter()
+            .map(|value| {
+                let i = *pk
+                    .table
+                    .value_index_mapping
+                    .get(value)
+                    .ok_or_else(|| Error::ValueNotInTable(format!("{}", value)))?
+                    as u64;
+                if i < lo || i >= hi {
+                    return Err(Error::ValueOutOfRange(format!("{}", value)));
+                }
+                Ok(i)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let rows: Vec<Vec<E::Fr>> = values
+            .iter()
+            .zip(&positions)
+            .map(|(&v, &i)| vec![v, E::Fr::from(i)])
+            .collect();
+        let (columns, lookup_proof) = MultiColumnCq::<E, FS>::prove(&pk.lookup, &rows)?;
+        let lower: Vec<E::Fr> = positions.iter().map(|&i| E::Fr::from(i - lo)).collect();
+        let (_, lower_proof) = DecomposableCq::<E, FS>::prove(&pk.range, &pk.range_table, &lower)?;
+        let upper: Vec<E::Fr> = positions.iter().map(|&i| E::Fr::from(hi - 1 - i)).collect();
+        let (_, upper_proof) = DecomposableCq::<E, FS>::prove(&pk.range, &pk.range_table, &upper)?;
+
+        Ok((
+            SegmentStatement {
+                f: columns.columns[0].f,
+                lo,
+                hi,
+            },
+            SegmentProof {
+                p_cm: columns.columns[1].f,
+                lookup_proof,
+                lower_proof,
+                upper_proof,
+            },
+        ))
+    }
+
+    pub fn verify(
+        vk: &SegmentVerifierKey<E>,
+        statement: &SegmentStatement<E>,
+        proof: &SegmentProof<E>,
+    ) -> Result<(), Error> {
+        check_segment(statement.lo, statement.hi, vk.lookup.vk.table_size)?;
+        let g = E::G1Affine::prime_subgroup_generator();
+        let lower = proof.p_cm.into_projective() - g.mul(E::Fr::from(statement.lo));
+        let upper = g.mul(E::Fr::from(statement.hi - 1)) - proof.p_cm.into_projective();
+        let columns = MultiColumnStatement {
+            columns: vec![Statement::new(statement.f), Statement::new(proof.p_cm)],
+        };
+
+        // all cq proofs are checked with one final exponentiation
+        let lookup_check =
+            MultiColumnCq::<E, FS>::pairing_equation(&vk.lookup, &columns, &proof.lookup_proof)?;
+        let mut checks = vec![(
+            lookup_check,
+            Error::Verification(VerificationError::BatchedPairingFailed),
+        )];
+        checks.extend(DecomposableCq::<E, FS>::pairing_checks(
+            &vk.range,
+            &Statement::new(lower.into_affine()),
+            &proof.lower_proof,
+        )?);
+        checks.extend(DecomposableCq::<E, FS>::pairing_checks(
+            &vk.range,
+            &Statement::new(upper.into_affine()),
+            &proof.upper_proof,
+        )?);
+        verify_folded::<E, FS>(checks)
+    }
+}
+
+/// `[lo, hi)` is a nonempty range of positions of a table of `table_size` entries
+fn check_segment(lo: u64, hi: u64, table_size: usize) -> Result<(), Error> {
+    if lo >= hi || hi > table_size as u64 {
+        return Err(Error::IndexOutOfTable(hi as usize, table_size));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod segment_tests {
+    use ark_bn254::{Bn254, Fr};
+    use ark_std::test_rng;
+    use rand_chacha::ChaChaRng;
+    use sha3::Keccak256;
+
+    use crate::{error::Error, rng::SimpleHashFiatShamirRng, table::Table, utils::to_field};
+
+    use super::SegmentCq;
+
+    type FS = SimpleHashFiatShamirRng<Keccak256, ChaChaRng>;
+
+    #[test]
+    fn test_segment_lookup() {
+        let mut rng = test_rng();
+        let table = Table::<Fr>::new(&to_field(&(100..116).collect::<Vec<_>>())).unwrap();
+        let (pk, vk) = SegmentCq::<Bn254, FS>::setup(table, 2, 4, &mut rng).unwrap();
+
+        // positions 4..8 hold 104..108
+        let values = to_field(&[104, 107, 104, 105]);
+        let (statement, proof) = SegmentCq::<Bn254, FS>::prove(&pk, &values, 4, 8).unwrap();
+        assert!(SegmentCq::<Bn254, FS>::verify(&vk, &statement, &proof).is_ok());
+
+        // the bounds are part of the statement
+        let mut narrower = statement.clone();
+        narrower.hi = 7;
+        assert!(SegmentCq::<Bn254, FS>::verify(&vk, &narrower, &proof).is_err());
+        let mut shifted = statement.clone();
+        shifted.lo = 5;
+        assert!(SegmentCq::<Bn254, FS>::verify(&vk, &shifted, &proof).is_err());
+
+        let res = SegmentCq::<Bn254, FS>::prove(&pk, &to_field(&[104, 108, 104, 105]), 4, 8);
+        let expected = Error::ValueOutOfRange(format!("{}", Fr::from(108u64)));
+        assert_eq!(res.err(), Some(expected));
+        let res = SegmentCq::<Bn254, FS>::prove(&pk, &values, 4, 17);
+        assert_eq!(res.err(), Some(Error::IndexOutOfTable(17, 16)));
+    }
+}
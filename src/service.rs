@@ -0,0 +1,353 @@
+//! Proving off the caller's thread, for servers on an async runtime that can't block for the seconds a proof takes.
+//!
+//! [`ProvingService`] owns a key and a fixed number of worker threads fed from a bounded queue. `try_submit`
+//! rejects jobs with `Error::QueueFull` once the queue is full and `submit` waits for a free slot, so a burst of
+//! requests can't pile up unbounded witnesses in memory. Every job resolves a [`ProofFuture`], which is an ordinary
+//! `Future` for any executor and can also be waited on from synchronous code.
+
+use std::{
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Receiver, SyncSender, TrySendError},
+        Arc, Condvar, Mutex,
+    },
+    task::{Context, Poll, Waker},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use ark_ec::PairingEngine;
+
+use crate::{
+    cq::CqProverKey,
+    data_structures::{Proof, Statement, Witness},
+    error::Error,
+    metrics::ProverMetrics,
+    prover::Prover,
+    rng::FiatShamirRng,
+};
+
+/// Measurements of one job
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct JobMetrics {
+    /// time between submission and a worker picking the job up
+    pub queued: Duration,
+    /// worker that proved the job, in `0..workers`
+    pub worker: usize,
+    pub prover: ProverMetrics,
+}
+
+pub struct ProvedJob<E: PairingEngine> {
+    pub statement: Statement<E>,
+    pub proof: Proof<E>,
+    pub metrics: JobMetrics,
+}
+
+type JobResult<E> = Result<ProvedJob<E>, Error>;
+
+struct Slot<E: PairingEngine> {
+    result: Option<JobResult<E>>,
+    waker: Option<Waker>,
+}
+
+struct Shared<E: PairingEngine> {
+    slot: Mutex<Slot<E>>,
+    done: Condvar,
+}
+
+/// Resolves with the statement, proof and metrics of a submitted job
+pub struct ProofFuture<E: PairingEngine> {
+    shared: Arc<Shared<E>>,
+}
+
+impl<E: PairingEngine> ProofFuture<E> {
+    /// Blocks the current thread until the job is done
+    pub fn wait(self) -> JobResult<E> {
+        let mut slot = self.shared.slot.lock().unwrap();
+        loop {
+            if let Some(result) = slot.result.take() {
+                return result;
+            }
+            slot = self.shared.done.wait(slot).unwrap();
+        }
+    }
+}
+
+impl<E: PairingEngine> Future for ProofFuture<E> {
+    type Output = JobResult<E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut slot = self.shared.slot.lock().unwrap();
+        match slot.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                slot.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Worker side of a `ProofFuture`, resolves it with `Error::ServiceStopped` if dropped without a result, e.g.
+/// when the prover panics
+struct Completer<E: PairingEngine> {
+    shared: Option<Arc<Shared<E>>>,
+}
+
+impl<E: PairingEngine> Completer<E> {
+    fn complete(mut self, result: JobResult<E>) {
+        if let Some(shared) = self.shared.take() {
+            Self::resolve(&shared, result);
+        }
+    }
+
+    fn resolve(shared: &Shared<E>, result: JobResult<E>) {
+        let mut slot = shared.slot.lock().unwrap();
+        slot.result = Some(result);
+        if let Some(waker) = slot.waker.take() {
+            waker.wake();
+        }
+        shared.done.notify_all();
+    }
+}
+
+impl<E: PairingEngine> Drop for Completer<E> {
+    fn drop(&mut self) {
+        if let Some(shared) = self.shared.take() {
+            Self::resolve(&shared, Err(Error::ServiceStopped));
+        }
+    }
+}
+
+struct Job<E: PairingEngine> {
+    witness_values: Vec<E::Fr>,
+    submitted: Instant,
+    completer: Completer<E>,
+}
+
+pub struct ProvingService<E: PairingEngine, FS> {
+    sender: Option<SyncSender<Job<E>>>,
+    workers: Vec<JoinHandle<()>>,
+    pending: Arc<AtomicUsize>,
+    capacity: usize,
+    _fs: PhantomData<fn() -> FS>,
+}
+
+impl<E: PairingEngine, FS: FiatShamirRng + 'static> ProvingService<E, FS> {
+    /// Starts `workers` threads proving against `key`, with room for `capacity` jobs waiting for a worker
+    pub fn new(key: Arc<CqProverKey<E>>, workers: usize, capacity: usize) -> Result<Self, Error> {
+        if workers == 0 {
+            return Err(Error::UnsupportedParallelism(0));
+        }
+        let (sender, receiver) = mpsc::sync_channel::<Job<E>>(capacity);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let pending = Arc::new(AtomicUsize::new(0));
+
+        let workers = (0..workers)
+            .map(|worker| {
+                let (key, receiver, pending) = (key.clone(), receiver.clone(), pending.clone());
+                thread::Builder::new()
+                    .name(format!("cqext-prover-{}", worker))
+                    .spawn(move || Self::work(worker, &key, &receiver, &pending))
+                    .map_err(|e| Error::Io(e.to_string()))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(Self {
+            sender: Some(sender),
+            workers,
+            pending,
+            capacity,
+            _fs: PhantomData,
+        })
+    }
+
+    fn work(
+        worker: usize,
+        key: &CqProverKey<E>,
+        receiver: &Mutex<Receiver<Job<E>>>,
+        pending: &AtomicUsize,
+    ) {
+        loop {
+            // the lock is released before proving, so idle workers keep taking jobs
+            let job = match receiver.lock().unwrap().recv() {
+                Ok(job) => job,
+                Err(_) => return,
+            };
+            pending.fetch_sub(1, Ordering::Relaxed);
+            let queued = job.submitted.elapsed();
+            let result =
+                Self::prove(key, &job.witness_values).map(|(statement, proof, prover)| ProvedJob {
+                    statement,
+                    proof,
+                    metrics: JobMetrics {
+                        queued,
+                        worker,
+                        prover,
+                    },
+                });
+            job.completer.complete(result);
+        }
+    }
+
+    fn prove(
+        key: &CqProverKey<E>,
+        witness_values: &Vec<E::Fr>,
+    ) -> Result<(Statement<E>, Proof<E>, ProverMetrics), Error> {
+        let witness = Witness::<E::Fr>::new(witness_values)?;
+        let statement = Statement::from_witness(&key.pk, &witness);
+        let (proof, metrics) = Prover::<E, FS>::prove_with_metrics(
+            &key.pk, &key.vk, &key.index, &key.table, &witness, &statement,
+        )?;
+        Ok((statement, proof, metrics))
+    }
+
+    fn job(witness_values: Vec<E::Fr>) -> (Job<E>, ProofFuture<E>) {
+        let shared = Arc::new(Shared {
+            slot: Mutex::new(Slot {
+                result: None,
+                waker: None,
+            }),
+            done: Condvar::new(),
+        });
+        let job = Job {
+            witness_values,
+            submitted: Instant::now(),
+            completer: Completer {
+                shared: Some(shared.clone()),
+            },
+        };
+        (job, ProofFuture { shared })
+    }
+
+    /// Queues a proof of `witness_values`, fails with `Error::QueueFull` instead of waiting for a free slot
+    pub fn try_submit(&self, witness_values: Vec<E::Fr>) -> Result<ProofFuture<E>, Error> {
+        let sender = self.sender.as_ref().ok_or(Error::ServiceStopped)?;
+        let (job, future) = Self::job(witness_values);
+        self.pending.fetch_add(1, Ordering::Relaxed);
+        sender.try_send(job).map_err(|e| {
+            self.pending.fetch_sub(1, Ordering::Relaxed);
+            match e {
+                TrySendError::Full(_) => Error::QueueFull(self.capacity),
+                TrySendError::Disconnected(_) => Error::ServiceStopped,
+            }
+        })?;
+        Ok(future)
+    }
+
+    /// Queues a proof of `witness_values`, blocks while the queue is full
+    pub fn submit(&self, witness_values: Vec<E::Fr>) -> Result<ProofFuture<E>, Error> {
+        let sender = self.sender.as_ref().ok_or(Error::ServiceStopped)?;
+        let (job, future) = Self::job(witness_values);
+        self.pending.fetch_add(1, Ordering::Relaxed);
+        sender.send(job).map_err(|_| {
+            self.pending.fetch_sub(1, Ordering::Relaxed);
+            Error::ServiceStopped
+        })?;
+        Ok(future)
+    }
+
+    /// Jobs submitted and not yet picked up by a worker
+    pub fn pending(&self) -> usize {
+        self.pending.load(Ordering::Relaxed)
+    }
+
+    pub fn workers(&self) -> usize {
+        self.workers.len()
+    }
+}
+
+impl<E: PairingEngine, FS> Drop for ProvingService<E, FS> {
+    /// Finishes the queued jobs and joins the workers
+    fn drop(&mut self) {
+        self.sender = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod service_tests {
+    use std::{
+        future::Future,
+        sync::Arc,
+        task::{Context, Poll, Wake},
+        thread::{self, Thread},
+    };
+
+    use ark_bn254::{Bn254, Fr};
+    use ark_std::test_rng;
+    use rand_chacha::ChaChaRng;
+    use sha3::Keccak256;
+
+    use crate::{
+        config::CqConfig, cq::Cq, error::Error, rng::SimpleHashFiatShamirRng, table::Table,
+        utils::to_field,
+    };
+
+    use super::ProvingService;
+
+    type FS = SimpleHashFiatShamirRng<Keccak256, ChaChaRng>;
+
+    struct Unpark(Thread);
+
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let waker = Arc::new(Unpark(thread::current())).into();
+        let mut cx = Context::from_waker(&waker);
+        let mut future = Box::pin(future);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_proving_service() {
+        let mut rng = test_rng();
+        let table = Table::<Fr>::new(&to_field(&[1, 5, 10, 15, 20, 25, 30, 35])).unwrap();
+        let config = CqConfig::<Bn254, FS>::builder()
+            .table_size(8)
+            .witness_size(4)
+            .build()
+            .unwrap();
+        let (pk, vk) = config.unsafe_setup(table, &mut rng).unwrap();
+        let pk = Arc::new(pk);
+
+        let res = ProvingService::<Bn254, FS>::new(pk.clone(), 0, 4);
+        assert_eq!(res.err(), Some(Error::UnsupportedParallelism(0)));
+        let service = ProvingService::<Bn254, FS>::new(pk, 2, 4).unwrap();
+        assert_eq!(service.workers(), 2);
+
+        let witnesses = [[5, 15, 20, 35], [1, 1, 10, 30], [35, 35, 35, 35]];
+        let futures: Vec<_> = witnesses
+            .iter()
+            .map(|w| service.submit(to_field(w)).unwrap())
+            .collect();
+        let failed = service.submit(to_field(&[2, 5, 10, 15])).unwrap();
+
+        for (i, future) in futures.into_iter().enumerate() {
+            let job = if i == 0 {
+                block_on(future).unwrap()
+            } else {
+                future.wait().unwrap()
+            };
+            assert!(job.metrics.worker < 2);
+            assert!(job.metrics.prover.msm_count > 0);
+            assert!(Cq::<Bn254, FS>::verify(&vk, &job.statement, &job.proof).is_ok());
+        }
+        assert!(matches!(failed.wait(), Err(Error::ValueNotInTable(_))));
+        assert_eq!(service.pending(), 0);
+    }
+}
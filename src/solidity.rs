@@ -0,0 +1,617 @@
+//! Solidity verifier for BN254 proofs.
+//!
+//! ChaCha is too expensive to run in the EVM, so proofs checked on chain have to be produced with
+//! [`EvmFiatShamirRng`], which draws challenges from Keccak256 in counter mode. The generated contract
+//! replays exactly that transcript and checks the batched pairing with the `ecAdd`, `ecMul`,
+//! `modexp` and `ecPairing` precompiles.
+//!
+//! The contract exposes `verifyProof(uint256[2] statement, uint256[19] proof)`, the statement is the
+//! commitment `(x, y)` and the proof words are, in order, `m_cm`, `a_cm`, `qa_cm`, `b0_cm`, `qb_cm`,
+//! `p_cm`, `b0_at_gamma`, `f_at_gamma`, `a_at_zero`, `pi_gamma` and `a0_cm`, every point as `(x, y)`
+//! with `(0, 0)` for infinity.
+
+use ark_bn254::{Bn254, Fq, Fr, G1Affine, G2Affine};
+use ark_ec::AffineCurve;
+use ark_ff::{to_bytes, BigInteger, Field, FpParameters, PrimeField};
+use sha3::{Digest, Keccak256};
+
+use crate::{
+    cq::CqVerifierKey,
+    data_structures::{Proof, Statement},
+    rng::{KeccakCounterRng, SimpleHashFiatShamirRng, CHALLENGE_DST},
+    transcript::round_separator,
+    PROTOCOL_NAME, PROTOCOL_VERSION,
+};
+
+/// Fiat-Shamir rng matched by the generated contract
+pub type EvmFiatShamirRng = SimpleHashFiatShamirRng<Keccak256, KeccakCounterRng>;
+
+fn uint<F: PrimeField>(x: F) -> String {
+    format!("0x{}", hex::encode(x.into_repr().to_bytes_be()))
+}
+
+pub(crate) fn g1_words(p: &G1Affine) -> [Fq; 2] {
+    if p.infinity {
+        [Fq::from(0u64); 2]
+    } else {
+        [p.x, p.y]
+    }
+}
+
+/// Precompile order, imaginary part first
+pub(crate) fn g2_words(p: &G2Affine) -> [Fq; 4] {
+    if p.infinity {
+        [Fq::from(0u64); 4]
+    } else {
+        [p.x.c1, p.x.c0, p.y.c1, p.y.c0]
+    }
+}
+
+fn g2_constant(name: &str, p: &G2Affine) -> String {
+    let words = g2_words(p);
+    format!(
+        "    uint256 constant {name}_X1 = {};\n    uint256 constant {name}_X0 = {};\n    uint256 constant {name}_Y1 = {};\n    uint256 constant {name}_Y0 = {};\n",
+        uint(words[0]),
+        uint(words[1]),
+        uint(words[2]),
+        uint(words[3]),
+        name = name,
+    )
+}
+
+/// Emits a contract that verifies proofs for the table behind `key`, which must not be a zk key or one for
+/// witnesses larger than the table
+pub fn solidity_verifier(key: &CqVerifierKey<Bn254>) -> String {
+    assert!(
+        !key.vk.is_zk(),
+        "the contract only checks proofs of the plain variant"
+    );
+    assert!(
+        key.vk.x_pow_a_bound_2.is_none(),
+        "the contract doesn't check the degree bound of witnesses larger than the table"
+    );
+    let vk = &key.vk;
+    let common = &key.common;
+
+    let two_256 = Fr::from(2u64).pow([256u64]);
+    let b0_factor =
+        Fr::from(vk.table_size as u64) * Fr::from(vk.witness_size as u64).inverse().unwrap();
+    let initial_seed = Keccak256::digest(PROTOCOL_NAME);
+
+    let mut g2_constants = String::new();
+    g2_constants += &g2_constant("G2", &G2Affine::prime_subgroup_generator());
+    g2_constants += &g2_constant("X", &vk.x_2);
+    g2_constants += &g2_constant("X_POW_B0_BOUND", &vk.x_pow_b0_bound_2);
+    g2_constants += &g2_constant("ZV", &common.zv_2);
+    g2_constants += &g2_constant("T", &common.t_2);
+
+    TEMPLATE
+        .replace(
+            "$R_MODULUS",
+            &format!(
+                "0x{}",
+                hex::encode(<Fr as PrimeField>::Params::MODULUS.to_bytes_be())
+            ),
+        )
+        .replace(
+            "$Q_MODULUS",
+            &format!(
+                "0x{}",
+                hex::encode(<Fq as PrimeField>::Params::MODULUS.to_bytes_be())
+            ),
+        )
+        .replace("$TWO_256", &uint(two_256))
+        .replace(
+            "$CHALLENGE_DST",
+            core::str::from_utf8(CHALLENGE_DST).expect("the tag is ascii"),
+        )
+        .replace("$TABLE_SIZE", &vk.table_size.to_string())
+        .replace("$WITNESS_SIZE", &vk.witness_size.to_string())
+        .replace("$B0_FACTOR", &uint(b0_factor))
+        .replace("$INITIAL_SEED", &format!("0x{}", hex::encode(initial_seed)))
+        .replace(
+            "$COMMON",
+            &hex::encode(
+                [
+                    round_separator(PROTOCOL_VERSION, b"public-input"),
+                    to_bytes![vk, common].expect("writing to a vec can't fail"),
+                ]
+                .concat(),
+            ),
+        )
+        .replace(
+            "$ROUND_1",
+            &hex::encode(round_separator(PROTOCOL_VERSION, b"round-1")),
+        )
+        .replace(
+            "$ROUND_2",
+            &hex::encode(round_separator(PROTOCOL_VERSION, b"round-2")),
+        )
+        .replace(
+            "$ROUND_3",
+            &hex::encode(round_separator(PROTOCOL_VERSION, b"round-3")),
+        )
+        .replace("$PROOF_WORDS", &PROOF_WORDS.to_string())
+        .replace("$G2_CONSTANTS", g2_constants.trim_end())
+}
+
+/// Number of words of a proof in `verifyProof` calldata
+pub const PROOF_WORDS: usize = 19;
+
+/// A big endian uint256 as the EVM reads it
+pub type EvmWord = [u8; 32];
+
+fn word<F: PrimeField>(x: F) -> EvmWord {
+    let mut word = [0u8; 32];
+    word.copy_from_slice(&x.into_repr().to_bytes_be());
+    word
+}
+
+fn g1_evm_words(p: &G1Affine) -> [EvmWord; 2] {
+    g1_words(p).map(word)
+}
+
+impl Statement<Bn254> {
+    /// The commitment as `(x, y)`, `(0, 0)` for infinity
+    pub fn to_evm_words(&self) -> [EvmWord; 2] {
+        g1_evm_words(&self.f)
+    }
+}
+
+impl Proof<Bn254> {
+    /// Proof words in the order `verifyProof` reads them, see the module docs
+    pub fn to_evm_words(&self) -> [EvmWord; PROOF_WORDS] {
+        let first = &self.first_msg;
+        let second = &self.second_msg;
+        let third = &self.third_msg;
+
+        let mut words = Vec::with_capacity(PROOF_WORDS);
+        for p in [
+            first.m_cm,
+            second.a_cm,
+            second.qa_cm,
+            second.b0_cm,
+            second.qb_cm,
+            second.p_cm,
+        ] {
+            words.extend(g1_evm_words(&p));
+        }
+        words.extend([third.b0_at_gamma, third.f_at_gamma, third.a_at_zero].map(word));
+        words.extend(g1_evm_words(&third.pi_gamma));
+        words.extend(g1_evm_words(&third.a0_cm));
+
+        words.try_into().unwrap()
+    }
+
+    /// ABI encoding of the proof as a `uint256[19]` argument
+    pub fn to_evm_calldata(&self) -> Vec<u8> {
+        self.to_evm_words().concat()
+    }
+}
+
+/// Complete calldata of a `verifyProof(statement, proof)` call, selector included
+pub fn verify_proof_calldata(statement: &Statement<Bn254>, proof: &Proof<Bn254>) -> Vec<u8> {
+    let selector = Keccak256::digest(b"verifyProof(uint256[2],uint256[19])");
+    let mut calldata = selector[..4].to_vec();
+    calldata.extend(statement.to_evm_words().concat());
+    calldata.extend(proof.to_evm_calldata());
+    calldata
+}
+
+const TEMPLATE: &str = r#"// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.0;
+
+/// cq lookup verifier for a table of size $TABLE_SIZE and witnesses of size $WITNESS_SIZE, generated by cqext
+contract CqVerifier {
+    uint256 constant R = $R_MODULUS;
+    uint256 constant Q = $Q_MODULUS;
+
+    /// 2^256 mod R, for reducing the 48 byte outputs of hash_to_field
+    uint256 constant TWO_256 = $TWO_256;
+    bytes constant CHALLENGE_DST = "$CHALLENGE_DST";
+
+    uint256 constant WITNESS_SIZE = $WITNESS_SIZE;
+    /// table_size / witness_size
+    uint256 constant B0_FACTOR = $B0_FACTOR;
+
+    /// keccak256 of the protocol name
+    bytes32 constant INITIAL_SEED = $INITIAL_SEED;
+    /// transcript encoding of the verifier key and the preprocessed table commitments, after the public input separator
+    bytes constant COMMON = hex"$COMMON";
+    /// round separators, `version || label length || label`
+    bytes constant ROUND_1 = hex"$ROUND_1";
+    bytes constant ROUND_2 = hex"$ROUND_2";
+    bytes constant ROUND_3 = hex"$ROUND_3";
+
+    uint256 constant G1_X = 1;
+    uint256 constant G1_Y = 2;
+
+$G2_CONSTANTS
+
+    struct Challenges {
+        uint256 beta;
+        uint256 gamma;
+        uint256 eta;
+        uint256 u;
+    }
+
+    function verifyProof(uint256[2] calldata statement, uint256[$PROOF_WORDS] calldata proof) external view returns (bool) {
+        require(statement[0] < Q && statement[1] < Q, "statement not in field");
+        for (uint256 i = 0; i < $PROOF_WORDS; i++) {
+            require(proof[i] < (i >= 12 && i < 15 ? R : Q), "proof not in field");
+        }
+
+        Challenges memory ch = deriveChallenges(statement, proof);
+        uint256[2][5] memory points = pairingPoints(statement, proof, ch);
+
+        uint256[30] memory input;
+        uint256[4][5] memory g2s = [
+            [G2_X1, G2_X0, G2_Y1, G2_Y0],
+            [X_X1, X_X0, X_Y1, X_Y0],
+            [X_POW_B0_BOUND_X1, X_POW_B0_BOUND_X0, X_POW_B0_BOUND_Y1, X_POW_B0_BOUND_Y0],
+            [ZV_X1, ZV_X0, ZV_Y1, ZV_Y0],
+            [T_X1, T_X0, T_Y1, T_Y0]
+        ];
+        for (uint256 i = 0; i < 5; i++) {
+            input[6 * i] = points[i][0];
+            input[6 * i + 1] = points[i][1];
+            for (uint256 j = 0; j < 4; j++) {
+                input[6 * i + 2 + j] = g2s[i][j];
+            }
+        }
+
+        uint256[1] memory out;
+        bool ok;
+        assembly {
+            ok := staticcall(gas(), 0x08, input, 0x3c0, out, 0x20)
+        }
+        return ok && out[0] == 1;
+    }
+
+    /// replays `SimpleHashFiatShamirRng<Keccak256, KeccakCounterRng>` over the arkworks `ToBytes` encodings,
+    /// the challenges after each absorb are hashToField(seed, 0), hashToField(seed, 1), ...
+    function deriveChallenges(uint256[2] calldata statement, uint256[$PROOF_WORDS] calldata proof)
+        internal
+        pure
+        returns (Challenges memory ch)
+    {
+        bytes32 seed = absorb(
+            INITIAL_SEED,
+            abi.encodePacked(COMMON, g1Bytes(statement[0], statement[1]), ROUND_1, g1Bytes(proof[0], proof[1]))
+        );
+        ch.beta = hashToField(seed, 0);
+
+        seed = absorb(
+            seed,
+            abi.encodePacked(
+                ROUND_2,
+                g1Bytes(proof[2], proof[3]),
+                g1Bytes(proof[4], proof[5]),
+                g1Bytes(proof[6], proof[7]),
+                g1Bytes(proof[8], proof[9]),
+                g1Bytes(proof[10], proof[11])
+            )
+        );
+        ch.gamma = hashToField(seed, 0);
+        ch.eta = hashToField(seed, 1);
+
+        seed = absorb(
+            seed,
+            abi.encodePacked(
+                ROUND_3,
+                reverseBytes(proof[12]),
+                reverseBytes(proof[13]),
+                reverseBytes(proof[14]),
+                g1Bytes(proof[15], proof[16]),
+                g1Bytes(proof[17], proof[18])
+            )
+        );
+        ch.u = hashToField(seed, 0);
+    }
+
+    /// g1 points of the batched pairing, paired with [1]_2, [x]_2, [x^b0_bound]_2, zv_2 and t_2
+    function pairingPoints(uint256[2] calldata statement, uint256[$PROOF_WORDS] calldata proof, Challenges memory ch)
+        internal
+        view
+        returns (uint256[2][5] memory points)
+    {
+        uint256 v = batchedEvaluation(proof, ch);
+        uint256 u2 = mulmod(ch.u, ch.u, R);
+        uint256 u3 = mulmod(u2, ch.u, R);
+
+        // c = b0_cm + eta * f + eta^2 * qb_cm
+        uint256[2] memory c = ecAdd(
+            ecAdd(point(proof, 6), ecMul([statement[0], statement[1]], ch.eta)),
+            ecMul(point(proof, 8), mulmod(ch.eta, ch.eta, R))
+        );
+        // l = gamma * pi_gamma + c - v * [1]_1
+        uint256[2] memory l = ecAdd(ecAdd(ecMul(point(proof, 15), ch.gamma), c), ecMul([G1_X, G1_Y], R - v));
+        // a_pt = a_cm - a_at_zero * [1]_1
+        uint256[2] memory aPt = ecAdd(point(proof, 2), ecMul([G1_X, G1_Y], R - proof[14]));
+
+        // -m_cm - u * p_cm + u^2 * l + u^3 * a_pt + beta * a_cm, the beta term moves [beta]_2 off t_2
+        points[0] = ecAdd(
+            ecAdd(ecAdd(neg(point(proof, 0)), ecMul(point(proof, 10), R - ch.u)), ecMul(l, u2)),
+            ecAdd(ecMul(aPt, u3), ecMul(point(proof, 2), ch.beta))
+        );
+        // -(u^2 * pi_gamma + u^3 * a0_cm)
+        points[1] = neg(ecAdd(ecMul(point(proof, 15), u2), ecMul(point(proof, 17), u3)));
+        points[2] = ecMul(point(proof, 6), ch.u);
+        points[3] = neg(point(proof, 4));
+        points[4] = point(proof, 2);
+    }
+
+    /// b0(gamma) + eta * f(gamma) + eta^2 * qb(gamma)
+    function batchedEvaluation(uint256[$PROOF_WORDS] calldata proof, Challenges memory ch) internal view returns (uint256) {
+        uint256 b0AtGamma = proof[12];
+        uint256 fAtGamma = proof[13];
+
+        uint256 b0 = mulmod(B0_FACTOR, proof[14], R);
+        uint256 bAtGamma = addmod(mulmod(b0AtGamma, ch.gamma, R), b0, R);
+        uint256 zhAtGamma = addmod(expmod(ch.gamma, WITNESS_SIZE), R - 1, R);
+        require(zhAtGamma != 0, "gamma in witness domain");
+        uint256 qbAtGamma = mulmod(
+            addmod(mulmod(bAtGamma, addmod(fAtGamma, ch.beta, R), R), R - 1, R),
+            expmod(zhAtGamma, R - 2),
+            R
+        );
+
+        return addmod(
+            addmod(b0AtGamma, mulmod(ch.eta, fAtGamma, R), R),
+            mulmod(mulmod(ch.eta, ch.eta, R), qbAtGamma, R),
+            R
+        );
+    }
+
+    function absorb(bytes32 seed, bytes memory data) internal pure returns (bytes32) {
+        return keccak256(abi.encodePacked(data, seed));
+    }
+
+    /// RFC 9380 hash_to_field of seed || counter with expand_message_xmd over keccak256, whose block is 136 bytes
+    function hashToField(bytes32 seed, uint64 counter) internal pure returns (uint256) {
+        bytes memory dstPrime = abi.encodePacked(CHALLENGE_DST, uint8(CHALLENGE_DST.length));
+        bytes32 b0 = keccak256(abi.encodePacked(new bytes(136), seed, counter, uint16(48), uint8(0), dstPrime));
+        bytes32 b1 = keccak256(abi.encodePacked(b0, uint8(1), dstPrime));
+        bytes32 b2 = keccak256(abi.encodePacked(b0 ^ b1, uint8(2), dstPrime));
+        // b1 || b2[..16] as a big endian integer hi * 2^256 + lo
+        uint256 hi = uint256(b1) >> 128;
+        uint256 lo = (uint256(b1) << 128) | (uint256(b2) >> 128);
+        return addmod(mulmod(hi, TWO_256, R), lo % R, R);
+    }
+
+    /// arkworks `ToBytes` of an affine point: little endian x and y, then the infinity flag
+    function g1Bytes(uint256 x, uint256 y) internal pure returns (bytes memory) {
+        if (x == 0 && y == 0) {
+            return abi.encodePacked(uint256(0), reverseBytes(1), uint8(1));
+        }
+        return abi.encodePacked(reverseBytes(x), reverseBytes(y), uint8(0));
+    }
+
+    function reverseBytes(uint256 v) internal pure returns (uint256) {
+        v = ((v & 0xFF00FF00FF00FF00FF00FF00FF00FF00FF00FF00FF00FF00FF00FF00FF00FF00) >> 8)
+            | ((v & 0x00FF00FF00FF00FF00FF00FF00FF00FF00FF00FF00FF00FF00FF00FF00FF00FF) << 8);
+        v = ((v & 0xFFFF0000FFFF0000FFFF0000FFFF0000FFFF0000FFFF0000FFFF0000FFFF0000) >> 16)
+            | ((v & 0x0000FFFF0000FFFF0000FFFF0000FFFF0000FFFF0000FFFF0000FFFF0000FFFF) << 16);
+        v = ((v & 0xFFFFFFFF00000000FFFFFFFF00000000FFFFFFFF00000000FFFFFFFF00000000) >> 32)
+            | ((v & 0x00000000FFFFFFFF00000000FFFFFFFF00000000FFFFFFFF00000000FFFFFFFF) << 32);
+        v = ((v & 0xFFFFFFFFFFFFFFFF0000000000000000FFFFFFFFFFFFFFFF0000000000000000) >> 64)
+            | ((v & 0x0000000000000000FFFFFFFFFFFFFFFF0000000000000000FFFFFFFFFFFFFFFF) << 64);
+        return (v >> 128) | (v << 128);
+    }
+
+    function point(uint256[$PROOF_WORDS] calldata proof, uint256 i) internal pure returns (uint256[2] memory) {
+        return [proof[i], proof[i + 1]];
+    }
+
+    function neg(uint256[2] memory p) internal pure returns (uint256[2] memory) {
+        if (p[0] == 0 && p[1] == 0) {
+            return p;
+        }
+        return [p[0], Q - p[1]];
+    }
+
+    function ecAdd(uint256[2] memory a, uint256[2] memory b) internal view returns (uint256[2] memory r) {
+        uint256[4] memory input = [a[0], a[1], b[0], b[1]];
+        bool ok;
+        assembly {
+            ok := staticcall(gas(), 0x06, input, 0x80, r, 0x40)
+        }
+        require(ok, "ecAdd failed");
+    }
+
+    function ecMul(uint256[2] memory p, uint256 s) internal view returns (uint256[2] memory r) {
+        uint256[3] memory input = [p[0], p[1], s];
+        bool ok;
+        assembly {
+            ok := staticcall(gas(), 0x07, input, 0x60, r, 0x40)
+        }
+        require(ok, "ecMul failed");
+    }
+
+    function expmod(uint256 base, uint256 exponent) internal view returns (uint256) {
+        uint256[6] memory input = [uint256(32), 32, 32, base, exponent, R];
+        uint256[1] memory out;
+        bool ok;
+        assembly {
+            ok := staticcall(gas(), 0x05, input, 0xc0, out, 0x20)
+        }
+        require(ok, "modexp failed");
+        return out[0];
+    }
+}
+"#;
+
+#[cfg(test)]
+mod solidity_tests {
+    use ark_bn254::{Bn254, Fq, Fr, G1Affine};
+    use ark_ff::{to_bytes, BigInteger, Field, One, PrimeField, Zero};
+    use ark_std::test_rng;
+    use sha3::{Digest, Keccak256};
+
+    use crate::{
+        cq::{Cq, CqVerifierKey},
+        data_structures::{Proof, Statement},
+        rng::CHALLENGE_DST,
+        transcript::round_separator,
+        utils::to_field,
+        verifier::Verifier,
+        PROTOCOL_NAME, PROTOCOL_VERSION,
+    };
+
+    use super::{
+        g1_words, g2_words, solidity_verifier, verify_proof_calldata, EvmFiatShamirRng, PROOF_WORDS,
+    };
+
+    type FS = EvmFiatShamirRng;
+
+    /// the transcript encoding the contract computes from the EVM words
+    fn g1_bytes(p: &G1Affine) -> Vec<u8> {
+        let [x, y] = g1_words(p);
+        let (y, infinity) = if x.is_zero() && y.is_zero() {
+            (Fq::one(), 1u8)
+        } else {
+            (y, 0u8)
+        };
+        [
+            x.into_repr().to_bytes_le(),
+            y.into_repr().to_bytes_le(),
+            vec![infinity],
+        ]
+        .concat()
+    }
+
+    fn absorb(seed: [u8; 32], data: &[u8]) -> [u8; 32] {
+        Keccak256::digest([data, &seed].concat()).into()
+    }
+
+    /// the contract's hashToField
+    fn squeeze(seed: [u8; 32], counter: &mut u64) -> Fr {
+        let dst_prime = [CHALLENGE_DST, &[CHALLENGE_DST.len() as u8]].concat();
+        let b0 = Keccak256::digest(
+            [
+                &[0u8; 136][..],
+                &seed,
+                &counter.to_be_bytes(),
+                &[0, 48, 0],
+                &dst_prime,
+            ]
+            .concat(),
+        );
+        let b1 = Keccak256::digest([&b0[..], &[1], &dst_prime].concat());
+        let xored: Vec<u8> = b0.iter().zip(&b1).map(|(a, b)| a ^ b).collect();
+        let b2 = Keccak256::digest([&xored[..], &[2], &dst_prime].concat());
+        *counter += 1;
+
+        let hi = Fr::from_be_bytes_mod_order(&b1[..16]);
+        let lo = Fr::from_be_bytes_mod_order(&[&b1[16..], &b2[..16]].concat());
+        hi * Fr::from(2u64).pow([256u64]) + lo
+    }
+
+    fn setup() -> (CqVerifierKey<Bn254>, Statement<Bn254>, Proof<Bn254>) {
+        let mut rng = test_rng();
+        let table_values = to_field::<Fr>(&[1, 5, 10, 15, 20, 25, 30, 35]);
+        let (prover_key, verifier_key) =
+            Cq::<Bn254, FS>::setup(&table_values, 4, &mut rng).unwrap();
+        let (statement, proof) =
+            Cq::<Bn254, FS>::prove(&prover_key, &to_field(&[5, 15, 20, 35])).unwrap();
+        (verifier_key, statement, proof)
+    }
+
+    #[test]
+    fn test_contract_transcript_matches() {
+        let (key, statement, proof) = setup();
+        assert!(Cq::<Bn254, FS>::verify(&key, &statement, &proof).is_ok());
+
+        let expected =
+            Verifier::<Bn254, FS>::derive_challenges(&key.vk, &key.common, &statement, &proof);
+
+        let mut seed: [u8; 32] = Keccak256::digest(PROTOCOL_NAME).into();
+        seed = absorb(
+            seed,
+            &[
+                round_separator(PROTOCOL_VERSION, b"public-input"),
+                to_bytes![key.vk, key.common].unwrap(),
+                g1_bytes(&statement.f),
+                round_separator(PROTOCOL_VERSION, b"round-1"),
+                g1_bytes(&proof.first_msg.m_cm),
+            ]
+            .concat(),
+        );
+        let beta = squeeze(seed, &mut 0);
+
+        let second = &proof.second_msg;
+        let bytes: Vec<u8> = round_separator(PROTOCOL_VERSION, b"round-2")
+            .into_iter()
+            .chain(
+                [
+                    second.a_cm,
+                    second.qa_cm,
+                    second.b0_cm,
+                    second.qb_cm,
+                    second.p_cm,
+                ]
+                .iter()
+                .flat_map(g1_bytes),
+            )
+            .collect();
+        seed = absorb(seed, &bytes);
+        let mut counter = 0;
+        let gamma = squeeze(seed, &mut counter);
+        let eta = squeeze(seed, &mut counter);
+
+        let third = &proof.third_msg;
+        let bytes = [
+            round_separator(PROTOCOL_VERSION, b"round-3"),
+            third.b0_at_gamma.into_repr().to_bytes_le(),
+            third.f_at_gamma.into_repr().to_bytes_le(),
+            third.a_at_zero.into_repr().to_bytes_le(),
+            g1_bytes(&third.pi_gamma),
+            g1_bytes(&third.a0_cm),
+        ]
+        .concat();
+        seed = absorb(seed, &bytes);
+        let u = squeeze(seed, &mut 0);
+
+        assert_eq!(expected.beta, beta);
+        assert_eq!(expected.gamma, gamma);
+        assert_eq!(expected.eta, eta);
+        assert_eq!(expected.u, u);
+    }
+
+    #[test]
+    fn test_generated_contract() {
+        let (key, _, _) = setup();
+        let source = solidity_verifier(&key);
+
+        assert!(!source.contains('$'));
+        assert!(source.contains("contract CqVerifier"));
+        let x_c1 = format!(
+            "0x{}",
+            hex::encode(g2_words(&key.vk.x_2)[0].into_repr().to_bytes_be())
+        );
+        assert!(source.contains(&format!("uint256 constant X_X1 = {};", x_c1)));
+        assert!(source.contains(&hex::encode(to_bytes![key.common].unwrap())));
+    }
+
+    #[test]
+    fn test_evm_calldata() {
+        let (_, statement, proof) = setup();
+
+        let words = proof.to_evm_words();
+        let m_cm = proof.first_msg.m_cm;
+        assert_eq!(words[0].to_vec(), m_cm.x.into_repr().to_bytes_be());
+        assert_eq!(words[1].to_vec(), m_cm.y.into_repr().to_bytes_be());
+        assert_eq!(
+            words[14].to_vec(),
+            proof.third_msg.a_at_zero.into_repr().to_bytes_be()
+        );
+
+        let calldata = verify_proof_calldata(&statement, &proof);
+        assert_eq!(calldata.len(), 4 + 32 * (2 + PROOF_WORDS));
+        assert_eq!(calldata[4..36], statement.to_evm_words()[0]);
+        assert_eq!(calldata[4 + 64..], proof.to_evm_calldata()[..]);
+
+        let infinity = Statement::<Bn254>::new(G1Affine::zero());
+        assert_eq!(infinity.to_evm_words(), [[0u8; 32]; 2]);
+    }
+}
@@ -0,0 +1,1091 @@
+//! Loaders for srs material produced by trusted setup ceremonies.
+//!
+//! Deployments index their tables against an [`Srs`], which can only be built from a ceremony output or from
+//! externally produced powers that pass the same checks, so no machine that ran the setup code knew tau. The
+//! sampling `utils::unsafe_setup_from_rng` is only built for tests and with the `test-utils` feature.
+
+use std::{
+    cmp::max,
+    fs::{self, File},
+    io::{BufReader, Read, Seek, SeekFrom},
+    iter,
+    path::Path,
+};
+
+use ark_bls12_381::Bls12_381;
+use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G2Affine};
+use ark_ec::{msm::VariableBaseMSM, AffineCurve, PairingEngine};
+use ark_ff::{
+    BigInteger256, BigInteger384, FftField, FftParameters, Field, FpParameters, One, PrimeField,
+    Zero,
+};
+use ark_serialize::CanonicalSerialize;
+use ark_std::{
+    rand::{Rng, SeedableRng},
+    UniformRand,
+};
+use rand_chacha::ChaChaRng;
+use sha3::{Digest, Keccak256};
+
+use crate::{error::Error, utils::is_valid_point};
+
+const PTAU_MAGIC: &[u8; 4] = b"ptau";
+const PTAU_HEADER: u32 = 1;
+const PTAU_TAU_G1: u32 = 2;
+const PTAU_TAU_G2: u32 = 3;
+const FQ_BYTES: u64 = 32;
+
+fn io_err(e: std::io::Error) -> Error {
+    Error::Io(e.to_string())
+}
+
+fn read_u32<R: Read>(r: &mut R) -> Result<u32, Error> {
+    let mut buf = [0u8; 4];
+    r.read_exact(&mut buf).map_err(io_err)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64<R: Read>(r: &mut R) -> Result<u64, Error> {
+    let mut buf = [0u8; 8];
+    r.read_exact(&mut buf).map_err(io_err)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_limbs<R: Read>(r: &mut R) -> Result<BigInteger256, Error> {
+    let mut limbs = [0u64; 4];
+    for limb in limbs.iter_mut() {
+        *limb = read_u64(r)?;
+    }
+    Ok(BigInteger256::new(limbs))
+}
+
+/// ptau files store base field elements little endian in Montgomery form
+fn read_fq_montgomery<R: Read>(r: &mut R, r_inv: Fq) -> Result<Fq, Error> {
+    let repr = read_limbs(r)?;
+    let x = Fq::from_repr(repr)
+        .ok_or_else(|| Error::InvalidSrsFile("field element is not reduced".to_string()))?;
+    Ok(x * r_inv)
+}
+
+/// Inverse of the Montgomery constant 2^256 mod q
+fn montgomery_r_inv() -> Fq {
+    Fq::from(2u64).pow([256u64]).inverse().unwrap()
+}
+
+fn g1_from_coords(x: Fq, y: Fq, i: usize) -> Result<G1Affine, Error> {
+    let p = if x.is_zero() && y.is_zero() {
+        G1Affine::zero()
+    } else {
+        G1Affine::new(x, y, false)
+    };
+    if !p.is_on_curve() || !p.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(Error::InvalidSrsPoint("g1", i));
+    }
+    Ok(p)
+}
+
+/// Coordinates in `[x.c0, x.c1, y.c0, y.c1]` order
+fn g2_from_coords(c: [Fq; 4], i: usize) -> Result<G2Affine, Error> {
+    let x = Fq2::new(c[0], c[1]);
+    let y = Fq2::new(c[2], c[3]);
+    let p = if x.is_zero() && y.is_zero() {
+        G2Affine::zero()
+    } else {
+        G2Affine::new(x, y, false)
+    };
+    if !p.is_on_curve() || !p.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(Error::InvalidSrsPoint("g2", i));
+    }
+    Ok(p)
+}
+
+fn read_g1<R: Read>(r: &mut R, r_inv: Fq, i: usize) -> Result<G1Affine, Error> {
+    let x = read_fq_montgomery(r, r_inv)?;
+    let y = read_fq_montgomery(r, r_inv)?;
+    g1_from_coords(x, y, i)
+}
+
+fn read_g2<R: Read>(r: &mut R, r_inv: Fq, i: usize) -> Result<G2Affine, Error> {
+    let mut c = [Fq::zero(); 4];
+    for c in c.iter_mut() {
+        *c = read_fq_montgomery(r, r_inv)?;
+    }
+    g2_from_coords(c, i)
+}
+
+/// Rng seeded with a hash of all points, for challenges a malformed srs can't be tuned to pass
+fn points_rng<E: PairingEngine>(srs_g1: &[E::G1Affine], srs_g2: &[E::G2Affine]) -> ChaChaRng {
+    let mut bytes = vec![];
+    for p in srs_g1 {
+        p.serialize(&mut bytes).unwrap();
+    }
+    for p in srs_g2 {
+        p.serialize(&mut bytes).unwrap();
+    }
+    let mut seed = [0u8; 32];
+    seed.copy_from_slice(&Keccak256::digest(&bytes));
+    ChaChaRng::from_seed(seed)
+}
+
+/// Checks that both vectors start at the standard generators and are powers of the same tau.
+///
+/// Consecutive powers are compared with a random linear combination, the coefficients are derived
+/// from a hash of the points so a malformed srs can't be tuned to pass.
+pub(crate) fn check_powers<E: PairingEngine>(
+    srs_g1: &[E::G1Affine],
+    srs_g2: &[E::G2Affine],
+) -> Result<(), Error> {
+    if srs_g1[0] != E::G1Affine::prime_subgroup_generator()
+        || srs_g2[0] != E::G2Affine::prime_subgroup_generator()
+    {
+        return Err(Error::SrsMismatch);
+    }
+    if srs_g1.len() < 2 || srs_g2.len() < 2 {
+        return Ok(());
+    }
+
+    let r = E::Fr::rand(&mut points_rng::<E>(srs_g1, srs_g2));
+
+    let d = max(srs_g1.len(), srs_g2.len()) - 1;
+    let coeffs: Vec<_> = iter::successors(Some(E::Fr::one()), |c| Some(*c * r))
+        .take(d)
+        .map(|c| c.into_repr())
+        .collect();
+
+    // sum_i r^i [x^(i+1)]_1 paired with [1]_2 equals sum_i r^i [x^i]_1 paired with [x]_2
+    let d1 = srs_g1.len() - 1;
+    let lhs = VariableBaseMSM::multi_scalar_mul(&srs_g1[1..], &coeffs[..d1]);
+    let rhs = VariableBaseMSM::multi_scalar_mul(&srs_g1[..d1], &coeffs[..d1]);
+    if E::pairing(lhs, srs_g2[0]) != E::pairing(rhs, srs_g2[1]) {
+        return Err(Error::SrsMismatch);
+    }
+
+    let d2 = srs_g2.len() - 1;
+    let lhs = VariableBaseMSM::multi_scalar_mul(&srs_g2[1..], &coeffs[..d2]);
+    let rhs = VariableBaseMSM::multi_scalar_mul(&srs_g2[..d2], &coeffs[..d2]);
+    if E::pairing(srs_g1[0], lhs) != E::pairing(srs_g1[1], rhs) {
+        return Err(Error::SrsMismatch);
+    }
+
+    Ok(())
+}
+
+/// Loads `[x^0..=x^max_power_g1]_1` and `[x^0..=x^max_power_g2]_2` from a snarkjs Powers of Tau file
+pub fn from_ptau<P: AsRef<Path>>(
+    path: P,
+    max_power_g1: usize,
+    max_power_g2: usize,
+) -> Result<(Vec<G1Affine>, Vec<G2Affine>), Error> {
+    let file = File::open(path).map_err(io_err)?;
+    from_ptau_reader(BufReader::new(file), max_power_g1, max_power_g2)
+}
+
+/// Sections of a ptau file, `id -> (offset, size)`, and its number of G1 and G2 powers
+struct PtauHeader {
+    sections: Vec<Option<(u64, u64)>>,
+    num_g1: usize,
+    num_g2: usize,
+}
+
+impl PtauHeader {
+    fn section(&self, id: u32) -> Result<(u64, u64), Error> {
+        ptau_section(&self.sections, id)
+    }
+}
+
+fn ptau_section(sections: &[Option<(u64, u64)>], id: u32) -> Result<(u64, u64), Error> {
+    sections[id as usize].ok_or_else(|| Error::InvalidSrsFile(format!("missing section {}", id)))
+}
+
+fn read_ptau_header<R: Read + Seek>(reader: &mut R) -> Result<PtauHeader, Error> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic).map_err(io_err)?;
+    if &magic != PTAU_MAGIC {
+        return Err(Error::InvalidSrsFile("not a ptau file".to_string()));
+    }
+    let _version = read_u32(reader)?;
+    let num_sections = read_u32(reader)?;
+
+    // section id -> (offset, size)
+    let mut sections = vec![None; 16];
+    for _ in 0..num_sections {
+        let id = read_u32(reader)? as usize;
+        let size = read_u64(reader)?;
+        let offset = reader.stream_position().map_err(io_err)?;
+        if id < sections.len() {
+            sections[id] = Some((offset, size));
+        }
+        reader
+            .seek(SeekFrom::Current(size as i64))
+            .map_err(io_err)?;
+    }
+
+    // header: n8, q, power
+    let (header_offset, _) = ptau_section(&sections, PTAU_HEADER)?;
+    reader
+        .seek(SeekFrom::Start(header_offset))
+        .map_err(io_err)?;
+    let n8 = read_u32(reader)? as u64;
+    if n8 != FQ_BYTES {
+        return Err(Error::InvalidSrsFile(format!(
+            "unexpected field size {}",
+            n8
+        )));
+    }
+    let q = read_limbs(reader)?;
+    if q != <Fq as PrimeField>::Params::MODULUS {
+        return Err(Error::InvalidSrsFile("not a bn254 ceremony".to_string()));
+    }
+    let power = read_u32(reader)?;
+    // no domain of the scalar field goes past its 2-adicity, larger headers can't be a ceremony
+    let max_power = <Fr as FftField>::FftParams::TWO_ADICITY.min(usize::BITS - 2);
+    if power > max_power {
+        return Err(Error::InvalidSrsFile(format!(
+            "power {} is above {}",
+            power, max_power
+        )));
+    }
+    let num_g2 = 1usize
+        .checked_shl(power)
+        .ok_or_else(|| Error::InvalidSrsFile(format!("power {} is too large", power)))?;
+    let num_g1 = 2 * num_g2 - 1;
+
+    Ok(PtauHeader {
+        sections,
+        num_g1,
+        num_g2,
+    })
+}
+
+/// Highest powers of tau in G1 and G2 of a snarkjs Powers of Tau file, from its header alone
+pub fn ptau_max_powers<P: AsRef<Path>>(path: P) -> Result<(usize, usize), Error> {
+    let file = File::open(path).map_err(io_err)?;
+    let header = read_ptau_header(&mut BufReader::new(file))?;
+    Ok((header.num_g1 - 1, header.num_g2 - 1))
+}
+
+pub fn from_ptau_reader<R: Read + Seek>(
+    mut reader: R,
+    max_power_g1: usize,
+    max_power_g2: usize,
+) -> Result<(Vec<G1Affine>, Vec<G2Affine>), Error> {
+    let header = read_ptau_header(&mut reader)?;
+    let (num_g1, num_g2) = (header.num_g1, header.num_g2);
+    if max_power_g1 + 1 > num_g1 {
+        return Err(Error::SrsG1TooSmall(max_power_g1 + 1, num_g1));
+    }
+    if max_power_g2 + 1 > num_g2 {
+        return Err(Error::SrsG2TooSmall(max_power_g2 + 1, num_g2));
+    }
+
+    let r_inv = montgomery_r_inv();
+
+    let (g1_offset, g1_size) = header.section(PTAU_TAU_G1)?;
+    if g1_size != num_g1 as u64 * 2 * FQ_BYTES {
+        return Err(Error::InvalidSrsFile(
+            "tauG1 section has wrong size".to_string(),
+        ));
+    }
+    reader.seek(SeekFrom::Start(g1_offset)).map_err(io_err)?;
+    let srs_g1 = (0..=max_power_g1)
+        .map(|i| read_g1(&mut reader, r_inv, i))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (g2_offset, g2_size) = header.section(PTAU_TAU_G2)?;
+    if g2_size != num_g2 as u64 * 4 * FQ_BYTES {
+        return Err(Error::InvalidSrsFile(
+            "tauG2 section has wrong size".to_string(),
+        ));
+    }
+    reader.seek(SeekFrom::Start(g2_offset)).map_err(io_err)?;
+    let srs_g2 = (0..=max_power_g2)
+        .map(|i| read_g2(&mut reader, r_inv, i))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    check_powers::<Bn254>(&srs_g1, &srs_g2)?;
+
+    Ok((srs_g1, srs_g2))
+}
+
+/// Size of the Aztec Ignition header, seven big endian u32
+const IGNITION_HEADER_BYTES: usize = 28;
+
+fn read_u32_be<R: Read>(r: &mut R) -> Result<u32, Error> {
+    let mut buf = [0u8; 4];
+    r.read_exact(&mut buf).map_err(io_err)?;
+    Ok(u32::from_be_bytes(buf))
+}
+
+/// Ignition stores field elements as four big endian u64 limbs, least significant limb first
+fn read_fq_ignition<R: Read>(r: &mut R) -> Result<Fq, Error> {
+    let mut limbs = [0u64; 4];
+    for limb in limbs.iter_mut() {
+        let mut buf = [0u8; 8];
+        r.read_exact(&mut buf).map_err(io_err)?;
+        *limb = u64::from_be_bytes(buf);
+    }
+    Fq::from_repr(BigInteger256::new(limbs))
+        .ok_or_else(|| Error::InvalidSrsFile("field element is not reduced".to_string()))
+}
+
+struct IgnitionHeader {
+    transcript_number: u32,
+    num_g1_points: u32,
+    num_g2_points: u32,
+    start_from: u32,
+}
+
+fn read_ignition_header<R: Read>(r: &mut R) -> Result<IgnitionHeader, Error> {
+    let mut fields = [0u32; IGNITION_HEADER_BYTES / 4];
+    for field in fields.iter_mut() {
+        *field = read_u32_be(r)?;
+    }
+    Ok(IgnitionHeader {
+        transcript_number: fields[0],
+        num_g1_points: fields[4],
+        num_g2_points: fields[5],
+        start_from: fields[6],
+    })
+}
+
+/// Loads `[x^0..=x^max_power_g1]_1` and `[x^0..=x^max_power_g2]_2` from the Aztec Ignition transcripts
+/// `transcript00.dat`, `transcript01.dat`, ... in `dir`
+///
+/// Ignition only published `[x]_2` in G2, so `max_power_g2` can be at most 1. The G1 powers (about
+/// 100 million of them) are enough to commit to witnesses and tables of any practical size, but
+/// indexing a cq table of size `n` needs `[x^n]_2` which has to come from another ceremony.
+pub fn from_ignition<P: AsRef<Path>>(
+    dir: P,
+    max_power_g1: usize,
+    max_power_g2: usize,
+) -> Result<(Vec<G1Affine>, Vec<G2Affine>), Error> {
+    let dir = dir.as_ref();
+    let mut files = vec![];
+    for i in 0.. {
+        let path = dir.join(format!("transcript{:02}.dat", i));
+        if !path.exists() {
+            break;
+        }
+        files.push(BufReader::new(File::open(path).map_err(io_err)?));
+    }
+    from_ignition_readers(files, max_power_g1, max_power_g2)
+}
+
+/// Transcripts are read in order until `max_power_g1` is reached. Each transcript ends with a
+/// checksum that is not checked here, the points are instead validated on the curve, in the
+/// subgroup and as consecutive powers of the same tau.
+pub fn from_ignition_readers<R: Read>(
+    transcripts: impl IntoIterator<Item = R>,
+    max_power_g1: usize,
+    max_power_g2: usize,
+) -> Result<(Vec<G1Affine>, Vec<G2Affine>), Error> {
+    if max_power_g2 > 1 {
+        return Err(Error::SrsG2TooSmall(max_power_g2 + 1, 2));
+    }
+
+    // the transcripts start at [x]_1 and [x]_2
+    let mut srs_g1 = vec![G1Affine::prime_subgroup_generator()];
+    let mut srs_g2 = vec![G2Affine::prime_subgroup_generator()];
+
+    for (i, mut reader) in transcripts.into_iter().enumerate() {
+        if srs_g1.len() > max_power_g1 && srs_g2.len() > max_power_g2 {
+            break;
+        }
+        let header = read_ignition_header(&mut reader)?;
+        if header.transcript_number as usize != i {
+            return Err(Error::InvalidSrsFile(format!(
+                "expected transcript {}, got {}",
+                i, header.transcript_number
+            )));
+        }
+        if header.start_from as usize != srs_g1.len() - 1 {
+            return Err(Error::InvalidSrsFile(format!(
+                "transcript {} starts at point {}",
+                i, header.start_from
+            )));
+        }
+
+        for _ in 0..header.num_g1_points {
+            let power = srs_g1.len();
+            let x = read_fq_ignition(&mut reader)?;
+            let y = read_fq_ignition(&mut reader)?;
+            if power > max_power_g1 {
+                continue;
+            }
+            srs_g1.push(g1_from_coords(x, y, power)?);
+        }
+
+        for j in 0..header.num_g2_points {
+            let mut c = [Fq::zero(); 4];
+            for c in c.iter_mut() {
+                *c = read_fq_ignition(&mut reader)?;
+            }
+            if j == 0 && srs_g2.len() <= max_power_g2 {
+                srs_g2.push(g2_from_coords(c, 1)?);
+            }
+        }
+    }
+
+    if srs_g1.len() <= max_power_g1 {
+        return Err(Error::SrsG1TooSmall(max_power_g1 + 1, srs_g1.len()));
+    }
+    if srs_g2.len() <= max_power_g2 {
+        return Err(Error::SrsG2TooSmall(max_power_g2 + 1, srs_g2.len()));
+    }
+
+    check_powers::<Bn254>(&srs_g1, &srs_g2)?;
+
+    Ok((srs_g1, srs_g2))
+}
+
+/// Size of the G1 part of the EIP-4844 ceremony
+pub const EIP4844_G1_POWERS: usize = 4096;
+/// Size of the G2 part of the EIP-4844 ceremony, this bounds tables to 64 entries since indexing
+/// a table of size `n` needs `[x^n]_2`
+pub const EIP4844_G2_POWERS: usize = 65;
+
+const BLS_G1_BYTES: usize = 48;
+const BLS_G2_BYTES: usize = 96;
+
+/// Parses a big endian base field element, rejecting non reduced encodings
+fn bls_fq_from_be(bytes: &[u8]) -> Option<ark_bls12_381::Fq> {
+    let mut limbs = [0u64; 6];
+    for (i, limb) in limbs.iter_mut().enumerate() {
+        let end = BLS_G1_BYTES - 8 * i;
+        *limb = u64::from_be_bytes(bytes[end - 8..end].try_into().unwrap());
+    }
+    ark_bls12_381::Fq::from_repr(BigInteger384::new(limbs))
+}
+
+/// Reads the compression, infinity and sign flags of a zcash encoded point and clears them
+fn zcash_flags(bytes: &mut [u8]) -> Option<(bool, bool)> {
+    let flags = bytes[0];
+    bytes[0] &= 0x1f;
+    if flags & 0x80 == 0 {
+        return None;
+    }
+    let infinity = flags & 0x40 != 0;
+    let greatest = flags & 0x20 != 0;
+    if infinity && (greatest || bytes.iter().any(|b| *b != 0)) {
+        return None;
+    }
+    Some((infinity, greatest))
+}
+
+/// Decodes a compressed point in the zcash BLS12-381 format
+fn bls_g1_from_zcash(bytes: &[u8]) -> Option<ark_bls12_381::G1Affine> {
+    let mut bytes = bytes.to_vec();
+    let (infinity, greatest) = zcash_flags(&mut bytes)?;
+    if infinity {
+        return Some(ark_bls12_381::G1Affine::zero());
+    }
+    let x = bls_fq_from_be(&bytes)?;
+    let p = ark_bls12_381::G1Affine::get_point_from_x(x, greatest)?;
+    p.is_in_correct_subgroup_assuming_on_curve().then_some(p)
+}
+
+/// Decodes a compressed point in the zcash BLS12-381 format, `x.c1` comes first
+fn bls_g2_from_zcash(bytes: &[u8]) -> Option<ark_bls12_381::G2Affine> {
+    let mut bytes = bytes.to_vec();
+    let (infinity, greatest) = zcash_flags(&mut bytes)?;
+    if infinity {
+        return Some(ark_bls12_381::G2Affine::zero());
+    }
+    let c1 = bls_fq_from_be(&bytes[..BLS_G1_BYTES])?;
+    let c0 = bls_fq_from_be(&bytes[BLS_G1_BYTES..])?;
+    let x = ark_bls12_381::Fq2::new(c0, c1);
+    let p = ark_bls12_381::G2Affine::get_point_from_x(x, greatest)?;
+    p.is_in_correct_subgroup_assuming_on_curve().then_some(p)
+}
+
+fn hex_line<'a>(lines: &mut impl Iterator<Item = &'a str>, len: usize) -> Result<Vec<u8>, Error> {
+    let line = lines
+        .next()
+        .ok_or_else(|| Error::InvalidSrsFile("unexpected end of file".to_string()))?;
+    let bytes = hex::decode(line.trim().trim_start_matches("0x"))
+        .map_err(|e| Error::InvalidSrsFile(e.to_string()))?;
+    if bytes.len() != len {
+        return Err(Error::InvalidSrsFile(format!(
+            "expected {} bytes, got {}",
+            len,
+            bytes.len()
+        )));
+    }
+    Ok(bytes)
+}
+
+fn count_line<'a>(lines: &mut impl Iterator<Item = &'a str>) -> Result<usize, Error> {
+    lines
+        .next()
+        .and_then(|line| line.trim().parse().ok())
+        .ok_or_else(|| Error::InvalidSrsFile("missing point count".to_string()))
+}
+
+/// Loads `[x^0..=x^max_power_g1]_1` and `[x^0..=x^max_power_g2]_2` from the Ethereum KZG ceremony
+/// output in the c-kzg `trusted_setup.txt` format
+pub fn from_eip4844<P: AsRef<Path>>(
+    path: P,
+    max_power_g1: usize,
+    max_power_g2: usize,
+) -> Result<(Vec<ark_bls12_381::G1Affine>, Vec<ark_bls12_381::G2Affine>), Error> {
+    let contents = fs::read_to_string(path).map_err(io_err)?;
+    from_eip4844_str(&contents, max_power_g1, max_power_g2)
+}
+
+/// The file holds the point counts, the G1 points in bit reversed lagrange form, the G2 powers and
+/// the G1 powers, one hex encoded compressed point per line. Older files without the G1 powers are
+/// rejected.
+pub fn from_eip4844_str(
+    contents: &str,
+    max_power_g1: usize,
+    max_power_g2: usize,
+) -> Result<(Vec<ark_bls12_381::G1Affine>, Vec<ark_bls12_381::G2Affine>), Error> {
+    let mut lines = contents.lines().filter(|line| !line.trim().is_empty());
+    let num_g1 = count_line(&mut lines)?;
+    let num_g2 = count_line(&mut lines)?;
+    if max_power_g1 + 1 > num_g1 {
+        return Err(Error::SrsG1TooSmall(max_power_g1 + 1, num_g1));
+    }
+    if max_power_g2 + 1 > num_g2 {
+        return Err(Error::SrsG2TooSmall(max_power_g2 + 1, num_g2));
+    }
+
+    // lagrange points are not needed, the monomial section follows the G2 points
+    for _ in 0..num_g1 {
+        hex_line(&mut lines, BLS_G1_BYTES)?;
+    }
+
+    let mut srs_g2 = Vec::with_capacity(max_power_g2 + 1);
+    for i in 0..num_g2 {
+        let bytes = hex_line(&mut lines, BLS_G2_BYTES)?;
+        if i <= max_power_g2 {
+            srs_g2.push(bls_g2_from_zcash(&bytes).ok_or(Error::InvalidSrsPoint("g2", i))?);
+        }
+    }
+
+    let mut srs_g1 = Vec::with_capacity(max_power_g1 + 1);
+    for i in 0..=max_power_g1 {
+        let bytes = hex_line(&mut lines, BLS_G1_BYTES)
+            .map_err(|_| Error::InvalidSrsFile("missing monomial G1 section".to_string()))?;
+        srs_g1.push(bls_g1_from_zcash(&bytes).ok_or(Error::InvalidSrsPoint("g1", i))?);
+    }
+
+    check_powers::<Bls12_381>(&srs_g1, &srs_g2)?;
+
+    Ok((srs_g1, srs_g2))
+}
+
+/// Domain separation tag of `Srs::insecure_from_seed`
+pub const INSECURE_SRS_DST: &[u8] = b"CQEXT-V01-INSECURE-SRS";
+
+/// How much of an srs `Srs::verify_structure` checks
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StructureCheck {
+    /// every point in the subgroup and all powers at once with a random linear combination, two msms per group
+    Full,
+    /// that many consecutive pairs per group at positions derived from a hash of the srs, four pairings each
+    Spot(usize),
+}
+
+/// Powers of tau in G1 and G2 that passed the curve, subgroup and consecutive power checks
+#[derive(Clone, Debug, PartialEq)]
+pub struct Srs<E: PairingEngine> {
+    pub(crate) g1: Vec<E::G1Affine>,
+    pub(crate) g2: Vec<E::G2Affine>,
+}
+
+impl<E: PairingEngine> Srs<E> {
+    /// Checks powers produced outside of cqext, e.g. by another ceremony tool
+    pub fn from_external(g1: Vec<E::G1Affine>, g2: Vec<E::G2Affine>) -> Result<Self, Error> {
+        Self::from_external_with(g1, g2, StructureCheck::Full)
+    }
+
+    /// `from_external` with a cheaper check for srs files too large to check in full on every load
+    pub fn from_external_with(
+        g1: Vec<E::G1Affine>,
+        g2: Vec<E::G2Affine>,
+        check: StructureCheck,
+    ) -> Result<Self, Error> {
+        if g1.is_empty() {
+            return Err(Error::SrsG1TooSmall(1, 0));
+        }
+        if g2.is_empty() {
+            return Err(Error::SrsG2TooSmall(1, 0));
+        }
+        let srs = Self { g1, g2 };
+        srs.verify_structure(check)?;
+        Ok(srs)
+    }
+
+    /// Checks that the points are in the subgroup, start at the generators and are consecutive powers of one
+    /// secret, fully or at sampled positions
+    pub fn verify_structure(&self, check: StructureCheck) -> Result<(), Error> {
+        match check {
+            StructureCheck::Full => {
+                if let Some(i) = self.g1.iter().position(|p| !is_valid_point(p)) {
+                    return Err(Error::InvalidSrsPoint("g1", i));
+                }
+                if let Some(i) = self.g2.iter().position(|p| !is_valid_point(p)) {
+                    return Err(Error::InvalidSrsPoint("g2", i));
+                }
+                check_powers::<E>(&self.g1, &self.g2)
+            }
+            StructureCheck::Spot(samples) => self.spot_check(samples),
+        }
+    }
+
+    fn spot_check(&self, samples: usize) -> Result<(), Error> {
+        let (g1, g2) = (&self.g1, &self.g2);
+        if g1[0] != E::G1Affine::prime_subgroup_generator()
+            || g2[0] != E::G2Affine::prime_subgroup_generator()
+        {
+            return Err(Error::SrsMismatch);
+        }
+        if g1.len() < 2 || g2.len() < 2 {
+            return Ok(());
+        }
+        if !is_valid_point(&g1[1]) {
+            return Err(Error::InvalidSrsPoint("g1", 1));
+        }
+        if !is_valid_point(&g2[1]) {
+            return Err(Error::InvalidSrsPoint("g2", 1));
+        }
+
+        // e([x^(i+1)]_1, [1]_2) = e([x^i]_1, [x]_2) and e([1]_1, [x^(i+1)]_2) = e([x]_1, [x^i]_2)
+        let mut rng = points_rng::<E>(g1, g2);
+        for _ in 0..samples {
+            let i = rng.gen_range(0..g1.len() - 1);
+            if !is_valid_point(&g1[i + 1]) {
+                return Err(Error::InvalidSrsPoint("g1", i + 1));
+            }
+            if E::pairing(g1[i + 1], g2[0]) != E::pairing(g1[i], g2[1]) {
+                return Err(Error::SrsMismatch);
+            }
+            let j = rng.gen_range(0..g2.len() - 1);
+            if !is_valid_point(&g2[j + 1]) {
+                return Err(Error::InvalidSrsPoint("g2", j + 1));
+            }
+            if E::pairing(g1[0], g2[j + 1]) != E::pairing(g1[1], g2[j]) {
+                return Err(Error::SrsMismatch);
+            }
+        }
+        Ok(())
+    }
+
+    /// Srs with tau derived from `label` alone, `[x^0..=x^degree]_1` and `[x^0..=x^(degree+1)]_2` as plain keys for
+    /// tables of `degree + 1` entries need. Tau is RFC 9380 `hash_to_field` with SHA-256 of the label under
+    /// `INSECURE_SRS_DST`, so fixtures in other languages can derive the same points; anyone knowing the label
+    /// can forge proofs.
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn insecure_from_seed(label: &str, degree: usize) -> Self {
+        use zeroize::Zeroize;
+
+        let mut tau = crate::hash_to_field::hash_to_field::<E::Fr, sha2::Sha256>(
+            label.as_bytes(),
+            INSECURE_SRS_DST,
+            1,
+        )[0];
+        let (g1, g2) = crate::utils::unsafe_setup_from_tau::<E>(degree, degree + 1, tau);
+        tau.zeroize();
+        Self { g1, g2 }
+    }
+
+    /// Highest power of tau in G1
+    pub fn max_power_g1(&self) -> usize {
+        self.g1.len() - 1
+    }
+
+    pub fn max_power_g2(&self) -> usize {
+        self.g2.len() - 1
+    }
+
+    /// `[x^0..=x^max_power_g1]_1` and `[x^0..=x^max_power_g2]_2` of this srs, fully checked again since the whole
+    /// srs may only have been spot-checked and the prefix is usually small enough for a full check
+    pub fn truncate(&self, max_power_g1: usize, max_power_g2: usize) -> Result<Self, Error> {
+        let srs = self.prefix(max_power_g1, max_power_g2)?;
+        srs.verify_structure(StructureCheck::Full)?;
+        Ok(srs)
+    }
+
+    /// `truncate` without the check, for callers that only read the points
+    pub(crate) fn prefix(&self, max_power_g1: usize, max_power_g2: usize) -> Result<Self, Error> {
+        if max_power_g1 >= self.g1.len() {
+            return Err(Error::SrsG1TooSmall(max_power_g1 + 1, self.g1.len()));
+        }
+        if max_power_g2 >= self.g2.len() {
+            return Err(Error::SrsG2TooSmall(max_power_g2 + 1, self.g2.len()));
+        }
+        Ok(Self {
+            g1: self.g1[..=max_power_g1].to_vec(),
+            g2: self.g2[..=max_power_g2].to_vec(),
+        })
+    }
+
+    pub fn g1(&self) -> &[E::G1Affine] {
+        &self.g1
+    }
+
+    pub fn g2(&self) -> &[E::G2Affine] {
+        &self.g2
+    }
+
+    pub fn into_parts(self) -> (Vec<E::G1Affine>, Vec<E::G2Affine>) {
+        (self.g1, self.g2)
+    }
+}
+
+impl Srs<Bn254> {
+    /// See `from_ptau`
+    pub fn load_ptau<P: AsRef<Path>>(
+        path: P,
+        max_power_g1: usize,
+        max_power_g2: usize,
+    ) -> Result<Self, Error> {
+        let (g1, g2) = from_ptau(path, max_power_g1, max_power_g2)?;
+        Ok(Self { g1, g2 })
+    }
+
+    /// See `from_ignition`
+    pub fn load_ignition<P: AsRef<Path>>(
+        dir: P,
+        max_power_g1: usize,
+        max_power_g2: usize,
+    ) -> Result<Self, Error> {
+        let (g1, g2) = from_ignition(dir, max_power_g1, max_power_g2)?;
+        Ok(Self { g1, g2 })
+    }
+}
+
+impl Srs<Bls12_381> {
+    /// See `from_eip4844`
+    pub fn load_eip4844<P: AsRef<Path>>(
+        path: P,
+        max_power_g1: usize,
+        max_power_g2: usize,
+    ) -> Result<Self, Error> {
+        let (g1, g2) = from_eip4844(path, max_power_g1, max_power_g2)?;
+        Ok(Self { g1, g2 })
+    }
+}
+
+#[cfg(test)]
+mod srs_tests {
+    use std::io::Cursor;
+
+    use ark_bls12_381::Bls12_381;
+    use ark_bn254::{Bn254, Fq, Fr, G1Affine, G2Affine};
+    use ark_ff::{BigInteger, Field, FpParameters, PrimeField, Zero};
+    use ark_std::{rand::rngs::StdRng, test_rng};
+    use rand_chacha::ChaChaRng;
+    use sha3::Keccak256;
+
+    use crate::{
+        config::CqConfig,
+        cq::Cq,
+        error::Error,
+        rng::SimpleHashFiatShamirRng,
+        table::Table,
+        utils::{to_field, unsafe_setup_from_rng},
+    };
+
+    use super::{
+        from_eip4844_str, from_ignition_readers, from_ptau_reader, ptau_max_powers, Srs,
+        StructureCheck,
+    };
+
+    type FS = SimpleHashFiatShamirRng<Keccak256, ChaChaRng>;
+
+    fn write_fq(out: &mut Vec<u8>, x: Fq) {
+        let r = Fq::from(2u64).pow([256u64]);
+        out.extend_from_slice(&(x * r).into_repr().to_bytes_le());
+    }
+
+    fn write_section(out: &mut Vec<u8>, id: u32, data: &[u8]) {
+        out.extend_from_slice(&id.to_le_bytes());
+        out.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        out.extend_from_slice(data);
+    }
+
+    /// Minimal ptau file with only the sections the loader reads
+    fn ptau_bytes(power: u32, srs_g1: &[G1Affine], srs_g2: &[G2Affine]) -> Vec<u8> {
+        let mut header = vec![];
+        header.extend_from_slice(&32u32.to_le_bytes());
+        header.extend_from_slice(&<Fq as PrimeField>::Params::MODULUS.to_bytes_le());
+        header.extend_from_slice(&power.to_le_bytes());
+        header.extend_from_slice(&power.to_le_bytes());
+
+        let mut g1 = vec![];
+        for p in srs_g1 {
+            write_fq(&mut g1, p.x);
+            write_fq(&mut g1, p.y);
+        }
+
+        let mut g2 = vec![];
+        for p in srs_g2 {
+            write_fq(&mut g2, p.x.c0);
+            write_fq(&mut g2, p.x.c1);
+            write_fq(&mut g2, p.y.c0);
+            write_fq(&mut g2, p.y.c1);
+        }
+
+        let mut out = b"ptau".to_vec();
+        out.extend_from_slice(&1u32.to_le_bytes());
+        out.extend_from_slice(&3u32.to_le_bytes());
+        write_section(&mut out, 1, &header);
+        write_section(&mut out, 2, &g1);
+        write_section(&mut out, 3, &g2);
+        out
+    }
+
+    #[test]
+    fn test_from_ptau() {
+        let power = 3;
+        let mut rng = test_rng();
+        let (srs_g1, srs_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(
+            (1 << (power + 1)) - 2,
+            (1 << power) - 1,
+            &mut rng,
+        );
+        let bytes = ptau_bytes(power, &srs_g1, &srs_g2);
+
+        let (g1, g2) = from_ptau_reader(Cursor::new(&bytes), 7, 7).unwrap();
+        assert_eq!(g1, srs_g1[..8]);
+        assert_eq!(g2, srs_g2[..8]);
+
+        let res = from_ptau_reader(Cursor::new(&bytes), 7, 8);
+        assert_eq!(res.err(), Some(Error::SrsG2TooSmall(9, 8)));
+
+        let path = std::env::temp_dir().join(format!("cqext-ptau-{}.ptau", std::process::id()));
+        std::fs::write(&path, &bytes).unwrap();
+        let max_powers = ptau_max_powers(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(max_powers, Ok((14, 7)));
+    }
+
+    #[test]
+    fn test_from_ptau_rejects_bad_points() {
+        let power = 2;
+        let mut rng = test_rng();
+        let (mut srs_g1, srs_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(
+            (1 << (power + 1)) - 2,
+            (1 << power) - 1,
+            &mut rng,
+        );
+        srs_g1[2].y += Fq::from(1u64);
+        assert!(!srs_g1[2].y.is_zero());
+        let bytes = ptau_bytes(power, &srs_g1, &srs_g2);
+
+        let res = from_ptau_reader(Cursor::new(&bytes), 3, 3);
+        assert_eq!(res.err(), Some(Error::InvalidSrsPoint("g1", 2)));
+    }
+
+    #[test]
+    fn test_from_ptau_rejects_bogus_power() {
+        for power in [29, 63, u32::MAX] {
+            let bytes = ptau_bytes(power, &[], &[]);
+            let res = from_ptau_reader(Cursor::new(&bytes), 3, 3);
+            assert!(matches!(res, Err(Error::InvalidSrsFile(_))));
+        }
+    }
+
+    fn zcash_bytes(x: &[ark_bls12_381::Fq], greatest: bool) -> Vec<u8> {
+        let mut bytes: Vec<u8> = x.iter().flat_map(|c| c.into_repr().to_bytes_be()).collect();
+        bytes[0] |= 0x80;
+        if greatest {
+            bytes[0] |= 0x20;
+        }
+        bytes
+    }
+
+    fn eip4844_str(
+        srs_g1: &[ark_bls12_381::G1Affine],
+        srs_g2: &[ark_bls12_381::G2Affine],
+    ) -> String {
+        let g1: Vec<_> = srs_g1
+            .iter()
+            .map(|p| hex::encode(zcash_bytes(&[p.x], p.y > -p.y)))
+            .collect();
+        let g2: Vec<_> = srs_g2
+            .iter()
+            .map(|p| hex::encode(zcash_bytes(&[p.x.c1, p.x.c0], p.y > -p.y)))
+            .collect();
+
+        // the lagrange section is skipped by the loader, the monomial points stand in for it
+        let mut lines = vec![srs_g1.len().to_string(), srs_g2.len().to_string()];
+        lines.extend(g1.iter().cloned());
+        lines.extend(g2);
+        lines.extend(g1);
+        lines.join("\n")
+    }
+
+    #[test]
+    fn test_from_eip4844() {
+        let mut rng = test_rng();
+        let (srs_g1, srs_g2) = unsafe_setup_from_rng::<Bls12_381, StdRng>(15, 8, &mut rng);
+        let contents = eip4844_str(&srs_g1, &srs_g2);
+
+        let (g1, g2) = from_eip4844_str(&contents, 7, 8).unwrap();
+        assert_eq!(g1, srs_g1[..8]);
+        assert_eq!(g2, srs_g2);
+
+        let res = from_eip4844_str(&contents, 7, 9);
+        assert_eq!(res.err(), Some(Error::SrsG2TooSmall(10, 9)));
+    }
+
+    #[test]
+    fn test_from_eip4844_rejects_inconsistent_powers() {
+        let mut rng = test_rng();
+        let (mut srs_g1, srs_g2) = unsafe_setup_from_rng::<Bls12_381, StdRng>(7, 4, &mut rng);
+        srs_g1.swap(2, 3);
+        let contents = eip4844_str(&srs_g1, &srs_g2);
+
+        let res = from_eip4844_str(&contents, 7, 4);
+        assert_eq!(res.err(), Some(Error::SrsMismatch));
+    }
+
+    fn write_fq_ignition(out: &mut Vec<u8>, x: Fq) {
+        for limb in x.into_repr().0 {
+            out.extend_from_slice(&limb.to_be_bytes());
+        }
+    }
+
+    fn ignition_transcript(
+        number: u32,
+        start_from: usize,
+        srs_g1: &[G1Affine],
+        srs_g2: &[G2Affine],
+    ) -> Vec<u8> {
+        let mut out = vec![];
+        for field in [
+            number,
+            2,
+            6,
+            2,
+            srs_g1.len() as u32,
+            srs_g2.len() as u32,
+            start_from as u32,
+        ] {
+            out.extend_from_slice(&field.to_be_bytes());
+        }
+        for p in srs_g1 {
+            write_fq_ignition(&mut out, p.x);
+            write_fq_ignition(&mut out, p.y);
+        }
+        for p in srs_g2 {
+            for c in [p.x.c0, p.x.c1, p.y.c0, p.y.c1] {
+                write_fq_ignition(&mut out, c);
+            }
+        }
+        out.extend_from_slice(&[0u8; 64]);
+        out
+    }
+
+    #[test]
+    fn test_from_ignition() {
+        let mut rng = test_rng();
+        let (srs_g1, srs_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(6, 2, &mut rng);
+        let transcripts = vec![
+            ignition_transcript(0, 0, &srs_g1[1..4], &srs_g2[1..]),
+            ignition_transcript(1, 3, &srs_g1[4..], &[]),
+        ];
+
+        let (g1, g2) = from_ignition_readers(transcripts.iter().map(|t| &t[..]), 5, 1).unwrap();
+        assert_eq!(g1, srs_g1[..6]);
+        assert_eq!(g2, srs_g2[..2]);
+
+        let res = from_ignition_readers(transcripts.iter().map(|t| &t[..]), 7, 1);
+        assert_eq!(res.err(), Some(Error::SrsG1TooSmall(8, 7)));
+
+        let res = from_ignition_readers(transcripts.iter().map(|t| &t[..]), 5, 2);
+        assert_eq!(res.err(), Some(Error::SrsG2TooSmall(3, 2)));
+
+        let res = from_ignition_readers(transcripts.iter().rev().map(|t| &t[..]), 5, 1);
+        assert!(matches!(res, Err(Error::InvalidSrsFile(_))));
+    }
+
+    #[test]
+    fn test_srs_from_external() {
+        let mut rng = test_rng();
+        let (srs_g1, srs_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(7, 8, &mut rng);
+
+        let srs = Srs::<Bn254>::from_external(srs_g1.clone(), srs_g2.clone()).unwrap();
+        let table = Table::<Fr>::new(&to_field(&[1, 5, 10, 15, 20, 25, 30, 35])).unwrap();
+        let config = CqConfig::<Bn254, FS>::builder()
+            .table_size(8)
+            .witness_size(4)
+            .build()
+            .unwrap();
+        let (pk, vk) = config.setup(&srs, table).unwrap();
+        let (statement, proof) = Cq::<Bn254, FS>::prove(&pk, &to_field(&[5, 15, 20, 35])).unwrap();
+        assert!(Cq::<Bn254, FS>::verify(&vk, &statement, &proof).is_ok());
+
+        let mut tampered = srs_g1.clone();
+        tampered[3] = srs_g1[4];
+        let res = Srs::<Bn254>::from_external(tampered, srs_g2.clone());
+        assert_eq!(res.err(), Some(Error::SrsMismatch));
+        let res = Srs::<Bn254>::from_external(Vec::new(), srs_g2);
+        assert_eq!(res.err(), Some(Error::SrsG1TooSmall(1, 0)));
+    }
+
+    #[test]
+    fn test_srs_verify_structure() {
+        let mut rng = test_rng();
+        let (srs_g1, srs_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(15, 16, &mut rng);
+        let srs = Srs::<Bn254>::from_external_with(
+            srs_g1.clone(),
+            srs_g2.clone(),
+            StructureCheck::Spot(64),
+        )
+        .unwrap();
+        assert!(srs.verify_structure(StructureCheck::Full).is_ok());
+
+        let mut tampered = srs_g1;
+        tampered[1] = tampered[2];
+        for check in [StructureCheck::Spot(64), StructureCheck::Full] {
+            let res = Srs::<Bn254>::from_external_with(tampered.clone(), srs_g2.clone(), check);
+            assert_eq!(res.err(), Some(Error::SrsMismatch));
+        }
+    }
+
+    #[test]
+    fn test_srs_truncate() {
+        let mut rng = test_rng();
+        let (srs_g1, srs_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(31, 32, &mut rng);
+        let srs = Srs::<Bn254>::from_external(srs_g1.clone(), srs_g2.clone()).unwrap();
+        let table = Table::<Fr>::new(&to_field(&[1, 5, 10, 15, 20, 25, 30, 35])).unwrap();
+        let config = CqConfig::<Bn254, FS>::builder()
+            .table_size(8)
+            .witness_size(4)
+            .build()
+            .unwrap();
+
+        let small = config.extract_srs(&srs).unwrap();
+        assert_eq!((small.max_power_g1(), small.max_power_g2()), (7, 8));
+        assert_eq!(small.g1(), &srs_g1[..8]);
+        let (pk, vk) = config.setup(&small, table).unwrap();
+        let (statement, proof) = Cq::<Bn254, FS>::prove(&pk, &to_field(&[5, 15, 20, 35])).unwrap();
+        assert!(Cq::<Bn254, FS>::verify(&vk, &statement, &proof).is_ok());
+
+        assert_eq!(
+            srs.truncate(32, 8).err(),
+            Some(Error::SrsG1TooSmall(33, 32))
+        );
+        assert_eq!(
+            small.truncate(7, 9).err(),
+            Some(Error::SrsG2TooSmall(10, 9))
+        );
+    }
+
+    #[test]
+    fn test_insecure_from_seed() {
+        let srs = Srs::<Bn254>::insecure_from_seed("cqext fixtures", 7);
+        assert_eq!((srs.max_power_g1(), srs.max_power_g2()), (7, 8));
+        assert_eq!(srs, Srs::<Bn254>::insecure_from_seed("cqext fixtures", 7));
+        assert_eq!(
+            srs.truncate(3, 4).unwrap(),
+            Srs::<Bn254>::insecure_from_seed("cqext fixtures", 3)
+        );
+        assert_ne!(srs, Srs::<Bn254>::insecure_from_seed("other", 7));
+        assert!(srs.verify_structure(StructureCheck::Full).is_ok());
+    }
+}
@@ -1,26 +1,100 @@
-use std::collections::BTreeMap;
-
 use ark_ff::{FftField, ToBytes};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use ark_std::{collections::BTreeMap, format, io, ops::Range, vec, vec::Vec};
 
-use crate::error::Error;
+use crate::{error::Error, utils::max_table_size};
 
 #[derive(Debug)]
 pub struct Table<F: FftField> {
     pub(crate) size: usize,
     pub(crate) values: Vec<F>,
     pub(crate) value_index_mapping: BTreeMap<F, usize>,
+    /// number of values before the padding, which repeats one of them
+    pub(crate) unpadded_size: usize,
+}
+
+/// A value [`Table::canonicalize`] found more than once, with the raw positions of its first and current copy
+#[derive(Clone, Debug, PartialEq)]
+pub struct DuplicateValue<F> {
+    pub value: F,
+    pub first: usize,
+    pub position: usize,
+}
+
+/// Outcome of [`Table::canonicalize`]
+#[derive(Debug)]
+pub struct CanonicalTable<F: FftField> {
+    pub table: Table<F>,
+    /// table position of every raw value
+    pub positions: Vec<usize>,
+    /// every repeated copy, in raw order
+    pub duplicates: Vec<DuplicateValue<F>>,
 }
 
 impl<F: FftField> ToBytes for Table<F> {
-    fn write<W: std::io::Write>(&self, mut w: W) -> std::io::Result<()> {
+    fn write<W: io::Write>(&self, mut w: W) -> io::Result<()> {
         self.values.write(&mut w)
     }
 }
 
+// only the values are encoded, deserialization rebuilds and revalidates the table. Values are otherwise distinct, so
+// the trailing copies of the last value are padding, all but the first of them unless it also appears earlier.
+impl<F: FftField> CanonicalSerialize for Table<F> {
+    fn serialize<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        self.values.serialize(writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.values.serialized_size()
+    }
+
+    fn serialize_uncompressed<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        self.values.serialize_uncompressed(writer)
+    }
+
+    fn uncompressed_size(&self) -> usize {
+        self.values.uncompressed_size()
+    }
+}
+
+impl<F: FftField> CanonicalDeserialize for Table<F> {
+    fn deserialize<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        let values = Vec::<F>::deserialize(reader)?;
+        Self::from_encoded(&values).map_err(|_| SerializationError::InvalidData)
+    }
+
+    fn deserialize_uncompressed<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        let values = Vec::<F>::deserialize_uncompressed(reader)?;
+        Self::from_encoded(&values).map_err(|_| SerializationError::InvalidData)
+    }
+}
+
 impl<F: FftField> Table<F> {
     pub fn new(values: &Vec<F>) -> Result<Self, Error> {
-        if !values.len().is_power_of_two() {
-            return Err(Error::TableSizeNotPow2(values.len()));
+        Self::padded_to(values, values.len())
+    }
+
+    /// Table of any nonempty size, padded to the next power of two, see `padded_to`
+    pub fn padded(values: &[F]) -> Result<Self, Error> {
+        Self::padded_to(values, values.len().next_power_of_two())
+    }
+
+    /// Table of `size` entries, the values followed by copies of the last one. The padding adds no value to the
+    /// table, so any witness of the padded table is one of the original values.
+    pub fn padded_to(values: &[F], size: usize) -> Result<Self, Error> {
+        let last = match values.last() {
+            Some(&last) => last,
+            None => return Err(Error::TableSizeNotPow2(0)),
+        };
+        if size < values.len() {
+            return Err(Error::TableSizeMismatch(size, values.len()));
+        }
+        if !size.is_power_of_two() {
+            return Err(Error::TableSizeNotPow2(size));
+        }
+        // same bound as `CqConfig`, so a table that builds can be indexed
+        if size > max_table_size::<F>() {
+            return Err(Error::TableTooLargeForField(size, max_table_size::<F>()));
         }
         let mut value_index_mapping = BTreeMap::<F, usize>::default();
         for (i, &ti) in values.iter().enumerate() {
@@ -29,22 +103,198 @@ impl<F: FftField> Table<F> {
                 return Err(Error::DuplicateValueInTable(format!("{}", ti)));
             }
         }
+        let mut padded = values.to_vec();
+        padded.resize(size, last);
         Ok(Self {
-            size: values.len(),
-            values: values.clone(),
+            size,
+            values: padded,
             value_index_mapping,
+            unpadded_size: values.len(),
+        })
+    }
+
+    fn from_encoded(values: &[F]) -> Result<Self, Error> {
+        let last = match values.last() {
+            Some(last) => last,
+            None => return Err(Error::TableSizeNotPow2(0)),
+        };
+        let run = values.iter().rev().take_while(|&v| v == last).count();
+        let mut unpadded_size = values.len() - run;
+        if !values[..unpadded_size].contains(last) {
+            unpadded_size += 1;
+        }
+        let mut table = Self::padded_to(&values[..unpadded_size], values.len())?;
+        table.values = values.to_vec();
+        Ok(table)
+    }
+
+    /// Table of the distinct values of `raw` in ascending order, padded like `padded`, so lists with the same values
+    /// give the same table and index. Repeats are dropped and reported instead of rejected; `positions` maps raw
+    /// positions to table positions, e.g. to rewrite witnesses or multiplicities given against `raw`.
+    pub fn canonicalize(raw: &[F]) -> Result<CanonicalTable<F>, Error> {
+        let mut first = BTreeMap::<F, usize>::new();
+        let mut duplicates = Vec::new();
+        for (position, &value) in raw.iter().enumerate() {
+            match first.get(&value) {
+                Some(&first) => duplicates.push(DuplicateValue {
+                    value,
+                    first,
+                    position,
+                }),
+                None => {
+                    first.insert(value, position);
+                }
+            }
+        }
+        let values: Vec<F> = first.keys().copied().collect();
+        let table = Self::padded(&values)?;
+        let positions = raw
+            .iter()
+            .map(|value| table.value_index_mapping[value])
+            .collect();
+        Ok(CanonicalTable {
+            table,
+            positions,
+            duplicates,
         })
     }
+
+    /// Contiguous range, e.g. `Table::range(0..1 << 16)` for CPRange
+    pub fn range(range: Range<u64>) -> Result<Self, Error> {
+        let size = range.end.saturating_sub(range.start) as usize;
+        Self::from_fn(size, |i| F::from(range.start + i as u64))
+    }
+
+    /// Table of `f(0), ..., f(size - 1)` such as powers or bit masks, the size is checked against the
+    /// field's domains before any value is computed
+    pub fn from_fn(size: usize, f: impl Fn(usize) -> F) -> Result<Self, Error> {
+        if !size.is_power_of_two() {
+            return Err(Error::TableSizeNotPow2(size));
+        }
+        if size > max_table_size::<F>() {
+            return Err(Error::TableTooLargeForField(size, max_table_size::<F>()));
+        }
+        Self::new(&(0..size).map(f).collect())
+    }
+
+    /// Table without the value index, for columns that only feed the indexer and for folded tables whose
+    /// witnesses come with their positions. Values may repeat.
+    pub(crate) fn from_values_unchecked(values: Vec<F>) -> Self {
+        Self {
+            size: values.len(),
+            unpadded_size: values.len(),
+            values,
+            value_index_mapping: BTreeMap::new(),
+        }
+    }
+
+    /// Replaces the value at `i` by a value not elsewhere in the table and returns the previous one. The padding
+    /// and the value it copies are fixed.
+    pub(crate) fn replace(&mut self, i: usize, value: F) -> Result<F, Error> {
+        if i >= self.unpadded_size {
+            return Err(Error::IndexOutOfTable(i, self.unpadded_size));
+        }
+        if self.padding_source() == Some(i) {
+            return Err(Error::PaddedEntry(i));
+        }
+        if matches!(self.value_index_mapping.get(&value), Some(&j) if j != i) {
+            return Err(Error::DuplicateValueInTable(format!("{}", value)));
+        }
+        let prev = self.values[i];
+        self.value_index_mapping.remove(&prev);
+        self.value_index_mapping.insert(value, i);
+        self.values[i] = value;
+        Ok(prev)
+    }
+
+    /// Writes `value` over the first padding entry, returning its position and the value it held
+    pub(crate) fn push(&mut self, value: F) -> Result<(usize, F), Error> {
+        let i = self.unpadded_size;
+        if i == self.size {
+            return Err(Error::TableFull(self.size));
+        }
+        if self.value_index_mapping.contains_key(&value) {
+            return Err(Error::DuplicateValueInTable(format!("{}", value)));
+        }
+        let prev = self.values[i];
+        self.value_index_mapping.insert(value, i);
+        self.values[i] = value;
+        self.unpadded_size += 1;
+        Ok((i, prev))
+    }
+
+    /// Table of `base`'s size with `values` at the free positions of `base` and its padding value everywhere
+    /// else, for `Index::union`. Only for indexing, it has no value index.
+    pub fn extension_of(base: &Self, values: &[F]) -> Result<Self, Error> {
+        if values.len() > base.free_capacity() {
+            return Err(Error::TableFull(base.size));
+        }
+        let fill = base.values[base.size - 1];
+        let mut extension = vec![fill; base.size];
+        extension[base.unpadded_size..base.unpadded_size + values.len()].copy_from_slice(values);
+        Ok(Self::from_values_unchecked(extension))
+    }
+
+    /// The values of `base` followed by those of an `extension_of(base, values)`, checked for repeats
+    pub(crate) fn union(base: &Self, extension: &Self) -> Result<Self, Error> {
+        if extension.size != base.size {
+            return Err(Error::TableSizeMismatch(base.size, extension.size));
+        }
+        let (k, fill) = (base.unpadded_size, base.values[base.size - 1]);
+        let m = extension.values[k..]
+            .iter()
+            .take_while(|&&v| v != fill)
+            .count();
+        let only_fill = |values: &[F]| values.iter().all(|&v| v == fill);
+        if !only_fill(&extension.values[..k]) || !only_fill(&extension.values[k + m..]) {
+            return Err(Error::NotAnExtension);
+        }
+        // the padding stays the copies of the base padding value, which is what the extension index holds
+        let mut values = extension.values.clone();
+        values[..k].copy_from_slice(&base.values[..k]);
+        let mut table = Self::padded_to(&values[..k + m], base.size)?;
+        table.values = values;
+        Ok(table)
+    }
+
+    /// Position of the value the padding repeats
+    fn padding_source(&self) -> Option<usize> {
+        if self.size == self.unpadded_size {
+            return None;
+        }
+        self.value_index_mapping
+            .get(&self.values[self.size - 1])
+            .copied()
+    }
+
+    /// Number of values that can still be appended with `Index::append_entry`
+    pub fn free_capacity(&self) -> usize {
+        self.size - self.unpadded_size
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn values(&self) -> &[F] {
+        &self.values
+    }
+
+    /// Number of application values, `size` minus the padding
+    pub fn unpadded_size(&self) -> usize {
+        self.unpadded_size
+    }
 }
 
 #[cfg(test)]
 pub mod table_tests {
-    use crate::error::Error;
+    use crate::{error::Error, utils::to_field};
     use ark_bn254::Fr;
     use ark_ff::UniformRand;
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
     use ark_std::test_rng;
 
-    use super::Table;
+    use super::{DuplicateValue, Table};
 
     #[test]
     fn test_correct_table() {
@@ -79,4 +329,93 @@ pub mod table_tests {
             Error::DuplicateValueInTable(format!("{}", table_values[5]))
         );
     }
+    #[test]
+    fn test_padded() {
+        let values = to_field::<Fr>(&[1, 5, 10, 15, 20]);
+        let table = Table::padded(&values).unwrap();
+        assert_eq!((table.size(), table.unpadded_size()), (8, 5));
+        assert_eq!(table.values()[7], Fr::from(20u64));
+        assert_eq!(table.value_index_mapping[&Fr::from(20u64)], 4);
+
+        let mut bytes = vec![];
+        table.serialize(&mut bytes).unwrap();
+        let decoded = Table::<Fr>::deserialize(&bytes[..]).unwrap();
+        assert_eq!(decoded.values(), table.values());
+        assert_eq!(decoded.unpadded_size(), 5);
+
+        // after an append the padding repeats a value that isn't the last one
+        let mut grown = Table::padded_to(&values, 8).unwrap();
+        assert_eq!(grown.push(Fr::from(7u64)), Ok((5, Fr::from(20u64))));
+        let mut bytes = vec![];
+        grown.serialize(&mut bytes).unwrap();
+        let decoded = Table::<Fr>::deserialize(&bytes[..]).unwrap();
+        assert_eq!(decoded.values(), grown.values());
+        assert_eq!(decoded.free_capacity(), 2);
+
+        assert_eq!(Table::padded_to(&values, 16).unwrap().size(), 16);
+        assert_eq!(
+            Table::padded_to(&values, 4).unwrap_err(),
+            Error::TableSizeMismatch(4, 5)
+        );
+        assert_eq!(
+            Table::<Fr>::padded(&[]).unwrap_err(),
+            Error::TableSizeNotPow2(0)
+        );
+    }
+
+    #[test]
+    fn test_generators() {
+        let table = Table::<Fr>::range(3..11).unwrap();
+        assert_eq!(table.values()[0], Fr::from(3u64));
+        assert_eq!(table.values()[7], Fr::from(10u64));
+
+        let masks = Table::<Fr>::from_fn(16, |i| Fr::from((1u64 << i) - 1)).unwrap();
+        assert_eq!(masks.values()[4], Fr::from(0b1111u64));
+
+        assert_eq!(
+            Table::<Fr>::range(0..12).unwrap_err(),
+            Error::TableSizeNotPow2(12)
+        );
+        assert_eq!(
+            Table::<Fr>::from_fn(1 << 28, |i| Fr::from(i as u64)).unwrap_err(),
+            Error::TableTooLargeForField(1 << 28, 1 << 27)
+        );
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        let raw = to_field::<Fr>(&[20, 5, 10, 5, 1, 20, 5]);
+        let canonical = Table::canonicalize(&raw).unwrap();
+        assert_eq!(
+            canonical.table.values(),
+            &to_field::<Fr>(&[1, 5, 10, 20])[..]
+        );
+        assert_eq!(canonical.positions, vec![3, 1, 2, 1, 0, 3, 1]);
+        let (five, twenty) = (Fr::from(5u64), Fr::from(20u64));
+        assert_eq!(
+            canonical.duplicates,
+            vec![
+                DuplicateValue {
+                    value: five,
+                    first: 1,
+                    position: 3
+                },
+                DuplicateValue {
+                    value: twenty,
+                    first: 0,
+                    position: 5
+                },
+                DuplicateValue {
+                    value: five,
+                    first: 1,
+                    position: 6
+                },
+            ]
+        );
+
+        // the order of the raw values doesn't matter
+        let shuffled = Table::canonicalize(&to_field::<Fr>(&[10, 1, 20, 5])).unwrap();
+        assert_eq!(shuffled.table.values(), canonical.table.values());
+        assert!(shuffled.duplicates.is_empty());
+    }
 }
@@ -0,0 +1,231 @@
+//! Tables from operational files: allowlists, precomputed function tables and the like.
+//!
+//! Every loader pads the values with `Table::padded`, so the files may hold any number of distinct values. Text
+//! values are parsed according to a [`ValueFormat`]; a value that doesn't parse or isn't below the field modulus is
+//! rejected with its position rather than reduced.
+
+use std::io::{BufRead, Read};
+
+use ark_ff::PrimeField;
+
+use crate::{error::Error, table::Table};
+
+/// Encoding of a single table value in a text file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValueFormat {
+    /// decimal integer, e.g. `1234`
+    Decimal,
+    /// big endian hex integer with an optional `0x` prefix, e.g. `0x04d2`
+    Hex,
+    /// hex of the compressed ark-serialize encoding of the field element, as in the JSON artifacts
+    Canonical,
+}
+
+/// Field element from big endian bytes of an integer below the modulus
+fn from_be_bytes<F: PrimeField>(bytes: &[u8]) -> Option<F> {
+    let start = bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len());
+    let bytes = &bytes[start..];
+    let mut repr = F::BigInt::default();
+    let limbs = repr.as_mut();
+    if bytes.len() > limbs.len() * 8 {
+        return None;
+    }
+    for (i, &b) in bytes.iter().rev().enumerate() {
+        limbs[i / 8] |= (b as u64) << (8 * (i % 8));
+    }
+    F::from_repr(repr)
+}
+
+/// Big endian bytes of a decimal integer
+fn decimal_to_be_bytes(s: &str) -> Option<Vec<u8>> {
+    let mut bytes = vec![];
+    for c in s.chars() {
+        let mut carry = c.to_digit(10)?;
+        for b in bytes.iter_mut().rev() {
+            let v = *b as u32 * 10 + carry;
+            *b = v as u8;
+            carry = v >> 8;
+        }
+        if carry > 0 {
+            bytes.insert(0, carry as u8);
+        }
+    }
+    Some(bytes)
+}
+
+impl ValueFormat {
+    /// Parses one value, surrounding whitespace is ignored
+    pub fn parse<F: PrimeField>(&self, value: &str) -> Option<F> {
+        let value = value.trim();
+        if value.is_empty() {
+            return None;
+        }
+        match self {
+            Self::Decimal => from_be_bytes(&decimal_to_be_bytes(value)?),
+            Self::Hex => {
+                let digits = value.strip_prefix("0x").unwrap_or(value);
+                // odd lengths get a leading zero nibble
+                let digits = if digits.len() % 2 == 1 {
+                    format!("0{}", digits)
+                } else {
+                    digits.to_string()
+                };
+                from_be_bytes(&hex::decode(digits).ok()?)
+            }
+            Self::Canonical => {
+                let bytes = hex::decode(value.strip_prefix("0x").unwrap_or(value)).ok()?;
+                let mut reader = &bytes[..];
+                let f = F::deserialize(&mut reader).ok()?;
+                reader.is_empty().then_some(f)
+            }
+        }
+    }
+}
+
+fn parse_at<F: PrimeField>(format: ValueFormat, value: &str, position: usize) -> Result<F, Error> {
+    format
+        .parse(value)
+        .ok_or_else(|| Error::InvalidTableValue(position, value.trim().to_string()))
+}
+
+impl<F: PrimeField> Table<F> {
+    /// Table of one `column` of a comma separated file, skipping the first line if `header` is set as well as
+    /// empty lines and lines starting with `#`. Positions in errors are line numbers.
+    pub fn from_csv<R: BufRead>(
+        reader: R,
+        column: usize,
+        header: bool,
+        format: ValueFormat,
+    ) -> Result<Self, Error> {
+        let mut values = vec![];
+        for (i, line) in reader.lines().enumerate().skip(header as usize) {
+            let line = line.map_err(|e| Error::Io(e.to_string()))?;
+            if line.trim().is_empty() || line.trim_start().starts_with('#') {
+                continue;
+            }
+            let field = line
+                .split(',')
+                .nth(column)
+                .ok_or_else(|| Error::InvalidTableValue(i + 1, line.clone()))?;
+            values.push(parse_at(format, field, i + 1)?);
+        }
+        Self::padded(&values)
+    }
+
+    /// Table of a JSON array whose entries are strings in `format` or, for decimal values, numbers. Positions in
+    /// errors are array indices.
+    #[cfg(feature = "serde")]
+    pub fn from_json<R: Read>(reader: R, format: ValueFormat) -> Result<Self, Error> {
+        use serde_json::Value;
+
+        let entries: Vec<Value> =
+            serde_json::from_reader(reader).map_err(|e| Error::Serialization(e.to_string()))?;
+        let values = entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| match entry {
+                Value::String(s) => parse_at(format, s, i),
+                Value::Number(n) if format == ValueFormat::Decimal => n
+                    .as_u64()
+                    .map(F::from)
+                    .ok_or_else(|| Error::InvalidTableValue(i, n.to_string())),
+                _ => Err(Error::InvalidTableValue(i, entry.to_string())),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::padded(&values)
+    }
+
+    /// Table of concatenated compressed field elements, as written by `CanonicalSerialize` without a length
+    /// prefix. Positions in errors are value indices.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let size = F::zero().serialized_size();
+        if bytes.len() % size != 0 {
+            return Err(Error::InvalidEncoding(
+                "table bytes are not a whole number of values",
+            ));
+        }
+        let values = bytes
+            .chunks(size)
+            .enumerate()
+            .map(|(i, mut chunk)| {
+                F::deserialize(&mut chunk)
+                    .map_err(|_| Error::InvalidTableValue(i, hex::encode(chunk)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::padded(&values)
+    }
+
+    /// `from_bytes` of everything `reader` holds
+    pub fn read_bytes<R: Read>(mut reader: R) -> Result<Self, Error> {
+        let mut bytes = vec![];
+        reader
+            .read_to_end(&mut bytes)
+            .map_err(|e| Error::Io(e.to_string()))?;
+        Self::from_bytes(&bytes)
+    }
+}
+
+#[cfg(test)]
+mod table_loader_tests {
+    use ark_bn254::Fr;
+    use ark_ff::{BigInteger, FpParameters, PrimeField};
+    use ark_serialize::CanonicalSerialize;
+
+    use crate::{error::Error, table::Table, utils::to_field};
+
+    use super::ValueFormat;
+
+    #[test]
+    fn test_table_loaders() {
+        let csv = "id,value\n# allowlist\n1,10\n2,0x14\n\n3,30\n";
+        let res = Table::<Fr>::from_csv(csv.as_bytes(), 1, true, ValueFormat::Decimal);
+        assert_eq!(
+            res.err(),
+            Some(Error::InvalidTableValue(4, "0x14".to_string()))
+        );
+        let csv = "id,value\n1,10\n2,20\n3,30\n";
+        let table = Table::<Fr>::from_csv(csv.as_bytes(), 1, true, ValueFormat::Decimal).unwrap();
+        assert_eq!(table.values(), &to_field::<Fr>(&[10, 20, 30, 30])[..]);
+
+        let mut bytes = vec![];
+        for v in to_field::<Fr>(&[10, 20, 30]) {
+            v.serialize(&mut bytes).unwrap();
+        }
+        assert_eq!(
+            Table::<Fr>::from_bytes(&bytes).unwrap().values(),
+            table.values()
+        );
+        assert!(Table::<Fr>::from_bytes(&bytes[1..]).is_err());
+
+        assert_eq!(ValueFormat::Hex.parse::<Fr>("0x1f"), Some(Fr::from(31u64)));
+        let mut canonical = vec![];
+        Fr::from(31u64).serialize(&mut canonical).unwrap();
+        let canonical = format!("0x{}", hex::encode(canonical));
+        assert_eq!(
+            ValueFormat::Canonical.parse::<Fr>(&canonical),
+            Some(Fr::from(31u64))
+        );
+        // the modulus itself is rejected instead of read as 0
+        let modulus = hex::encode(<Fr as PrimeField>::Params::MODULUS.to_bytes_be());
+        assert_eq!(ValueFormat::Hex.parse::<Fr>(&modulus), None);
+        assert_eq!(
+            ValueFormat::Decimal.parse::<Fr>(
+                "21888242871839275222246405745257275088548364400416034343698204186575808495617"
+            ),
+            None
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_table_from_json() {
+        let json = r#"[1, "2", "3", "4", "5"]"#;
+        let table = Table::<Fr>::from_json(json.as_bytes(), ValueFormat::Decimal).unwrap();
+        assert_eq!((table.size(), table.unpadded_size()), (8, 5));
+        let res = Table::<Fr>::from_json(r#"[1, true]"#.as_bytes(), ValueFormat::Decimal);
+        assert_eq!(
+            res.err(),
+            Some(Error::InvalidTableValue(1, "true".to_string()))
+        );
+    }
+}
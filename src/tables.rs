@@ -0,0 +1,3 @@
+//! Ready made tables.
+
+pub mod standard;
@@ -0,0 +1,101 @@
+//! Canonical tables for byte oriented circuits.
+//!
+//! Tables of binary operations are single column: the row `(a, b, a op b)` is encoded as `a + 2^8·b + 2^16·(a op b)`
+//! with [`encode_byte_op`], so witness values have to be encoded the same way before they are looked up.
+
+use ark_ff::FftField;
+
+use crate::table::Table;
+
+/// `[0, 2^8)`
+pub fn u8_range<F: FftField>() -> Table<F> {
+    Table::range(0..1 << 8).expect("2^8 values")
+}
+
+/// `[0, 2^16)`
+pub fn u16_range<F: FftField>() -> Table<F> {
+    Table::range(0..1 << 16).expect("2^16 values")
+}
+
+/// `a + 2^8·b + 2^16·c`, the row `(a, b, c)` of a byte operation table
+pub fn encode_byte_op<F: FftField>(a: u8, b: u8, c: u8) -> F {
+    F::from(a as u64 | (b as u64) << 8 | (c as u64) << 16)
+}
+
+/// Rows `(a, b, op(a, b))` for every pair of bytes
+pub fn byte_op<F: FftField>(op: impl Fn(u8, u8) -> u8) -> Table<F> {
+    Table::from_fn(1 << 16, |i| {
+        let (a, b) = (i as u8, (i >> 8) as u8);
+        encode_byte_op(a, b, op(a, b))
+    })
+    .expect("2^16 distinct rows")
+}
+
+pub fn xor8<F: FftField>() -> Table<F> {
+    byte_op(|a, b| a ^ b)
+}
+
+pub fn and8<F: FftField>() -> Table<F> {
+    byte_op(|a, b| a & b)
+}
+
+pub fn or8<F: FftField>() -> Table<F> {
+    byte_op(|a, b| a | b)
+}
+
+/// Rows `(a, s, a << s)` for shifts `s < 8`, the result is truncated to a byte
+pub fn shl8<F: FftField>() -> Table<F> {
+    shift8(|a, s| a << s)
+}
+
+/// Rows `(a, s, a >> s)` for shifts `s < 8`
+pub fn shr8<F: FftField>() -> Table<F> {
+    shift8(|a, s| a >> s)
+}
+
+fn shift8<F: FftField>(op: impl Fn(u8, u8) -> u8) -> Table<F> {
+    Table::from_fn(1 << 11, |i| {
+        let (a, s) = (i as u8, (i >> 8) as u8);
+        encode_byte_op(a, s, op(a, s))
+    })
+    .expect("2^11 distinct rows")
+}
+
+#[cfg(test)]
+mod standard_tests {
+    use ark_bn254::{Bn254, Fr};
+    use ark_std::{test_rng, vec::Vec};
+    use rand_chacha::ChaChaRng;
+    use sha3::Keccak256;
+
+    use crate::{config::CqConfig, cq::Cq, rng::SimpleHashFiatShamirRng};
+
+    use super::{encode_byte_op, shl8, xor8};
+
+    type FS = SimpleHashFiatShamirRng<Keccak256, ChaChaRng>;
+
+    #[test]
+    fn test_byte_tables() {
+        let xor = xor8::<Fr>();
+        assert_eq!(xor.size(), 1 << 16);
+        assert_eq!(xor.values()[0x3c5a], encode_byte_op(0x5a, 0x3c, 0x66));
+        assert_eq!(shl8::<Fr>().values()[0x3ff], encode_byte_op(0xff, 3, 0xf8));
+
+        let mut rng = test_rng();
+        let config = CqConfig::<Bn254, FS>::builder()
+            .table_size(1 << 11)
+            .witness_size(4)
+            .build()
+            .unwrap();
+        let (pk, vk) = config.unsafe_setup(shl8(), &mut rng).unwrap();
+        let rows: Vec<Fr> = [(1, 1, 2), (0x81, 1, 0x02), (0xf0, 7, 0), (7, 0, 7)]
+            .iter()
+            .map(|&(a, s, c)| encode_byte_op(a, s, c))
+            .collect();
+        let (statement, proof) = Cq::<Bn254, FS>::prove(&pk, &rows).unwrap();
+        assert!(Cq::<Bn254, FS>::verify(&vk, &statement, &proof).is_ok());
+
+        let wrong = [encode_byte_op(1, 1, 3), rows[1], rows[2], rows[3]].to_vec();
+        assert!(Cq::<Bn254, FS>::prove(&pk, &wrong).is_err());
+    }
+}
@@ -1,11 +1,14 @@
-use std::iter;
-
 use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
-use ark_ff::{Field, PrimeField};
-use ark_poly::{univariate::DensePolynomial, EvaluationDomain, GeneralEvaluationDomain};
+use ark_ff::{Field, One, PrimeField};
+use ark_poly::{
+    univariate::DensePolynomial, EvaluationDomain, GeneralEvaluationDomain, UVPolynomial,
+};
+use ark_std::{cfg_into_iter, cfg_iter, ops::Range, vec::Vec};
 use fk::UpperToeplitz;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
-use crate::utils::is_pow_2;
+use crate::{kzg::Kzg, utils::is_pow_2};
 
 pub fn compute_lagrange_basis_commitments<C: AffineCurve>(tau_powers: &[C]) -> Vec<C> {
     let n = tau_powers.len();
@@ -18,19 +21,17 @@ pub fn compute_lagrange_basis_commitments<C: AffineCurve>(tau_powers: &[C]) -> V
         .unwrap()
         .into_repr();
 
-    let tau_projective: Vec<C::Projective> = tau_powers
-        .iter()
+    let tau_projective: Vec<C::Projective> = cfg_iter!(tau_powers)
         .map(|tau_pow_i| tau_pow_i.into_projective())
         .collect();
     let p_evals: Vec<C::Projective> = domain.fft(&tau_projective);
-    let p_evals_reversed = iter::once(p_evals[0]).chain(p_evals.into_iter().skip(1).rev());
 
-    let mut ls: Vec<C::Projective> = p_evals_reversed
-        .into_iter()
-        .map(|pi| pi.mul(n_inv))
+    // [L_i(x)]_1 is the (n - i mod n)-th evaluation, scaled by 1/n
+    let mut ls: Vec<C::Projective> = cfg_into_iter!(0..n)
+        .map(|i| p_evals[(n - i) % n].mul(n_inv))
         .collect();
     C::Projective::batch_normalization(&mut ls);
-    ls.iter().map(|li| li.into_affine()).collect()
+    cfg_iter!(ls).map(|li| li.into_affine()).collect()
 }
 
 pub fn compute_qs<E: PairingEngine>(
@@ -38,6 +39,16 @@ pub fn compute_qs<E: PairingEngine>(
     domain: &GeneralEvaluationDomain<E::Fr>,
     srs_g1: &[E::G1Affine],
 ) -> Vec<E::G1Affine> {
+    let h = compute_fk_h::<E>(t, domain, srs_g1);
+    compute_qs_from_h::<E>(&h, domain)
+}
+
+/// First half of `compute_qs`, the commitments [h_i(x)]_1 of the Toeplitz product of Feist–Khovratovich
+pub fn compute_fk_h<E: PairingEngine>(
+    t: &DensePolynomial<E::Fr>,
+    domain: &GeneralEvaluationDomain<E::Fr>,
+    srs_g1: &[E::G1Affine],
+) -> Vec<E::G1Projective> {
     /*
         - N (table size) is always pow2
         - Toeplitz multiplication will happen in 2 * N, so appending zero commitments on hs is not needed
@@ -45,31 +56,86 @@ pub fn compute_qs<E: PairingEngine>(
 
     let toeplitz = UpperToeplitz::from_poly(t);
 
-    let mut srs_proj: Vec<E::G1Projective> = srs_g1.iter().map(|t| t.into_projective()).collect();
+    let mut srs_proj: Vec<E::G1Projective> =
+        cfg_iter!(srs_g1).map(|t| t.into_projective()).collect();
     srs_proj.reverse();
 
-    let h_commitments: Vec<E::G1Projective> = toeplitz.mul_by_vec(&srs_proj);
+    let mut h_commitments: Vec<E::G1Projective> = toeplitz.mul_by_vec(&srs_proj);
     assert_eq!(h_commitments.len(), 2 * domain.size());
+    h_commitments.truncate(domain.size());
+    h_commitments
+}
 
-    let ks: Vec<_> = domain.fft(&h_commitments[..domain.size()]);
+/// Second half of `compute_qs`, [Qi(x)]_1 from the output of `compute_fk_h`
+pub fn compute_qs_from_h<E: PairingEngine>(
+    h: &[E::G1Projective],
+    domain: &GeneralEvaluationDomain<E::Fr>,
+) -> Vec<E::G1Affine> {
+    let ks: Vec<_> = domain.fft(h);
 
     let n_inv = domain.size_as_field_element().inverse().unwrap();
-    let normalized_roots = domain.elements().map(|g_i| g_i * n_inv);
+    let normalized_roots: Vec<E::Fr> = domain.elements().map(|g_i| g_i * n_inv).collect();
 
-    let mut qs: Vec<E::G1Projective> = ks
-        .iter()
-        .zip(normalized_roots)
+    let mut qs: Vec<E::G1Projective> = cfg_iter!(ks)
+        .zip(cfg_iter!(normalized_roots))
         .map(|(ki, normalizer_i)| ki.mul(normalizer_i.into_repr()))
         .collect();
 
     E::G1Projective::batch_normalization(&mut qs);
-    qs.iter().map(|qi| qi.into_affine()).collect()
+    cfg_iter!(qs).map(|qi| qi.into_affine()).collect()
+}
+
+/// Same as `compute_qs` with one division and one MSM per table element, O(N^2), kept as a baseline
+pub fn compute_qs_naive<E: PairingEngine>(
+    t: &DensePolynomial<E::Fr>,
+    domain: &GeneralEvaluationDomain<E::Fr>,
+    srs_g1: &[E::G1Affine],
+) -> Vec<E::G1Affine> {
+    compute_qs_naive_range::<E>(t, domain, srs_g1, 0..domain.size())
+}
+
+/// The quotients `range` of `compute_qs_naive`, for indexers that compute them in batches
+pub fn compute_qs_naive_range<E: PairingEngine>(
+    t: &DensePolynomial<E::Fr>,
+    domain: &GeneralEvaluationDomain<E::Fr>,
+    srs_g1: &[E::G1Affine],
+    range: Range<usize>,
+) -> Vec<E::G1Affine> {
+    let n_inv = domain.size_as_field_element().inverse().unwrap();
+
+    let mut qs: Vec<E::G1Projective> = cfg_into_iter!(range)
+        .map(|i| {
+            // [(T(x) - T(w^i)) / (x - w^i)]_1 · w^i / N
+            let root = domain.element(i);
+            let divisor = DensePolynomial::from_coefficients_slice(&[-root, E::Fr::one()]);
+            let quotient = t / &divisor;
+            Kzg::<E>::commit_g1(srs_g1, &quotient).mul((root * n_inv).into_repr())
+        })
+        .collect();
+
+    E::G1Projective::batch_normalization(&mut qs);
+    cfg_iter!(qs).map(|qi| qi.into_affine()).collect()
+}
+
+/// Quotient of `coeffs` by `X - point` with Ruffini's rule in the buffer of `coeffs`, the remainder is dropped
+pub fn divide_by_linear<F: Field>(mut coeffs: Vec<F>, point: F) -> DensePolynomial<F> {
+    for i in (1..coeffs.len()).rev() {
+        let carry = coeffs[i] * point;
+        coeffs[i - 1] += carry;
+    }
+    if !coeffs.is_empty() {
+        coeffs.remove(0);
+    }
+    DensePolynomial::from_coefficients_vec(coeffs)
 }
 
 #[cfg(test)]
 mod test_tools {
     use ark_bn254::{Bn254, Fr, G1Affine};
-    use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
+    use ark_ff::{One, UniformRand, Zero};
+    use ark_poly::{
+        univariate::DensePolynomial, EvaluationDomain, GeneralEvaluationDomain, UVPolynomial,
+    };
     use ark_std::{rand::rngs::StdRng, test_rng};
 
     use crate::{
@@ -77,7 +143,7 @@ mod test_tools {
         utils::{construct_lagrange_basis, unsafe_setup_from_rng},
     };
 
-    use super::compute_lagrange_basis_commitments;
+    use super::{compute_lagrange_basis_commitments, divide_by_linear};
 
     #[test]
     fn test_li_commitments() {
@@ -97,4 +163,21 @@ mod test_tools {
         let lagrange_basis_1_fast = compute_lagrange_basis_commitments(&srs_g1);
         assert_eq!(lagrange_basis_1_slow, lagrange_basis_1_fast);
     }
+
+    #[test]
+    fn test_divide_by_linear() {
+        let mut rng = test_rng();
+        let poly = DensePolynomial::<Fr>::rand(9, &mut rng);
+        let point = Fr::rand(&mut rng);
+        let divisor = DensePolynomial::from_coefficients_slice(&[-point, Fr::one()]);
+
+        assert_eq!(
+            divide_by_linear(poly.coeffs.clone(), point),
+            &poly / &divisor
+        );
+        assert_eq!(
+            divide_by_linear(Vec::<Fr>::new(), point),
+            DensePolynomial::zero()
+        );
+    }
 }
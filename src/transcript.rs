@@ -1,44 +1,331 @@
 use ark_ec::PairingEngine;
-use ark_ff::{to_bytes, Field, ToBytes};
+use ark_ff::ToBytes;
+use ark_serialize::CanonicalSerialize;
+use ark_std::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
 
 use crate::{
     data_structures::Statement,
     indexer::CommonPreprocessedInput,
     prover::{ProverFirstMessage, ProverSecondMessage, ProverThirdMessage},
     rng::FiatShamirRng,
+    verifier::VerifierKey,
+    PROTOCOL_VERSION,
 };
 
+/// Challenge generation of the prover and the verifier.
+///
+/// Both sides absorb the same labeled elements in the same order, so any implementation works as long as
+/// every challenge depends on everything absorbed before it. Implement it to run the protocol on a
+/// transcript shared with an outer proof.
+pub trait Transcript<E: PairingEngine> {
+    /// Separates the messages of a round, `version` is the `PROTOCOL_VERSION` of the proof
+    fn begin_round(&mut self, version: u8, label: &'static [u8]);
+    fn absorb_g1(&mut self, label: &'static [u8], point: &E::G1Affine);
+    fn absorb_g2(&mut self, label: &'static [u8], point: &E::G2Affine);
+    fn absorb_field(&mut self, label: &'static [u8], x: &E::Fr);
+    fn squeeze_challenge(&mut self, label: &'static [u8]) -> E::Fr;
+}
+
+/// [`Transcript`] over a `FiatShamirRng`, labels are ignored and the `ToBytes` encodings of everything
+/// absorbed since the last challenge go into a single `absorb`. Challenges come from `squeeze_field`.
 pub struct TranscriptOracle<FS: FiatShamirRng> {
     fs_rng: FS,
+    pending: Vec<u8>,
 }
 
 impl<FS: FiatShamirRng> TranscriptOracle<FS> {
     pub fn initialize<'a, T: 'a + ToBytes>(initial_input: &'a T) -> Self {
-        let fs_rng = FS::initialize(&to_bytes![initial_input].unwrap());
-        Self { fs_rng }
+        let mut bytes = Vec::new();
+        initial_input
+            .write(&mut bytes)
+            .expect("failed to convert to bytes");
+        Self {
+            fs_rng: FS::initialize(&bytes),
+            pending: Vec::new(),
+        }
     }
 
-    pub fn squeeze_challenge<F: Field>(&mut self) -> F {
-        F::rand(&mut self.fs_rng)
+    fn write<T: ToBytes>(&mut self, value: &T) {
+        value
+            .write(&mut self.pending)
+            .expect("failed to convert to bytes");
     }
+}
 
-    pub fn stream_public_input<E: PairingEngine>(
-        &mut self,
-        common: &CommonPreprocessedInput<E>,
-        statement: &Statement<E>,
-    ) {
-        self.fs_rng.absorb(&to_bytes![common, statement].unwrap());
+/// `version || label length || label`, the bytes a [`TranscriptOracle`] absorbs at the start of a round
+pub fn round_separator(version: u8, label: &[u8]) -> Vec<u8> {
+    [&[version, label.len() as u8][..], label].concat()
+}
+
+impl<E: PairingEngine, FS: FiatShamirRng> Transcript<E> for TranscriptOracle<FS> {
+    fn begin_round(&mut self, version: u8, label: &'static [u8]) {
+        self.pending
+            .extend_from_slice(&round_separator(version, label));
+    }
+
+    fn absorb_g1(&mut self, _: &'static [u8], point: &E::G1Affine) {
+        self.write(point);
     }
 
-    pub fn stream_first_message<E: PairingEngine>(&mut self, msg: &ProverFirstMessage<E>) {
-        self.fs_rng.absorb(&to_bytes![msg].unwrap());
+    fn absorb_g2(&mut self, _: &'static [u8], point: &E::G2Affine) {
+        self.write(point);
     }
 
-    pub fn stream_second_message<E: PairingEngine>(&mut self, msg: &ProverSecondMessage<E>) {
-        self.fs_rng.absorb(&to_bytes![msg].unwrap());
+    fn absorb_field(&mut self, _: &'static [u8], x: &E::Fr) {
+        self.write(x);
+    }
+
+    fn squeeze_challenge(&mut self, _: &'static [u8]) -> E::Fr {
+        if !self.pending.is_empty() {
+            self.fs_rng.absorb(&self.pending);
+            self.pending.clear();
+        }
+        self.fs_rng.squeeze_field()
+    }
+}
+
+/// One operation seen by a [`RecordingTranscript`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TranscriptEntry {
+    /// `round`, `g1`, `g2`, `field` or `challenge`, the value of a round is its version
+    pub kind: String,
+    pub label: String,
+    /// compressed ark-serialize encoding as `0x` prefixed hex
+    pub value: String,
+}
+
+/// Wraps a transcript and records every absorbed element and every challenge with its label, for
+/// finding the first operation where two implementations disagree
+pub struct RecordingTranscript<T> {
+    inner: T,
+    entries: Vec<TranscriptEntry>,
+}
+
+impl<T> RecordingTranscript<T> {
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            entries: Vec::new(),
+        }
     }
 
-    pub fn stream_third_message<E: PairingEngine>(&mut self, msg: &ProverThirdMessage<E>) {
-        self.fs_rng.absorb(&to_bytes![msg].unwrap());
+    pub fn entries(&self) -> &[TranscriptEntry] {
+        &self.entries
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Position of the first entry that differs from `other`, or where the shorter log ends
+    pub fn first_divergence(&self, other: &[TranscriptEntry]) -> Option<usize> {
+        let mismatch = self.entries.iter().zip(other).position(|(a, b)| a != b);
+        match mismatch {
+            Some(i) => Some(i),
+            None if self.entries.len() != other.len() => Some(self.entries.len().min(other.len())),
+            None => None,
+        }
+    }
+
+    fn record<V: CanonicalSerialize>(&mut self, kind: &str, label: &[u8], value: &V) {
+        let mut bytes = Vec::with_capacity(value.serialized_size());
+        value
+            .serialize(&mut bytes)
+            .expect("serializing into a vec can't fail");
+        self.entries.push(TranscriptEntry {
+            kind: kind.to_string(),
+            label: String::from_utf8_lossy(label).into_owned(),
+            value: format!("0x{}", hex::encode(bytes)),
+        });
+    }
+}
+
+impl<E: PairingEngine, T: Transcript<E>> Transcript<E> for RecordingTranscript<T> {
+    fn begin_round(&mut self, version: u8, label: &'static [u8]) {
+        self.record("round", label, &version);
+        self.inner.begin_round(version, label);
+    }
+
+    fn absorb_g1(&mut self, label: &'static [u8], point: &E::G1Affine) {
+        self.record("g1", label, point);
+        self.inner.absorb_g1(label, point);
+    }
+
+    fn absorb_g2(&mut self, label: &'static [u8], point: &E::G2Affine) {
+        self.record("g2", label, point);
+        self.inner.absorb_g2(label, point);
+    }
+
+    fn absorb_field(&mut self, label: &'static [u8], x: &E::Fr) {
+        self.record("field", label, x);
+        self.inner.absorb_field(label, x);
+    }
+
+    fn squeeze_challenge(&mut self, label: &'static [u8]) -> E::Fr {
+        let challenge = self.inner.squeeze_challenge(label);
+        self.record("challenge", label, &challenge);
+        challenge
+    }
+}
+
+/// Binds the proof to the verifier key, the table and the statement
+pub(crate) fn absorb_public_input<E: PairingEngine, T: Transcript<E>>(
+    transcript: &mut T,
+    vk: &VerifierKey<E>,
+    common: &CommonPreprocessedInput<E>,
+    statement: &Statement<E>,
+) {
+    transcript.begin_round(PROTOCOL_VERSION, b"public-input");
+    transcript.absorb_g2(b"x", &vk.x_2);
+    transcript.absorb_g2(b"x_pow_b0_bound", &vk.x_pow_b0_bound_2);
+    transcript.absorb_field(b"table_size", &E::Fr::from(vk.table_size as u64));
+    transcript.absorb_field(b"witness_size", &E::Fr::from(vk.witness_size as u64));
+    if let Some(x_sq_2) = &vk.x_sq_2 {
+        transcript.absorb_g2(b"x_sq", x_sq_2);
+    }
+    transcript.absorb_g2(b"zv", &common.zv_2);
+    transcript.absorb_g2(b"t", &common.t_2);
+    transcript.absorb_g1(b"f", &statement.f);
+}
+
+pub(crate) fn absorb_first_message<E: PairingEngine, T: Transcript<E>>(
+    transcript: &mut T,
+    msg: &ProverFirstMessage<E>,
+) {
+    transcript.begin_round(PROTOCOL_VERSION, b"round-1");
+    transcript.absorb_g1(b"m", &msg.m_cm);
+}
+
+pub(crate) fn absorb_second_message<E: PairingEngine, T: Transcript<E>>(
+    transcript: &mut T,
+    msg: &ProverSecondMessage<E>,
+) {
+    transcript.begin_round(PROTOCOL_VERSION, b"round-2");
+    transcript.absorb_g1(b"a", &msg.a_cm);
+    transcript.absorb_g1(b"qa", &msg.qa_cm);
+    transcript.absorb_g1(b"b0", &msg.b0_cm);
+    transcript.absorb_g1(b"qb", &msg.qb_cm);
+    transcript.absorb_g1(b"p", &msg.p_cm);
+    if let Some(g_cm) = &msg.g_cm {
+        transcript.absorb_g1(b"g", g_cm);
+    }
+    if let Some(a_shift_cm) = &msg.a_shift_cm {
+        transcript.absorb_g1(b"a_shift", a_shift_cm);
+    }
+}
+
+pub(crate) fn absorb_third_message<E: PairingEngine, T: Transcript<E>>(
+    transcript: &mut T,
+    msg: &ProverThirdMessage<E>,
+) {
+    transcript.begin_round(PROTOCOL_VERSION, b"round-3");
+    transcript.absorb_field(b"b0_at_gamma", &msg.b0_at_gamma);
+    transcript.absorb_field(b"f_at_gamma", &msg.f_at_gamma);
+    transcript.absorb_field(b"a_at_zero", &msg.a_at_zero);
+    transcript.absorb_g1(b"pi_gamma", &msg.pi_gamma);
+    transcript.absorb_g1(b"a0", &msg.a0_cm);
+}
+
+#[cfg(test)]
+mod transcript_tests {
+    use ark_bn254::{Bn254, Fr, G1Affine, G2Affine};
+    use ark_std::{test_rng, vec::Vec};
+    use rand_chacha::ChaChaRng;
+    use sha3::Keccak256;
+
+    use crate::{
+        cq::Cq,
+        data_structures::{Statement, Witness},
+        prover::Prover,
+        rng::SimpleHashFiatShamirRng,
+        utils::to_field,
+        verifier::Verifier,
+        PROTOCOL_NAME,
+    };
+
+    use super::{Transcript, TranscriptOracle};
+
+    type FS = SimpleHashFiatShamirRng<Keccak256, ChaChaRng>;
+
+    /// records the labels it sees, like an outer protocol logging its transcript
+    struct Recorder {
+        inner: TranscriptOracle<FS>,
+        labels: Vec<&'static [u8]>,
+    }
+
+    impl Transcript<Bn254> for Recorder {
+        fn begin_round(&mut self, version: u8, label: &'static [u8]) {
+            self.labels.push(label);
+            Transcript::<Bn254>::begin_round(&mut self.inner, version, label);
+        }
+
+        fn absorb_g1(&mut self, label: &'static [u8], point: &G1Affine) {
+            self.labels.push(label);
+            Transcript::<Bn254>::absorb_g1(&mut self.inner, label, point);
+        }
+
+        fn absorb_g2(&mut self, label: &'static [u8], point: &G2Affine) {
+            self.labels.push(label);
+            Transcript::<Bn254>::absorb_g2(&mut self.inner, label, point);
+        }
+
+        fn absorb_field(&mut self, label: &'static [u8], x: &Fr) {
+            self.labels.push(label);
+            Transcript::<Bn254>::absorb_field(&mut self.inner, label, x);
+        }
+
+        fn squeeze_challenge(&mut self, label: &'static [u8]) -> Fr {
+            self.labels.push(label);
+            Transcript::<Bn254>::squeeze_challenge(&mut self.inner, label)
+        }
+    }
+
+    fn recorder() -> Recorder {
+        Recorder {
+            inner: TranscriptOracle::initialize(&PROTOCOL_NAME),
+            labels: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_custom_transcript() {
+        let mut rng = test_rng();
+        let (pk, vk) =
+            Cq::<Bn254, FS>::setup(&to_field(&[1, 5, 10, 15, 20, 25, 30, 35]), 4, &mut rng)
+                .unwrap();
+        let witness = Witness::<Fr>::new(&to_field(&[5, 15, 20, 35])).unwrap();
+        let statement = Statement::from_witness(&pk.pk, &witness);
+
+        let mut prover_transcript = recorder();
+        let (proof, _) = Prover::prove_with_transcript(
+            &mut prover_transcript,
+            &pk.pk,
+            &pk.vk,
+            &pk.index,
+            &pk.table,
+            &witness,
+            &statement,
+        )
+        .unwrap();
+
+        let mut verifier_transcript = recorder();
+        assert!(Verifier::verify_with_transcript(
+            &mut verifier_transcript,
+            &vk.vk,
+            &vk.common,
+            &statement,
+            &proof
+        )
+        .is_ok());
+        assert_eq!(prover_transcript.labels, verifier_transcript.labels);
+        assert_eq!(prover_transcript.labels.len(), 26);
+
+        // the adapter over the rng produces the same challenges as `Cq::verify`
+        assert!(Cq::<Bn254, FS>::verify(&vk, &statement, &proof).is_ok());
     }
 }
@@ -1,49 +1,47 @@
-use ark_ec::{AffineCurve, PairingEngine};
-use ark_ff::{FftField, Field, One, PrimeField};
-use ark_poly::{univariate::DensePolynomial, UVPolynomial};
-use ark_std::rand::RngCore;
-use ark_std::UniformRand;
-use std::{cmp::max, iter};
+use ark_ec::AffineCurve;
+#[cfg(any(test, feature = "test-utils"))]
+use ark_ec::PairingEngine;
+use ark_ff::{FftField, FftParameters, Field};
+#[cfg(any(test, feature = "test-utils"))]
+use ark_ff::{One, PrimeField};
+use ark_poly::{
+    univariate::DensePolynomial, EvaluationDomain, GeneralEvaluationDomain, UVPolynomial,
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::vec::Vec;
+#[cfg(any(test, feature = "test-utils"))]
+use ark_std::{cmp::max, iter, rand::RngCore, UniformRand};
+#[cfg(any(test, feature = "test-utils"))]
+use zeroize::Zeroize;
 
-/// Create srs from rng
+use crate::error::Error;
+
+/// Create srs from rng. Whoever runs it knows tau and can forge proofs, so it is only built for tests and with the
+/// `test-utils` feature; deployments load a `srs::Srs` instead. Tau and its powers are wiped before returning.
+#[cfg(any(test, feature = "test-utils"))]
 pub fn unsafe_setup_from_rng<E: PairingEngine, R: RngCore>(
     max_power_g1: usize,
     max_power_g2: usize,
     rng: &mut R,
 ) -> (Vec<E::G1Affine>, Vec<E::G2Affine>) {
-    let tau = E::Fr::rand(rng);
-    let size = max(max_power_g1 + 1, max_power_g2 + 1);
-    let powers_of_tau: Vec<E::Fr> = iter::successors(Some(E::Fr::one()), |p| Some(*p * tau))
-        .take(size)
-        .collect();
-
-    let g1_gen = E::G1Affine::prime_subgroup_generator();
-    let g2_gen = E::G2Affine::prime_subgroup_generator();
-
-    let srs_g1: Vec<E::G1Affine> = powers_of_tau
-        .iter()
-        .take(max_power_g1 + 1)
-        .map(|tp| g1_gen.mul(tp.into_repr()).into())
-        .collect();
-
-    let srs_g2: Vec<E::G2Affine> = powers_of_tau
-        .iter()
-        .take(max_power_g2 + 1)
-        .map(|tp| g2_gen.mul(tp.into_repr()).into())
-        .collect();
-    (srs_g1, srs_g2)
+    let mut tau = E::Fr::rand(rng);
+    let srs = unsafe_setup_from_tau::<E>(max_power_g1, max_power_g2, tau);
+    tau.zeroize();
+    srs
 }
 
-/// Create srs from specific tau
-pub fn unsafe_setup_from_tau<E: PairingEngine, R: RngCore>(
+/// Create srs from specific tau, the powers of tau are wiped before returning but the caller's copy of tau isn't
+#[cfg(any(test, feature = "test-utils"))]
+pub fn unsafe_setup_from_tau<E: PairingEngine>(
     max_power_g1: usize,
     max_power_g2: usize,
-    tau: E::Fr,
+    mut tau: E::Fr,
 ) -> (Vec<E::G1Affine>, Vec<E::G2Affine>) {
     let size = max(max_power_g1 + 1, max_power_g2 + 1);
-    let powers_of_tau: Vec<E::Fr> = iter::successors(Some(E::Fr::one()), |p| Some(*p * tau))
+    let mut powers_of_tau: Vec<E::Fr> = iter::successors(Some(E::Fr::one()), |p| Some(*p * tau))
         .take(size)
         .collect();
+    tau.zeroize();
 
     let g1_gen = E::G1Affine::prime_subgroup_generator();
     let g2_gen = E::G2Affine::prime_subgroup_generator();
@@ -59,6 +57,7 @@ pub fn unsafe_setup_from_tau<E: PairingEngine, R: RngCore>(
         .take(max_power_g2 + 1)
         .map(|tp| g2_gen.mul(tp.into_repr()).into())
         .collect();
+    powers_of_tau.zeroize();
     (srs_g1, srs_g2)
 }
 
@@ -85,6 +84,33 @@ pub fn construct_lagrange_basis<F: FftField>(evaluation_domain: &[F]) -> Vec<Den
     bases
 }
 
+/// `p` is on the curve and in the prime order subgroup: decompressing its x coordinate, which checks both, gives `p`
+/// back. Uncompressed ark-serialize decoding only checks the subgroup, assuming the point is on the curve.
+pub(crate) fn is_valid_point<G: AffineCurve>(p: &G) -> bool {
+    let mut bytes = Vec::new();
+    p.serialize(&mut bytes).is_ok() && G::deserialize(&bytes[..]).map_or(false, |q| q == *p)
+}
+
+/// Largest fft domain of the field, 2^two-adicity elements
+pub(crate) fn max_domain_size<F: FftField>() -> usize {
+    1usize
+        .checked_shl(F::FftParams::TWO_ADICITY)
+        .unwrap_or(usize::MAX)
+}
+
+/// Largest table size of the field, the quotients need a domain of twice the table size
+pub(crate) fn max_table_size<F: FftField>() -> usize {
+    1usize
+        .checked_shl(F::FftParams::TWO_ADICITY - 1)
+        .unwrap_or(usize::MAX)
+}
+
+/// Domain of `size` elements, an error instead of a panic when the field has no domain that large
+pub(crate) fn domain<F: FftField>(size: usize) -> Result<GeneralEvaluationDomain<F>, Error> {
+    GeneralEvaluationDomain::<F>::new(size)
+        .ok_or_else(|| Error::DomainTooLarge(size, max_domain_size::<F>()))
+}
+
 pub fn is_pow_2(x: usize) -> bool {
     (x & (x - 1)) == 0
 }
@@ -1,41 +1,398 @@
-use std::{iter, marker::PhantomData, ops::Neg};
-
 use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
-use ark_ff::{Field, One};
-use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
+use ark_ff::{Field, One, ToBytes, Zero};
+use ark_poly::EvaluationDomain;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use ark_std::{io, iter, marker::PhantomData, ops::Neg, vec, vec::Vec};
 
 use crate::{
     data_structures::{Proof, Statement},
-    error::Error,
+    error::{Error, VerificationError},
     indexer::CommonPreprocessedInput,
     rng::FiatShamirRng,
-    transcript::TranscriptOracle,
-    PROTOCOL_NAME,
+    transcript::{
+        absorb_first_message, absorb_public_input, absorb_second_message, absorb_third_message,
+        RecordingTranscript, Transcript, TranscriptOracle,
+    },
+    utils::{domain, is_pow_2, is_valid_point, max_domain_size, max_table_size},
+    PROTOCOL_NAME, PROTOCOL_VERSION,
 };
 
+#[derive(Clone)]
 pub struct VerifierKey<E: PairingEngine> {
+    pub(crate) x_2: E::G2Affine,
+    pub(crate) x_pow_b0_bound_2: E::G2Affine,
     pub(crate) x: E::G2Prepared,
     pub(crate) x_pow_b0_bound: E::G2Prepared,
+    /// [1]_2, prepared once for all proofs
+    pub(crate) g: E::G2Prepared,
     pub(crate) table_size: usize,
     pub(crate) witness_size: usize,
+    /// [x^2]_2, only in keys of the zk variant
+    pub(crate) x_sq_2: Option<E::G2Affine>,
+    pub(crate) x_sq: Option<E::G2Prepared>,
+    /// [x^(D+1-N)]_2, only in plain keys whose srs goes past x^(N-1)
+    pub(crate) x_pow_a_bound_2: Option<E::G2Affine>,
+    pub(crate) x_pow_a_bound: Option<E::G2Prepared>,
+    /// highest power D of x in G1 of the srs, the degree bounds are only sound if no higher power is known
+    pub(crate) srs_degree: usize,
+}
+
+/// Highest power of x in G1 of an srs of exactly the needed size: x^(N+1) for zk keys, x^(max(N, n)-1) otherwise
+pub(crate) fn exact_srs_degree(table_size: usize, witness_size: usize, zk: bool) -> usize {
+    if zk {
+        table_size.saturating_add(1)
+    } else {
+        table_size.max(witness_size).saturating_sub(1)
+    }
+}
+
+/// Power of x that lifts B0, of degree n - 2 (n once blinded), to the highest power D of the srs
+pub(crate) fn b0_bound_shift(srs_degree: usize, witness_size: usize, zk: bool) -> usize {
+    if zk {
+        srs_degree - witness_size
+    } else {
+        srs_degree - (witness_size - 2)
+    }
+}
+
+/// Power of x that lifts A, of degree N - 1, to the highest power D of the srs, if the srs goes past x^(N-1).
+/// zk keys bound A through G instead.
+pub(crate) fn a_bound_shift(table_size: usize, srs_degree: usize, zk: bool) -> Option<usize> {
+    (!zk && srs_degree >= table_size).then(|| srs_degree - (table_size - 1))
+}
+
+/// Degree bound the verifier enforces on a committed polynomial of a proof
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DegreeBound {
+    /// proof element committing to the polynomial, `g` for G = N·(A(X) - A(0)) - n·P(X) of zk proofs, which
+    /// is committed through `a_cm` and `p_cm`
+    pub element: &'static str,
+    /// the polynomial has degree below it
+    pub degree: usize,
+    /// commitment to x^shift times the polynomial, checked against [x^shift]_2, or `None` if the powers of the
+    /// proving key are the bound
+    pub shifted: Option<(&'static str, usize)>,
 }
 
 impl<E: PairingEngine> VerifierKey<E> {
-    pub fn new(srs_g2: &[E::G2Affine], table_size: usize, witness_size: usize) -> Self {
+    /// Key for a proving key whose srs stops at x^(max(N, n)-1) in G1, or x^(N+1) for zk keys. Fails when
+    /// `srs_g2` doesn't reach x^N, or x^(n-N) for witnesses larger than the table. The G1 srs has to stop at the
+    /// degree the sizes need, see `for_srs_degree` for a larger one. Witnesses larger than the table need a plain
+    /// key.
+    pub fn for_table(
+        srs_g2: &[E::G2Affine],
+        table_size: usize,
+        witness_size: usize,
+        zk: bool,
+    ) -> Result<Self, Error> {
+        if witness_size < 2 {
+            return Err(Error::WitnessSizeTooSmall(witness_size));
+        }
+        let srs_degree = exact_srs_degree(table_size, witness_size, zk);
+        Self::for_srs_degree(srs_g2, srs_degree, table_size, witness_size, zk)
+    }
+
+    /// `for_table` for an srs whose powers stop at x^srs_degree in G1, e.g. a universal srs shared by tables of
+    /// different sizes. `srs_degree` is the one of the whole ceremony: a prover can commit with any power it
+    /// knows, so the degree bounds are checked against the highest one, which needs `srs_g2` to reach
+    /// x^(D+1-N) and x^(D+2-n). zk keys need the srs to stop at x^(N+1).
+    pub fn for_srs_degree(
+        srs_g2: &[E::G2Affine],
+        srs_degree: usize,
+        table_size: usize,
+        witness_size: usize,
+        zk: bool,
+    ) -> Result<Self, Error> {
+        if witness_size < 2 {
+            return Err(Error::WitnessSizeTooSmall(witness_size));
+        }
+        if table_size == 0 || !is_pow_2(table_size) {
+            return Err(Error::TableSizeNotPow2(table_size));
+        }
+        if zk && witness_size > table_size {
+            return Err(Error::WitnessLargerThanTable(witness_size, table_size));
+        }
+        let exact = exact_srs_degree(table_size, witness_size, zk);
+        if srs_degree < exact {
+            return Err(Error::SrsG1TooSmall(exact + 1, srs_degree + 1));
+        }
+        if zk && srs_degree > exact {
+            return Err(Error::SrsG1TooLarge(
+                exact + 1,
+                srs_degree.saturating_add(1),
+            ));
+        }
+        let degree = table_size
+            .max(b0_bound_shift(srs_degree, witness_size, zk))
+            .max(a_bound_shift(table_size, srs_degree, zk).unwrap_or(0));
+        if srs_g2.len() < degree + 1 {
+            return Err(Error::SrsG2TooSmall(degree + 1, srs_g2.len()));
+        }
+        let vk = Self::build(srs_g2, srs_degree, table_size, witness_size, zk);
+        vk.check_shape()?;
+        Ok(vk)
+    }
+
+    fn build(
+        srs_g2: &[E::G2Affine],
+        srs_degree: usize,
+        table_size: usize,
+        witness_size: usize,
+        zk: bool,
+    ) -> Self {
+        let b0_shift = b0_bound_shift(srs_degree, witness_size, zk);
+        let a_shift = a_bound_shift(table_size, srs_degree, zk);
+        Self::from_parts(srs_g2[1], srs_g2[b0_shift], table_size, witness_size)
+            .with_x_sq(zk.then(|| srs_g2[2]))
+            .with_a_bound(a_shift.map(|shift| srs_g2[shift]))
+            .with_srs_degree(srs_degree)
+    }
+
+    /// Plain key with the srs degree of the sizes, set the others with `with_*`
+    pub(crate) fn from_parts(
+        x_2: E::G2Affine,
+        x_pow_b0_bound_2: E::G2Affine,
+        table_size: usize,
+        witness_size: usize,
+    ) -> Self {
         Self {
-            x: srs_g2[1].into(),
-            x_pow_b0_bound: srs_g2[table_size - 1 - (witness_size - 2)].into(),
+            x_2,
+            x_pow_b0_bound_2,
+            x: x_2.into(),
+            x_pow_b0_bound: x_pow_b0_bound_2.into(),
+            g: E::G2Affine::prime_subgroup_generator().into(),
             table_size,
             witness_size,
+            x_sq_2: None,
+            x_sq: None,
+            x_pow_a_bound_2: None,
+            x_pow_a_bound: None,
+            srs_degree: exact_srs_degree(table_size, witness_size, false),
         }
     }
+
+    pub(crate) fn with_x_sq(mut self, x_sq_2: Option<E::G2Affine>) -> Self {
+        self.x_sq_2 = x_sq_2;
+        self.x_sq = x_sq_2.map(Into::into);
+        self
+    }
+
+    pub(crate) fn with_a_bound(mut self, x_pow_a_bound_2: Option<E::G2Affine>) -> Self {
+        self.x_pow_a_bound_2 = x_pow_a_bound_2;
+        self.x_pow_a_bound = x_pow_a_bound_2.map(Into::into);
+        self
+    }
+
+    pub(crate) fn with_srs_degree(mut self, srs_degree: usize) -> Self {
+        self.srs_degree = srs_degree;
+        self
+    }
+
+    /// Checks the sizes of a key that wasn't built by `for_table`, e.g. a deserialized one, so that verifying
+    /// with it fails instead of panicking
+    pub(crate) fn check_shape(&self) -> Result<(), Error> {
+        if self.witness_size < 2 {
+            return Err(Error::WitnessSizeTooSmall(self.witness_size));
+        }
+        if !is_pow_2(self.witness_size) {
+            return Err(Error::WitnessSizeNotPow2(self.witness_size));
+        }
+        if self.table_size == 0 || !is_pow_2(self.table_size) {
+            return Err(Error::TableSizeNotPow2(self.table_size));
+        }
+        if self.table_size > max_table_size::<E::Fr>() {
+            return Err(Error::TableTooLargeForField(
+                self.table_size,
+                max_table_size::<E::Fr>(),
+            ));
+        }
+        if self.witness_size > max_domain_size::<E::Fr>() {
+            return Err(Error::DomainTooLarge(
+                self.witness_size,
+                max_domain_size::<E::Fr>(),
+            ));
+        }
+        if self.is_zk() && self.witness_size > self.table_size {
+            return Err(Error::WitnessLargerThanTable(
+                self.witness_size,
+                self.table_size,
+            ));
+        }
+        let exact = exact_srs_degree(self.table_size, self.witness_size, self.is_zk());
+        if self.srs_degree < exact {
+            return Err(Error::SrsG1TooSmall(exact + 1, self.srs_degree + 1));
+        }
+        if self.is_zk() && self.srs_degree > exact {
+            return Err(Error::SrsG1TooLarge(
+                exact + 1,
+                self.srs_degree.saturating_add(1),
+            ));
+        }
+        if self.x_pow_a_bound_2.is_some()
+            != a_bound_shift(self.table_size, self.srs_degree, self.is_zk()).is_some()
+        {
+            return Err(Error::InvalidEncoding(
+                "degree bound of A doesn't match the key sizes",
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn is_zk(&self) -> bool {
+        self.x_sq_2.is_some()
+    }
+
+    /// Highest power of x in G1 of the srs the key was made for
+    pub fn srs_degree(&self) -> usize {
+        self.srs_degree
+    }
+
+    /// Degree bounds the soundness of the protocol relies on, with how each one is enforced. In zk proofs the
+    /// bounds of the blinded A and B0 are looser, the one of G takes their place.
+    pub fn degree_bounds(&self) -> Vec<DegreeBound> {
+        let (n_table, n, zk) = (self.table_size, self.witness_size, self.is_zk());
+        // powers of x in the proving key
+        let srs_len = self.srs_degree + 1;
+        let a_shift = a_bound_shift(n_table, self.srs_degree, zk);
+        let b0_shift = b0_bound_shift(self.srs_degree, n, zk);
+        let mut bounds = vec![
+            DegreeBound {
+                element: "a_cm",
+                degree: srs_len - a_shift.unwrap_or(0),
+                shifted: a_shift.map(|shift| ("a_shift_cm", shift)),
+            },
+            DegreeBound {
+                element: "b0_cm",
+                degree: srs_len - b0_shift,
+                shifted: Some(("p_cm", b0_shift)),
+            },
+        ];
+        if zk {
+            // x^2 = x^(D+1-N), zk keys stop at x^(N+1)
+            let g_shift = srs_len - n_table;
+            bounds.push(DegreeBound {
+                element: "g",
+                degree: srs_len - g_shift,
+                shifted: Some(("g_cm", g_shift)),
+            });
+        }
+        bounds
+    }
+}
+
+// the transcript encoding, sizes are written as field elements
+impl<E: PairingEngine> ToBytes for VerifierKey<E> {
+    fn write<W: io::Write>(&self, mut w: W) -> io::Result<()> {
+        self.x_2.write(&mut w)?;
+        self.x_pow_b0_bound_2.write(&mut w)?;
+        E::Fr::from(self.table_size as u64).write(&mut w)?;
+        E::Fr::from(self.witness_size as u64).write(&mut w)?;
+        if let Some(x_sq_2) = &self.x_sq_2 {
+            x_sq_2.write(&mut w)?;
+        }
+        match &self.x_pow_a_bound_2 {
+            Some(x_pow_a_bound_2) => x_pow_a_bound_2.write(&mut w),
+            None => Ok(()),
+        }
+    }
+}
+
+// only the affine points are encoded, prepared points are recomputed on deserialization
+impl<E: PairingEngine> CanonicalSerialize for VerifierKey<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.x_2.serialize(&mut writer)?;
+        self.x_pow_b0_bound_2.serialize(&mut writer)?;
+        self.table_size.serialize(&mut writer)?;
+        self.witness_size.serialize(&mut writer)?;
+        self.x_sq_2.serialize(&mut writer)?;
+        self.x_pow_a_bound_2.serialize(&mut writer)?;
+        self.srs_degree.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.x_2.serialized_size()
+            + self.x_pow_b0_bound_2.serialized_size()
+            + self.table_size.serialized_size()
+            + self.witness_size.serialized_size()
+            + self.x_sq_2.serialized_size()
+            + self.x_pow_a_bound_2.serialized_size()
+            + self.srs_degree.serialized_size()
+    }
+
+    fn serialize_uncompressed<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.x_2.serialize_uncompressed(&mut writer)?;
+        self.x_pow_b0_bound_2.serialize_uncompressed(&mut writer)?;
+        self.table_size.serialize_uncompressed(&mut writer)?;
+        self.witness_size.serialize_uncompressed(&mut writer)?;
+        self.x_sq_2.serialize_uncompressed(&mut writer)?;
+        self.x_pow_a_bound_2.serialize_uncompressed(&mut writer)?;
+        self.srs_degree.serialize_uncompressed(&mut writer)
+    }
+
+    fn uncompressed_size(&self) -> usize {
+        self.x_2.uncompressed_size()
+            + self.x_pow_b0_bound_2.uncompressed_size()
+            + self.table_size.uncompressed_size()
+            + self.witness_size.uncompressed_size()
+            + self.x_sq_2.uncompressed_size()
+            + self.x_pow_a_bound_2.uncompressed_size()
+            + self.srs_degree.uncompressed_size()
+    }
 }
 
-pub struct Verifier<E: PairingEngine, FS: FiatShamirRng> {
+impl<E: PairingEngine> CanonicalDeserialize for VerifierKey<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let x_2 = E::G2Affine::deserialize(&mut reader)?;
+        let x_pow_b0_bound_2 = E::G2Affine::deserialize(&mut reader)?;
+        let table_size = usize::deserialize(&mut reader)?;
+        let witness_size = usize::deserialize(&mut reader)?;
+        let x_sq_2 = Option::<E::G2Affine>::deserialize(&mut reader)?;
+        let x_pow_a_bound_2 = Option::<E::G2Affine>::deserialize(&mut reader)?;
+        let srs_degree = usize::deserialize(&mut reader)?;
+        let vk = Self::from_parts(x_2, x_pow_b0_bound_2, table_size, witness_size)
+            .with_x_sq(x_sq_2)
+            .with_a_bound(x_pow_a_bound_2)
+            .with_srs_degree(srs_degree);
+        vk.check_shape()
+            .map_err(|_| SerializationError::InvalidData)?;
+        Ok(vk)
+    }
+
+    fn deserialize_uncompressed<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let x_2 = E::G2Affine::deserialize_uncompressed(&mut reader)?;
+        let x_pow_b0_bound_2 = E::G2Affine::deserialize_uncompressed(&mut reader)?;
+        let table_size = usize::deserialize_uncompressed(&mut reader)?;
+        let witness_size = usize::deserialize_uncompressed(&mut reader)?;
+        let x_sq_2 = Option::<E::G2Affine>::deserialize_uncompressed(&mut reader)?;
+        let x_pow_a_bound_2 = Option::<E::G2Affine>::deserialize_uncompressed(&mut reader)?;
+        let srs_degree = usize::deserialize_uncompressed(&mut reader)?;
+        let vk = Self::from_parts(x_2, x_pow_b0_bound_2, table_size, witness_size)
+            .with_x_sq(x_sq_2)
+            .with_a_bound(x_pow_a_bound_2)
+            .with_srs_degree(srs_degree);
+        vk.check_shape()
+            .map_err(|_| SerializationError::InvalidData)?;
+        Ok(vk)
+    }
+}
+
+/// Verifier of the protocol, `FS` is the Fiat-Shamir rng of `verify` and `derive_challenges`, or the
+/// [`Transcript`] of `verify_with_transcript`
+pub struct Verifier<E: PairingEngine, FS> {
     _e: PhantomData<E>,
     _fs: PhantomData<FS>,
 }
 
+/// Verifier challenges, normally derived from the transcript
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Challenges<F: Field> {
+    pub beta: F,
+    pub gamma: F,
+    pub eta: F,
+    /// separator for pairing batching
+    pub u: F,
+}
+
 impl<E: PairingEngine, FS: FiatShamirRng> Verifier<E, FS> {
     pub fn verify(
         vk: &VerifierKey<E>,
@@ -43,25 +400,188 @@ impl<E: PairingEngine, FS: FiatShamirRng> Verifier<E, FS> {
         statement: &Statement<E>,
         proof: &Proof<E>,
     ) -> Result<(), Error> {
+        let challenges = Self::derive_challenges(vk, common, statement, proof);
+        Self::verify_with_challenges(vk, common, statement, proof, &challenges)
+    }
+
+    /// Verifies many proofs for the same keys with a constant number of pairings: the equations are combined
+    /// with powers of a challenge over all transcripts. On failure the batch is bisected and the position of the
+    /// first invalid proof is returned in `BatchProofFailed`.
+    pub fn batch_verify(
+        vk: &VerifierKey<E>,
+        common: &CommonPreprocessedInput<E>,
+        instances: &[(Statement<E>, Proof<E>)],
+    ) -> Result<(), Error> {
+        let mut terms = Vec::with_capacity(instances.len());
+        let mut transcript = TranscriptOracle::<FS>::initialize(&PROTOCOL_NAME);
+        Transcript::<E>::begin_round(&mut transcript, PROTOCOL_VERSION, b"batch");
+        for (statement, proof) in instances {
+            let challenges = Self::derive_challenges(vk, common, statement, proof);
+            Transcript::<E>::absorb_field(&mut transcript, b"u", &challenges.u);
+            terms.push(Self::terms(vk, common, statement, proof, &challenges)?.linear);
+        }
+        let r = Transcript::<E>::squeeze_challenge(&mut transcript, b"r");
+        let scalars: Vec<E::Fr> = iter::successors(Some(E::Fr::one()), |p| Some(*p * r))
+            .take(terms.len())
+            .collect();
+
+        if batch_holds(vk, common, &terms, &scalars) {
+            return Ok(());
+        }
+        // the whole batch fails, so one half of every failing range does
+        let (mut start, mut end) = (0, terms.len());
+        while end - start > 1 {
+            let mid = start + (end - start) / 2;
+            if batch_holds(vk, common, &terms[start..mid], &scalars[start..mid]) {
+                start = mid;
+            } else {
+                end = mid;
+            }
+        }
+        Err(Error::BatchProofFailed(start))
+    }
+
+    /// Fiat-Shamir driver, replays the transcript of the proof
+    pub fn derive_challenges(
+        vk: &VerifierKey<E>,
+        common: &CommonPreprocessedInput<E>,
+        statement: &Statement<E>,
+        proof: &Proof<E>,
+    ) -> Challenges<E::Fr> {
         let mut transcipt = TranscriptOracle::<FS>::initialize(&PROTOCOL_NAME);
+        Verifier::<E, TranscriptOracle<FS>>::derive_challenges_with(
+            &mut transcipt,
+            vk,
+            common,
+            statement,
+            proof,
+        )
+    }
+
+    /// Replays the transcript like `derive_challenges`, recording every element and challenge
+    pub fn record_transcript(
+        vk: &VerifierKey<E>,
+        common: &CommonPreprocessedInput<E>,
+        statement: &Statement<E>,
+        proof: &Proof<E>,
+    ) -> RecordingTranscript<TranscriptOracle<FS>> {
+        let mut transcript =
+            RecordingTranscript::new(TranscriptOracle::<FS>::initialize(&PROTOCOL_NAME));
+        Verifier::<E, RecordingTranscript<TranscriptOracle<FS>>>::derive_challenges_with(
+            &mut transcript,
+            vk,
+            common,
+            statement,
+            proof,
+        );
+        transcript
+    }
+}
+
+impl<E: PairingEngine, T: Transcript<E>> Verifier<E, T> {
+    /// Verifies a proof made with `Prover::prove_with_transcript` on a transcript in the same state
+    pub fn verify_with_transcript(
+        transcript: &mut T,
+        vk: &VerifierKey<E>,
+        common: &CommonPreprocessedInput<E>,
+        statement: &Statement<E>,
+        proof: &Proof<E>,
+    ) -> Result<(), Error> {
+        let challenges = Self::derive_challenges_with(transcript, vk, common, statement, proof);
+        Self::verify_with_challenges(vk, common, statement, proof, &challenges)
+    }
+
+    /// Replays the proof on `transcript`
+    pub fn derive_challenges_with(
+        transcript: &mut T,
+        vk: &VerifierKey<E>,
+        common: &CommonPreprocessedInput<E>,
+        statement: &Statement<E>,
+        proof: &Proof<E>,
+    ) -> Challenges<E::Fr> {
+        absorb_public_input(transcript, vk, common, statement);
 
-        transcipt.stream_public_input(common, statement);
+        absorb_first_message(transcript, &proof.first_msg);
 
-        transcipt.stream_first_message(&proof.first_msg);
+        let beta = transcript.squeeze_challenge(b"beta");
 
-        let beta: E::Fr = transcipt.squeeze_challenge();
+        absorb_second_message(transcript, &proof.second_msg);
 
-        transcipt.stream_second_message(&proof.second_msg);
+        let gamma = transcript.squeeze_challenge(b"gamma");
+        let eta = transcript.squeeze_challenge(b"eta");
 
-        let gamma: E::Fr = transcipt.squeeze_challenge();
-        let eta: E::Fr = transcipt.squeeze_challenge();
+        absorb_third_message(transcript, &proof.third_msg);
 
-        transcipt.stream_third_message(&proof.third_msg);
+        let u = transcript.squeeze_challenge(b"u");
+
+        Challenges {
+            beta,
+            gamma,
+            eta,
+            u,
+        }
+    }
+}
+
+impl<E: PairingEngine, FS> Verifier<E, FS> {
+    /// Checks the proof against explicitly given challenges, used for running the protocol interactively
+    pub fn verify_with_challenges(
+        vk: &VerifierKey<E>,
+        common: &CommonPreprocessedInput<E>,
+        statement: &Statement<E>,
+        proof: &Proof<E>,
+        challenges: &Challenges<E::Fr>,
+    ) -> Result<(), Error> {
+        let terms = Self::terms(vk, common, statement, proof, challenges)?;
+        if terms.equation.holds() {
+            return Ok(());
+        }
+        Err(Error::Verification(Self::diagnose(
+            vk, common, statement, proof, &terms,
+        )))
+    }
+
+    /// The batched pairing check of `verify_with_challenges`, for folding with other checks
+    pub(crate) fn pairing_equation(
+        vk: &VerifierKey<E>,
+        common: &CommonPreprocessedInput<E>,
+        statement: &Statement<E>,
+        proof: &Proof<E>,
+        challenges: &Challenges<E::Fr>,
+    ) -> Result<PairingEquation<E>, Error> {
+        Ok(Self::terms(vk, common, statement, proof, challenges)?.equation)
+    }
+
+    fn terms(
+        vk: &VerifierKey<E>,
+        common: &CommonPreprocessedInput<E>,
+        statement: &Statement<E>,
+        proof: &Proof<E>,
+        challenges: &Challenges<E::Fr>,
+    ) -> Result<Terms<E>, Error> {
+        if proof.version != PROTOCOL_VERSION {
+            return Err(Error::Verification(
+                VerificationError::UnsupportedProtocolVersion(proof.version),
+            ));
+        }
+        if proof.second_msg.g_cm.is_some() != vk.is_zk() {
+            return Err(Error::Verification(VerificationError::ZkModeMismatch(
+                vk.is_zk(),
+            )));
+        }
+        if proof.second_msg.a_shift_cm.is_some() != vk.x_pow_a_bound.is_some() {
+            return Err(Error::Verification(VerificationError::ProofShapeMismatch));
+        }
+
+        let Challenges {
+            beta,
+            gamma,
+            eta,
+            u,
+        } = *challenges;
 
-        // separator for pairing batching
-        let u: E::Fr = transcipt.squeeze_challenge();
         let u_powers: Vec<E::Fr> = iter::successors(Some(u), |u_pow| Some(*u_pow * u))
-            .take(4)
+            .take(5)
             .collect();
 
         // NOTE: for easier convention, every pairing that is written on rhs of paper will be negated for usage in product of pairings
@@ -69,18 +589,23 @@ impl<E: PairingEngine, FS: FiatShamirRng> Verifier<E, FS> {
         let g_1 = E::G1Affine::prime_subgroup_generator();
         let g_2 = E::G2Affine::prime_subgroup_generator();
 
-        let witness_domain = GeneralEvaluationDomain::<E::Fr>::new(vk.witness_size).unwrap();
+        let witness_domain = domain::<E::Fr>(vk.witness_size)?;
 
         let n_table = E::Fr::from(vk.table_size as u64);
         let n = E::Fr::from(vk.witness_size as u64);
+        let n_inv = n
+            .inverse()
+            .ok_or(Error::WitnessSizeTooSmall(vk.witness_size))?;
 
-        let b0 = n_table * proof.third_msg.a_at_zero * n.inverse().unwrap();
+        let b0 = n_table * proof.third_msg.a_at_zero * n_inv;
         let b_at_gamma = proof.third_msg.b0_at_gamma * gamma + b0;
         let f_at_gamma = proof.third_msg.f_at_gamma;
-        let zh_at_gamma = witness_domain.evaluate_vanishing_polynomial(gamma);
+        let zh_at_gamma_inv = witness_domain
+            .evaluate_vanishing_polynomial(gamma)
+            .inverse()
+            .ok_or(Error::Verification(VerificationError::GammaInDomain))?;
 
-        let qb_at_gamma =
-            (b_at_gamma * (f_at_gamma + beta) - E::Fr::one()) * zh_at_gamma.inverse().unwrap();
+        let qb_at_gamma = (b_at_gamma * (f_at_gamma + beta) - E::Fr::one()) * zh_at_gamma_inv;
 
         let v = proof.third_msg.b0_at_gamma + eta * f_at_gamma + eta * eta * qb_at_gamma;
         let minus_v_g1 = g_1.mul(-v).into_affine();
@@ -99,82 +624,665 @@ impl<E: PairingEngine, FS: FiatShamirRng> Verifier<E, FS> {
 
         let beta_2 = g_2.mul(beta).into_affine();
 
-        let lhs_batched_1 = (proof.first_msg.m_cm.neg().into_projective()
+        let mut lhs_batched_1 = proof.first_msg.m_cm.neg().into_projective()
             + proof.second_msg.p_cm.mul(-u_powers[0])
             + l.mul(u_powers[1])
-            + a_pt.mul(u_powers[2]))
-        .into_affine();
+            + a_pt.mul(u_powers[2]);
         let lhs_batched_x = (proof.third_msg.pi_gamma.mul(u_powers[1])
             + proof.third_msg.a0_cm.mul(u_powers[2]))
         .neg()
         .into_affine();
 
-        let res = E::product_of_pairings(&[
-            (lhs_batched_1.into(), g_2.into()),
-            (lhs_batched_x.into(), vk.x.clone()),
-            (
-                proof.second_msg.b0_cm.mul(u_powers[0]).into_affine().into(),
-                vk.x_pow_b0_bound.clone(),
-            ),
-            (proof.second_msg.qa_cm.neg().into(), common.zv_2.into()),
-            (proof.second_msg.a_cm.into(), (common.t_2 + beta_2).into()),
-        ]);
+        // zk proofs: the x^N coefficients of A and of the shifted B0 cancel in G = N·(A - A(0)) - n·P,
+        // which replaces the degree bounds on A and B that blinding gives up
+        let zk_g = proof.second_msg.g_cm.map(|g_cm| {
+            let g = (a_pt.mul(n_table) - proof.second_msg.p_cm.mul(n)).into_affine();
+            lhs_batched_1 -= g_cm.mul(u_powers[4]);
+            (g, g_cm)
+        });
 
-        if res != E::Fqk::one() {
-            if cfg!(feature = "debug") {
-                // check well formation of A
-                {
-                    let res = E::product_of_pairings(&[
-                        (proof.second_msg.a_cm.into(), (common.t_2 + beta_2).into()),
-                        (proof.second_msg.qa_cm.neg().into(), common.zv_2.into()),
-                        (proof.first_msg.m_cm.neg().into(), g_2.into()),
-                    ]);
-
-                    if res != E::Fqk::one() {
-                        return Err(Error::Pairing1Failed);
-                    }
-                }
+        // witnesses larger than the table: e(A, [x^(n-N)]_2) = e([x^(n-N)·A(x)]_1, [1]_2) keeps A below x^N
+        let x_pow_a_bound = proof.second_msg.a_shift_cm.map(|a_shift_cm| {
+            lhs_batched_1 -= a_shift_cm.mul(u_powers[3]);
+            proof.second_msg.a_cm.mul(u_powers[3]).into_affine()
+        });
+
+        let linear = LinearTerms {
+            g: lhs_batched_1.into_affine(),
+            x: lhs_batched_x,
+            x_pow_b0_bound: proof.second_msg.b0_cm.mul(u_powers[0]).into_affine(),
+            zv: proof.second_msg.qa_cm.neg(),
+            t_plus_beta: proof.second_msg.a_cm,
+            x_sq: zk_g.map(|(g, _)| g.mul(u_powers[4]).into_affine()),
+            x_pow_a_bound,
+            beta,
+            beta_2,
+        };
 
-                // check b0 degree
-                {
-                    let res = E::product_of_pairings(&[
-                        (proof.second_msg.b0_cm.into(), vk.x_pow_b0_bound.clone()),
-                        (proof.second_msg.p_cm.neg().into(), g_2.into()),
-                    ]);
+        Ok(Terms {
+            equation: PairingEquation {
+                pairs: linear.pairs(vk, common),
+                challenge: u,
+            },
+            linear,
+            l,
+            a_pt,
+            zk_g,
+        })
+    }
 
-                    if res != E::Fqk::one() {
-                        return Err(Error::Pairing2Failed);
-                    }
+    /// Checks the points of a rejected proof and then the pairings of the batched equation one by one to report
+    /// which one fails. Only runs once the batched equation failed, so valid proofs don't pay for it.
+    fn diagnose(
+        vk: &VerifierKey<E>,
+        common: &CommonPreprocessedInput<E>,
+        statement: &Statement<E>,
+        proof: &Proof<E>,
+        terms: &Terms<E>,
+    ) -> VerificationError {
+        let points = [
+            ("f", Some(statement.f)),
+            ("m_cm", Some(proof.first_msg.m_cm)),
+            ("a_cm", Some(proof.second_msg.a_cm)),
+            ("qa_cm", Some(proof.second_msg.qa_cm)),
+            ("b0_cm", Some(proof.second_msg.b0_cm)),
+            ("qb_cm", Some(proof.second_msg.qb_cm)),
+            ("p_cm", Some(proof.second_msg.p_cm)),
+            ("g_cm", proof.second_msg.g_cm),
+            ("a_shift_cm", proof.second_msg.a_shift_cm),
+            ("a0_cm", Some(proof.third_msg.a0_cm)),
+            ("pi_gamma", Some(proof.third_msg.pi_gamma)),
+        ];
+        for (name, point) in points {
+            if let Some(point) = point {
+                if !is_valid_point(&point) {
+                    return VerificationError::MalformedPoint(name);
                 }
+            }
+        }
 
-                // check openings at gamma
-                {
-                    let res = E::product_of_pairings(&[
-                        (l.into(), g_2.into()),
-                        (proof.third_msg.pi_gamma.neg().into(), vk.x.clone()),
-                    ]);
+        // check well formation of A
+        {
+            let res = E::product_of_pairings(&[
+                (
+                    proof.second_msg.a_cm.into(),
+                    (common.t_2 + terms.linear.beta_2).into(),
+                ),
+                (proof.second_msg.qa_cm.neg().into(), common.zv.clone()),
+                (proof.first_msg.m_cm.neg().into(), vk.g.clone()),
+            ]);
 
-                    if res != E::Fqk::one() {
-                        return Err(Error::Pairing3Failed);
-                    }
-                }
+            if res != E::Fqk::one() {
+                return VerificationError::WellFormednessFailed;
+            }
+        }
 
-                // check a opening at zero
-                {
-                    let res = E::product_of_pairings(&[
-                        (a_pt.into(), g_2.into()),
-                        (proof.third_msg.a0_cm.neg().into(), vk.x.clone()),
-                    ]);
+        // check b0 degree
+        {
+            let res = E::product_of_pairings(&[
+                (proof.second_msg.b0_cm.into(), vk.x_pow_b0_bound.clone()),
+                (proof.second_msg.p_cm.neg().into(), vk.g.clone()),
+            ]);
 
-                    if res != E::Fqk::one() {
-                        return Err(Error::Pairing4Failed);
-                    }
-                }
-            } else {
-                return Err(Error::BatchedPairingFailed);
+            if res != E::Fqk::one() {
+                return VerificationError::DegreeBoundFailed("b0_cm");
             }
         }
 
-        Ok(())
+        // check a degree
+        if let (Some(a_shift_cm), Some(x_pow_a_bound)) =
+            (proof.second_msg.a_shift_cm, &vk.x_pow_a_bound)
+        {
+            let res = E::product_of_pairings(&[
+                (proof.second_msg.a_cm.into(), x_pow_a_bound.clone()),
+                (a_shift_cm.neg().into(), vk.g.clone()),
+            ]);
+
+            if res != E::Fqk::one() {
+                return VerificationError::DegreeBoundFailed("a_cm");
+            }
+        }
+
+        // check openings at gamma
+        {
+            let res = E::product_of_pairings(&[
+                (terms.l.into(), vk.g.clone()),
+                (proof.third_msg.pi_gamma.neg().into(), vk.x.clone()),
+            ]);
+
+            if res != E::Fqk::one() {
+                return VerificationError::OpeningFailed("pi_gamma");
+            }
+        }
+
+        // check a opening at zero
+        {
+            let res = E::product_of_pairings(&[
+                (terms.a_pt.into(), vk.g.clone()),
+                (proof.third_msg.a0_cm.neg().into(), vk.x.clone()),
+            ]);
+
+            if res != E::Fqk::one() {
+                return VerificationError::OpeningFailed("a0_cm");
+            }
+        }
+
+        // check the zk coefficient relation
+        if let (Some((g, g_cm)), Some(x_sq)) = (terms.zk_g, &vk.x_sq) {
+            let res = E::product_of_pairings(&[
+                (g.into(), x_sq.clone()),
+                (g_cm.neg().into(), vk.g.clone()),
+            ]);
+
+            if res != E::Fqk::one() {
+                return VerificationError::DegreeBoundFailed("g_cm");
+            }
+        }
+
+        VerificationError::BatchedPairingFailed
+    }
+}
+
+/// Intermediate points of the batched equation, kept for `diagnose`
+struct Terms<E: PairingEngine> {
+    equation: PairingEquation<E>,
+    linear: LinearTerms<E>,
+    l: E::G1Affine,
+    a_pt: E::G1Affine,
+    zk_g: Option<(E::G1Affine, E::G1Affine)>,
+}
+
+/// G1 sides of the batched equation by their G2 point, linear in the proof so equations of many proofs add up
+struct LinearTerms<E: PairingEngine> {
+    g: E::G1Affine,
+    x: E::G1Affine,
+    x_pow_b0_bound: E::G1Affine,
+    zv: E::G1Affine,
+    /// paired with [T(x)]_2 + beta·[1]_2
+    t_plus_beta: E::G1Affine,
+    x_sq: Option<E::G1Affine>,
+    x_pow_a_bound: Option<E::G1Affine>,
+    beta: E::Fr,
+    beta_2: E::G2Affine,
+}
+
+impl<E: PairingEngine> LinearTerms<E> {
+    fn pairs(
+        &self,
+        vk: &VerifierKey<E>,
+        common: &CommonPreprocessedInput<E>,
+    ) -> Vec<(E::G1Affine, E::G2Prepared)> {
+        let mut pairs = Vec::with_capacity(7);
+        if let (Some(x_sq_term), Some(x_sq)) = (self.x_sq, &vk.x_sq) {
+            pairs.push((x_sq_term, x_sq.clone()));
+        }
+        if let (Some(a_term), Some(x_pow_a_bound)) = (self.x_pow_a_bound, &vk.x_pow_a_bound) {
+            pairs.push((a_term, x_pow_a_bound.clone()));
+        }
+        pairs.extend([
+            (self.g, vk.g.clone()),
+            (self.x, vk.x.clone()),
+            (self.x_pow_b0_bound, vk.x_pow_b0_bound.clone()),
+            (self.zv, common.zv.clone()),
+            (self.t_plus_beta, (common.t_2 + self.beta_2).into()),
+        ]);
+        pairs
+    }
+}
+
+/// `sum_i r_i·terms_i` with `e(A, [T(x)]_2 + beta·[1]_2)` split so that every G2 point is fixed, one pairing
+/// per G2 point whatever the number of proofs
+fn batch_holds<E: PairingEngine>(
+    vk: &VerifierKey<E>,
+    common: &CommonPreprocessedInput<E>,
+    terms: &[LinearTerms<E>],
+    scalars: &[E::Fr],
+) -> bool {
+    // G1 sides for [1]_2, [x]_2, [x^(N-1-(n-2))]_2, [zV(x)]_2, [T(x)]_2, [x^2]_2 and [x^(n-N)]_2
+    let mut sums = [E::G1Projective::zero(); 7];
+    for (term, r) in terms.iter().zip(scalars) {
+        sums[0] += term.g.mul(*r) + term.t_plus_beta.mul(term.beta * r);
+        sums[1] += term.x.mul(*r);
+        sums[2] += term.x_pow_b0_bound.mul(*r);
+        sums[3] += term.zv.mul(*r);
+        sums[4] += term.t_plus_beta.mul(*r);
+        if let Some(x_sq_term) = term.x_sq {
+            sums[5] += x_sq_term.mul(*r);
+        }
+        if let Some(a_term) = term.x_pow_a_bound {
+            sums[6] += a_term.mul(*r);
+        }
+    }
+    E::G1Projective::batch_normalization(&mut sums);
+
+    // the G2 points of the variant of the key, the sum of a missing one is zero
+    let g2 = [
+        Some(vk.g.clone()),
+        Some(vk.x.clone()),
+        Some(vk.x_pow_b0_bound.clone()),
+        Some(common.zv.clone()),
+        Some(common.t.clone()),
+        vk.x_sq.clone(),
+        vk.x_pow_a_bound.clone(),
+    ];
+    let pairs: Vec<(E::G1Prepared, E::G2Prepared)> = sums
+        .iter()
+        .zip(g2)
+        .filter_map(|(g1, g2)| Some((g1.into_affine().into(), g2?)))
+        .collect();
+    E::product_of_pairings(&pairs).is_one()
+}
+
+/// Pairs whose product is one for a valid proof, with the last challenge of the transcript they come from
+pub(crate) struct PairingEquation<E: PairingEngine> {
+    pub(crate) pairs: Vec<(E::G1Affine, E::G2Prepared)>,
+    pub(crate) challenge: E::Fr,
+}
+
+impl<E: PairingEngine> PairingEquation<E> {
+    pub(crate) fn holds(&self) -> bool {
+        let pairs: Vec<(E::G1Prepared, E::G2Prepared)> = self
+            .pairs
+            .iter()
+            .map(|(g1, g2)| ((*g1).into(), g2.clone()))
+            .collect();
+        E::product_of_pairings(&pairs).is_one()
+    }
+}
+
+/// Checks all `checks` with one final exponentiation: the i-th equation is scaled by `r^i` for a challenge
+/// `r` bound to all of them. If the folded product isn't one, the error of the first failing check is returned.
+pub(crate) fn verify_folded<E: PairingEngine, FS: FiatShamirRng>(
+    checks: Vec<(PairingEquation<E>, Error)>,
+) -> Result<(), Error> {
+    let mut transcript = TranscriptOracle::<FS>::initialize(&PROTOCOL_NAME);
+    Transcript::<E>::begin_round(&mut transcript, PROTOCOL_VERSION, b"fold");
+    for (equation, _) in &checks {
+        Transcript::<E>::absorb_field(&mut transcript, b"c", &equation.challenge);
+        for (g1, _) in &equation.pairs {
+            Transcript::<E>::absorb_g1(&mut transcript, b"p", g1);
+        }
+    }
+    let r = Transcript::<E>::squeeze_challenge(&mut transcript, b"r");
+
+    let mut pairs = Vec::new();
+    let mut r_pow = E::Fr::one();
+    for (equation, _) in &checks {
+        for (g1, g2) in &equation.pairs {
+            pairs.push((g1.mul(r_pow).into_affine().into(), g2.clone()));
+        }
+        r_pow *= r;
+    }
+    if E::product_of_pairings(&pairs).is_one() {
+        return Ok(());
+    }
+
+    match checks.into_iter().find(|(equation, _)| !equation.holds()) {
+        Some((_, err)) => Err(err),
+        None => Err(Error::Verification(VerificationError::BatchedPairingFailed)),
+    }
+}
+
+#[cfg(test)]
+mod verifier_tests {
+    use ark_bn254::{Bn254, Fr, G1Affine};
+    use ark_ec::{AffineCurve, ProjectiveCurve};
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+    use ark_std::{rand::rngs::StdRng, test_rng, UniformRand};
+    use rand_chacha::ChaChaRng;
+    use sha3::Keccak256;
+
+    use crate::{
+        data_structures::{Proof, ProvingKey, Statement, Witness},
+        error::{Error, VerificationError},
+        indexer::Index,
+        kzg::Kzg,
+        prover::{Prover, State},
+        rng::SimpleHashFiatShamirRng,
+        table::Table,
+        utils::{to_field, unsafe_setup_from_rng},
+    };
+
+    use super::{Challenges, DegreeBound, Verifier, VerifierKey};
+
+    type FS = SimpleHashFiatShamirRng<Keccak256, ChaChaRng>;
+
+    #[test]
+    fn test_interactive_rounds() {
+        let n = 8;
+        let mut rng = test_rng();
+
+        let (srs_g1, srs_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(n - 1, n, &mut rng);
+        let pk = ProvingKey::<Bn254>::new(srs_g1);
+
+        let table = Table::new(&to_field(&[1, 5, 10, 15, 20, 25, 30, 35])).unwrap();
+        let index = Index::<Bn254>::gen(&pk.srs_g1, &srs_g2, &table).unwrap();
+
+        let witness = Witness::<Fr>::new(&to_field(&[5, 15, 20, 35])).unwrap();
+        let statement = Statement::from_witness(&pk, &witness);
+
+        let vk = VerifierKey::<Bn254>::for_table(&srs_g2, table.size, witness.size, false).unwrap();
+
+        let challenges = Challenges {
+            beta: Fr::rand(&mut rng),
+            gamma: Fr::rand(&mut rng),
+            eta: Fr::rand(&mut rng),
+            u: Fr::rand(&mut rng),
+        };
+
+        let mut state = State::new(&pk, &index, &table, &witness).unwrap();
+        let first_msg = Prover::<Bn254, FS>::round_1(&mut state).unwrap();
+        let second_msg = Prover::<Bn254, FS>::round_2(&mut state, challenges.beta).unwrap();
+        let third_msg =
+            Prover::<Bn254, FS>::round_3(&mut state, challenges.gamma, challenges.eta).unwrap();
+        let mut proof = Proof::new(first_msg, second_msg, third_msg);
+
+        let res = Verifier::<Bn254, FS>::verify_with_challenges(
+            &vk,
+            &index.common,
+            &statement,
+            &proof,
+            &challenges,
+        );
+        assert!(res.is_ok());
+
+        // the same messages must be rejected under a different beta
+        let malformed = Challenges {
+            beta: challenges.beta + Fr::from(1u64),
+            ..challenges
+        };
+        let res = Verifier::<Bn254, FS>::verify_with_challenges(
+            &vk,
+            &index.common,
+            &statement,
+            &proof,
+            &malformed,
+        );
+        assert!(res.is_err());
+
+        // Q_B can't be evaluated on the witness domain
+        let in_domain = Challenges {
+            gamma: Fr::from(1u64),
+            ..challenges
+        };
+        let res = Verifier::<Bn254, FS>::verify_with_challenges(
+            &vk,
+            &index.common,
+            &statement,
+            &proof,
+            &in_domain,
+        );
+        assert_eq!(
+            res,
+            Err(Error::Verification(VerificationError::GammaInDomain))
+        );
+
+        // proofs of a different protocol version are rejected before any pairing
+        proof.version = 0x20;
+        let res = Verifier::<Bn254, FS>::verify_with_challenges(
+            &vk,
+            &index.common,
+            &statement,
+            &proof,
+            &challenges,
+        );
+        assert_eq!(
+            res,
+            Err(Error::Verification(
+                VerificationError::UnsupportedProtocolVersion(0x20)
+            ))
+        );
+    }
+
+    #[test]
+    fn test_batch_verify() {
+        let n = 8;
+        let mut rng = test_rng();
+
+        let (srs_g1, srs_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(n - 1, n, &mut rng);
+        let pk = ProvingKey::<Bn254>::new(srs_g1);
+        let table = Table::new(&to_field(&[1, 5, 10, 15, 20, 25, 30, 35])).unwrap();
+        let index = Index::<Bn254>::gen(&pk.srs_g1, &srs_g2, &table).unwrap();
+        let vk = VerifierKey::<Bn254>::for_table(&srs_g2, table.size, 4, false).unwrap();
+
+        let mut instances: Vec<_> = [
+            [5, 15, 20, 35],
+            [1, 1, 1, 1],
+            [35, 30, 25, 20],
+            [10, 5, 10, 5],
+            [1, 5, 10, 15],
+        ]
+        .iter()
+        .map(|values| {
+            let witness = Witness::<Fr>::new(&to_field(values)).unwrap();
+            let statement = Statement::from_witness(&pk, &witness);
+            let proof =
+                Prover::<Bn254, FS>::prove(&pk, &vk, &index, &table, &witness, &statement).unwrap();
+            (statement, proof)
+        })
+        .collect();
+        assert!(Verifier::<Bn254, FS>::batch_verify(&vk, &index.common, &instances).is_ok());
+        assert!(Verifier::<Bn254, FS>::batch_verify(&vk, &index.common, &[]).is_ok());
+
+        // the statement of another proof
+        instances[3].0 = Statement::new(instances[1].0.f);
+        let res = Verifier::<Bn254, FS>::batch_verify(&vk, &index.common, &instances);
+        assert_eq!(res, Err(Error::BatchProofFailed(3)));
+    }
+
+    #[test]
+    fn test_failing_check_is_named() {
+        let n = 8;
+        let mut rng = test_rng();
+
+        let (srs_g1, srs_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(n - 1, n, &mut rng);
+        let pk = ProvingKey::<Bn254>::new(srs_g1);
+        let table = Table::new(&to_field(&[1, 5, 10, 15, 20, 25, 30, 35])).unwrap();
+        let index = Index::<Bn254>::gen(&pk.srs_g1, &srs_g2, &table).unwrap();
+        let witness = Witness::<Fr>::new(&to_field(&[5, 15, 20, 35])).unwrap();
+        let statement = Statement::from_witness(&pk, &witness);
+        let vk = VerifierKey::<Bn254>::for_table(&srs_g2, table.size, witness.size, false).unwrap();
+        let prove =
+            || Prover::<Bn254, FS>::prove(&pk, &vk, &index, &table, &witness, &statement).unwrap();
+        let g = G1Affine::prime_subgroup_generator();
+
+        // each tampered point breaks one relation only, whatever challenges it leads to
+        let mut shifted_b0 = prove();
+        shifted_b0.second_msg.b0_cm = shifted_b0.second_msg.b0_cm + g;
+        let mut wrong_opening = prove();
+        wrong_opening.third_msg.a0_cm = wrong_opening.third_msg.a0_cm + g;
+        let mut wrong_a = prove();
+        wrong_a.first_msg.m_cm = wrong_a.first_msg.m_cm + g;
+
+        for (proof, expected) in [
+            (shifted_b0, VerificationError::DegreeBoundFailed("b0_cm")),
+            (wrong_opening, VerificationError::OpeningFailed("a0_cm")),
+            (wrong_a, VerificationError::WellFormednessFailed),
+        ] {
+            let res = Verifier::<Bn254, FS>::verify(&vk, &index.common, &statement, &proof);
+            assert_eq!(res, Err(Error::Verification(expected)));
+        }
+    }
+
+    #[test]
+    fn test_degree_bound_of_large_witness() {
+        let mut rng = test_rng();
+
+        // the srs reaches x^15 for the witness, A of the table of 4 entries must stay below x^4
+        let (srs_g1, srs_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(15, 12, &mut rng);
+        let pk = ProvingKey::<Bn254>::new(srs_g1);
+        let table = Table::new(&to_field(&[1, 5, 10, 15])).unwrap();
+        let index = Index::<Bn254>::gen(&pk.srs_g1, &srs_g2, &table).unwrap();
+        let witness = Witness::<Fr>::new(&to_field(&[
+            5, 1, 1, 15, 10, 5, 5, 15, 1, 10, 10, 1, 1, 1, 1, 1,
+        ]))
+        .unwrap();
+        let statement = Statement::from_witness(&pk, &witness);
+        let vk = VerifierKey::<Bn254>::for_table(&srs_g2, table.size, witness.size, false).unwrap();
+        assert_eq!(
+            vk.degree_bounds(),
+            vec![
+                DegreeBound {
+                    element: "a_cm",
+                    degree: 4,
+                    shifted: Some(("a_shift_cm", 12)),
+                },
+                DegreeBound {
+                    element: "b0_cm",
+                    degree: 15,
+                    shifted: Some(("p_cm", 1)),
+                },
+            ]
+        );
+        assert_eq!(
+            VerifierKey::<Bn254>::for_table(&srs_g2, table.size, 4, false)
+                .unwrap()
+                .degree_bounds()[0]
+                .shifted,
+            None
+        );
+
+        let prove =
+            || Prover::<Bn254, FS>::prove(&pk, &vk, &index, &table, &witness, &statement).unwrap();
+        let verify = |proof: &Proof<Bn254>| {
+            Verifier::<Bn254, FS>::verify(&vk, &index.common, &statement, proof)
+        };
+        assert!(verify(&prove()).is_ok());
+
+        // A(X) + zV(X) with QA(X) + T(X) + beta is well formed and shifts A(0), only its degree gives it away.
+        // beta only depends on the first message.
+        let mut proof = prove();
+        let beta =
+            Verifier::<Bn254, FS>::derive_challenges(&vk, &index.common, &statement, &proof).beta;
+        let srs = &pk.srs_g1;
+        let t_1 = Kzg::<Bn254>::msm_g1(&index.ls, table.values()).into_affine();
+        proof.second_msg.a_cm = proof.second_msg.a_cm + srs[4] + -srs[0];
+        proof.second_msg.qa_cm = proof.second_msg.qa_cm + t_1 + srs[0].mul(beta).into_affine();
+        assert_eq!(
+            verify(&proof),
+            Err(Error::Verification(VerificationError::DegreeBoundFailed(
+                "a_cm"
+            )))
+        );
+
+        let mut proof = prove();
+        proof.second_msg.a_shift_cm = None;
+        assert_eq!(
+            verify(&proof),
+            Err(Error::Verification(VerificationError::ProofShapeMismatch))
+        );
+    }
+
+    #[test]
+    fn test_deserialized_key_is_checked() {
+        let mut rng = test_rng();
+        let (_, srs_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(7, 8, &mut rng);
+        let vk = VerifierKey::<Bn254>::for_table(&srs_g2, 8, 4, false).unwrap();
+        let mut bytes = vec![];
+        vk.serialize(&mut bytes).unwrap();
+        assert!(VerifierKey::<Bn254>::deserialize(&bytes[..]).is_ok());
+
+        // sizes `for_table` rejects don't come back through deserialization either
+        for (table_size, witness_size) in [(8, 0), (8, 3), (6, 4), (4, 8)] {
+            let bad = VerifierKey::<Bn254>::from_parts(
+                vk.x_2,
+                vk.x_pow_b0_bound_2,
+                table_size,
+                witness_size,
+            );
+            let mut bytes = vec![];
+            bad.serialize(&mut bytes).unwrap();
+            assert!(matches!(
+                VerifierKey::<Bn254>::deserialize(&bytes[..]),
+                Err(SerializationError::InvalidData)
+            ));
+        }
+
+        // zk keys only hold for an srs that stops at x^(N+1), plain keys past x^(N-1) need the bound of A
+        let zk_past_degree = VerifierKey::<Bn254>::for_table(&srs_g2, 8, 4, true)
+            .unwrap()
+            .with_srs_degree(15);
+        let plain_past_degree = vk.clone().with_srs_degree(15);
+        for bad in [zk_past_degree, plain_past_degree] {
+            let mut bytes = vec![];
+            bad.serialize(&mut bytes).unwrap();
+            assert!(matches!(
+                VerifierKey::<Bn254>::deserialize(&bytes[..]),
+                Err(SerializationError::InvalidData)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_universal_srs_bounds() {
+        let mut rng = test_rng();
+
+        // an srs up to x^31 for a table of 8 entries, a prover knows the powers past x^7
+        let (srs_g1, srs_g2) = unsafe_setup_from_rng::<Bn254, StdRng>(31, 32, &mut rng);
+        let pk = ProvingKey::<Bn254>::new(srs_g1);
+        let table = Table::new(&to_field(&[1, 5, 10, 15, 20, 25, 30, 35])).unwrap();
+        let index = Index::<Bn254>::gen(&pk.srs_g1[..8], &srs_g2, &table).unwrap();
+        let witness = Witness::<Fr>::new(&to_field(&[5, 15, 20, 35])).unwrap();
+        let statement = Statement::from_witness(&pk, &witness);
+        let vk = VerifierKey::<Bn254>::for_srs_degree(&srs_g2, 31, 8, 4, false).unwrap();
+        assert_eq!(vk.x_pow_b0_bound_2, srs_g2[29]);
+        assert_eq!(vk.x_pow_a_bound_2, Some(srs_g2[24]));
+        assert_eq!(
+            vk.degree_bounds(),
+            vec![
+                DegreeBound {
+                    element: "a_cm",
+                    degree: 8,
+                    shifted: Some(("a_shift_cm", 24)),
+                },
+                DegreeBound {
+                    element: "b0_cm",
+                    degree: 3,
+                    shifted: Some(("p_cm", 29)),
+                },
+            ]
+        );
+
+        assert_eq!(
+            VerifierKey::<Bn254>::for_srs_degree(&srs_g2[..29], 31, 8, 4, false).err(),
+            Some(Error::SrsG2TooSmall(30, 29))
+        );
+        assert_eq!(
+            VerifierKey::<Bn254>::for_srs_degree(&srs_g2, 6, 8, 4, false).err(),
+            Some(Error::SrsG1TooSmall(8, 7))
+        );
+        assert_eq!(
+            VerifierKey::<Bn254>::for_srs_degree(&srs_g2, 31, 8, 4, true).err(),
+            Some(Error::SrsG1TooLarge(10, 32))
+        );
+
+        let prove =
+            || Prover::<Bn254, FS>::prove(&pk, &vk, &index, &table, &witness, &statement).unwrap();
+        let verify = |proof: &Proof<Bn254>| {
+            Verifier::<Bn254, FS>::verify(&vk, &index.common, &statement, proof)
+        };
+        assert!(verify(&prove()).is_ok());
+
+        // a proving key cut below x^31 can't prove for the key
+        let short = ProvingKey::<Bn254>::new(pk.srs_g1[..8].to_vec());
+        assert_eq!(
+            Prover::<Bn254, FS>::prove(&short, &vk, &index, &table, &witness, &statement).err(),
+            Some(Error::SrsG1TooSmall(32, 8))
+        );
+
+        // A(X) + zV(X) is well formed, x^8 of the srs gives it away
+        let mut proof = prove();
+        let beta =
+            Verifier::<Bn254, FS>::derive_challenges(&vk, &index.common, &statement, &proof).beta;
+        let srs = &pk.srs_g1;
+        let t_1 = Kzg::<Bn254>::msm_g1(&index.ls, table.values()).into_affine();
+        proof.second_msg.a_cm = proof.second_msg.a_cm + srs[8] + -srs[0];
+        proof.second_msg.qa_cm = proof.second_msg.qa_cm + t_1 + srs[0].mul(beta).into_affine();
+        assert_eq!(
+            verify(&proof),
+            Err(Error::Verification(VerificationError::DegreeBoundFailed(
+                "a_cm"
+            )))
+        );
     }
 }
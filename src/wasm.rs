@@ -0,0 +1,171 @@
+//! wasm-bindgen API over the BN254 `Cq` facade, build with
+//! `wasm-pack build --target web -- --features wasm`.
+//!
+//! ```js
+//! const keys = setup(new BigUint64Array([1n, 5n, 10n, 15n]), 2, 42n);
+//! const proof = new Prover(keys.prover_key()).prove(new BigUint64Array([5n, 15n]));
+//! new Verifier(keys.verifier_key()).verify(proof.statement(), proof.proof());
+//! ```
+//!
+//! Values are passed as `BigUint64Array`s and keys, statements and proofs as `Uint8Array`s.
+
+use ark_bn254::{Bn254, Fr};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+#[cfg(any(test, feature = "test-utils"))]
+use ark_std::rand::{rngs::StdRng, SeedableRng};
+use rand_chacha::ChaChaRng;
+use sha3::Keccak256;
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    cq::{Cq, CqProverKey, CqVerifierKey},
+    data_structures::{Proof, Statement},
+    encoding::{Artifact, Compression},
+    error::Error,
+    rng::SimpleHashFiatShamirRng,
+};
+
+type FS = SimpleHashFiatShamirRng<Keccak256, ChaChaRng>;
+
+fn js_error(e: Error) -> JsError {
+    JsError::new(&format!("{:?}", e))
+}
+
+fn to_bytes<T: CanonicalSerialize>(value: &T) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(value.serialized_size());
+    value
+        .serialize(&mut bytes)
+        .expect("serializing into a vec can't fail");
+    bytes
+}
+
+fn from_bytes<T: CanonicalDeserialize>(mut bytes: &[u8]) -> Result<T, JsError> {
+    let value = T::deserialize(&mut bytes).map_err(|e| JsError::new(&format!("{:?}", e)))?;
+    if !bytes.is_empty() {
+        return Err(JsError::new("trailing bytes"));
+    }
+    Ok(value)
+}
+
+fn to_field(values: &[u64]) -> Vec<Fr> {
+    values.iter().map(|v| Fr::from(*v)).collect()
+}
+
+#[wasm_bindgen]
+pub struct Setup {
+    prover_key: Vec<u8>,
+    verifier_key: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl Setup {
+    pub fn prover_key(&self) -> Vec<u8> {
+        self.prover_key.clone()
+    }
+
+    pub fn verifier_key(&self) -> Vec<u8> {
+        self.verifier_key.clone()
+    }
+}
+
+/// Indexes a table for witnesses of `witness_size`, the srs is sampled from `seed` and is not secure, only built with the
+/// `test-utils` feature
+#[cfg(any(test, feature = "test-utils"))]
+#[wasm_bindgen]
+pub fn setup(table: &[u64], witness_size: usize, seed: u64) -> Result<Setup, JsError> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let (pk, vk) =
+        Cq::<Bn254, FS>::setup(&to_field(table), witness_size, &mut rng).map_err(js_error)?;
+    Ok(Setup {
+        prover_key: to_bytes(&pk),
+        verifier_key: to_bytes(&vk),
+    })
+}
+
+#[wasm_bindgen]
+pub struct LookupProof {
+    statement: Vec<u8>,
+    proof: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl LookupProof {
+    pub fn statement(&self) -> Vec<u8> {
+        self.statement.clone()
+    }
+
+    pub fn proof(&self) -> Vec<u8> {
+        self.proof.clone()
+    }
+}
+
+/// Keeps a decoded prover key around across proofs
+#[wasm_bindgen]
+pub struct Prover {
+    key: CqProverKey<Bn254>,
+}
+
+#[wasm_bindgen]
+impl Prover {
+    #[wasm_bindgen(constructor)]
+    pub fn new(prover_key: &[u8]) -> Result<Prover, JsError> {
+        Ok(Self {
+            key: from_bytes(prover_key)?,
+        })
+    }
+
+    pub fn prove(&self, witness: &[u64]) -> Result<LookupProof, JsError> {
+        let (statement, proof) =
+            Cq::<Bn254, FS>::prove(&self.key, &to_field(witness)).map_err(js_error)?;
+        Ok(LookupProof {
+            statement: statement.encode(Compression::Compressed),
+            proof: proof.encode(Compression::Compressed),
+        })
+    }
+}
+
+#[wasm_bindgen]
+pub struct Verifier {
+    key: CqVerifierKey<Bn254>,
+}
+
+#[wasm_bindgen]
+impl Verifier {
+    #[wasm_bindgen(constructor)]
+    pub fn new(verifier_key: &[u8]) -> Result<Verifier, JsError> {
+        Ok(Self {
+            key: from_bytes(verifier_key)?,
+        })
+    }
+
+    /// False for proofs that don't verify as well as for malformed encodings
+    pub fn verify(&self, statement: &[u8], proof: &[u8]) -> bool {
+        match (Statement::decode(statement), Proof::decode(proof)) {
+            (Ok(statement), Ok(proof)) => {
+                Cq::<Bn254, FS>::verify(&self.key, &statement, &proof).is_ok()
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod wasm_tests {
+    use super::{setup, Prover, Verifier};
+
+    // error paths construct a `JsError` and can only run on wasm
+    #[test]
+    fn test_wasm_api_roundtrip() {
+        let keys = setup(&[1, 5, 10, 15, 20, 25, 30, 35], 4, 42).ok().unwrap();
+        let proof = Prover::new(&keys.prover_key())
+            .ok()
+            .unwrap()
+            .prove(&[5, 15, 20, 35])
+            .ok()
+            .unwrap();
+
+        let verifier = Verifier::new(&keys.verifier_key()).ok().unwrap();
+        assert!(verifier.verify(&proof.statement(), &proof.proof()));
+        assert!(!verifier.verify(&proof.proof(), &proof.statement()));
+    }
+}
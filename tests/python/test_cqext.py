@@ -0,0 +1,27 @@
+# run with `maturin develop --features test-utils && pytest tests/python`
+import pytest
+
+import cqext
+
+TABLE = [1, 5, 10, 15, 20, 25, 30, 35]
+
+
+def test_roundtrip():
+    pk, vk = cqext.setup(TABLE, witness_size=4, seed=42)
+    statement, proof = cqext.prove(pk, [5, 15, 20, 35])
+    assert cqext.verify(vk, statement, proof)
+
+    statement = cqext.Statement.from_bytes(statement.to_bytes())
+    proof = cqext.Proof.from_bytes(proof.to_bytes())
+    vk = cqext.VerifierKey.from_bytes(vk.to_bytes())
+    assert cqext.verify(vk, statement, proof)
+
+
+def test_rejects_bad_values():
+    pk, _ = cqext.setup(TABLE, witness_size=4, seed=42)
+    with pytest.raises(ValueError):
+        cqext.prove(pk, [5, 15, 20, 36])
+    with pytest.raises(OverflowError):
+        cqext.prove(pk, [5, 15, 20, -1])
+    with pytest.raises(ValueError):
+        cqext.setup(TABLE[:6], witness_size=4, seed=42)